@@ -0,0 +1,544 @@
+use crate::errors::*;
+use crate::vertex::{EdgeId, VertexBuffer};
+use crate::components::SpherePoint;
+use crate::dijkstra::{search_edge_ids, search_from_sources, snap_query};
+use crate::path::Path;
+use crate::rng::SplitMix64;
+use crate::hashing::from_node_index;
+
+/// Accessibility of single node, see `closeness_centrality`.
+///
+/// * `average_cost` - mean travel cost from sampled nodes reaching this node, infinite when none does
+/// * `reached_samples` - number of sampled nodes, other than the node itself, reaching this node
+/// * `closeness` - inverse of `average_cost`, 0.0 for unreachable nodes
+/// * `heat` - `closeness` scaled to [0.0, 1.0] over all nodes, ready for heatmap colouring
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Accessibility {
+    pub average_cost: f64,
+    pub reached_samples: usize,
+    pub closeness: f64,
+    pub heat: f64,
+}
+
+/// Returns Accessibility of every node of VertexBuffer, in order of nodes.
+///
+/// # Arguments:
+/// * `vertex` which is &VertexBuffer - graph to analyse
+/// * `sample_size` which is usize - number of nodes costs are averaged over, all nodes when it is not lower than node count
+/// * `seed` which is u64 - seed of node sampling, the same seed gives the same result
+///
+/// # Remarks:
+///
+/// One shortest path search is run from every sampled node, so the cost is `sample_size` single source
+/// searches. Exact closeness centrality is obtained with sample of all nodes, smaller samples give its estimate.
+///
+/// Fails with `InvalidParameter` when `sample_size` is 0.
+pub fn closeness_centrality(vertex: &VertexBuffer, sample_size: usize, seed: u64) -> Result<Vec<Accessibility>> {
+    if sample_size == 0 {
+        return Err(Error::from_kind(ErrorKind::InvalidParameter));
+    }
+    let samples = sample_nodes(vertex.len(), sample_size, seed);
+    let mut sums: Vec<f64> = vec![0.0; vertex.len()];
+    let mut reached: Vec<usize> = vec![0; vertex.len()];
+    for sample in samples {
+        let labels = search_from_sources(&[sample], vertex);
+        for (vertex_index, cost) in labels.settled_costs() {
+            if vertex_index != sample {
+                sums[vertex_index] += cost;
+                reached[vertex_index] += 1;
+            }
+        }
+    }
+    let mut result: Vec<Accessibility> = sums.iter()
+        .zip(reached.iter())
+        .map(|(sum, reached_samples)| {
+            let average_cost = if *reached_samples > 0 { sum / *reached_samples as f64 } else { f64::INFINITY };
+            let closeness = if average_cost > 0.0 { 1.0 / average_cost } else { 0.0 };
+            Accessibility {average_cost, reached_samples: *reached_samples, closeness, heat: 0.0}
+        })
+        .collect();
+    let max_closeness = result.iter().map(|accessibility| accessibility.closeness).fold(0.0, f64::max);
+    if max_closeness.is_finite() && max_closeness > 0.0 {
+        result.iter_mut().for_each(|accessibility| accessibility.heat = accessibility.closeness / max_closeness);
+    }
+    Ok(result)
+}
+
+/// Effect of losing single connection of a route, see `route_robustness`.
+///
+/// * `edge_id` - connection of the route
+/// * `detour_cost` - cost of the cheapest route avoiding the connection, None when there is none
+/// * `cost_increase` - `detour_cost` minus cost of the route, infinite when there is no detour
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EdgeSensitivity {
+    pub edge_id: EdgeId,
+    pub detour_cost: Option<f64>,
+    pub cost_increase: f64,
+}
+
+/// Returns EdgeSensitivity of every connection of given Path, in travel order.
+///
+/// # Arguments:
+/// * `path` which is &Path - route found with connection costs of VertexBuffer, like from `dijkstra::find_path`
+/// * `vertex` which is &VertexBuffer - graph the route was found on
+///
+/// # Remarks:
+///
+/// Every connection is removed on its own (single-link failure) and the route between the same
+/// nodes is searched again, so the cost is one point-to-point search per connection. Parallel
+/// connections between the same nodes stay usable, so removing one of them costs nothing.
+///
+/// ```
+/// use path_navigator::analysis::route_robustness;
+/// use path_navigator::components::*;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::dijkstra::find_path;
+/// use path_navigator::vertex::VertexBuffer;
+///
+/// let connections = vec![
+///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+///     SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 2.0)),
+///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(1.0, 1.0)),
+///     SphereConnection::new(SpherePoint::new(1.0, 1.0), SpherePoint::new(0.0, 1.0)),
+/// ];
+/// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+/// let path = find_path(&SpherePoint::new(0.0, 0.0), &SpherePoint::new(0.0, 2.0), &vertex).unwrap();
+/// let sensitivity = route_robustness(&path, &vertex);
+/// assert!(sensitivity[0].cost_increase > 0.0);
+/// assert_eq!(sensitivity[1].detour_cost, None);
+/// ```
+pub fn route_robustness(path: &Path, vertex: &VertexBuffer) -> Vec<EdgeSensitivity> {
+    let (start_index, finish_index) = match (path.vertex_indices.first(), path.vertex_indices.last()) {
+        (Some(start_index), Some(finish_index)) => (*start_index, *finish_index),
+        _ => return Vec::new(),
+    };
+    let cost = path.cost();
+    path.edge_ids.iter()
+        .map(|removed| {
+            let detour = search_edge_ids(start_index, finish_index, vertex, |_, _, graph| {
+                if from_node_index(graph.edge_id) == *removed { None } else { Some(graph.cost) }
+            });
+            let detour_cost = detour
+                .and_then(|edge_ids| Path::from_edge_ids(start_index, edge_ids, vertex))
+                .map(|detour| detour.cost());
+            let cost_increase = detour_cost.map_or(f64::INFINITY, |detour_cost| detour_cost - cost);
+            EdgeSensitivity {edge_id: *removed, detour_cost, cost_increase}
+        })
+        .collect()
+}
+
+/// Options of `route_diversity`.
+///
+/// * `stretch` - share above the optimal cost routes may cost, 0.25 by default, so routes up to 25 % costlier count
+/// * `samples` - number of penalty sampling searches, 30 by default
+/// * `penalty` - share of connection cost added for every distinct route already using it, 1.0 by default
+/// * `noise` - connection costs are scaled by random factors from 1.0 to `1.0 + noise` in every sample, 0.3 by default
+/// * `max_similarity` - routes with `Path::similarity` above this value to a route found earlier are the
+///   same route, 0.8 by default
+/// * `seed` - seed of random factors, the same seed gives the same result
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiversityOptions {
+    pub stretch: f64,
+    pub samples: usize,
+    pub penalty: f64,
+    pub noise: f64,
+    pub max_similarity: f64,
+    pub seed: u64,
+}
+
+impl Default for DiversityOptions {
+    fn default() -> Self {
+        Self {stretch: 0.25, samples: 30, penalty: 1.0, noise: 0.3, max_similarity: 0.8, seed: 0}
+    }
+}
+
+/// Result of `route_diversity`.
+///
+/// * `routes` - meaningfully distinct routes within stretch of the optimum, the optimal one first
+/// * `score` - effective number of distinct routes: every route adds the share of it not shared with
+///   the most similar earlier route, so disjoint routes add 1.0 each and the score is at least 1.0
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteDiversity {
+    pub routes: Vec<Path>,
+    pub score: f64,
+}
+
+/// Estimates how many meaningfully distinct routes between two points cost at most `stretch` above the optimum,
+/// a measure of redundancy between two locations.
+///
+/// # Arguments:
+/// * `start` which is &SpherePoint - start point, snapped to the closest point on VertexBuffer
+/// * `finish` which is &SpherePoint - finish point, snapped to the closest point on VertexBuffer
+/// * `vertex` which is &VertexBuffer - precalculated vertex for avaliable paths on given geometry
+/// * `options` which is &DiversityOptions - stretch, sampling and distinctness of routes
+///
+/// # Remarks:
+///
+/// Every sample searches the optimal route on costs penalized on connections of routes found so far and
+/// randomly perturbed, then the route is accepted when its real cost is within stretch and it is not too
+/// similar to any accepted route. Sampling explores a few alternatives around the accepted ones, it is an
+/// estimate and rarely finds all distinct routes of large graphs.
+///
+/// Returns None when both points snap to the same node or finish is not reachable. Fails with `InvalidParameter`
+/// for negative or not finite stretch, penalty or noise and `max_similarity` outside of [0.0, 1.0].
+///
+/// ```
+/// use path_navigator::analysis::*;
+/// use path_navigator::components::*;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::vertex::VertexBuffer;
+///
+/// let connections = vec![
+///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.1, 1.0)),
+///     SphereConnection::new(SpherePoint::new(0.1, 1.0), SpherePoint::new(0.0, 2.0)),
+///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(-0.1, 1.0)),
+///     SphereConnection::new(SpherePoint::new(-0.1, 1.0), SpherePoint::new(0.0, 2.0)),
+/// ];
+/// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+/// let (start, finish) = (SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 2.0));
+/// let diversity = route_diversity(&start, &finish, &vertex, &DiversityOptions::default()).unwrap().unwrap();
+/// assert_eq!(diversity.routes.len(), 2);
+/// assert_eq!(diversity.score, 2.0);
+/// ```
+pub fn route_diversity(start: &SpherePoint, finish: &SpherePoint, vertex: &VertexBuffer, options: &DiversityOptions)
+-> Result<Option<RouteDiversity>> {
+    let is_invalid = |value: f64| !value.is_finite() || value < 0.0;
+    if is_invalid(options.stretch) || is_invalid(options.penalty) || is_invalid(options.noise)
+        || !(0.0..=1.0).contains(&options.max_similarity) {
+        return Err(Error::from_kind(ErrorKind::InvalidParameter));
+    }
+    let (start_index, finish_index) = match snap_query(start, finish, vertex) {
+        Some(query) => query,
+        None => return Ok(None),
+    };
+    let optimum = match search_edge_ids(start_index, finish_index, vertex, |_, _, graph| Some(graph.cost))
+        .and_then(|edge_ids| Path::from_edge_ids(start_index, edge_ids, vertex)) {
+        Some(optimum) => optimum,
+        None => return Ok(None),
+    };
+    let max_cost = optimum.cost() * (1.0 + options.stretch);
+    let mut uses: Vec<usize> = vec![0; vertex.edge_count()];
+    optimum.edge_ids.iter().for_each(|edge_id| uses[*edge_id] += 1);
+    let mut routes: Vec<Path> = vec![optimum];
+    let mut score = 1.0;
+    let mut rng = SplitMix64::new(options.seed);
+    for _ in 0..options.samples {
+        let factors: Vec<f64> = (0..vertex.edge_count()).map(|_| 1.0 + options.noise * rng.next_f64()).collect();
+        let sample = search_edge_ids(start_index, finish_index, vertex, |_, _, graph| {
+            Some(graph.cost * factors[from_node_index(graph.edge_id)] * (1.0 + options.penalty * uses[from_node_index(graph.edge_id)] as f64))
+        }).and_then(|edge_ids| Path::from_edge_ids(start_index, edge_ids, vertex));
+        let route = match sample {
+            Some(route) if route.cost() <= max_cost => route,
+            _ => continue,
+        };
+        let closest = routes.iter().map(|accepted| route.similarity(accepted)).fold(0.0, f64::max);
+        if closest <= options.max_similarity {
+            route.edge_ids.iter().for_each(|edge_id| uses[*edge_id] += 1);
+            score += 1.0 - closest;
+            routes.push(route);
+        }
+    }
+    Ok(Some(RouteDiversity {routes, score}))
+}
+
+/// Transitive closure of VertexBuffer, `reachable[from][to]` is true when `to` can be reached from `from`.
+/// Every node reaches itself.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReachabilityMatrix {
+    pub reachable: Vec<Vec<bool>>,
+}
+
+impl ReachabilityMatrix {
+    /// Returns true when `to` can be reached from `from`, false for unknown nodes.
+    pub fn is_reachable(&self, from: usize, to: usize) -> bool {
+        self.reachable.get(from).and_then(|row| row.get(to)).copied().unwrap_or(false)
+    }
+
+    /// Returns true when every node can be reached from every other one.
+    pub fn all_reachable(&self) -> bool {
+        self.reachable.iter().all(|row| row.iter().all(|reachable| *reachable))
+    }
+
+    /// Returns pairs of nodes `(from, to)` where `to` cannot be reached from `from`, in ascending order.
+    pub fn unreachable_pairs(&self) -> Vec<(usize, usize)> {
+        self.reachable.iter().enumerate()
+            .flat_map(|(from, row)| row.iter().enumerate()
+                .filter(|(_, reachable)| !**reachable)
+                .map(move |(to, _)| (from, to)))
+            .collect()
+    }
+}
+
+/// Returns ReachabilityMatrix of VertexBuffer with at most `max_nodes` nodes, meant for validating
+/// connectivity of curated networks in test suites. The matrix takes `len()` squared booleans and one
+/// traversal from every node, hence the limit. Fails with `DataItemIncorrect` for larger graphs.
+///
+/// ```
+/// use path_navigator::analysis::reachability_matrix;
+/// use path_navigator::components::*;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::vertex::VertexBuffer;
+///
+/// let connections = vec![
+///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+///     SphereConnection::new(SpherePoint::new(5.0, 5.0), SpherePoint::new(5.0, 6.0)),
+/// ];
+/// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+/// let matrix = reachability_matrix(&vertex, 200).unwrap();
+/// assert!(matrix.is_reachable(0, 1));
+/// assert!(!matrix.all_reachable());
+/// assert_eq!(matrix.unreachable_pairs().len(), 8);
+/// ```
+pub fn reachability_matrix(vertex: &VertexBuffer, max_nodes: usize) -> Result<ReachabilityMatrix> {
+    if vertex.len() > max_nodes {
+        return Err(Error::from_kind(ErrorKind::DataItemIncorrect));
+    }
+    let reachable = (0..vertex.len())
+        .map(|from| {
+            let mut row: Vec<bool> = vec![false; vertex.len()];
+            let mut stack: Vec<usize> = vec![from];
+            row[from] = true;
+            while let Some(node) = stack.pop() {
+                for graph in &vertex.vector[node].graphs {
+                    let child = from_node_index(graph.vertex_index);
+                    if !row[child] {
+                        row[child] = true;
+                        stack.push(child);
+                    }
+                }
+            }
+            row
+        })
+        .collect();
+    Ok(ReachabilityMatrix {reachable})
+}
+
+/// Bucket of cost histogram, holding connections with cost from `lower` up to, but excluding, `upper`.
+/// The last bucket includes `upper` as well.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CostBucket {
+    pub lower: f64,
+    pub upper: f64,
+    pub count: usize,
+}
+
+/// Distribution of connection costs of VertexBuffer, see `cost_statistics`. Percentiles are taken by
+/// nearest rank, so they are costs of actual connections, `median` being the lower one for even counts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CostStatistics {
+    pub count: usize,
+    pub min: f64,
+    pub median: f64,
+    pub p95: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub buckets: Vec<CostBucket>,
+}
+
+/// Returns distribution of connection costs with histogram of `bucket_count` buckets of equal width
+/// between the lowest and the highest cost, so suspicious outliers, like connections of zero or huge
+/// cost from data errors, stand out before routing. Every connection is counted once, with cost in
+/// the direction it was appended.
+///
+/// Fails with `InvalidParameter` for zero buckets and with `DataItemIncomplete` for VertexBuffer
+/// without connections.
+///
+/// ```
+/// use path_navigator::analysis::cost_statistics;
+/// use path_navigator::components::*;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::vertex::VertexBuffer;
+///
+/// let connections: Vec<SphereConnection> = (0..20)
+///     .map(|i| SphereConnection::new(SpherePoint::new(0.0, i as f64), SpherePoint::new(0.0, i as f64 + 1.0)))
+///     .collect();
+/// let mut vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+/// vertex.update_costs(&[(7, 5000.0)]).unwrap();
+/// let statistics = cost_statistics(&vertex, 10).unwrap();
+/// assert_eq!(statistics.max, 5000.0);
+/// assert_eq!(statistics.buckets[0].count, 19);
+/// assert_eq!(statistics.buckets[9].count, 1);
+/// ```
+pub fn cost_statistics(vertex: &VertexBuffer, bucket_count: usize) -> Result<CostStatistics> {
+    if bucket_count == 0 {
+        return Err(Error::from_kind(ErrorKind::InvalidParameter));
+    }
+    let mut costs: Vec<f64> = (0..vertex.edge_count()).filter_map(|edge_id| vertex.edge_cost(edge_id)).collect();
+    if costs.is_empty() {
+        return Err(Error::from_kind(ErrorKind::DataItemIncomplete));
+    }
+    costs.sort_by(|a, b| a.total_cmp(b));
+    let count = costs.len();
+    let percentile = |share: f64| costs[((share * count as f64).ceil() as usize).clamp(1, count) - 1];
+    let (min, max) = (costs[0], costs[count - 1]);
+    let width = (max - min) / bucket_count as f64;
+    let mut buckets: Vec<CostBucket> = (0..bucket_count)
+        .map(|bucket| CostBucket {
+            lower: min + width * bucket as f64,
+            upper: if bucket + 1 == bucket_count { max } else { min + width * (bucket + 1) as f64 },
+            count: 0,
+        })
+        .collect();
+    for cost in &costs {
+        let bucket = if width > 0.0 { (((cost - min) / width) as usize).min(bucket_count - 1) } else { 0 };
+        buckets[bucket].count += 1;
+    }
+    Ok(CostStatistics {
+        count,
+        min,
+        median: percentile(0.5),
+        p95: percentile(0.95),
+        max,
+        mean: costs.iter().sum::<f64>() / count as f64,
+        buckets,
+    })
+}
+
+/// Returns `sample_size` distinct node indices drawn uniformly, or all nodes for larger samples.
+pub(crate) fn sample_nodes(count: usize, sample_size: usize, seed: u64) -> Vec<usize> {
+    let mut nodes: Vec<usize> = (0..count).collect();
+    if sample_size >= count {
+        return nodes;
+    }
+    let mut rng = SplitMix64::new(seed);
+    for i in 0..sample_size {
+        let j = i + rng.below(count - i);
+        nodes.swap(i, j);
+    }
+    nodes.truncate(sample_size);
+    nodes
+}
+
+#[cfg(test)]
+mod analysis_tests {
+    use super::*;
+    use crate::components::{SphereConnection, SpherePoint};
+    use crate::data::{get_radius_km, CelestialObject};
+    use crate::vertex::{BuildOptions, EdgePolicy};
+
+    #[test]
+    fn test_closeness_centrality_of_line() {
+        // given: 0 - 1 - 2 - 3 - 4 with equal spacing
+        let connections: Vec<SphereConnection> = (0..4)
+            .map(|i| SphereConnection::new(SpherePoint::new(0.0, i as f64), SpherePoint::new(0.0, i as f64 + 1.0)))
+            .collect();
+        let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        // when
+        let exact = closeness_centrality(&vertex, 10, 3).unwrap();
+        let sampled = closeness_centrality(&vertex, 2, 3).unwrap();
+        // then
+        assert_eq!(exact[2].heat, 1.0);
+        assert!(exact[0].heat < exact[1].heat);
+        assert!(relative_eq!(exact[0].average_cost, exact[4].average_cost, max_relative = 1e-12));
+        assert!(relative_eq!(exact[0].average_cost / exact[2].average_cost, 10.0 / 6.0, max_relative = 1e-9));
+        assert!(exact.iter().all(|accessibility| accessibility.reached_samples == 4));
+        assert_eq!(sampled.iter().map(|accessibility| accessibility.reached_samples).sum::<usize>(), 8);
+        assert!(closeness_centrality(&vertex, 0, 3).is_err());
+    }
+
+    #[test]
+    fn test_cost_statistics() {
+        // given: costs 1 to 100 on a line of connections
+        let connections: Vec<SphereConnection> = (0..100)
+            .map(|i| SphereConnection::new(SpherePoint::new(0.0, i as f64 * 0.1), SpherePoint::new(0.0, (i + 1) as f64 * 0.1)))
+            .collect();
+        let mut vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        let updates: Vec<(EdgeId, f64)> = (0..100).map(|edge_id| (edge_id, (100 - edge_id) as f64)).collect();
+        vertex.update_costs(&updates).unwrap();
+        // when
+        let statistics = cost_statistics(&vertex, 4).unwrap();
+        vertex.update_costs(&(0..100).map(|edge_id| (edge_id, 2.0)).collect::<Vec<(EdgeId, f64)>>()).unwrap();
+        let uniform = cost_statistics(&vertex, 3).unwrap();
+        // then
+        assert_eq!((statistics.count, statistics.min, statistics.max), (100, 1.0, 100.0));
+        assert_eq!((statistics.median, statistics.p95), (50.0, 95.0));
+        assert_eq!(statistics.mean, 50.5);
+        assert_eq!(statistics.buckets.iter().map(|bucket| bucket.count).collect::<Vec<usize>>(), vec![25, 25, 25, 25]);
+        assert_eq!((statistics.buckets[0].lower, statistics.buckets[3].upper), (1.0, 100.0));
+        assert_eq!(uniform.buckets.iter().map(|bucket| bucket.count).collect::<Vec<usize>>(), vec![100, 0, 0]);
+        assert_eq!((uniform.median, uniform.p95), (2.0, 2.0));
+        assert!(cost_statistics(&vertex, 0).is_err());
+    }
+
+    #[test]
+    fn test_reachability_matrix() {
+        // given: line 0 - 1 - 2 and separate connection 3 - 4
+        let connections = vec![
+            SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+            SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 2.0)),
+            SphereConnection::new(SpherePoint::new(5.0, 5.0), SpherePoint::new(5.0, 6.0)),
+        ];
+        let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        // when
+        let matrix = reachability_matrix(&vertex, 5).unwrap();
+        // then
+        assert!(matrix.is_reachable(0, 2) && matrix.is_reachable(2, 0) && matrix.is_reachable(4, 4));
+        assert!(!matrix.is_reachable(1, 3) && !matrix.is_reachable(0, 42));
+        assert_eq!(&matrix.unreachable_pairs()[..3], &[(0, 3), (0, 4), (1, 3)]);
+        assert_eq!(matrix.unreachable_pairs().len(), 12);
+        assert!(reachability_matrix(&vertex, 4).is_err());
+    }
+
+    #[test]
+    fn test_route_robustness() {
+        // given: route 0 - 1 - 2 with a long bypass 0 - 3 - 2 and a parallel connection 1 - 2
+        let first = SpherePoint::new(0.0, 0.0);
+        let second = SpherePoint::new(0.0, 1.0);
+        let third = SpherePoint::new(0.0, 2.0);
+        let bypass = SpherePoint::new(2.0, 1.0);
+        let connections = vec![
+            SphereConnection::new(first.clone(), second.clone()),
+            SphereConnection::new(second.clone(), third.clone()),
+            SphereConnection::new(first.clone(), bypass.clone()),
+            SphereConnection::new(bypass.clone(), third.clone()),
+            SphereConnection::new(third.clone(), second),
+        ];
+        let options = BuildOptions {duplicates: EdgePolicy::KeepAll, ..BuildOptions::default()};
+        let vertex = VertexBuffer::with_options(connections, CelestialObject::EARTH, &options).unwrap();
+        let path = crate::dijkstra::find_path(&first, &third, &vertex).unwrap();
+        // when
+        let sensitivity = route_robustness(&path, &vertex);
+        // then
+        let radius = get_radius_km(&CelestialObject::EARTH);
+        let bypass_cost = SphereConnection::new(first, bypass.clone()).cost(radius)
+            + SphereConnection::new(bypass, third).cost(radius);
+        assert_eq!(sensitivity.iter().map(|edge| edge.edge_id).collect::<Vec<EdgeId>>(), path.edge_ids);
+        assert!(relative_eq!(sensitivity[0].detour_cost.unwrap(), bypass_cost, max_relative = 1e-6));
+        assert!(relative_eq!(sensitivity[0].cost_increase, bypass_cost - path.cost(), max_relative = 1e-6));
+        assert!(sensitivity[1].cost_increase.abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_route_diversity_of_ladder() {
+        // given: three parallel corridors joined at both ends, the outer one much longer, and a bridge
+        // with a single corridor
+        let point = |lat: f64, lng: f64| SpherePoint::new(lat, lng);
+        let connections = vec![
+            SphereConnection::new(point(0.0, 0.0), point(0.0, 1.0)),
+            SphereConnection::new(point(0.0, 1.0), point(0.0, 2.0)),
+            SphereConnection::new(point(0.0, 0.0), point(0.2, 1.0)),
+            SphereConnection::new(point(0.2, 1.0), point(0.0, 2.0)),
+            SphereConnection::new(point(0.0, 0.0), point(-1.5, 1.0)),
+            SphereConnection::new(point(-1.5, 1.0), point(0.0, 2.0)),
+            SphereConnection::new(point(0.0, 2.0), point(0.0, 3.0)),
+        ];
+        let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        let options = DiversityOptions {seed: 9, ..DiversityOptions::default()};
+        // when
+        let parallel = route_diversity(&point(0.0, 0.0), &point(0.0, 2.0), &vertex, &options).unwrap().unwrap();
+        let through_bridge = route_diversity(&point(0.0, 0.0), &point(0.0, 3.0), &vertex, &options).unwrap().unwrap();
+        let bridge = route_diversity(&point(0.0, 2.0), &point(0.0, 3.0), &vertex, &options).unwrap().unwrap();
+        let relaxed = route_diversity(&point(0.0, 0.0), &point(0.0, 2.0), &vertex,
+            &DiversityOptions {stretch: 2.0, ..options.clone()}).unwrap().unwrap();
+        // then: the outer corridor is too long unless stretch allows it
+        assert_eq!(parallel.routes.len(), 2);
+        assert_eq!(parallel.routes[0].edge_ids, vec![0, 1]);
+        assert_eq!(parallel.score, 2.0);
+        assert_eq!(relaxed.routes.len(), 3);
+        assert!(through_bridge.score > 1.0 && through_bridge.score < 2.0);
+        assert_eq!((bridge.routes.len(), bridge.score), (1, 1.0));
+        assert!(route_diversity(&point(0.0, 0.0), &point(0.0, 0.0), &vertex, &options).unwrap().is_none());
+        assert!(route_diversity(&point(0.0, 0.0), &point(0.0, 2.0), &vertex,
+            &DiversityOptions {max_similarity: 1.5, ..options}).is_err());
+    }
+}
@@ -0,0 +1,166 @@
+use crate::vertex::*;
+use crate::components::SpherePoint;
+use crate::dijkstra::{snap_query, search_edge_ids, search_from_sources};
+use crate::partition::Partitioning;
+use crate::path::Path;
+use crate::hashing::from_node_index;
+
+/// # ArcFlags
+/// Preprocessed flags over partitioned VertexBuffer: every directed connection gets one flag per
+/// partition, set when the connection lies on some shortest path towards a node of that partition.
+/// Query towards a node of partition `p` then skips connections without flag `p`, which on static
+/// graphs prunes most of the search space far from the target.
+///
+/// # Remarks:
+///
+/// Flags are computed with one shortest path search towards every boundary node, run from it over
+/// `VertexBuffer::reversed` when the graph has one-way connections, and they reflect costs at build
+/// time, so they have to be rebuilt after `update_costs`.
+///
+/// ```
+/// use path_navigator::arc_flags::ArcFlags;
+/// use path_navigator::components::*;
+/// use path_navigator::dijkstra::find_path;
+/// use path_navigator::partition::partition;
+/// use path_navigator::vertex::generators::random;
+///
+/// let vertex = random(60, 3.0, &SphereBoundingBox::new(49.0, 14.0, 54.8, 24.1), 3).unwrap();
+/// let flags = ArcFlags::build(&vertex, partition(&vertex, 4).unwrap());
+/// let start = vertex.vector[0].coordinates.clone();
+/// let finish = vertex.vector[59].coordinates.clone();
+/// let path = flags.find_path(&start, &finish, &vertex).unwrap();
+/// assert!((path.cost() - find_path(&start, &finish, &vertex).unwrap().cost()).abs() < 1e-9);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArcFlags {
+    pub partitioning: Partitioning,
+    // flags[node][relation] is a bitset over partitions, in order of `graphs` of the node
+    flags: Vec<Vec<Vec<u64>>>,
+}
+
+impl ArcFlags {
+    /// Computes flags of all connections of VertexBuffer for given partitioning of its nodes.
+    pub fn build(vertex: &VertexBuffer, partitioning: Partitioning) -> Self {
+        let words = partitioning.count().div_ceil(64).max(1);
+        let mut flags: Vec<Vec<Vec<u64>>> = vertex.vector.iter()
+            .map(|node| vec![vec![0; words]; node.graphs.len()])
+            .collect();
+        let set = |flags: &mut Vec<Vec<Vec<u64>>>, node: usize, relation: usize, partition: usize| {
+            flags[node][relation][partition / 64] |= 1 << (partition % 64);
+        };
+        for (node, vertex_point) in vertex.vector.iter().enumerate() {
+            for (relation, graph) in vertex_point.graphs.iter().enumerate() {
+                let partition = partitioning.partitions[from_node_index(graph.vertex_index)];
+                if partitioning.partitions[node] == partition {
+                    set(&mut flags, node, relation, partition);
+                }
+            }
+        }
+        // costs towards a node are costs from it over the reverse graph, which is the graph itself when two-way
+        let reversed = if vertex.is_directed() { Some(vertex.reversed()) } else { None };
+        for (partition, boundary_nodes) in partitioning.boundary_nodes.iter().enumerate() {
+            for boundary_node in boundary_nodes {
+                let costs = search_from_sources(&[*boundary_node], reversed.as_ref().unwrap_or(vertex)).costs;
+                for (node, vertex_point) in vertex.vector.iter().enumerate() {
+                    let node_cost = match costs[node] {
+                        Some(cost) => cost,
+                        None => continue,
+                    };
+                    for (relation, graph) in vertex_point.graphs.iter().enumerate() {
+                        let towards = costs[from_node_index(graph.vertex_index)].map_or(f64::INFINITY, |cost| cost + graph.cost);
+                        if towards <= node_cost + node_cost * 1e-12 {
+                            set(&mut flags, node, relation, partition);
+                        }
+                    }
+                }
+            }
+        }
+        Self {partitioning, flags}
+    }
+
+    /// Returns Path which is the shortest path between two given points, see `dijkstra::find_path`.
+    /// VertexBuffer has to be the one flags were built for.
+    pub fn find_path(&self, start: &SpherePoint, finish: &SpherePoint, vertex: &VertexBuffer) -> Option<Path> {
+        let (start_index, finish_index) = snap_query(start, finish, vertex)?;
+        let target = self.partitioning.partitions[finish_index];
+        let (word, bit) = (target / 64, 1 << (target % 64));
+        let edge_ids = search_edge_ids(start_index, finish_index, vertex, |node, relation, graph| {
+            if self.flags[node][relation][word] & bit == 0 { None } else { Some(graph.cost) }
+        })?;
+        Path::from_edge_ids(start_index, edge_ids, vertex)
+    }
+
+    /// Returns share of directed connections flagged for given partition, 1.0 means no pruning.
+    pub fn flagged_share(&self, partition: usize) -> f64 {
+        let (word, bit) = (partition / 64, 1u64 << (partition % 64));
+        let arcs = self.flags.iter().flatten();
+        let total = arcs.clone().count();
+        if total == 0 {
+            return 0.0;
+        }
+        arcs.filter(|flags| flags.get(word).is_some_and(|bits| bits & bit != 0)).count() as f64 / total as f64
+    }
+}
+
+#[cfg(test)]
+mod arc_flags_tests {
+    use super::*;
+    use crate::components::SphereBoundingBox;
+    use crate::data::CelestialObject;
+    use crate::dijkstra::find_path;
+    use crate::partition::partition;
+    use crate::vertex::generators::{k_nearest, random};
+
+    #[test]
+    fn test_arc_flags_keep_shortest_paths() {
+        // given
+        let points: Vec<SpherePoint> = random(150, 2.0, &SphereBoundingBox::new(40.0, -10.0, 60.0, 30.0), 5).unwrap()
+            .vector.into_iter()
+            .map(|node| node.coordinates)
+            .collect();
+        let vertex = k_nearest(&points, 3, CelestialObject::EARTH).unwrap();
+        // when
+        let flags = ArcFlags::build(&vertex, partition(&vertex, 8).unwrap());
+        // then
+        for (from, to) in [(0, 149), (10, 75), (42, 7), (120, 121)] {
+            let start = &vertex.vector[from].coordinates;
+            let finish = &vertex.vector[to].coordinates;
+            let expected = find_path(start, finish, &vertex).unwrap();
+            let actual = flags.find_path(start, finish, &vertex).unwrap();
+            assert_eq!(actual.vertex_indices.last(), Some(&to));
+            assert!(relative_eq!(actual.cost(), expected.cost(), max_relative = 1e-9));
+        }
+        assert!((0..8).all(|partition| flags.flagged_share(partition) < 0.9));
+    }
+
+    #[test]
+    fn test_arc_flags_on_one_way_graphs() {
+        // given: the ring split into two partitions and one-way random network
+        let ring = crate::fixtures::one_way_ring();
+        let points: Vec<SpherePoint> = random(120, 2.0, &SphereBoundingBox::new(40.0, -10.0, 60.0, 30.0), 8).unwrap()
+            .vector.into_iter()
+            .map(|node| node.coordinates)
+            .collect();
+        let directed = VertexBuffer::new_directed(k_nearest(&points, 3, CelestialObject::EARTH).unwrap().to_connections(),
+            CelestialObject::EARTH).unwrap();
+        // when
+        let ring_flags = ArcFlags::build(&ring, partition(&ring, 2).unwrap());
+        let flags = ArcFlags::build(&directed, partition(&directed, 6).unwrap());
+        // then
+        for (from, to) in [(1, 0), (2, 1), (3, 2), (0, 3)] {
+            let path = ring_flags.find_path(&ring.vector[from].coordinates, &ring.vector[to].coordinates, &ring).unwrap();
+            assert_eq!(path.len(), 3);
+        }
+        for from in (0..directed.len()).step_by(11) {
+            for to in (0..directed.len()).step_by(13) {
+                let (start, finish) = (&directed.vector[from].coordinates, &directed.vector[to].coordinates);
+                let expected = find_path(start, finish, &directed);
+                let actual = flags.find_path(start, finish, &directed);
+                assert_eq!(actual.is_some(), expected.is_some());
+                if let (Some(actual), Some(expected)) = (actual, expected) {
+                    assert!(relative_eq!(actual.cost(), expected.cost(), max_relative = 1e-9));
+                }
+            }
+        }
+    }
+}
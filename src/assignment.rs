@@ -0,0 +1,144 @@
+use crate::errors::*;
+use crate::vertex::VertexBuffer;
+use crate::components::SpherePoint;
+use crate::dijkstra::{get_closest_point, search_edge_ids};
+use crate::path::Path;
+use crate::hashing::from_node_index;
+
+/// Options of `assign_routes`.
+///
+/// * `iterations` - maximal number of rerouting rounds, 5 by default
+/// * `penalty` - share of connection cost added for every route already using it, 0.1 by default,
+///   so a connection used by 10 routes costs twice as much for the next one
+#[derive(Debug, Clone, PartialEq)]
+pub struct AssignmentOptions {
+    pub iterations: usize,
+    pub penalty: f64,
+}
+
+impl Default for AssignmentOptions {
+    fn default() -> Self {
+        Self {iterations: 5, penalty: 0.1}
+    }
+}
+
+/// Result of `assign_routes`.
+///
+/// * `paths` - route of every origin-destination pair in order of pairs, None when it is unreachable;
+///   costs of paths are connection costs of VertexBuffer without congestion penalties
+/// * `flows` - number of routes using each connection, indexed by EdgeId
+/// * `iterations` - number of rounds run, lower than `AssignmentOptions::iterations` when routes settled earlier
+#[derive(Debug, Clone, PartialEq)]
+pub struct Assignment {
+    pub paths: Vec<Option<Path>>,
+    pub flows: Vec<usize>,
+    pub iterations: usize,
+}
+
+/// Routes many origin-destination pairs at once spreading them over the network, a simple
+/// iterative traffic assignment.
+///
+/// # Arguments:
+/// * `pairs` which is &[(SpherePoint, SpherePoint)] - origin and destination of every route, snapped to closest points
+/// * `vertex` which is &VertexBuffer - precalculated vertex for avaliable paths on given geometry
+/// * `options` which is &AssignmentOptions - number of rounds and congestion penalty
+///
+/// # Remarks:
+///
+/// In every round each route is searched again with its own load removed and cost of every connection
+/// raised by `penalty` times number of other routes using it, then its load is put back. The first round
+/// assigns routes incrementally on growing load, so later rounds only fix routes pushed onto crowded
+/// corridors. Rounds stop when no route changes.
+///
+/// Fails with `InvalidParameter` for zero iterations and negative or not finite penalty.
+///
+/// ```
+/// use path_navigator::assignment::*;
+/// use path_navigator::components::*;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::vertex::VertexBuffer;
+///
+/// let connections = vec![
+///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 2.0)),
+///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.1, 1.0)),
+///     SphereConnection::new(SpherePoint::new(0.1, 1.0), SpherePoint::new(0.0, 2.0)),
+/// ];
+/// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+/// let pair = (SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 2.0));
+/// let assignment = assign_routes(&vec![pair; 4], &vertex, &AssignmentOptions::default()).unwrap();
+/// assert_eq!(assignment.flows[0], 2);
+/// assert_eq!(assignment.flows[1], 2);
+/// ```
+pub fn assign_routes(pairs: &[(SpherePoint, SpherePoint)], vertex: &VertexBuffer, options: &AssignmentOptions)
+-> Result<Assignment> {
+    if options.iterations == 0 || !options.penalty.is_finite() || options.penalty < 0.0 {
+        return Err(Error::from_kind(ErrorKind::InvalidParameter));
+    }
+    let mut flows: Vec<usize> = vec![0; vertex.edge_count()];
+    let mut paths: Vec<Option<Path>> = vec![None; pairs.len()];
+    if vertex.is_empty() {
+        return Ok(Assignment {paths, flows, iterations: 0});
+    }
+    let queries: Vec<(usize, usize)> = pairs.iter()
+        .map(|(origin, destination)| (get_closest_point(origin, vertex), get_closest_point(destination, vertex)))
+        .collect();
+    let mut iterations = 0;
+    while iterations < options.iterations {
+        iterations += 1;
+        let mut changed = false;
+        for (pair, (start_index, finish_index)) in queries.iter().enumerate() {
+            if let Some(path) = &paths[pair] {
+                path.edge_ids.iter().for_each(|edge_id| flows[*edge_id] -= 1);
+            }
+            let edge_ids = search_edge_ids(*start_index, *finish_index, vertex, |_, _, graph| {
+                Some(graph.cost * (1.0 + options.penalty * flows[from_node_index(graph.edge_id)] as f64))
+            });
+            let path = edge_ids.and_then(|edge_ids| Path::from_edge_ids(*start_index, edge_ids, vertex));
+            if let Some(path) = &path {
+                path.edge_ids.iter().for_each(|edge_id| flows[*edge_id] += 1);
+            }
+            if iterations == 1 || path.as_ref().map(|path| &path.edge_ids) != paths[pair].as_ref().map(|path| &path.edge_ids) {
+                changed = true;
+            }
+            paths[pair] = path;
+        }
+        if !changed {
+            break;
+        }
+    }
+    Ok(Assignment {paths, flows, iterations})
+}
+
+#[cfg(test)]
+mod assignment_tests {
+    use super::*;
+    use crate::components::SphereConnection;
+    use crate::data::CelestialObject;
+
+    #[test]
+    fn test_congestion_spreads_routes() {
+        // given: a direct corridor 0 - 1 and bypasses through 2 and through much longer 3
+        let origin = SpherePoint::new(0.0, 0.0);
+        let destination = SpherePoint::new(0.0, 2.0);
+        let connections = vec![
+            SphereConnection::new(origin.clone(), destination.clone()),
+            SphereConnection::new(origin.clone(), SpherePoint::new(0.1, 1.0)),
+            SphereConnection::new(SpherePoint::new(0.1, 1.0), destination.clone()),
+            SphereConnection::new(origin.clone(), SpherePoint::new(-0.8, 1.0)),
+            SphereConnection::new(SpherePoint::new(-0.8, 1.0), destination.clone()),
+        ];
+        let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        let pairs = vec![(origin, destination); 30];
+        // when
+        let free_flow = assign_routes(&pairs, &vertex, &AssignmentOptions {penalty: 0.0, ..AssignmentOptions::default()}).unwrap();
+        let congested = assign_routes(&pairs, &vertex, &AssignmentOptions::default()).unwrap();
+        // then
+        assert_eq!(free_flow.flows[0], 30);
+        assert_eq!(free_flow.iterations, 2);
+        assert!(congested.flows[0] < 30);
+        assert!(congested.flows[1] > congested.flows[3] && congested.flows[3] > 0);
+        assert_eq!(congested.flows[1], congested.flows[2]);
+        assert_eq!(congested.paths.iter().flatten().count(), 30);
+        assert!(assign_routes(&pairs, &vertex, &AssignmentOptions {iterations: 0, ..AssignmentOptions::default()}).is_err());
+    }
+}
@@ -0,0 +1,86 @@
+use crate::vertex::VertexBuffer;
+use crate::components::{SphereConnection, SpherePoint};
+use crate::search::{find_path_with, AStarSearch};
+
+/// Returns Vec<SphereConnection> which is the shortest path between two given points, like
+/// `dijkstra::find_shortest_path`, found by A* search.
+///
+/// # Arguments:
+/// * `start` which is &SpherePoint - start point, snapped to the closest point on VertexBuffer
+/// * `finish` which is &SpherePoint - finish point, snapped to the closest point on VertexBuffer
+/// * `vertex` which is &VertexBuffer - precalculated vertex for avaliable paths on given geometry
+///
+/// # Remarks:
+///
+/// Search runs `search::AStarSearch`, its frontier is ordered by cost from start plus great-circle
+/// distance to finish, so nodes lying away from finish are settled late or never. On long road-style graphs this settles a fraction
+/// of the nodes plain Dijkstra does. The estimate never exceeds the remaining cost while connection
+/// costs are not below their great-circle lengths in kilometers, as they are when VertexBuffer is
+/// built from connections. For costs in other units, or lowered by `update_costs`, the result may
+/// not be the cheapest path, use `dijkstra::find_shortest_path` then.
+///
+/// Returns None when finish is not reachable or both points snap to the same node.
+///
+/// ```
+/// use path_navigator::astar::find_shortest_path_astar;
+/// use path_navigator::components::*;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::dijkstra::find_shortest_path;
+/// use path_navigator::vertex::VertexBuffer;
+///
+/// let connections = vec![
+///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+///     SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 2.0)),
+///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(3.0, 1.0)),
+///     SphereConnection::new(SpherePoint::new(3.0, 1.0), SpherePoint::new(0.0, 2.0)),
+/// ];
+/// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+/// let (start, finish) = (SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 2.0));
+/// let path = find_shortest_path_astar(&start, &finish, &vertex).unwrap();
+/// assert_eq!(path.len(), 2);
+/// assert_eq!(Some(path), find_shortest_path(&start, &finish, &vertex));
+/// ```
+pub fn find_shortest_path_astar(start: &SpherePoint, finish: &SpherePoint, vertex: &VertexBuffer)
+-> Option<Vec<SphereConnection>> {
+    find_path_with(&mut AStarSearch::new(), start, finish, vertex).map(|path| path.connections)
+}
+
+#[cfg(test)]
+mod astar_tests {
+    use super::*;
+    use crate::components::SphereBoundingBox;
+    use crate::data::{get_radius_km, CelestialObject};
+    use crate::dijkstra::{find_path, search_targets};
+
+    #[test]
+    fn test_astar_matches_dijkstra_settling_less() {
+        // given: random network with an island far away
+        let bbox = SphereBoundingBox::new(40.0, 10.0, 44.0, 14.0);
+        let mut connections = crate::vertex::generators::random(2000, 3.0, &bbox, 3).unwrap().to_connections();
+        connections.push(SphereConnection::new(SpherePoint::new(-30.0, 100.0), SpherePoint::new(-30.0, 101.0)));
+        let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        let start = vertex.vector[0].coordinates.clone();
+        let mut rng = crate::rng::SplitMix64::new(8);
+        let (mut settled, mut dijkstra_settled) = (0, 0);
+        for _ in 0..20 {
+            let finish_index = 1 + rng.below(vertex.len() - 3);
+            let finish = vertex.vector[finish_index].coordinates.clone();
+            // when
+            let path = find_shortest_path_astar(&start, &finish, &vertex);
+            let mut search = AStarSearch::new();
+            find_path_with(&mut search, &start, &finish, &vertex);
+            settled += search.settled_count();
+            dijkstra_settled += search_targets(&[0], &[finish_index], &vertex).settled.len();
+            // then
+            let expected = find_path(&start, &finish, &vertex);
+            assert_eq!(path.is_some(), expected.is_some());
+            if let (Some(path), Some(expected)) = (path, expected) {
+                let radius = get_radius_km(&vertex.celestial_object);
+                let cost: f64 = path.iter().map(|connection| connection.cost(radius)).sum();
+                assert!(relative_eq!(cost, expected.cost(), max_relative = 1e-9));
+            }
+        }
+        assert!(settled < dijkstra_settled);
+        assert!(find_shortest_path_astar(&start, &SpherePoint::new(-30.0, 100.0), &vertex).is_none());
+    }
+}
@@ -0,0 +1,91 @@
+use crate::errors::*;
+use crate::vertex::VertexBuffer;
+use crate::components::SpherePoint;
+use crate::cost::{find_path_with_model, CostModel};
+use crate::dijkstra::{snap_query, search_edge_ids};
+use crate::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Sets shared flag when dropped, so blocking search knows nobody waits for its result.
+struct CancelOnDrop(Arc<AtomicBool>);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Returns Path which is the shortest path between two given points, see `dijkstra::find_path`,
+/// searched on tokio blocking task pool so async services do not block their executor.
+///
+/// # Remarks:
+///
+/// Dropping the future cancels the query: the search running on the blocking pool stops relaxing
+/// connections and finishes early. Fails with `Aborted` when the blocking task could not complete,
+/// like on runtime shutdown.
+///
+/// ```
+/// use path_navigator::async_api::route;
+/// use path_navigator::components::*;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::vertex::VertexBuffer;
+/// use std::sync::Arc;
+///
+/// let connections = vec![SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0))];
+/// let vertex = Arc::new(VertexBuffer::new(connections, CelestialObject::EARTH).unwrap());
+/// let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+/// let path = runtime.block_on(route(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0), vertex)).unwrap();
+/// assert_eq!(path.unwrap().vertex_indices, vec![0, 1]);
+/// ```
+pub async fn route(start: SpherePoint, finish: SpherePoint, vertex: Arc<VertexBuffer>) -> Result<Option<Path>> {
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let _guard = CancelOnDrop(cancelled.clone());
+    tokio::task::spawn_blocking(move || {
+        let (start_index, finish_index) = snap_query(&start, &finish, &vertex)?;
+        let edge_ids = search_edge_ids(start_index, finish_index, &vertex, |_, _, graph| {
+            if cancelled.load(Ordering::Relaxed) { None } else { Some(graph.cost) }
+        })?;
+        Path::from_edge_ids(start_index, edge_ids, &vertex)
+    })
+    .await
+    .map_err(|_| Error::from_kind(ErrorKind::Aborted))
+}
+
+/// Async wrapper of `cost::find_path_with_model`, run on tokio blocking task pool, see `route`.
+/// Dropping the future detaches the search, which still runs to its end.
+pub async fn route_with_model(start: SpherePoint, finish: SpherePoint, vertex: Arc<VertexBuffer>, model: CostModel)
+-> Result<Option<Path>> {
+    tokio::task::spawn_blocking(move || find_path_with_model(&start, &finish, &vertex, &model))
+        .await
+        .map_err(|_| Error::from_kind(ErrorKind::Aborted))?
+}
+
+#[cfg(test)]
+mod async_api_tests {
+    use super::*;
+    use crate::components::SphereConnection;
+    use crate::data::CelestialObject;
+
+    #[test]
+    fn test_async_routes_match_blocking_search() {
+        // given
+        let connections = vec![
+            SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+            SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 2.0)),
+        ];
+        let vertex = Arc::new(VertexBuffer::new(connections, CelestialObject::EARTH).unwrap());
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let start = SpherePoint::new(0.0, 0.0);
+        let finish = SpherePoint::new(0.0, 2.0);
+        // when
+        let path = runtime.block_on(route(start.clone(), finish.clone(), vertex.clone())).unwrap().unwrap();
+        let model_path = runtime.block_on(route_with_model(start.clone(), finish, vertex.clone(), CostModel::default()))
+            .unwrap().unwrap();
+        let same_point = runtime.block_on(route(start.clone(), start, vertex)).unwrap();
+        // then
+        assert_eq!(path.vertex_indices, vec![0, 1, 2]);
+        assert_eq!(model_path, path);
+        assert!(same_point.is_none());
+    }
+}
@@ -0,0 +1,254 @@
+use crate::errors::*;
+use crate::vertex::VertexBuffer;
+use crate::hub_labels::HubLabels;
+use crate::landmarks::Landmarks;
+use crate::spatial::SpatialIndex;
+use crate::data::get_radius_km;
+use crate::io::binary::{read_header, read_string, read_usize, write_header, write_string, write_u64};
+use std::io::{Read, Write};
+
+const MAGIC: &[u8; 4] = b"PNRB";
+const FORMAT_VERSION: u32 = 1;
+
+const GRAPH: &str = "graph";
+const HUB_LABELS: &str = "hub_labels";
+const LANDMARKS: &str = "landmarks";
+const SPATIAL_INDEX: &str = "spatial_index";
+
+/// Entry of RoutingBundle manifest, `length` is size of the section in bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BundleSection {
+    pub name: String,
+    pub length: u64,
+}
+
+/// # RoutingBundle
+/// VertexBuffer with its preprocessing artifacts in one file, so services load hub labels, landmarks
+/// and spatial index built offline instead of computing them again at startup.
+///
+/// # Remarks:
+///
+/// File starts with a manifest listing named sections and their lengths, followed by the sections
+/// in manifest order: `graph` in format of `VertexBuffer::save` and optional `hub_labels`, `landmarks`
+/// and `spatial_index` in formats of their own `save`. Loading skips sections of unknown names, so
+/// older readers accept bundles with artifacts added later. Artifacts are checked to be built for
+/// a graph of the same size and celestial object, they are not checked to be built from the same
+/// connections or costs, so rebuild them after changing the graph.
+///
+/// ```
+/// use path_navigator::bundle::RoutingBundle;
+/// use path_navigator::components::*;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::hub_labels::HubLabels;
+/// use path_navigator::vertex::VertexBuffer;
+///
+/// let connections = vec![
+///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+///     SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 2.0)),
+/// ];
+/// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+/// let bundle = RoutingBundle {hub_labels: Some(HubLabels::build(&vertex)), ..RoutingBundle::new(vertex)};
+/// let mut saved: Vec<u8> = Vec::new();
+/// bundle.save(&mut saved).unwrap();
+/// let manifest = RoutingBundle::read_manifest(&mut saved.as_slice()).unwrap();
+/// assert_eq!(manifest.iter().map(|section| section.name.as_str()).collect::<Vec<&str>>(), vec!["graph", "hub_labels"]);
+/// let loaded = RoutingBundle::load(&mut saved.as_slice()).unwrap();
+/// assert_eq!(loaded.hub_labels.unwrap().cost(0, 2), bundle.hub_labels.unwrap().cost(0, 2));
+/// ```
+#[derive(Debug, Clone)]
+pub struct RoutingBundle {
+    pub vertex: VertexBuffer,
+    pub hub_labels: Option<HubLabels>,
+    pub landmarks: Option<Landmarks>,
+    pub spatial_index: Option<SpatialIndex>,
+}
+
+impl RoutingBundle {
+    /// Creates bundle of VertexBuffer without artifacts.
+    pub fn new(vertex: VertexBuffer) -> Self {
+        Self {vertex, hub_labels: None, landmarks: None, spatial_index: None}
+    }
+
+    /// Writes manifest and sections of the graph and every present artifact.
+    /// Fails like `load` when artifacts do not belong to the graph, without writing anything.
+    pub fn save<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.check_artifacts()?;
+        let mut sections: Vec<(&str, Vec<u8>)> = Vec::new();
+        let mut graph: Vec<u8> = Vec::new();
+        self.vertex.save(&mut graph)?;
+        sections.push((GRAPH, graph));
+        if let Some(hub_labels) = &self.hub_labels {
+            let mut section: Vec<u8> = Vec::new();
+            hub_labels.save(&mut section)?;
+            sections.push((HUB_LABELS, section));
+        }
+        if let Some(landmarks) = &self.landmarks {
+            let mut section: Vec<u8> = Vec::new();
+            landmarks.save(&mut section)?;
+            sections.push((LANDMARKS, section));
+        }
+        if let Some(spatial_index) = &self.spatial_index {
+            let mut section: Vec<u8> = Vec::new();
+            spatial_index.save(&mut section)?;
+            sections.push((SPATIAL_INDEX, section));
+        }
+        write_header(writer, MAGIC, FORMAT_VERSION)?;
+        write_u64(writer, sections.len() as u64)?;
+        for (name, section) in &sections {
+            write_string(writer, name)?;
+            write_u64(writer, section.len() as u64)?;
+        }
+        for (_, section) in &sections {
+            writer.write_all(section)?;
+        }
+        Ok(())
+    }
+
+    /// Reads manifest of bundle written by `save` without reading its sections.
+    /// Fails with `DataItemIncorrect` for data in other format.
+    pub fn read_manifest<R: Read>(reader: &mut R) -> Result<Vec<BundleSection>> {
+        read_header(reader, MAGIC, FORMAT_VERSION)?;
+        let count = read_usize(reader)?;
+        (0..count)
+            .map(|_| {
+                let name = read_string(reader)?;
+                let length = read_usize(reader)? as u64;
+                Ok(BundleSection {name, length})
+            })
+            .collect()
+    }
+
+    /// Reads bundle written by `save`.
+    ///
+    /// # Remarks:
+    ///
+    /// Fails with `DataItemIncomplete` when there is no graph section, `DataItemIncorrect` for data
+    /// in other format, repeated sections or artifacts built for a graph of other size, and with
+    /// `BodyMismatch` for spatial index built for other celestial object.
+    pub fn load<R: Read>(reader: &mut R) -> Result<Self> {
+        let manifest = Self::read_manifest(reader)?;
+        let mut vertex: Option<VertexBuffer> = None;
+        let mut hub_labels: Option<HubLabels> = None;
+        let mut landmarks: Option<Landmarks> = None;
+        let mut spatial_index: Option<SpatialIndex> = None;
+        for (position, section) in manifest.iter().enumerate() {
+            if manifest[..position].iter().any(|other| other.name == section.name) {
+                return Err(Error::from_kind(ErrorKind::DataItemIncorrect));
+            }
+            let mut bytes: Vec<u8> = Vec::new();
+            reader.take(section.length).read_to_end(&mut bytes)?;
+            if bytes.len() as u64 != section.length {
+                return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+            }
+            let mut content = bytes.as_slice();
+            match section.name.as_str() {
+                GRAPH => vertex = Some(VertexBuffer::load(&mut content)?),
+                HUB_LABELS => hub_labels = Some(HubLabels::load(&mut content)?),
+                LANDMARKS => landmarks = Some(Landmarks::load(&mut content)?),
+                SPATIAL_INDEX => spatial_index = Some(SpatialIndex::load(&mut content)?),
+                _ => continue,
+            }
+            if !content.is_empty() {
+                return Err(Error::from_kind(ErrorKind::DataItemIncorrect));
+            }
+        }
+        let vertex = vertex.ok_or_else(|| Error::from_kind(ErrorKind::DataItemIncomplete))?;
+        let bundle = Self {vertex, hub_labels, landmarks, spatial_index};
+        bundle.check_artifacts()?;
+        Ok(bundle)
+    }
+
+    fn check_artifacts(&self) -> Result<()> {
+        let node_count = self.vertex.len();
+        let incorrect = || Err(Error::from_kind(ErrorKind::DataItemIncorrect));
+        if self.hub_labels.as_ref().is_some_and(|hub_labels| hub_labels.len() != node_count) {
+            return incorrect();
+        }
+        let is_other_landmarks = self.landmarks.as_ref().is_some_and(|landmarks| {
+            !landmarks.vertex_indices.is_empty() && landmarks.node_count() != node_count
+        });
+        if is_other_landmarks {
+            return incorrect();
+        }
+        if let Some(spatial_index) = &self.spatial_index {
+            if spatial_index.len() != node_count {
+                return incorrect();
+            }
+            let radius = get_radius_km(&self.vertex.celestial_object);
+            if spatial_index.radius() != radius {
+                return Err(Error::from_kind(ErrorKind::BodyMismatch(radius, spatial_index.radius())));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod bundle_tests {
+    use super::*;
+    use crate::components::{SphereConnection, SpherePoint};
+    use crate::data::CelestialObject;
+
+    #[test]
+    fn test_bundle_round_trip() {
+        // given: a line with metadata, an edge shape, a changed cost and all artifacts
+        let connections = vec![
+            SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+            SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 2.0)),
+            SphereConnection::new(SpherePoint::new(0.0, 2.0), SpherePoint::new(1.0, 2.0)),
+        ];
+        let mut vertex = VertexBuffer::new(connections, CelestialObject::MARS).unwrap();
+        vertex.set_edge_metadata(1, "name", "Main street").unwrap();
+        vertex.set_node_metadata(3, "name", "Depot").unwrap();
+        vertex.update_costs(&[(2, 7.5)]).unwrap();
+        vertex.set_edge_shape(2, vec![SpherePoint::new(0.5, 2.2)]).unwrap();
+        let bundle = RoutingBundle {
+            hub_labels: Some(HubLabels::build(&vertex)),
+            landmarks: Some(Landmarks::build(&vertex, 2).unwrap()),
+            spatial_index: Some(SpatialIndex::new(&vertex)),
+            ..RoutingBundle::new(vertex.clone())
+        };
+        let mut saved: Vec<u8> = Vec::new();
+        // when
+        bundle.save(&mut saved).unwrap();
+        let loaded = RoutingBundle::load(&mut saved.as_slice()).unwrap();
+        // then
+        assert_eq!(loaded.vertex.to_connections(), vertex.to_connections());
+        assert_eq!(loaded.vertex.edge_cost(2), Some(7.5));
+        assert_eq!(loaded.vertex.edge_metadata(1), vertex.edge_metadata(1));
+        assert_eq!(loaded.vertex.node_metadata(3), vertex.node_metadata(3));
+        assert_eq!(loaded.vertex.edge_shape(2), vertex.edge_shape(2));
+        assert_eq!(loaded.vertex.index_of(&SpherePoint::new(1.0, 2.0)), Some(3));
+        assert!(loaded.vertex.check_same_body(&vertex).is_ok());
+        assert_eq!(loaded.hub_labels, bundle.hub_labels);
+        assert_eq!(loaded.landmarks, bundle.landmarks);
+        assert_eq!(loaded.spatial_index.unwrap().nearest(&SpherePoint::new(0.9, 2.1)), Some(3));
+        let manifest = RoutingBundle::read_manifest(&mut saved.as_slice()).unwrap();
+        let names: Vec<&str> = manifest.iter().map(|section| section.name.as_str()).collect();
+        assert_eq!(names, vec!["graph", "hub_labels", "landmarks", "spatial_index"]);
+        assert!(manifest.iter().map(|section| section.length).sum::<u64>() < saved.len() as u64);
+    }
+
+    #[test]
+    fn test_bundle_rejects_mismatched_artifacts() {
+        // given
+        let line = |length: f64| vec![SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, length))];
+        let vertex = VertexBuffer::new(line(1.0), CelestialObject::EARTH).unwrap();
+        let longer = VertexBuffer::new(vec![
+            SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+            SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 2.0)),
+        ], CelestialObject::EARTH).unwrap();
+        let on_mars = VertexBuffer::new(line(1.0), CelestialObject::MARS).unwrap();
+        let mut saved: Vec<u8> = Vec::new();
+        RoutingBundle::new(vertex.clone()).save(&mut saved).unwrap();
+        // when
+        let other_size = RoutingBundle {hub_labels: Some(HubLabels::build(&longer)), ..RoutingBundle::new(vertex.clone())};
+        let other_body = RoutingBundle {spatial_index: Some(SpatialIndex::new(&on_mars)), ..RoutingBundle::new(vertex)};
+        // then
+        assert!(other_size.save(&mut Vec::new()).is_err());
+        assert!(matches!(other_body.save(&mut Vec::new()).unwrap_err().kind(), ErrorKind::BodyMismatch(..)));
+        assert!(RoutingBundle::load(&mut saved[..saved.len() - 1].as_ref()).is_err());
+        saved[0] = b'X';
+        assert!(RoutingBundle::load(&mut saved.as_slice()).is_err());
+    }
+}
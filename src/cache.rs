@@ -0,0 +1,297 @@
+use crate::errors::*;
+use crate::vertex::{EdgeId, GraphDiff, VertexBuffer};
+use crate::components::SpherePoint;
+use crate::dijkstra::{snap_query, search_edge_ids};
+use crate::path::Path;
+use crate::hashing::NodeMap;
+
+/// # RouteCache
+/// Shortest paths between snapped node pairs of one VertexBuffer, kept for repeated queries and
+/// invalidated selectively when connection costs change.
+///
+/// # Remarks:
+///
+/// Cache remembers `VertexBuffer::generation` its routes were searched on, so it is never used for another
+/// VertexBuffer, a clone of the original included. Changes applied through `update_costs` or described
+/// by `apply_diff` move it to the new generation dropping only routes the change can affect: routes
+/// using a changed connection and routes costlier than a connection whose cost went down, as a path
+/// through it could now be cheaper. Unreachable pairs stay cached, cost changes never connect components.
+/// Any other change flushes the whole cache: diff between VertexBuffers whose indices or EdgeIds do not
+/// line up, at once, and changes like `EditSession::commit` at the next query.
+///
+/// ```
+/// use path_navigator::cache::RouteCache;
+/// use path_navigator::components::*;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::vertex::VertexBuffer;
+///
+/// let connections = vec![
+///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+///     SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 2.0)),
+///     SphereConnection::new(SpherePoint::new(0.0, 5.0), SpherePoint::new(0.0, 6.0)),
+/// ];
+/// let mut vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+/// let mut cache = RouteCache::new();
+/// cache.find_path(&SpherePoint::new(0.0, 0.0), &SpherePoint::new(0.0, 1.0), &vertex);
+/// cache.find_path(&SpherePoint::new(0.0, 5.0), &SpherePoint::new(0.0, 6.0), &vertex);
+/// assert_eq!(cache.update_costs(&mut vertex, &[(2, 500.0)]).unwrap(), 1);
+/// assert_eq!(cache.len(), 1);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RouteCache {
+    version: Option<u64>,
+    generation: Option<u64>,
+    routes: NodeMap<(usize, usize), Option<Path>>,
+}
+
+impl RouteCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns number of cached node pairs, unreachable ones included.
+    pub fn len(&self) -> usize {
+        self.routes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+
+    /// Returns `VertexBuffer::version` of cached routes, None before the first query.
+    pub fn version(&self) -> Option<u64> {
+        self.version
+    }
+
+    /// Drops all cached routes.
+    pub fn clear(&mut self) {
+        self.routes.clear();
+    }
+
+    /// Returns the shortest path between closest points of VertexBuffer like `dijkstra::find_path`,
+    /// from cache when the same node pair was searched on the same version of VertexBuffer.
+    /// None when points snap to the same node or finish is not reachable.
+    pub fn find_path(&mut self, start: &SpherePoint, finish: &SpherePoint, vertex: &VertexBuffer) -> Option<Path> {
+        self.follow(vertex);
+        let key = snap_query(start, finish, vertex)?;
+        self.routes.entry(key)
+            .or_insert_with(|| {
+                let edge_ids = search_edge_ids(key.0, key.1, vertex, |_, _, graph| Some(graph.cost))?;
+                Path::from_edge_ids(key.0, edge_ids, vertex)
+            })
+            .clone()
+    }
+
+    /// Applies `VertexBuffer::update_costs` and drops cached routes affected by it.
+    /// Returns number of dropped routes, on error neither VertexBuffer nor cache is changed.
+    pub fn update_costs(&mut self, vertex: &mut VertexBuffer, updates: &[(EdgeId, f64)]) -> Result<usize> {
+        let old_costs: Vec<Option<f64>> = updates.iter().map(|(edge_id, _)| vertex.edge_cost(*edge_id)).collect();
+        let is_current = self.generation == Some(vertex.generation());
+        vertex.update_costs(updates)?;
+        let changes: Vec<(EdgeId, f64, f64)> = updates.iter()
+            .zip(old_costs)
+            .filter_map(|((edge_id, cost), old_cost)| Some((*edge_id, old_cost?, *cost)))
+            .collect();
+        Ok(self.advance(vertex, is_current, &changes))
+    }
+
+    /// Moves cache from `previous` VertexBuffer it was filled on to `vertex`, dropping cached routes
+    /// affected by GraphDiff found by `previous.structural_diff(vertex, ..)`. Routes are kept only when
+    /// both VertexBuffers have the same nodes at the same indices and the same connections under the
+    /// same EdgeIds, any other diff, or `previous` other than the cached VertexBuffer, flushes the cache.
+    /// Returns number of dropped routes, fails with `BodyMismatch` leaving the cache unchanged when
+    /// VertexBuffers lie on other celestial objects.
+    pub fn apply_diff(&mut self, diff: &GraphDiff, previous: &VertexBuffer, vertex: &VertexBuffer) -> Result<usize> {
+        previous.check_same_body(vertex)?;
+        let is_aligned = self.generation == Some(previous.generation())
+            && diff.added_nodes.is_empty() && diff.removed_nodes.is_empty()
+            && diff.added_edges.is_empty() && diff.removed_edges.is_empty()
+            && previous.len() == vertex.len() && previous.edge_count() == vertex.edge_count()
+            && previous.vector.iter().zip(&vertex.vector).all(|(node, other)| node.coordinates == other.coordinates)
+            && (0..previous.edge_count()).all(|edge_id| previous.edge(edge_id) == vertex.edge(edge_id)
+                && previous.edge_direction(edge_id) == vertex.edge_direction(edge_id))
+            && diff.cost_changes.iter().all(|change| change.edge_id == change.other_edge_id);
+        let changes: Vec<(EdgeId, f64, f64)> = diff.cost_changes.iter()
+            .map(|change| (change.edge_id, change.cost, change.other_cost))
+            .collect();
+        Ok(self.advance(vertex, is_aligned, &changes))
+    }
+
+    /// Adopts version of VertexBuffer, keeping routes unaffected by `changes` given as
+    /// (EdgeId, old cost, new cost) when `is_selective`, dropping all of them otherwise.
+    fn advance(&mut self, vertex: &VertexBuffer, is_selective: bool, changes: &[(EdgeId, f64, f64)]) -> usize {
+        let before = self.routes.len();
+        if is_selective {
+            let lowest_decrease = changes.iter()
+                .filter(|(_, old_cost, cost)| cost < old_cost)
+                .map(|(_, _, cost)| *cost)
+                .fold(f64::INFINITY, f64::min);
+            self.routes.retain(|_, path| match path {
+                Some(path) => path.cost() <= lowest_decrease
+                    && !path.edge_ids.iter().any(|edge_id| changes.iter().any(|(changed, _, _)| changed == edge_id)),
+                None => true,
+            });
+        } else {
+            self.routes.clear();
+        }
+        self.version = Some(vertex.version());
+        self.generation = Some(vertex.generation());
+        before - self.routes.len()
+    }
+
+    /// Flushes routes searched on other generation of VertexBuffer.
+    fn follow(&mut self, vertex: &VertexBuffer) {
+        if self.generation != Some(vertex.generation()) {
+            self.routes.clear();
+            self.version = Some(vertex.version());
+            self.generation = Some(vertex.generation());
+        }
+    }
+}
+
+#[cfg(test)]
+mod cache_tests {
+    use super::*;
+    use crate::components::SphereConnection;
+    use crate::data::CelestialObject;
+    use crate::edit::EditSession;
+    use crate::dijkstra::find_path;
+
+    #[test]
+    fn test_invalidation_on_cost_changes() {
+        // given: a corridor 0 - 1 - 2 with a bypass 0 - 3 - 2 and a separate island 4 - 5
+        let point = |lat: f64, lng: f64| SpherePoint::new(lat, lng);
+        let connections = vec![
+            SphereConnection::new(point(0.0, 0.0), point(0.0, 1.0)),
+            SphereConnection::new(point(0.0, 1.0), point(0.0, 2.0)),
+            SphereConnection::new(point(0.0, 0.0), point(1.0, 1.0)),
+            SphereConnection::new(point(1.0, 1.0), point(0.0, 2.0)),
+            SphereConnection::new(point(5.0, 5.0), point(5.0, 6.0)),
+        ];
+        let mut vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        let mut cache = RouteCache::new();
+        let queries = [(point(0.0, 0.0), point(0.0, 2.0)), (point(0.0, 0.0), point(0.0, 1.0)),
+            (point(5.0, 5.0), point(5.0, 6.0)), (point(0.0, 0.0), point(5.0, 5.0))];
+        let fill = |cache: &mut RouteCache, vertex: &VertexBuffer| {
+            queries.iter().for_each(|(start, finish)| { cache.find_path(start, finish, vertex); });
+        };
+        fill(&mut cache, &vertex);
+        // when: the island gets dearer, then the bypass gets cheaper
+        let dropped_by_increase = cache.update_costs(&mut vertex, &[(4, 1000.0)]).unwrap();
+        let kept_after_increase = cache.len();
+        let dropped_by_decrease = cache.update_costs(&mut vertex, &[(2, 1.0)]).unwrap();
+        // then
+        assert_eq!((dropped_by_increase, kept_after_increase), (1, 3));
+        // corridor route and its first leg are both costlier than the cheaper bypass connection
+        assert_eq!(dropped_by_decrease, 2);
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.version(), Some(vertex.version()));
+        fill(&mut cache, &vertex);
+        let edge_ids: Vec<Option<Vec<EdgeId>>> = queries.iter()
+            .map(|(start, finish)| cache.find_path(start, finish, &vertex).map(|path| path.edge_ids))
+            .collect();
+        assert_eq!(edge_ids, vec![Some(vec![2, 3]), Some(vec![0]), Some(vec![4]), None]);
+        assert!(cache.update_costs(&mut vertex, &[(42, 1.0)]).is_err());
+        assert_eq!(cache.len(), 4);
+    }
+
+    #[test]
+    fn test_flush_on_structural_changes() {
+        // given
+        let point = |lat: f64, lng: f64| SpherePoint::new(lat, lng);
+        let connections = vec![
+            SphereConnection::new(point(0.0, 0.0), point(0.0, 1.0)),
+            SphereConnection::new(point(0.0, 1.0), point(0.0, 2.0)),
+        ];
+        let mut vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        let mut cache = RouteCache::new();
+        cache.find_path(&point(0.0, 0.0), &point(0.0, 2.0), &vertex);
+        let dearer = {
+            let mut dearer = vertex.clone();
+            dearer.update_costs(&[(1, 500.0)]).unwrap();
+            dearer
+        };
+        // when
+        let dropped_by_diff = cache.apply_diff(&vertex.structural_diff(&dearer, 0.0).unwrap(), &vertex, &dearer).unwrap();
+        let mut session = EditSession::new(&vertex);
+        session.add_connection(SphereConnection::new(point(0.0, 0.0), point(0.0, 2.0)));
+        session.commit(&mut vertex).unwrap();
+        cache.find_path(&point(0.0, 0.0), &point(0.0, 1.0), &dearer);
+        let dropped_by_edit = cache.apply_diff(&dearer.structural_diff(&vertex, 0.0).unwrap(), &dearer, &vertex).unwrap();
+        // then
+        assert_eq!(dropped_by_diff, 1);
+        assert_eq!(dropped_by_edit, 1);
+        assert_eq!(vertex.version(), 1);
+        let direct = cache.find_path(&point(0.0, 0.0), &point(0.0, 2.0), &vertex).unwrap();
+        assert_eq!(direct.edge_ids, vec![2]);
+    }
+
+    #[test]
+    fn test_flush_on_diff_of_other_layout() {
+        // given: a line a - b - c, built again from the same connections in other order and one-way
+        let (a, b, c) = (SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 2.0));
+        let connections = vec![SphereConnection::new(a.clone(), b.clone()), SphereConnection::new(b.clone(), c.clone())];
+        let vertex = VertexBuffer::new(connections.clone(), CelestialObject::EARTH).unwrap();
+        let reordered = VertexBuffer::new(connections.iter().rev().cloned().collect(), CelestialObject::EARTH).unwrap();
+        let one_way = VertexBuffer::new_directed(connections, CelestialObject::EARTH).unwrap();
+        let mut cache = RouteCache::new();
+        cache.find_path(&a, &c, &vertex);
+        // when
+        let reordered_diff = vertex.structural_diff(&reordered, 0.0).unwrap();
+        let dropped_by_order = cache.apply_diff(&reordered_diff, &vertex, &reordered).unwrap();
+        let ordered = cache.find_path(&a, &c, &reordered);
+        let mut reverse_cache = RouteCache::new();
+        reverse_cache.find_path(&c, &a, &vertex);
+        let dropped_by_direction = reverse_cache.apply_diff(&vertex.structural_diff(&one_way, 0.0).unwrap(), &vertex, &one_way).unwrap();
+        // then
+        assert!(reordered_diff.is_empty());
+        assert_eq!(dropped_by_order, 1);
+        assert_eq!(ordered, find_path(&a, &c, &reordered));
+        assert_eq!(ordered.unwrap().vertex_indices, vec![2, 0, 1]);
+        assert_eq!(dropped_by_direction, 1);
+        assert!(reverse_cache.find_path(&c, &a, &one_way).is_none());
+    }
+
+    #[test]
+    fn test_diff_against_other_body() {
+        // given: the same line on Earth and on Mars
+        let (a, b) = (SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0));
+        let connections = vec![SphereConnection::new(a.clone(), b.clone())];
+        let earth = VertexBuffer::new(connections.clone(), CelestialObject::EARTH).unwrap();
+        let mars = VertexBuffer::new(connections, CelestialObject::MARS).unwrap();
+        let mut cache = RouteCache::new();
+        cache.find_path(&a, &b, &earth);
+        // when
+        let applied = cache.apply_diff(&GraphDiff::default(), &earth, &mars);
+        // then
+        assert!(matches!(earth.structural_diff(&mars, 0.0).unwrap_err().kind(), ErrorKind::BodyMismatch(_, _)));
+        assert!(matches!(applied.unwrap_err().kind(), ErrorKind::BodyMismatch(_, _)));
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_clones_with_the_same_version() {
+        // given: a corridor 0 - 1 - 2 with a bypass 0 - 3 - 2, made dearer in one clone each
+        let point = |lat: f64, lng: f64| SpherePoint::new(lat, lng);
+        let connections = vec![
+            SphereConnection::new(point(0.0, 0.0), point(0.0, 1.0)),
+            SphereConnection::new(point(0.0, 1.0), point(0.0, 2.0)),
+            SphereConnection::new(point(0.0, 0.0), point(1.0, 1.0)),
+            SphereConnection::new(point(1.0, 1.0), point(0.0, 2.0)),
+        ];
+        let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        let mut closed_corridor = vertex.clone();
+        closed_corridor.update_costs(&[(1, 1000.0)]).unwrap();
+        let mut closed_bypass = vertex.clone();
+        closed_bypass.update_costs(&[(3, 1000.0)]).unwrap();
+        let mut cache = RouteCache::new();
+        // when
+        let around = cache.find_path(&point(0.0, 0.0), &point(0.0, 2.0), &closed_corridor).unwrap();
+        let along = cache.find_path(&point(0.0, 0.0), &point(0.0, 2.0), &closed_bypass).unwrap();
+        // then
+        assert_eq!(closed_corridor.version(), closed_bypass.version());
+        assert_ne!(closed_corridor.generation(), closed_bypass.generation());
+        assert_eq!(around.edge_ids, vec![2, 3]);
+        assert_eq!(along.edge_ids, vec![0, 1]);
+    }
+}
@@ -0,0 +1,184 @@
+use crate::errors::*;
+use crate::vertex::VertexBuffer;
+use crate::components::*;
+use crate::rng::SplitMix64;
+
+/// Result of `k_means`: `memberships[i]` is the cluster of node `i` and `centroids[c]` the center of cluster `c`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clustering {
+    pub centroids: Vec<SpherePoint>,
+    pub memberships: Vec<usize>,
+    pub iterations: usize,
+}
+
+impl Clustering {
+    /// Returns indices of nodes belonging to given cluster, in ascending order.
+    pub fn members(&self, cluster: usize) -> Vec<usize> {
+        (0..self.memberships.len())
+            .filter(|index| self.memberships[*index] == cluster)
+            .collect()
+    }
+}
+
+/// Groups nodes of VertexBuffer into `k` clusters by great-circle distance (spherical k-means).
+///
+/// # Arguments:
+/// * `vertex` which is &VertexBuffer - nodes to group, connections are ignored
+/// * `k` which is usize - number of clusters, from 1 up to number of nodes
+/// * `max_iterations` which is usize - upper limit of assignment/update rounds
+/// * `seed` which is u64 - seed of k-means++ initialization, the same seed gives the same clustering
+///
+/// # Remarks:
+///
+/// Centroid of a cluster is the mean of unit vectors of its nodes projected back onto the sphere,
+/// which minimizes the sum of chord distances and works across poles and the antimeridian.
+/// Cluster left empty takes over the node farthest from its centroid, so every cluster has at least one node.
+///
+/// Fails with `InvalidParameter` when `k` is 0 or greater than number of nodes.
+///
+/// ```
+/// use path_navigator::clustering::k_means;
+/// use path_navigator::components::*;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::vertex::VertexBuffer;
+///
+/// let connections = vec![
+///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 0.1)),
+///     SphereConnection::new(SpherePoint::new(0.0, 0.1), SpherePoint::new(0.0, 90.0)),
+///     SphereConnection::new(SpherePoint::new(0.0, 90.0), SpherePoint::new(0.0, 90.1)),
+/// ];
+/// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+/// let clustering = k_means(&vertex, 2, 100, 1).unwrap();
+/// assert_eq!(clustering.memberships[0], clustering.memberships[1]);
+/// assert_ne!(clustering.memberships[1], clustering.memberships[2]);
+/// ```
+pub fn k_means(vertex: &VertexBuffer, k: usize, max_iterations: usize, seed: u64) -> Result<Clustering> {
+    let count = vertex.len();
+    if k == 0 || k > count {
+        return Err(Error::from_kind(ErrorKind::InvalidParameter));
+    }
+    let vectors: Vec<Vector3> = vertex.vector.iter().map(|node| node.coordinates.to_vector()).collect();
+    let mut centroids = initial_centroids(&vectors, k, seed);
+    let mut memberships: Vec<usize> = vec![0; count];
+    let mut iterations = 0;
+    while iterations < max_iterations {
+        iterations += 1;
+        let mut changed = iterations == 1;
+        for (index, vector) in vectors.iter().enumerate() {
+            let nearest = nearest_centroid(vector, &centroids);
+            if nearest != memberships[index] {
+                memberships[index] = nearest;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+        let mut sums: Vec<Vector3> = vec![[0.0; 3]; k];
+        let mut sizes: Vec<usize> = vec![0; k];
+        for (index, vector) in vectors.iter().enumerate() {
+            let cluster = memberships[index];
+            sizes[cluster] += 1;
+            (0..3).for_each(|axis| sums[cluster][axis] += vector[axis]);
+        }
+        for cluster in 0..k {
+            if sizes[cluster] > 0 && norm(&sums[cluster]) > 0.0 {
+                centroids[cluster] = normalize(&sums[cluster]);
+            }
+        }
+        for cluster in 0..k {
+            if sizes[cluster] > 0 {
+                continue;
+            }
+            let farthest = (0..count)
+                .filter(|index| sizes[memberships[*index]] > 1)
+                .min_by(|a, b| {
+                    dot(&vectors[*a], &centroids[memberships[*a]]).total_cmp(&dot(&vectors[*b], &centroids[memberships[*b]]))
+                })
+                .unwrap(); // k <= count, so some cluster has more than one node
+            sizes[memberships[farthest]] -= 1;
+            sizes[cluster] = 1;
+            memberships[farthest] = cluster;
+            centroids[cluster] = vectors[farthest];
+        }
+    }
+    Ok(Clustering {
+        centroids: centroids.iter().map(SpherePoint::from_vector).collect(),
+        memberships,
+        iterations,
+    })
+}
+
+/// k-means++ initialization: every next centroid is a node drawn with probability proportional
+/// to squared chord distance to the nearest centroid chosen so far.
+fn initial_centroids(vectors: &[Vector3], k: usize, seed: u64) -> Vec<Vector3> {
+    let mut rng = SplitMix64::new(seed);
+    let mut centroids: Vec<Vector3> = vec![vectors[rng.below(vectors.len())]];
+    let squared_chord = |a: &Vector3, b: &Vector3| (2.0 - 2.0 * dot(a, b)).max(0.0);
+    let mut weights: Vec<f64> = vectors.iter().map(|vector| squared_chord(vector, &centroids[0])).collect();
+    while centroids.len() < k {
+        let total: f64 = weights.iter().sum();
+        let chosen = if total > 0.0 {
+            let mut threshold = rng.next_f64() * total;
+            let mut chosen = weights.iter().rposition(|weight| *weight > 0.0).unwrap();
+            for (index, weight) in weights.iter().enumerate() {
+                if *weight > 0.0 && threshold < *weight {
+                    chosen = index;
+                    break;
+                }
+                threshold -= weight;
+            }
+            chosen
+        } else {
+            rng.below(vectors.len())
+        };
+        centroids.push(vectors[chosen]);
+        for (index, vector) in vectors.iter().enumerate() {
+            weights[index] = weights[index].min(squared_chord(vector, &vectors[chosen]));
+        }
+    }
+    centroids
+}
+
+fn nearest_centroid(vector: &Vector3, centroids: &[Vector3]) -> usize {
+    (0..centroids.len())
+        .max_by(|a, b| dot(vector, &centroids[*a]).total_cmp(&dot(vector, &centroids[*b])).then(b.cmp(a)))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod clustering_tests {
+    use super::*;
+    use crate::vertex::generators::k_nearest;
+    use crate::data::CelestialObject;
+
+    #[test]
+    fn test_k_means_finds_separated_groups() {
+        // given
+        let mut points: Vec<SpherePoint> = Vec::new();
+        for center_lng in [-179.5, 60.0, -60.0] {
+            for i in 0..5 {
+                let mut lng = center_lng + i as f64 * 0.2;
+                if lng > 180.0 {
+                    lng -= 360.0;
+                }
+                points.push(SpherePoint::new(i as f64 * 0.1, lng));
+            }
+        }
+        let vertex = k_nearest(&points, 2, CelestialObject::EARTH).unwrap();
+        // when
+        let clustering = k_means(&vertex, 3, 50, 5).unwrap();
+        // then
+        for point_index in 0..points.len() {
+            let node = vertex.index_of(&points[point_index]).unwrap();
+            let group_leader = vertex.index_of(&points[point_index / 5 * 5]).unwrap();
+            assert_eq!(clustering.memberships[node], clustering.memberships[group_leader]);
+        }
+        let mut sizes: Vec<usize> = (0..3).map(|cluster| clustering.members(cluster).len()).collect();
+        sizes.sort();
+        assert_eq!(sizes, vec![5, 5, 5]);
+        assert_eq!(clustering, k_means(&vertex, 3, 50, 5).unwrap());
+        assert!(k_means(&vertex, 0, 50, 5).is_err());
+        assert!(k_means(&vertex, 16, 50, 5).is_err());
+    }
+}
@@ -1,4 +1,5 @@
 use crate::PartialEq;
+use std::f64::consts::PI;
 use std::fmt;
 
 /// # SpherePoint
@@ -58,6 +59,7 @@ use std::fmt;
 /// * [Haversian Formula, Wikipedia](https://en.wikipedia.org/wiki/Haversine_formula)
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SpherePoint {
     pub lat: f64,
     pub lng: f64,
@@ -75,13 +77,138 @@ impl PartialEq for SpherePoint {
     }
 }
 
+/// # GeoPoint
+/// Position on a celestial body which VertexBuffer can be built from and queried with.
+/// Types like points with altitude or ellipsoidal coordinates implement it by giving their
+/// spherical latitude and longitude in degrees; routing itself stays on the sphere.
+///
+/// ```
+/// use path_navigator::components::*;
+///
+/// struct Airport { code: &'static str, lat: f64, lng: f64 }
+///
+/// impl GeoPoint for Airport {
+///     fn latitude(&self) -> f64 { self.lat }
+///     fn longitude(&self) -> f64 { self.lng }
+/// }
+///
+/// let airport = Airport {code: "WAW", lat: 52.1657, lng: 20.9671};
+/// assert_eq!(airport.to_sphere_point(), SpherePoint::new(52.1657, 20.9671));
+/// ```
+pub trait GeoPoint {
+    fn latitude(&self) -> f64;
+    fn longitude(&self) -> f64;
+
+    fn to_sphere_point(&self) -> SpherePoint {
+        SpherePoint::new(self.latitude(), self.longitude())
+    }
+}
+
+impl GeoPoint for SpherePoint {
+    fn latitude(&self) -> f64 {
+        self.lat
+    }
+
+    fn longitude(&self) -> f64 {
+        self.lng
+    }
+
+    fn to_sphere_point(&self) -> SpherePoint {
+        self.clone()
+    }
+}
+
 impl fmt::Display for SpherePoint {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "SpherePoint({}, {})", self.lat, self.lng)
     }
 }
 
+/// Formula of great-circle distance.
+///
+/// * `Haversine` - used by `SphereConnection::cost`, well conditioned except for nearly antipodal points
+/// * `Vincenty` - special case of Vincenty formula for sphere, well conditioned for any distance,
+///   from sub-meter indoor or port segments to antipodes, at the cost of a few more trigonometric functions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DistanceFormula {
+    Haversine,
+    Vincenty,
+}
+
+/// Distance between two points, pluggable into VertexBuffer building (`VertexBuffer::with_metric`)
+/// and snapping (`VertexBuffer::snap_batch_with`). Implemented by HaversineMetric, VincentyMetric,
+/// RhumbMetric and LocalProjection, and by closures of `Fn(&SpherePoint, &SpherePoint) -> f64`.
+pub trait Metric {
+    /// Returns distance between points, in kilometers for metrics of this crate.
+    fn distance(&self, a: &SpherePoint, b: &SpherePoint) -> f64;
+}
+
+impl<F: Fn(&SpherePoint, &SpherePoint) -> f64> Metric for F {
+    fn distance(&self, a: &SpherePoint, b: &SpherePoint) -> f64 {
+        self(a, b)
+    }
+}
+
+/// Great-circle distance by haversine formula on sphere of given radius, see `SphereConnection::cost`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HaversineMetric {
+    pub radius: f64,
+}
+
+impl Metric for HaversineMetric {
+    fn distance(&self, a: &SpherePoint, b: &SpherePoint) -> f64 {
+        SphereConnection::new(a.clone(), b.clone()).cost(self.radius)
+    }
+}
+
+/// Great-circle distance by Vincenty formula on sphere of given radius, see DistanceFormula.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VincentyMetric {
+    pub radius: f64,
+}
+
+impl Metric for VincentyMetric {
+    fn distance(&self, a: &SpherePoint, b: &SpherePoint) -> f64 {
+        SphereConnection::new(a.clone(), b.clone()).cost_with(self.radius, DistanceFormula::Vincenty)
+    }
+}
+
+/// Length of rhumb line (loxodrome), path of constant bearing which ships and aircraft without
+/// continuous course changes follow, on sphere of given radius. The shorter way around the globe
+/// is taken, rhumb lines are never shorter than great circles and only the same along meridians
+/// and the equator.
+///
+/// ```
+/// use path_navigator::components::*;
+///
+/// let (start, finish) = (SpherePoint::new(50.0, 0.0), SpherePoint::new(50.0, 90.0));
+/// let rhumb = RhumbMetric {radius: 6371.0}.distance(&start, &finish);
+/// let great_circle = HaversineMetric {radius: 6371.0}.distance(&start, &finish);
+/// assert!(rhumb > great_circle);
+/// assert!((rhumb - 6371.0 * 50f64.to_radians().cos() * 90f64.to_radians()).abs() < 1e-6);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RhumbMetric {
+    pub radius: f64,
+}
+
+impl Metric for RhumbMetric {
+    fn distance(&self, a: &SpherePoint, b: &SpherePoint) -> f64 {
+        let (lat_1, lat_2) = (a.lat.to_radians(), b.lat.to_radians());
+        let delta_lat = lat_2 - lat_1;
+        let mut delta_lng = (b.lng - a.lng).to_radians();
+        if delta_lng.abs() > PI {
+            delta_lng -= 2.0 * PI * delta_lng.signum();
+        }
+        let stretched = ((PI / 4.0 + lat_2 / 2.0).tan() / (PI / 4.0 + lat_1 / 2.0).tan()).ln();
+        // along a parallel the stretched latitude difference vanishes, its ratio tends to cosine of latitude
+        let ratio = if stretched.abs() > GEOMETRY_EPSILON { delta_lat / stretched } else { lat_1.cos() };
+        self.radius * (delta_lat.powi(2) + (ratio * delta_lng).powi(2)).sqrt()
+    }
+}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SphereConnection {
     pub start: SpherePoint,
     pub finish: SpherePoint,
@@ -101,6 +228,45 @@ impl SphereConnection {
         let c = 2_f64 * a.sqrt().atan2((1_f64 - a).sqrt());
         radius * c
     }
+
+    /// Returns great-circle distance computed with given DistanceFormula, on sphere of given radius.
+    pub fn cost_with(&self, radius: f64, formula: DistanceFormula) -> f64 {
+        match formula {
+            DistanceFormula::Haversine => self.cost(radius),
+            DistanceFormula::Vincenty => {
+                let (sin_1, cos_1) = self.start.lat.to_radians().sin_cos();
+                let (sin_2, cos_2) = self.finish.lat.to_radians().sin_cos();
+                let (sin_lambda, cos_lambda) = (self.finish.lng - self.start.lng).to_radians().sin_cos();
+                let y = ((cos_2 * sin_lambda).powi(2) + (cos_1 * sin_2 - sin_1 * cos_2 * cos_lambda).powi(2)).sqrt();
+                let x = sin_1 * sin_2 + cos_1 * cos_2 * cos_lambda;
+                radius * y.atan2(x)
+            }
+        }
+    }
+
+    /// Returns equirectangular approximation of great-circle distance for short connections, with relative error
+    /// below `APPROXIMATION_TOLERANCE`, and None for connections it is not accurate enough for: longer than
+    /// `APPROXIMATION_MAX_DEGREES` in latitude or longitude, or reaching beyond 80° of latitude.
+    ///
+    /// ```
+    /// use path_navigator::components::*;
+    ///
+    /// let short = SphereConnection::new(SpherePoint::new(50.0, 19.9), SpherePoint::new(50.2, 20.4));
+    /// let exact = short.cost(6371.0);
+    /// assert!((short.approximate_cost(6371.0).unwrap() - exact).abs() < exact * APPROXIMATION_TOLERANCE);
+    /// assert!(SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 5.0)).approximate_cost(6371.0).is_none());
+    /// ```
+    pub fn approximate_cost(&self, radius: f64) -> Option<f64> {
+        let lat = self.finish.lat - self.start.lat;
+        // longitude difference across the antimeridian
+        let lng = (self.finish.lng - self.start.lng + 540.0) % 360.0 - 180.0;
+        if lat.abs() > APPROXIMATION_MAX_DEGREES || lng.abs() > APPROXIMATION_MAX_DEGREES
+            || self.start.lat.abs() > 80.0 || self.finish.lat.abs() > 80.0 {
+            return None;
+        }
+        let x = lng.to_radians() * ((self.start.lat + self.finish.lat) / 2.0).to_radians().cos();
+        Some(radius * x.hypot(lat.to_radians()))
+    }
 }
 
 impl PartialEq for SphereConnection {
@@ -115,6 +281,717 @@ impl fmt::Display for SphereConnection {
     }
 }
 
+/// # SphereBoundingBox
+/// Area between two parallels and two meridians, given in degrees.
+/// Box with `min_lng` greater than `max_lng` crosses the antimeridian.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SphereBoundingBox {
+    pub min_lat: f64,
+    pub min_lng: f64,
+    pub max_lat: f64,
+    pub max_lng: f64,
+}
+
+impl SphereBoundingBox {
+    pub fn new(min_lat: f64, min_lng: f64, max_lat: f64, max_lng: f64) -> Self {
+        Self {min_lat, min_lng, max_lat, max_lng}
+    }
+
+    /// Returns width of the box in degrees of longitude.
+    pub fn lng_span(&self) -> f64 {
+        if self.min_lng <= self.max_lng {
+            self.max_lng - self.min_lng
+        } else {
+            self.max_lng + 360.0 - self.min_lng
+        }
+    }
+
+    pub fn contains(&self, point: &SpherePoint) -> bool {
+        let in_lat = point.lat >= self.min_lat && point.lat <= self.max_lat;
+        let in_lng = if self.min_lng <= self.max_lng {
+            point.lng >= self.min_lng && point.lng <= self.max_lng
+        } else {
+            point.lng >= self.min_lng || point.lng <= self.max_lng
+        };
+        in_lat && in_lng
+    }
+
+    /// Returns the smallest box, not crossing the antimeridian, containing all points or None for no points.
+    pub fn from_points(points: &[SpherePoint]) -> Option<Self> {
+        let first = points.first()?;
+        let mut bbox = Self::new(first.lat, first.lng, first.lat, first.lng);
+        for point in points {
+            bbox.min_lat = bbox.min_lat.min(point.lat);
+            bbox.min_lng = bbox.min_lng.min(point.lng);
+            bbox.max_lat = bbox.max_lat.max(point.lat);
+            bbox.max_lng = bbox.max_lng.max(point.lng);
+        }
+        Some(bbox)
+    }
+
+    /// Returns point in the middle of latitude and longitude ranges.
+    pub fn center(&self) -> SpherePoint {
+        let mut lng = self.min_lng + self.lng_span() / 2.0;
+        if lng > 180.0 {
+            lng -= 360.0;
+        }
+        SpherePoint::new((self.min_lat + self.max_lat) / 2.0, lng)
+    }
+}
+
+/// Unit vector in Earth-centered Cartesian coordinates, used for great-circle geometry.
+pub(crate) type Vector3 = [f64; 3];
+
+pub(crate) fn cross(a: &Vector3, b: &Vector3) -> Vector3 {
+    [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]]
+}
+
+pub(crate) fn dot(a: &Vector3, b: &Vector3) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+pub(crate) fn norm(a: &Vector3) -> f64 {
+    dot(a, a).sqrt()
+}
+
+pub(crate) fn normalize(a: &Vector3) -> Vector3 {
+    let length = norm(a);
+    [a[0] / length, a[1] / length, a[2] / length]
+}
+
+impl SpherePoint {
+    pub(crate) fn to_vector(&self) -> Vector3 {
+        let lat = self.lat.to_radians();
+        let lng = self.lng.to_radians();
+        [lat.cos() * lng.cos(), lat.cos() * lng.sin(), lat.sin()]
+    }
+
+    /// Longitude of poles is undefined, points at poles get longitude 0.0.
+    pub(crate) fn from_vector(vector: &Vector3) -> Self {
+        let horizontal = (vector[0].powi(2) + vector[1].powi(2)).sqrt();
+        let lat = vector[2].atan2(horizontal);
+        let lng = if horizontal < GEOMETRY_EPSILON { 0.0 } else { vector[1].atan2(vector[0]) };
+        Self::new(lat.to_degrees(), lng.to_degrees())
+    }
+
+    /// Returns true for point at North or South Pole, where every longitude gives the same place.
+    pub fn is_pole(&self) -> bool {
+        self.lat.to_radians().cos().abs() < GEOMETRY_EPSILON
+    }
+}
+
+impl SphereConnection {
+    /// Returns central angle between start and finish in radians.
+    pub fn central_angle(&self) -> f64 {
+        let start = self.start.to_vector();
+        let finish = self.finish.to_vector();
+        norm(&cross(&start, &finish)).atan2(dot(&start, &finish))
+    }
+
+    /// Returns initial bearing of great-circle arc at start, in degrees clockwise from north in range [0.0, 360.0).
+    ///
+    /// # Remarks:
+    ///
+    /// At a pole every direction is the same, so connection starting at North Pole has bearing 180.0
+    /// and starting at South Pole has bearing 0.0, whatever longitudes are. Connection of zero length
+    /// has bearing 0.0.
+    pub fn initial_bearing(&self) -> f64 {
+        if self.start.is_pole() {
+            return if self.start.lat > 0.0 { 180.0 } else { 0.0 };
+        }
+        let (sin_1, cos_1) = self.start.lat.to_radians().sin_cos();
+        let (sin_2, cos_2) = self.finish.lat.to_radians().sin_cos();
+        let (sin_lambda, cos_lambda) = (self.finish.lng - self.start.lng).to_radians().sin_cos();
+        let y = sin_lambda * cos_2;
+        let x = cos_1 * sin_2 - sin_1 * cos_2 * cos_lambda;
+        if y.abs() < GEOMETRY_EPSILON && x.abs() < GEOMETRY_EPSILON {
+            return 0.0;
+        }
+        y.atan2(x).to_degrees().rem_euclid(360.0)
+    }
+
+    /// Returns bearing of great-circle arc at finish in travel direction, in degrees clockwise from north
+    /// in range [0.0, 360.0). Arc finishing at North Pole has bearing 0.0, at South Pole 180.0.
+    pub fn final_bearing(&self) -> f64 {
+        let reversed = SphereConnection::new(self.finish.clone(), self.start.clone());
+        (reversed.initial_bearing() + 180.0).rem_euclid(360.0)
+    }
+
+    /// Returns point on great-circle arc between start (`fraction` equal 0.0) and finish (`fraction` equal 1.0).
+    pub fn interpolate(&self, fraction: f64) -> SpherePoint {
+        let angle = self.central_angle();
+        if angle == 0.0 {
+            return self.start.clone();
+        }
+        let start = self.start.to_vector();
+        let finish = self.finish.to_vector();
+        let a = ((1.0 - fraction) * angle).sin() / angle.sin();
+        let b = (fraction * angle).sin() / angle.sin();
+        SpherePoint::from_vector(&[
+            a * start[0] + b * finish[0],
+            a * start[1] + b * finish[1],
+            a * start[2] + b * finish[2],
+        ])
+    }
+
+    /// Returns intersection point of two great-circle arcs, None when arcs do not cross
+    /// or lie on the same great circle.
+    pub fn intersection(&self, other: &SphereConnection) -> Option<SpherePoint> {
+        let a = self.start.to_vector();
+        let b = self.finish.to_vector();
+        let c = other.start.to_vector();
+        let d = other.finish.to_vector();
+        let normal_ab = cross(&a, &b);
+        let normal_cd = cross(&c, &d);
+        let line = cross(&normal_ab, &normal_cd);
+        if norm(&line) < GEOMETRY_EPSILON {
+            return None;
+        }
+        let candidate = normalize(&line);
+        let antipode = [-candidate[0], -candidate[1], -candidate[2]];
+        [candidate, antipode].iter()
+            .find(|p| is_on_arc(p, &a, &b, &normal_ab) && is_on_arc(p, &c, &d, &normal_cd))
+            .map(SpherePoint::from_vector)
+    }
+
+    /// Returns point of great-circle arc closest to given point, the nearer end when the closest point
+    /// of the whole great circle lies outside the arc.
+    ///
+    /// ```
+    /// use path_navigator::components::*;
+    ///
+    /// let equator = SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 10.0));
+    /// let foot = equator.closest_point(&SpherePoint::new(1.0, 4.0));
+    /// assert!(foot.lat.abs() < 1e-12 && (foot.lng - 4.0).abs() < 1e-12);
+    /// assert_eq!(equator.closest_point(&SpherePoint::new(1.0, -4.0)), equator.start);
+    /// ```
+    pub fn closest_point(&self, point: &SpherePoint) -> SpherePoint {
+        let a = self.start.to_vector();
+        let b = self.finish.to_vector();
+        let p = point.to_vector();
+        let nearer_end = if dot(&a, &p) >= dot(&b, &p) { self.start.clone() } else { self.finish.clone() };
+        let normal = cross(&a, &b);
+        if norm(&normal) < GEOMETRY_EPSILON {
+            return nearer_end;
+        }
+        let normal = normalize(&normal);
+        let offset = dot(&p, &normal);
+        let foot = [p[0] - offset * normal[0], p[1] - offset * normal[1], p[2] - offset * normal[2]];
+        if norm(&foot) < GEOMETRY_EPSILON || !is_on_arc(&foot, &a, &b, &normal) {
+            return nearer_end;
+        }
+        SpherePoint::from_vector(&normalize(&foot))
+    }
+
+    /// Returns the smallest central angle in radians between point and any point of great-circle arc.
+    pub fn angular_distance_to(&self, point: &SpherePoint) -> f64 {
+        let a = self.start.to_vector();
+        let b = self.finish.to_vector();
+        let p = point.to_vector();
+        let angle = |u: &Vector3, v: &Vector3| norm(&cross(u, v)).atan2(dot(u, v));
+        let to_endpoints = angle(&a, &p).min(angle(&b, &p));
+        let normal = cross(&a, &b);
+        if norm(&normal) < GEOMETRY_EPSILON {
+            return to_endpoints;
+        }
+        let normal = normalize(&normal);
+        let offset = dot(&p, &normal);
+        let foot = [p[0] - offset * normal[0], p[1] - offset * normal[1], p[2] - offset * normal[2]];
+        if norm(&foot) < GEOMETRY_EPSILON || !is_on_arc(&foot, &a, &b, &normal) {
+            return to_endpoints;
+        }
+        offset.abs().min(1.0).asin().min(to_endpoints)
+    }
+}
+
+/// Longest connection in degrees of latitude or longitude `SphereConnection::approximate_cost` approximates.
+pub const APPROXIMATION_MAX_DEGREES: f64 = 1.0;
+/// Bound of relative error of `SphereConnection::approximate_cost`, about 25 times its worst case.
+pub const APPROXIMATION_TOLERANCE: f64 = 1e-3;
+
+const GEOMETRY_EPSILON: f64 = 1e-12;
+
+// sharper corners, turning by more than 120 degrees, are beveled instead of mitered
+const MITER_LIMIT: f64 = 0.5;
+
+/// Returns polyline parallel to given one at central `angle` in radians, to the right of travel
+/// direction for positive angles and to the left for negative ones. Repeated points are ignored.
+/// Corners are mitered, or beveled when very sharp, and loops made by offsetting tight inner turns
+/// further than they are wide are cut out at their self-intersections.
+pub(crate) fn offset_polyline(points: &[SpherePoint], angle: f64) -> Vec<SpherePoint> {
+    let mut vectors: Vec<Vector3> = points.iter().map(SpherePoint::to_vector).collect();
+    vectors.dedup_by(|a, b| norm(&cross(a, b)) < GEOMETRY_EPSILON && dot(a, b) > 0.0);
+    if vectors.len() < 2 {
+        return vectors.iter().map(SpherePoint::from_vector).collect();
+    }
+    // right-hand normals of great circles of consecutive segments
+    let normals: Vec<Vector3> = vectors.windows(2).map(|pair| normalize(&cross(&pair[1], &pair[0]))).collect();
+    let shift = |point: &Vector3, direction: &Vector3, angle: f64| {
+        let (sin, cos) = angle.sin_cos();
+        [
+            cos * point[0] + sin * direction[0],
+            cos * point[1] + sin * direction[1],
+            cos * point[2] + sin * direction[2],
+        ]
+    };
+    let mut offset: Vec<Vector3> = vec![shift(&vectors[0], &normals[0], angle)];
+    for corner in 1..vectors.len() - 1 {
+        let (incoming, outgoing) = (&normals[corner - 1], &normals[corner]);
+        let bisector = [incoming[0] + outgoing[0], incoming[1] + outgoing[1], incoming[2] + outgoing[2]];
+        // cosine of half of the angle between normals, the miter is 1 / half_cosine times longer than offset
+        let half_cosine = norm(&bisector) / 2.0;
+        let miter_sine = angle.sin() / half_cosine;
+        if half_cosine > MITER_LIMIT && miter_sine.abs() <= 1.0 {
+            offset.push(shift(&vectors[corner], &normalize(&bisector), miter_sine.asin()));
+        } else {
+            offset.push(shift(&vectors[corner], incoming, angle));
+            offset.push(shift(&vectors[corner], outgoing, angle));
+        }
+    }
+    offset.push(shift(&vectors[vectors.len() - 1], &normals[normals.len() - 1], angle));
+    remove_loops(offset.iter().map(SpherePoint::from_vector).collect())
+}
+
+/// Returns polyline with every corner replaced by quadratic Bézier curve built from great-circle
+/// interpolation (spherical de Casteljau), starting and finishing on segments around the corner.
+/// Curves leave the corner by at most `max_deviation` central angle in radians and use at most
+/// half of each adjacent segment, one curve point is added per 5 degrees of turn.
+pub(crate) fn smooth_polyline(points: &[SpherePoint], max_deviation: f64) -> Vec<SpherePoint> {
+    if points.len() < 3 || max_deviation <= 0.0 {
+        return points.to_vec();
+    }
+    let mut result: Vec<SpherePoint> = vec![points[0].clone()];
+    for corner in 1..points.len() - 1 {
+        let to_previous = SphereConnection::new(points[corner].clone(), points[corner - 1].clone());
+        let to_next = SphereConnection::new(points[corner].clone(), points[corner + 1].clone());
+        let (previous_length, next_length) = (to_previous.central_angle(), to_next.central_angle());
+        let center = points[corner].to_vector();
+        let tangent = |point: &SpherePoint| {
+            let vector = point.to_vector();
+            let along = dot(&vector, &center);
+            normalize(&[vector[0] - along * center[0], vector[1] - along * center[1], vector[2] - along * center[2]])
+        };
+        if previous_length < GEOMETRY_EPSILON || next_length < GEOMETRY_EPSILON {
+            result.push(points[corner].clone());
+            continue;
+        }
+        // interior angle between both segments at the corner, PI for straight line
+        let interior = dot(&tangent(&points[corner - 1]), &tangent(&points[corner + 1])).clamp(-1.0, 1.0).acos();
+        let turn = std::f64::consts::PI - interior;
+        if turn < GEOMETRY_EPSILON {
+            result.push(points[corner].clone());
+            continue;
+        }
+        // midpoint of quadratic Bézier curve is half of arm length times cosine of half interior angle from the corner
+        let arm = (2.0 * max_deviation / (interior / 2.0).cos().max(GEOMETRY_EPSILON))
+            .min(previous_length / 2.0)
+            .min(next_length / 2.0);
+        let entry = to_previous.interpolate(arm / previous_length);
+        let exit = to_next.interpolate(arm / next_length);
+        let steps = ((turn.to_degrees() / 5.0).ceil() as usize).max(2);
+        for step in 0..=steps {
+            let fraction = step as f64 / steps as f64;
+            let first = SphereConnection::new(entry.clone(), points[corner].clone()).interpolate(fraction);
+            let second = SphereConnection::new(points[corner].clone(), exit.clone()).interpolate(fraction);
+            result.push(SphereConnection::new(first, second).interpolate(fraction));
+        }
+    }
+    result.push(points[points.len() - 1].clone());
+    result
+}
+
+/// Returns points of polyline kept by Douglas-Peucker simplification, every removed point is
+/// at most `tolerance` central angle in radians from the great-circle arc replacing it.
+/// The first and the last point are always kept.
+pub(crate) fn simplify_polyline(points: &[SpherePoint], tolerance: f64) -> Vec<SpherePoint> {
+    if points.len() < 3 {
+        return points.to_vec();
+    }
+    let mut keep: Vec<bool> = vec![false; points.len()];
+    keep[0] = true;
+    keep[points.len() - 1] = true;
+    let mut ranges: Vec<(usize, usize)> = vec![(0, points.len() - 1)];
+    while let Some((first, last)) = ranges.pop() {
+        let chord = SphereConnection::new(points[first].clone(), points[last].clone());
+        let farthest = (first + 1..last)
+            .map(|index| (index, chord.angular_distance_to(&points[index])))
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        if let Some((index, distance)) = farthest {
+            if distance > tolerance {
+                keep[index] = true;
+                ranges.push((first, index));
+                ranges.push((index, last));
+            }
+        }
+    }
+    points.iter().zip(keep).filter(|(_, keep)| *keep).map(|(point, _)| point.clone()).collect()
+}
+
+/// Cuts out parts of polyline between crossing segments, keeping the crossing point.
+fn remove_loops(mut points: Vec<SpherePoint>) -> Vec<SpherePoint> {
+    let segment = |points: &[SpherePoint], index: usize| SphereConnection::new(points[index].clone(), points[index + 1].clone());
+    'restart: loop {
+        let segments = points.len().saturating_sub(1);
+        for first in 0..segments {
+            for second in (first + 2..segments).rev() {
+                if let Some(crossing) = segment(&points, first).intersection(&segment(&points, second)) {
+                    points.splice(first + 1..=second, [crossing]);
+                    continue 'restart;
+                }
+            }
+        }
+        return points;
+    }
+}
+
+fn is_on_arc(point: &Vector3, start: &Vector3, finish: &Vector3, normal: &Vector3) -> bool {
+    dot(&cross(start, point), normal) >= -GEOMETRY_EPSILON
+        && dot(&cross(point, finish), normal) >= -GEOMETRY_EPSILON
+}
+
+/// # SpherePolygon
+/// Area on sphere bounded by great-circle arcs between consecutive vertices, the last vertex
+/// is connected back to the first one. Polygon is expected to be smaller than a hemisphere.
+///
+/// ## Intersecting path with polygon
+/// ```
+/// use path_navigator::components::*;
+/// let square = SpherePolygon::new(vec![
+///     SpherePoint::new(-1.0, -1.0),
+///     SpherePoint::new(-1.0, 1.0),
+///     SpherePoint::new(1.0, 1.0),
+///     SpherePoint::new(1.0, -1.0),
+/// ]);
+/// let path = vec![SphereConnection::new(SpherePoint::new(0.0, -2.0), SpherePoint::new(0.0, 2.0))];
+/// let intersection = square.intersect_path(&path);
+/// assert_eq!(intersection.crossings.len(), 2);
+/// assert!(intersection.segments[1].inside);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpherePolygon {
+    pub vertices: Vec<SpherePoint>,
+}
+
+/// Point where path crosses polygon boundary.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoundaryCrossing {
+    pub point: SpherePoint,
+    pub connection_index: usize,
+    pub entering: bool,
+}
+
+/// Part of a path connection lying completely inside or outside of polygon.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolygonSegment {
+    pub connection: SphereConnection,
+    pub connection_index: usize,
+    pub inside: bool,
+}
+
+/// Boundary crossings and in/out segments of path intersected with polygon, both in path order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathIntersection {
+    pub crossings: Vec<BoundaryCrossing>,
+    pub segments: Vec<PolygonSegment>,
+}
+
+impl SpherePolygon {
+    pub fn new(vertices: Vec<SpherePoint>) -> Self {
+        Self {vertices}
+    }
+
+    /// Returns the smallest convex polygon containing all given points, with vertices counterclockwise
+    /// seen from above. None when points lie on a single great circle or do not fit in a hemisphere.
+    ///
+    /// # Remarks:
+    ///
+    /// Points are projected from the center of the sphere onto the plane tangent at their mean
+    /// direction (gnomonic projection), which keeps great circles straight, so planar hull of
+    /// projected points is the hull on sphere.
+    ///
+    /// ```
+    /// use path_navigator::components::*;
+    /// let points = vec![
+    ///     SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 2.0), SpherePoint::new(2.0, 2.0),
+    ///     SpherePoint::new(2.0, 0.0), SpherePoint::new(1.0, 1.0),
+    /// ];
+    /// let hull = SpherePolygon::convex_hull(&points).unwrap();
+    /// assert_eq!(hull.vertices.len(), 4);
+    /// assert!(hull.contains(&SpherePoint::new(1.0, 1.0)));
+    /// ```
+    pub fn convex_hull(points: &[SpherePoint]) -> Option<Self> {
+        let vectors: Vec<Vector3> = points.iter().map(SpherePoint::to_vector).collect();
+        let sum = vectors.iter().fold([0.0; 3], |sum, vector| [sum[0] + vector[0], sum[1] + vector[1], sum[2] + vector[2]]);
+        if norm(&sum) < GEOMETRY_EPSILON {
+            return None;
+        }
+        let center = normalize(&sum);
+        if vectors.iter().any(|vector| dot(vector, &center) < GEOMETRY_EPSILON) {
+            return None;
+        }
+        let axis = if center[2].abs() < 0.9 { [0.0, 0.0, 1.0] } else { [1.0, 0.0, 0.0] };
+        let east = normalize(&cross(&axis, &center));
+        let north = cross(&center, &east);
+        let projected: Vec<(f64, f64)> = vectors.iter()
+            .map(|vector| (dot(vector, &east) / dot(vector, &center), dot(vector, &north) / dot(vector, &center)))
+            .collect();
+        let mut order: Vec<usize> = (0..points.len()).collect();
+        order.sort_by(|a, b| projected[*a].0.total_cmp(&projected[*b].0).then(projected[*a].1.total_cmp(&projected[*b].1)));
+        // positive for counterclockwise turn, zero (within precision relative to arms) for collinear points
+        let turn = |o: usize, a: usize, b: usize| {
+            let (o, a, b) = (projected[o], projected[a], projected[b]);
+            let (first, second) = ((a.0 - o.0, a.1 - o.1), (b.0 - o.0, b.1 - o.1));
+            let tolerance = GEOMETRY_EPSILON * first.0.hypot(first.1) * second.0.hypot(second.1);
+            first.0 * second.1 - first.1 * second.0 - tolerance
+        };
+        // Andrew's monotone chain, lower hull then upper hull, collinear points are dropped
+        let mut hull: Vec<usize> = Vec::new();
+        for pass in 0..2 {
+            let floor = hull.len();
+            let indices: Vec<usize> = if pass == 0 { order.clone() } else { order.iter().rev().copied().collect() };
+            for index in indices {
+                while hull.len() >= floor + 2 && turn(hull[hull.len() - 2], hull[hull.len() - 1], index) <= 0.0 {
+                    hull.pop();
+                }
+                hull.push(index);
+            }
+            hull.pop();
+        }
+        if hull.len() < 3 {
+            return None;
+        }
+        Some(Self::new(hull.iter().map(|index| points[*index].clone()).collect()))
+    }
+
+    /// Returns polygon boundary as great-circle connections.
+    pub fn edges(&self) -> Vec<SphereConnection> {
+        let count = self.vertices.len();
+        (0..count)
+            .map(|i| SphereConnection::new(self.vertices[i].clone(), self.vertices[(i + 1) % count].clone()))
+            .filter(|edge| edge.start != edge.finish)
+            .collect()
+    }
+
+    /// Returns true when point lies inside of polygon, using winding of polygon boundary around the point.
+    /// Points on the far hemisphere, as seen from the vertices centroid, are always outside,
+    /// this also distinguishes inside points from their antipodes.
+    pub fn contains(&self, point: &SpherePoint) -> bool {
+        if self.vertices.len() < 3 {
+            return false;
+        }
+        let p = point.to_vector();
+        let centroid = self.vertices.iter()
+            .map(|vertex| vertex.to_vector())
+            .fold([0.0; 3], |sum, v| [sum[0] + v[0], sum[1] + v[1], sum[2] + v[2]]);
+        if dot(&p, &centroid) <= 0.0 {
+            return false;
+        }
+        let winding: f64 = self.edges().iter()
+            .map(|edge| {
+                let a = edge.start.to_vector();
+                let b = edge.finish.to_vector();
+                let y = dot(&p, &cross(&a, &b));
+                let x = dot(&a, &b) - dot(&a, &p) * dot(&b, &p);
+                y.atan2(x)
+            })
+            .sum();
+        winding.abs() > std::f64::consts::PI
+    }
+
+    /// Returns true when great-circle arc of connection has any point inside of polygon or on its
+    /// boundary, including arcs touching a vertex or passing through without an end inside.
+    pub fn intersects(&self, connection: &SphereConnection) -> bool {
+        self.contains(&connection.start) || self.contains(&connection.finish)
+            || self.edges().iter().any(|edge| connection.intersection(edge).is_some())
+    }
+
+    /// Returns points where path crosses polygon boundary together with path split into
+    /// segments lying inside and outside of polygon.
+    pub fn intersect_path(&self, path: &[SphereConnection]) -> PathIntersection {
+        let edges = self.edges();
+        let mut crossings: Vec<BoundaryCrossing> = Vec::new();
+        let mut segments: Vec<PolygonSegment> = Vec::new();
+        for (connection_index, connection) in path.iter().enumerate() {
+            let mut points: Vec<SpherePoint> = edges.iter()
+                .filter_map(|edge| connection.intersection(edge))
+                .collect();
+            let distance_from_start = |point: &SpherePoint|
+                SphereConnection::new(connection.start.clone(), point.clone()).central_angle();
+            points.sort_by(|a, b| distance_from_start(a).total_cmp(&distance_from_start(b)));
+            points.dedup_by(|a, b| SphereConnection::new(a.clone(), b.clone()).central_angle() < 1e-9);
+            let mut pieces_start = connection.start.clone();
+            for point in points.iter().chain(std::iter::once(&connection.finish)) {
+                let piece = SphereConnection::new(pieces_start.clone(), point.clone());
+                if piece.central_angle() < 1e-9 {
+                    continue;
+                }
+                let inside = self.contains(&piece.interpolate(0.5));
+                if let Some(previous) = segments.last() {
+                    if previous.inside != inside {
+                        crossings.push(BoundaryCrossing {point: pieces_start.clone(), connection_index, entering: inside});
+                    }
+                }
+                segments.push(PolygonSegment {connection: piece, connection_index, inside});
+                pieces_start = point.clone();
+            }
+        }
+        PathIntersection {crossings, segments}
+    }
+}
+
+/// Returns true when great-circle arc between two points does not touch any of obstacles, a line of
+/// sight check for free-flight and any-angle planners before they add a shortcut connection.
+/// Arcs grazing obstacle boundary are not clear, see `SpherePolygon::intersects`.
+///
+/// ```
+/// use path_navigator::components::*;
+///
+/// let restricted = SpherePolygon::new(vec![
+///     SpherePoint::new(-1.0, -1.0), SpherePoint::new(-1.0, 1.0), SpherePoint::new(1.0, 1.0), SpherePoint::new(1.0, -1.0),
+/// ]);
+/// let obstacles = vec![restricted];
+/// assert!(!is_clear(&SpherePoint::new(0.0, -2.0), &SpherePoint::new(0.0, 2.0), &obstacles));
+/// assert!(is_clear(&SpherePoint::new(2.0, -2.0), &SpherePoint::new(2.0, 2.0), &obstacles));
+/// ```
+pub fn is_clear(a: &SpherePoint, b: &SpherePoint, obstacles: &[SpherePolygon]) -> bool {
+    let sight = SphereConnection::new(a.clone(), b.clone());
+    !obstacles.iter().any(|obstacle| obstacle.intersects(&sight))
+}
+
+/// Returns points along great-circle arc from `a` to `b` every `spacing_km` kilometers, starting with `a`
+/// and finishing with `b`, so the last gap is the only one which may be shorter. Gives `a` alone when both points
+/// are the same and just both points for not positive or not finite spacing. Antipodal points have no single
+/// great circle between them, so they give points of an arbitrary one.
+///
+/// # Arguments:
+/// * `a` which is &SpherePoint - first point
+/// * `b` which is &SpherePoint - last point
+/// * `spacing_km` which is f64 - distance between consecutive points in kilometers
+/// * `radius` which is f64 - radius of celestial object in kilometers, see `data::get_radius_km`
+///
+/// ```
+/// use path_navigator::components::*;
+///
+/// let link = great_circle_waypoints(&SpherePoint::new(0.0, 0.0), &SpherePoint::new(0.0, 10.0), 500.0, 6371.0);
+/// assert_eq!(link.len(), 4);
+/// let gap = SphereConnection::new(link[1].clone(), link[2].clone()).cost(6371.0);
+/// assert!((gap - 500.0).abs() < 1e-6);
+/// ```
+pub fn great_circle_waypoints(a: &SpherePoint, b: &SpherePoint, spacing_km: f64, radius: f64) -> Vec<SpherePoint> {
+    if a == b {
+        return vec![a.clone()];
+    }
+    let arc = SphereConnection::new(a.clone(), b.clone());
+    let length = arc.central_angle() * radius;
+    if !spacing_km.is_finite() || spacing_km <= 0.0 {
+        return vec![a.clone(), b.clone()];
+    }
+    // spacing dividing the arc up to rounding must not leave a tiny last gap
+    let gaps = (length / spacing_km * (1.0 - GEOMETRY_EPSILON)).ceil().max(1.0) as usize;
+    let mut points: Vec<SpherePoint> = (0..gaps).map(|i| arc.interpolate(i as f64 * spacing_km / length)).collect();
+    points[0] = a.clone();
+    points.push(b.clone());
+    points
+}
+
+/// # SphereCap
+/// Part of sphere within `angular_radius` (central angle in radians) from `center`,
+/// like area visible from a satellite or covered by a sensor.
+///
+/// ```
+/// use path_navigator::components::*;
+/// let cap = SphereCap::new(SpherePoint::new(0.0, 0.0), 2.0_f64.to_radians());
+/// let passing = SphereConnection::new(SpherePoint::new(1.0, -5.0), SpherePoint::new(1.0, 5.0));
+/// assert!(cap.contains(&SpherePoint::new(1.0, 1.0)));
+/// assert!(cap.intersects(&passing));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct SphereCap {
+    pub center: SpherePoint,
+    pub angular_radius: f64,
+}
+
+impl SphereCap {
+    pub fn new(center: SpherePoint, angular_radius: f64) -> Self {
+        Self {center, angular_radius}
+    }
+
+    pub fn contains(&self, point: &SpherePoint) -> bool {
+        SphereConnection::new(self.center.clone(), point.clone()).central_angle() <= self.angular_radius
+    }
+
+    /// Returns true when any point of the connection lies within the cap.
+    pub fn intersects(&self, connection: &SphereConnection) -> bool {
+        connection.angular_distance_to(&self.center) <= self.angular_radius
+    }
+
+    /// Returns true when the whole connection lies within the cap, which for caps not larger
+    /// than a hemisphere is when both ends do.
+    pub fn covers(&self, connection: &SphereConnection) -> bool {
+        self.contains(&connection.start) && self.contains(&connection.finish)
+    }
+}
+
+/// # LocalProjection
+/// Orthographic projection of the sphere onto the plane tangent at `origin`, with coordinates in
+/// kilometers towards east (x) and north (y).
+///
+/// # Remarks:
+///
+/// Euclidean distance between projected points is faster than haversine and does not suffer from
+/// cancellation on tiny coordinate deltas, but projection error grows with distance from origin
+/// (about 0.01% at 100 km on Earth), so it is meant for graphs spanning a few kilometers.
+///
+/// ```
+/// use path_navigator::components::*;
+///
+/// let first = SpherePoint::new(52.2297, 21.0122);
+/// let second = SpherePoint::new(52.2300, 21.0130);
+/// let bbox = SphereBoundingBox::from_points(&[first.clone(), second.clone()]).unwrap();
+/// let projection = LocalProjection::new(&bbox, 6371.0);
+/// let planar = projection.distance(&first, &second);
+/// let haversine = SphereConnection::new(first, second).cost(6371.0);
+/// assert!((planar - haversine).abs() < 1e-6);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalProjection {
+    pub origin: SpherePoint,
+    pub radius: f64,
+    east: Vector3,
+    north: Vector3,
+}
+
+impl LocalProjection {
+    /// Creates projection with origin in the center of given bounding box on sphere with given radius.
+    pub fn new(bbox: &SphereBoundingBox, radius: f64) -> Self {
+        let origin = bbox.center();
+        let lat = origin.lat.to_radians();
+        let lng = origin.lng.to_radians();
+        let east = [-lng.sin(), lng.cos(), 0.0];
+        let north = [-lat.sin() * lng.cos(), -lat.sin() * lng.sin(), lat.cos()];
+        Self {origin, radius, east, north}
+    }
+
+    /// Returns planar (x, y) coordinates of point in kilometers.
+    pub fn project(&self, point: &SpherePoint) -> (f64, f64) {
+        let vector = point.to_vector();
+        (self.radius * dot(&vector, &self.east), self.radius * dot(&vector, &self.north))
+    }
+
+    /// Returns Euclidean distance between projected points in kilometers.
+    pub fn distance(&self, first: &SpherePoint, second: &SpherePoint) -> f64 {
+        let (x_1, y_1) = self.project(first);
+        let (x_2, y_2) = self.project(second);
+        (x_2 - x_1).hypot(y_2 - y_1)
+    }
+}
+
+impl Metric for LocalProjection {
+    fn distance(&self, a: &SpherePoint, b: &SpherePoint) -> f64 {
+        LocalProjection::distance(self, a, b)
+    }
+}
+
 #[cfg(test)]
 mod components_tests {
    use super::*;
@@ -175,5 +1052,156 @@ mod components_tests {
        // then
        relative_eq!(0.284, short_connection.cost(radius));
    }
-}
 
+   #[test]
+   fn test_trans_polar_connection() {
+       // given: flight over North Pole along meridians 0 and 180
+       let flight = SphereConnection::new(SpherePoint::new(80.0, 0.0), SpherePoint::new(80.0, 180.0));
+       let from_pole = SphereConnection::new(SpherePoint::new(90.0, 45.0), SpherePoint::new(60.0, -100.0));
+       let to_south_pole = SphereConnection::new(SpherePoint::new(-60.0, 30.0), SpherePoint::new(-90.0, 0.0));
+       // when
+       let over_pole = flight.interpolate(0.5);
+       let before_pole = flight.interpolate(0.25);
+       let after_pole = flight.interpolate(0.75);
+       // then
+       assert!(relative_eq!(flight.central_angle(), 20_f64.to_radians(), max_relative = 1e-12));
+       assert!(over_pole.is_pole());
+       assert_eq!(over_pole.lng, 0.0);
+       assert!(relative_eq!(before_pole.lat, 85.0, max_relative = 1e-9) && before_pole.lng.abs() < 1e-9);
+       assert!(relative_eq!(after_pole.lat, 85.0, max_relative = 1e-9));
+       assert!(relative_eq!(after_pole.lng.abs(), 180.0, max_relative = 1e-9));
+       assert!(flight.initial_bearing().abs() < 1e-9);
+       assert!(relative_eq!(flight.final_bearing(), 180.0, max_relative = 1e-9));
+       assert_eq!(from_pole.initial_bearing(), 180.0);
+       assert!(relative_eq!(to_south_pole.initial_bearing(), 180.0, max_relative = 1e-9));
+       assert_eq!(to_south_pole.final_bearing(), 180.0);
+       assert!(relative_eq!(SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)).initial_bearing(), 90.0, max_relative = 1e-12));
+   }
+
+   #[test]
+   fn test_distance_formulas() {
+       // given
+       let radius = 6371.0;
+       let tiny = SphereConnection::new(SpherePoint::new(54.0, 18.0), SpherePoint::new(54.000_000_5, 18.000_000_5));
+       let long = SphereConnection::new(SpherePoint::new(54.35, 18.65), SpherePoint::new(-33.9, 151.2));
+       let antipodal = SphereConnection::new(SpherePoint::new(10.0, 20.0), SpherePoint::new(-10.0, -160.0 + 1e-7));
+       // when
+       let tiny_vincenty = tiny.cost_with(radius, DistanceFormula::Vincenty);
+       // then: equirectangular distance is exact for such small deltas
+       let (dlat, dlng) = (0.000_000_5_f64.to_radians(), 0.000_000_5_f64.to_radians() * 54.0_f64.to_radians().cos());
+       let expected = radius * (dlat.powi(2) + dlng.powi(2)).sqrt();
+       assert!(relative_eq!(tiny_vincenty, expected, max_relative = 1e-6));
+       assert!(relative_eq!(long.cost_with(radius, DistanceFormula::Vincenty), long.cost(radius), max_relative = 1e-12));
+       assert_eq!(long.cost_with(radius, DistanceFormula::Haversine), long.cost(radius));
+       assert!(antipodal.cost_with(radius, DistanceFormula::Vincenty) < std::f64::consts::PI * radius);
+   }
+
+   #[test]
+   fn test_cap_intersects_connection() {
+       // given
+       let cap = SphereCap::new(SpherePoint::new(10.0, 0.0), 1.0_f64.to_radians());
+       let through_middle = SphereConnection::new(SpherePoint::new(10.5, -5.0), SpherePoint::new(10.5, 5.0));
+       let ending_inside = SphereConnection::new(SpherePoint::new(30.0, 0.0), SpherePoint::new(10.2, 0.1));
+       let passing_by = SphereConnection::new(SpherePoint::new(12.0, -5.0), SpherePoint::new(12.0, 5.0));
+       let behind_end = SphereConnection::new(SpherePoint::new(10.0, 2.0), SpherePoint::new(10.0, 20.0));
+       // when, then
+       assert!(cap.intersects(&through_middle));
+       assert!(cap.intersects(&ending_inside));
+       assert!(!cap.intersects(&passing_by));
+       assert!(!cap.intersects(&behind_end));
+       assert!(!cap.covers(&ending_inside));
+       let to_nearest_end = SphereConnection::new(cap.center.clone(), behind_end.start.clone()).central_angle();
+       assert!(relative_eq!(behind_end.angular_distance_to(&cap.center), to_nearest_end, max_relative = 1e-12));
+   }
+
+   #[test]
+   fn test_polygon_contains() {
+       // given
+       let triangle = SpherePolygon::new(vec![
+           SpherePoint::new(0.0, 0.0),
+           SpherePoint::new(0.0, 10.0),
+           SpherePoint::new(10.0, 5.0),
+       ]);
+       // when, then
+       assert!(triangle.contains(&SpherePoint::new(3.0, 5.0)));
+       assert!(!triangle.contains(&SpherePoint::new(-3.0, 5.0)));
+       assert!(!triangle.contains(&SpherePoint::new(-5.0, -175.0)));
+   }
+
+   #[test]
+   fn test_path_polygon_intersection() {
+       // given
+       let square = SpherePolygon::new(vec![
+           SpherePoint::new(-1.0, -1.0),
+           SpherePoint::new(-1.0, 1.0),
+           SpherePoint::new(1.0, 1.0),
+           SpherePoint::new(1.0, -1.0),
+       ]);
+       let path = vec![
+           SphereConnection::new(SpherePoint::new(0.0, -2.0), SpherePoint::new(0.0, 0.0)),
+           SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(2.0, 0.0)),
+       ];
+       // when
+       let intersection = square.intersect_path(&path);
+       // then
+       assert_eq!(intersection.crossings.len(), 2);
+       assert!(intersection.crossings[0].entering);
+       assert_eq!(intersection.crossings[0].connection_index, 0);
+       assert!(relative_eq!(intersection.crossings[0].point.lng, -1.0, max_relative = 1e-9));
+       assert!(!intersection.crossings[1].entering);
+       assert_eq!(intersection.crossings[1].connection_index, 1);
+       assert!(relative_eq!(intersection.crossings[1].point.lat, 1.0, max_relative = 1e-3));
+       let inside: Vec<bool> = intersection.segments.iter().map(|segment| segment.inside).collect();
+       assert_eq!(inside, vec![false, true, true, false]);
+   }
+
+   #[test]
+   fn test_line_of_sight() {
+       // given: a square no-go zone and a thin triangle across the antimeridian
+       let square = SpherePolygon::new(vec![
+           SpherePoint::new(-1.0, -1.0),
+           SpherePoint::new(-1.0, 1.0),
+           SpherePoint::new(1.0, 1.0),
+           SpherePoint::new(1.0, -1.0),
+       ]);
+       let sliver = SpherePolygon::new(vec![
+           SpherePoint::new(10.0, 179.0),
+           SpherePoint::new(10.0, -179.0),
+           SpherePoint::new(11.0, 180.0),
+       ]);
+       let obstacles = vec![square, sliver];
+       let point = |lat: f64, lng: f64| SpherePoint::new(lat, lng);
+       // when, then
+       assert!(!is_clear(&point(0.0, -5.0), &point(0.0, 5.0), &obstacles));
+       assert!(!is_clear(&point(0.0, 0.0), &point(0.5, 0.5), &obstacles));
+       assert!(!is_clear(&point(0.5, -5.0), &point(0.0, 0.0), &obstacles));
+       assert!(!is_clear(&point(10.3, 170.0), &point(10.3, -170.0), &obstacles));
+       assert!(!is_clear(&point(-2.0, 1.0), &point(2.0, 1.0), &obstacles));
+       assert!(is_clear(&point(1.5, -5.0), &point(1.5, 5.0), &obstacles));
+       assert!(is_clear(&point(12.0, 170.0), &point(12.0, -170.0), &obstacles));
+       assert!(is_clear(&point(0.0, -5.0), &point(0.0, 5.0), &[]));
+   }
+
+   #[test]
+   fn test_great_circle_waypoints() {
+       // given: London to New York on Earth
+       let radius = get_radius_km(&CelestialObject::EARTH);
+       let (london, new_york) = (SpherePoint::new(51.5, -0.13), SpherePoint::new(40.71, -74.0));
+       let length = SphereConnection::new(london.clone(), new_york.clone()).cost(radius);
+       // when
+       let waypoints = great_circle_waypoints(&london, &new_york, 250.0, radius);
+       let exact = great_circle_waypoints(&london, &new_york, length / 4.0, radius);
+       // then
+       assert_eq!(waypoints.len(), (length / 250.0).ceil() as usize + 1);
+       assert_eq!((waypoints.first(), waypoints.last()), (Some(&london), Some(&new_york)));
+       for pair in waypoints[..waypoints.len() - 1].windows(2) {
+           assert!(relative_eq!(SphereConnection::new(pair[0].clone(), pair[1].clone()).cost(radius), 250.0, max_relative = 1e-9));
+       }
+       let along: f64 = waypoints.windows(2).map(|pair| SphereConnection::new(pair[0].clone(), pair[1].clone()).cost(radius)).sum();
+       assert!(relative_eq!(along, length, max_relative = 1e-9));
+       assert_eq!(exact.len(), 5);
+       assert_eq!(great_circle_waypoints(&london, &london, 250.0, radius), vec![london.clone()]);
+       assert_eq!(great_circle_waypoints(&london, &new_york, 0.0, radius).len(), 2);
+       assert_eq!(great_circle_waypoints(&london, &new_york, 1e9, radius).len(), 2);
+   }
+}
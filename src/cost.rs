@@ -0,0 +1,328 @@
+use crate::errors::*;
+use crate::vertex::*;
+use crate::components::{SphereConnection, SpherePoint};
+use crate::data::get_radius_km;
+use crate::dijkstra::{snap_query, search_edge_ids};
+use crate::elevation::{grade, ElevationProvider};
+use crate::path::Path;
+use crate::rng::SplitMix64;
+use crate::hashing::from_node_index;
+use std::cell::Cell;
+
+/// Metadata key with travel time of connection in hours.
+pub const DURATION_KEY: &str = "duration_h";
+/// Metadata key with travel speed on connection in kilometers per hour.
+pub const SPEED_KEY: &str = "speed_kmh";
+
+/// # CostModel
+/// Weighted sum of connection properties evaluated at query time, like
+/// `0.7 * time + 0.3 * distance + toll_penalty`.
+///
+/// * `distance_weight` - weight of `GraphRelation::cost`, which is haversine distance in kilometers unless changed
+/// * `duration_weight` - weight of travel time in hours, taken from `DURATION_KEY` metadata or computed
+///   from distance and `SPEED_KEY` metadata, or `default_speed_kmh` when connection has neither
+/// * `penalties` - metadata keys with weights, numeric values are multiplied by weight, `true` and `yes`
+///   count as 1.0, missing or other values as 0.0
+/// * `max_grade` - hard limit of grade, uphill or downhill, like 0.08 for 8 %, None by default.
+///   Connections steeper than the limit are not travelled, see `find_path_with_elevation`.
+/// * `jitter` - seeded random perturbation of connection costs, None by default, see CostJitter
+///
+/// `CostModel::default()` is plain distance, the objective of `dijkstra::find_path`.
+///
+/// ```
+/// use path_navigator::components::*;
+/// use path_navigator::cost::*;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::vertex::VertexBuffer;
+///
+/// let connections = vec![SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0))];
+/// let mut vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+/// vertex.set_edge_metadata(0, "toll", "true").unwrap();
+/// vertex.set_edge_metadata(0, DURATION_KEY, "2.5").unwrap();
+/// let model = CostModel {
+///     distance_weight: 0.0,
+///     duration_weight: 1.0,
+///     penalties: vec![("toll".to_string(), 10.0)],
+///     ..CostModel::default()
+/// };
+/// assert_eq!(model.edge_cost(&vertex, &vertex.vector[0].graphs[0]), 12.5);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct CostModel {
+    pub distance_weight: f64,
+    pub duration_weight: f64,
+    pub default_speed_kmh: f64,
+    pub penalties: Vec<(String, f64)>,
+    pub max_grade: Option<f64>,
+    pub jitter: Option<CostJitter>,
+}
+
+/// # CostJitter
+/// Seeded random perturbation of connection costs for a single query, so repeated identical requests,
+/// like a fleet leaving a depot, spread over routes of nearly the same cost. Every connection cost is
+/// multiplied by a factor from `[1 - amplitude, 1 + amplitude]` which depends only on `seed` and EdgeId,
+/// so a query is reproducible from its seed and both directions of a connection are perturbed alike.
+///
+/// ```
+/// use path_navigator::components::*;
+/// use path_navigator::cost::*;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::vertex::VertexBuffer;
+///
+/// let connections = vec![SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0))];
+/// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+/// let graph = &vertex.vector[0].graphs[0];
+/// let model = CostModel {jitter: Some(CostJitter {seed: 42, amplitude: 0.05}), ..CostModel::default()};
+/// let jittered = model.edge_cost(&vertex, graph);
+/// assert_ne!(jittered, graph.cost);
+/// assert!((jittered / graph.cost - 1.0).abs() <= 0.05);
+/// assert_eq!(jittered, model.edge_cost(&vertex, graph));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CostJitter {
+    pub seed: u64,
+    pub amplitude: f64,
+}
+
+impl CostJitter {
+    /// Returns factor connection with given EdgeId is multiplied by.
+    pub fn factor(&self, edge_id: EdgeId) -> f64 {
+        let mut rng = SplitMix64::new(self.seed ^ (edge_id as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+        1.0 + self.amplitude * (2.0 * rng.next_f64() - 1.0)
+    }
+}
+
+impl Default for CostModel {
+    fn default() -> Self {
+        Self {
+            distance_weight: 1.0,
+            duration_weight: 0.0,
+            default_speed_kmh: 50.0,
+            penalties: Vec::new(),
+            max_grade: None,
+            jitter: None,
+        }
+    }
+}
+
+impl CostModel {
+    /// Returns cost of connection under this model.
+    pub fn edge_cost(&self, vertex: &VertexBuffer, graph: &GraphRelation) -> f64 {
+        let metadata = vertex.edge_metadata(from_node_index(graph.edge_id));
+        let value = |key: &str| metadata.and_then(|metadata| metadata.get(key)).and_then(|value| parse_value(value));
+        let mut cost = self.distance_weight * graph.cost;
+        if self.duration_weight != 0.0 {
+            let duration = value(DURATION_KEY)
+                .unwrap_or_else(|| graph.cost / value(SPEED_KEY).unwrap_or(self.default_speed_kmh));
+            cost += self.duration_weight * duration;
+        }
+        for (key, weight) in &self.penalties {
+            cost += weight * value(key).unwrap_or(0.0);
+        }
+        match &self.jitter {
+            Some(jitter) => cost * jitter.factor(from_node_index(graph.edge_id)),
+            None => cost,
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        let weights = [self.distance_weight, self.duration_weight];
+        let is_invalid = |weight: &f64| !weight.is_finite() || *weight < 0.0;
+        if weights.iter().any(is_invalid) || self.penalties.iter().any(|(_, weight)| is_invalid(weight))
+            || self.default_speed_kmh.partial_cmp(&0.0) != Some(std::cmp::Ordering::Greater)
+            || self.max_grade.is_some_and(|max_grade| is_invalid(&max_grade))
+            || self.jitter.is_some_and(|jitter| is_invalid(&jitter.amplitude) || jitter.amplitude >= 1.0) {
+            return Err(Error::from_kind(ErrorKind::InvalidParameter));
+        }
+        Ok(())
+    }
+}
+
+fn parse_value(value: &str) -> Option<f64> {
+    match value {
+        "true" | "yes" => Some(1.0),
+        _ => value.parse::<f64>().ok(),
+    }
+}
+
+/// Returns Path minimizing cost given by CostModel between two given points, see `dijkstra::find_path`.
+/// Costs of the returned Path are CostModel costs.
+///
+/// Fails with `InvalidParameter` for negative or not finite weights, not positive default speed or
+/// jitter amplitude outside of [0.0, 1.0), and with `NegativeCost` when metadata makes cost of any
+/// explored connection negative. Slope limit needs elevations, so CostModel with `max_grade` fails with `InvalidParameter`,
+/// use `find_path_with_elevation` instead.
+pub fn find_path_with_model(start: &SpherePoint, finish: &SpherePoint, vertex: &VertexBuffer, model: &CostModel)
+-> Result<Option<Path>> {
+    if model.max_grade.is_some() {
+        return Err(Error::from_kind(ErrorKind::InvalidParameter));
+    }
+    search_with_model(start, finish, vertex, model, |_, _| true)
+}
+
+/// Returns Path minimizing cost given by CostModel like `find_path_with_model`, skipping connections
+/// steeper than `CostModel::max_grade` in either travel direction, as hard constraint for rovers or bicycles.
+/// Connections with unknown elevation of an end are travelled.
+///
+/// ```
+/// use path_navigator::components::*;
+/// use path_navigator::cost::*;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::vertex::VertexBuffer;
+///
+/// let connections = vec![
+///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 0.02)),
+///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.01, 0.01)),
+///     SphereConnection::new(SpherePoint::new(0.01, 0.01), SpherePoint::new(0.0, 0.02)),
+/// ];
+/// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+/// // a 300 m ridge across the direct road
+/// let terrain = |point: &SpherePoint| Some(if point.lat == 0.0 && point.lng > 0.015 { 300.0 } else { 0.0 });
+/// let rover = CostModel {max_grade: Some(0.1), ..CostModel::default()};
+/// let start = SpherePoint::new(0.0, 0.0);
+/// let finish = SpherePoint::new(0.0, 0.02);
+/// let path = find_path_with_elevation(&start, &finish, &vertex, &rover, &terrain).unwrap();
+/// assert!(path.is_none());
+/// ```
+pub fn find_path_with_elevation(start: &SpherePoint, finish: &SpherePoint, vertex: &VertexBuffer, model: &CostModel,
+    elevation: &dyn ElevationProvider) -> Result<Option<Path>> {
+    let radius = get_radius_km(&vertex.celestial_object);
+    search_with_model(start, finish, vertex, model, |from, graph| {
+        let max_grade = match model.max_grade {
+            Some(max_grade) => max_grade,
+            None => return true,
+        };
+        let connection = SphereConnection::new(vertex.vector[from].coordinates.clone(), vertex.vector[from_node_index(graph.vertex_index)].coordinates.clone());
+        grade(&connection, elevation, radius).is_none_or(|grade| grade.abs() <= max_grade)
+    })
+}
+
+/// Searches Path with CostModel costs over connections accepted by `allowed`, called with the node
+/// a connection leaves and its GraphRelation.
+fn search_with_model<F>(start: &SpherePoint, finish: &SpherePoint, vertex: &VertexBuffer, model: &CostModel, allowed: F)
+-> Result<Option<Path>>
+where F: Fn(usize, &GraphRelation) -> bool {
+    model.validate()?;
+    let (start_index, finish_index) = match snap_query(start, finish, vertex) {
+        Some(indices) => indices,
+        None => return Ok(None),
+    };
+    let negative: Cell<Option<(EdgeId, f64)>> = Cell::new(None);
+    let edge_ids = search_edge_ids(start_index, finish_index, vertex, |from, _, graph| {
+        if !allowed(from, graph) {
+            return None;
+        }
+        let cost = model.edge_cost(vertex, graph);
+        if cost.is_nan() || cost < 0.0 {
+            negative.set(Some((from_node_index(graph.edge_id), cost)));
+            return None;
+        }
+        Some(cost)
+    });
+    if let Some((edge_id, cost)) = negative.get() {
+        return Err(Error::from_kind(ErrorKind::NegativeCost(edge_id, cost)));
+    }
+    let mut path = match edge_ids.and_then(|edge_ids| Path::from_edge_ids(start_index, edge_ids, vertex)) {
+        Some(path) => path,
+        None => return Ok(None),
+    };
+    path.costs = path.vertex_indices.iter()
+        .zip(path.edge_ids.iter())
+        .map(|(from, edge_id)| {
+            let graph = vertex.vector[*from].graphs.iter().find(|rel| from_node_index(rel.edge_id) == *edge_id).unwrap();
+            model.edge_cost(vertex, graph)
+        })
+        .collect();
+    Ok(Some(path))
+}
+
+#[cfg(test)]
+mod cost_tests {
+    use super::*;
+    use crate::components::SphereConnection;
+    use crate::data::CelestialObject;
+
+    #[test]
+    fn test_model_avoids_slow_and_tolled_connections() {
+        // given: direct connection 0 - 1 with toll, and a toll-free detour 0 - 2 - 1
+        let start = SpherePoint::new(0.0, 0.0);
+        let detour = SpherePoint::new(0.5, 1.0);
+        let finish = SpherePoint::new(0.0, 2.0);
+        let connections = vec![
+            SphereConnection::new(start.clone(), finish.clone()),
+            SphereConnection::new(start.clone(), detour.clone()),
+            SphereConnection::new(detour, finish.clone()),
+        ];
+        let mut vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        vertex.set_edge_metadata(0, "toll", "yes").unwrap();
+        let fastest = CostModel {distance_weight: 0.0, duration_weight: 1.0, ..CostModel::default()};
+        let without_tolls = CostModel {penalties: vec![("toll".to_string(), 1000.0)], ..CostModel::default()};
+        // when
+        let shortest = find_path_with_model(&start, &finish, &vertex, &CostModel::default()).unwrap().unwrap();
+        let tolls_avoided = find_path_with_model(&start, &finish, &vertex, &without_tolls).unwrap().unwrap();
+        // then
+        assert_eq!(shortest.vertex_indices, vec![0, 1]);
+        assert_eq!(tolls_avoided.vertex_indices, vec![0, 2, 1]);
+        assert!(relative_eq!(fastest.edge_cost(&vertex, &vertex.vector[0].graphs[0]), shortest.cost() / 50.0, max_relative = 1e-12));
+        vertex.set_edge_metadata(0, "toll", "-2000").unwrap();
+        assert!(find_path_with_model(&start, &finish, &vertex, &without_tolls).is_err());
+        assert!(find_path_with_model(&start, &finish, &vertex, &CostModel {default_speed_kmh: 0.0, ..CostModel::default()}).is_err());
+    }
+
+    #[test]
+    fn test_jitter_spreads_identical_requests() {
+        // given: a depot with four parallel routes of the same length to the destination
+        let (depot, destination) = (SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 2.0));
+        let mut connections: Vec<SphereConnection> = Vec::new();
+        for lat in [-0.2, -0.1, 0.1, 0.2] {
+            connections.push(SphereConnection::new(depot.clone(), SpherePoint::new(lat, 1.0)));
+            connections.push(SphereConnection::new(SpherePoint::new(lat, 1.0), destination.clone()));
+        }
+        let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        let route = |seed: u64, amplitude: f64| {
+            let model = CostModel {jitter: Some(CostJitter {seed, amplitude}), ..CostModel::default()};
+            find_path_with_model(&depot, &destination, &vertex, &model).unwrap().unwrap()
+        };
+        // when
+        let fleet: Vec<Path> = (0..20).map(|seed| route(seed, 0.05)).collect();
+        // then
+        let mut middles: Vec<usize> = fleet.iter().map(|path| path.vertex_indices[1]).collect();
+        middles.sort_unstable();
+        middles.dedup();
+        assert!(middles.len() > 1);
+        assert_eq!(route(7, 0.05), route(7, 0.05));
+        assert_eq!(route(7, 0.0).vertex_indices, find_path_with_model(&depot, &destination, &vertex, &CostModel::default()).unwrap().unwrap().vertex_indices);
+        let invalid = CostModel {jitter: Some(CostJitter {seed: 1, amplitude: 1.0}), ..CostModel::default()};
+        assert!(find_path_with_model(&depot, &destination, &vertex, &invalid).is_err());
+    }
+
+    #[test]
+    fn test_slope_limit_excludes_steep_connections() {
+        // given: direct road over a hill at node 1, a longer flat road around it and a node of unknown elevation
+        let connections = vec![
+            SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 0.01)),
+            SphereConnection::new(SpherePoint::new(0.0, 0.01), SpherePoint::new(0.0, 0.02)),
+            SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.01, 0.01)),
+            SphereConnection::new(SpherePoint::new(0.01, 0.01), SpherePoint::new(0.0, 0.02)),
+        ];
+        let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        let hill = SpherePoint::new(0.0, 0.01);
+        let unknown = SpherePoint::new(0.01, 0.01);
+        let terrain = move |point: &SpherePoint| match point {
+            point if *point == hill => Some(120.0),
+            point if *point == unknown => None,
+            _ => Some(0.0),
+        };
+        let (start, finish) = (SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 0.02));
+        let profile = |max_grade: f64| CostModel {max_grade: Some(max_grade), ..CostModel::default()};
+        // when: the hill is about 10.8 % steep both ways
+        let bicycle = find_path_with_elevation(&start, &finish, &vertex, &profile(0.08), &terrain).unwrap().unwrap();
+        let car = find_path_with_elevation(&start, &finish, &vertex, &profile(0.12), &terrain).unwrap().unwrap();
+        // then
+        assert_eq!(bicycle.vertex_indices, vec![0, 3, 2]);
+        assert_eq!(car.vertex_indices, vec![0, 1, 2]);
+        assert_eq!(find_path_with_elevation(&start, &finish, &vertex, &CostModel::default(), &terrain).unwrap().unwrap(), car);
+        assert!(find_path_with_model(&start, &finish, &vertex, &profile(0.08)).is_err());
+        assert!(find_path_with_elevation(&start, &finish, &vertex, &profile(-0.1), &terrain).is_err());
+    }
+}
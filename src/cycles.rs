@@ -0,0 +1,196 @@
+use crate::errors::*;
+use crate::vertex::{EdgeId, VertexBuffer};
+use crate::dijkstra::search_edge_ids;
+use crate::path::Path;
+use crate::hashing::from_node_index;
+use std::collections::VecDeque;
+
+/// Returns fundamental cycles of VertexBuffer, one closed Path per connection outside of a spanning
+/// forest, so the result is empty exactly when the graph has no cycles. Parallel connections and
+/// self-loops are cycles too. Every cycle starts and finishes at the first node of the connection
+/// closing it and its cost is the circuit length.
+///
+/// # Remarks:
+///
+/// Spanning forest is built breadth-first from the lowest node of every component, so cycles go
+/// through its tree paths and are not the shortest ones in general. Every cycle of the graph is
+/// a combination of fundamental cycles, their count is `edge_count - len + component count`.
+/// Fails with `InvalidParameter` on graphs with one-way connections, where fundamental cycles of
+/// the undirected graph cannot be travelled in general; `shortest_cycle_through` works on them.
+///
+/// ```
+/// use path_navigator::components::*;
+/// use path_navigator::cycles::cycle_basis;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::vertex::VertexBuffer;
+///
+/// let connections = vec![
+///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+///     SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(1.0, 1.0)),
+///     SphereConnection::new(SpherePoint::new(1.0, 1.0), SpherePoint::new(0.0, 0.0)),
+///     SphereConnection::new(SpherePoint::new(1.0, 1.0), SpherePoint::new(2.0, 2.0)),
+/// ];
+/// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+/// let cycles = cycle_basis(&vertex).unwrap();
+/// assert_eq!(cycles.len(), 1);
+/// assert_eq!(cycles[0].len(), 3);
+/// assert!(cycles[0].is_closed());
+/// ```
+pub fn cycle_basis(vertex: &VertexBuffer) -> Result<Vec<Path>> {
+    if vertex.is_directed() {
+        return Err(Error::from_kind(ErrorKind::InvalidParameter));
+    }
+    let mut parents: Vec<Option<(usize, EdgeId)>> = vec![None; vertex.len()];
+    let mut depths: Vec<Option<usize>> = vec![None; vertex.len()];
+    let mut tree_edges: Vec<bool> = vec![false; vertex.edge_count()];
+    for root in 0..vertex.len() {
+        if depths[root].is_some() {
+            continue;
+        }
+        depths[root] = Some(0);
+        let mut queue: VecDeque<usize> = VecDeque::from(vec![root]);
+        while let Some(node) = queue.pop_front() {
+            let depth = depths[node].unwrap();
+            for graph in &vertex.vector[node].graphs {
+                let (child, edge_id) = (from_node_index(graph.vertex_index), from_node_index(graph.edge_id));
+                if depths[child].is_none() {
+                    depths[child] = Some(depth + 1);
+                    parents[child] = Some((node, edge_id));
+                    tree_edges[edge_id] = true;
+                    queue.push_back(child);
+                }
+            }
+        }
+    }
+    // only roots have no parent, and climbing stops at the common ancestor before passing a root
+    let climb = |node: usize| parents[node];
+    let cycles = (0..vertex.edge_count())
+        .filter(|edge_id| !tree_edges[*edge_id])
+        .filter_map(|edge_id| {
+            let (from, to) = vertex.edge(edge_id)?;
+            let (mut up, mut down) = (from, to);
+            let mut up_edges: Vec<EdgeId> = Vec::new();
+            let mut down_edges: Vec<EdgeId> = Vec::new();
+            while depths[up] > depths[down] {
+                let (parent, parent_edge) = climb(up)?;
+                up_edges.push(parent_edge);
+                up = parent;
+            }
+            while depths[down] > depths[up] {
+                let (parent, parent_edge) = climb(down)?;
+                down_edges.push(parent_edge);
+                down = parent;
+            }
+            while up != down {
+                let (up_parent, up_edge) = climb(up)?;
+                let (down_parent, down_edge) = climb(down)?;
+                up_edges.push(up_edge);
+                down_edges.push(down_edge);
+                up = up_parent;
+                down = down_parent;
+            }
+            up_edges.extend(down_edges.iter().rev());
+            up_edges.push(edge_id);
+            Path::from_edge_ids(from, up_edges, vertex)
+        })
+        .collect();
+    Ok(cycles)
+}
+
+/// Returns the cheapest cycle starting and finishing at given node, as closed Path which does not
+/// visit any node twice, except a self-loop which is a cycle of single connection. None when
+/// the node is unknown or lies on no cycle.
+///
+/// ```
+/// use path_navigator::components::*;
+/// use path_navigator::cycles::shortest_cycle_through;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::vertex::VertexBuffer;
+///
+/// let connections = vec![
+///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+///     SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(1.0, 1.0)),
+///     SphereConnection::new(SpherePoint::new(1.0, 1.0), SpherePoint::new(0.0, 0.0)),
+///     SphereConnection::new(SpherePoint::new(1.0, 1.0), SpherePoint::new(2.0, 2.0)),
+/// ];
+/// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+/// let patrol = shortest_cycle_through(1, &vertex).unwrap();
+/// assert_eq!(patrol.len(), 3);
+/// assert_eq!(patrol.vertex_indices.first(), Some(&1));
+/// assert!(shortest_cycle_through(3, &vertex).is_none());
+/// ```
+pub fn shortest_cycle_through(vertex_index: usize, vertex: &VertexBuffer) -> Option<Path> {
+    let node = vertex.vector.get(vertex_index)?;
+    node.graphs.iter()
+        .filter_map(|first| {
+            let rest = if from_node_index(first.vertex_index) == vertex_index {
+                Vec::new()
+            } else {
+                search_edge_ids(from_node_index(first.vertex_index), vertex_index, vertex, |_, _, graph| {
+                    if graph.edge_id == first.edge_id { None } else { Some(graph.cost) }
+                })?
+            };
+            let edge_ids: Vec<EdgeId> = std::iter::once(from_node_index(first.edge_id)).chain(rest).collect();
+            Path::from_edge_ids(vertex_index, edge_ids, vertex)
+        })
+        .min_by(|a, b| a.cost().total_cmp(&b.cost()).then_with(|| a.vertex_indices.cmp(&b.vertex_indices)))
+}
+
+#[cfg(test)]
+mod cycles_tests {
+    use super::*;
+    use crate::components::{SphereConnection, SpherePoint};
+    use crate::data::CelestialObject;
+    use crate::vertex::{BuildOptions, EdgePolicy};
+
+    #[test]
+    fn test_cycles_of_two_rings() {
+        // given: small ring 0 - 1 - 2 and large ring 0 - 3 - 4 - 5 sharing node 0, tail 5 - 6,
+        // parallel connection 1 - 2 and self-loop at 6
+        let point = |lat: f64, lng: f64| SpherePoint::new(lat, lng);
+        let connections = vec![
+            SphereConnection::new(point(0.0, 0.0), point(0.0, 1.0)),
+            SphereConnection::new(point(0.0, 1.0), point(1.0, 1.0)),
+            SphereConnection::new(point(1.0, 1.0), point(0.0, 0.0)),
+            SphereConnection::new(point(0.0, 0.0), point(0.0, -3.0)),
+            SphereConnection::new(point(0.0, -3.0), point(-3.0, -3.0)),
+            SphereConnection::new(point(-3.0, -3.0), point(-3.0, 0.0)),
+            SphereConnection::new(point(-3.0, 0.0), point(0.0, 0.0)),
+            SphereConnection::new(point(-3.0, 0.0), point(-4.0, 0.0)),
+            SphereConnection::new(point(1.0, 1.0), point(0.0, 1.0)),
+            SphereConnection::new(point(-4.0, 0.0), point(-4.0, 0.0)),
+        ];
+        let options = BuildOptions {duplicates: EdgePolicy::KeepAll, self_loops: EdgePolicy::KeepAll, ..BuildOptions::default()};
+        let vertex = VertexBuffer::with_options(connections, CelestialObject::EARTH, &options).unwrap();
+        // when
+        let basis = cycle_basis(&vertex).unwrap();
+        let through_shared = shortest_cycle_through(0, &vertex).unwrap();
+        let through_large = shortest_cycle_through(4, &vertex).unwrap();
+        let through_parallel = shortest_cycle_through(1, &vertex).unwrap();
+        let through_loop = shortest_cycle_through(6, &vertex).unwrap();
+        // then
+        assert_eq!(basis.len(), vertex.edge_count() - vertex.len() + 1);
+        assert!(basis.iter().all(|cycle| cycle.is_closed()));
+        // small ring can be travelled either way at the same cost
+        assert_eq!(through_shared.len(), 3);
+        assert!(through_shared.vertex_indices.contains(&1) && through_shared.vertex_indices.contains(&2));
+        assert_eq!(through_large.len(), 4);
+        assert_eq!(through_parallel.len(), 2);
+        assert_eq!(through_loop.edge_ids, vec![9]);
+        assert!(shortest_cycle_through(42, &vertex).is_none());
+        let line = VertexBuffer::new(vec![SphereConnection::new(point(0.0, 0.0), point(0.0, 1.0))], CelestialObject::EARTH).unwrap();
+        assert!(cycle_basis(&line).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_cycles_on_one_way_ring() {
+        // given
+        let ring = crate::fixtures::one_way_ring();
+        // when
+        let basis = cycle_basis(&ring);
+        let patrol = shortest_cycle_through(1, &ring).unwrap();
+        // then
+        assert!(basis.is_err());
+        assert_eq!(patrol.vertex_indices, vec![1, 2, 3, 0, 1]);
+    }
+}
@@ -1,97 +1,182 @@
 use crate::vertex::*;
 use crate::components::{SphereConnection, SpherePoint};
+#[cfg(feature = "fast-math")]
+use crate::components::APPROXIMATION_TOLERANCE;
 use crate::data::get_radius_km;
-use std::f64::INFINITY;
-use std::f64::MAX;
-use std::collections::HashMap;
-
-struct Dijkstra{
-    costs: HashMap<usize, f64>,
-    parents: HashMap<usize, Option<usize>>,
-    start_index: usize,
+use crate::errors::*;
+use crate::instrumentation::{Instrumentation, QueryMetrics};
+use crate::io::geojson;
+use crate::path::Path;
+use crate::cycles::shortest_cycle_through;
+use crate::hashing::{from_node_index, NodeMap};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use std::time::{Duration, Instant};
+
+/// # SearchArena
+/// Reusable label storage of point-to-point searches, like `find_path_in`. Labels are kept in
+/// vectors indexed by node, which is much cheaper than hashing on dense meshes, and only the nodes
+/// touched by the previous search are reset, so many short queries on a large VertexBuffer do not
+/// pay for its size every time. Vectors grow to the node count of VertexBuffer on first use.
+///
+/// ```
+/// use path_navigator::components::*;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::dijkstra::{find_path, find_path_in, SearchArena};
+/// use path_navigator::vertex::VertexBuffer;
+///
+/// let connections = vec![
+///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+///     SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 2.0)),
+/// ];
+/// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+/// let mut arena = SearchArena::with_capacity(vertex.len(), 16);
+/// let (start, finish) = (SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 2.0));
+/// assert_eq!(find_path_in(&mut arena, &start, &finish, &vertex), find_path(&start, &finish, &vertex));
+/// assert_eq!(find_path_in(&mut arena, &finish, &start, &vertex), find_path(&finish, &start, &vertex));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SearchArena {
+    costs: Vec<f64>,
+    parents: Vec<Option<(usize, EdgeId)>>,
+    visited: Vec<bool>,
+    touched: Vec<usize>,
+    frontier: BinaryHeap<FrontierEntry>,
+}
+
+impl SearchArena {
+    /// Returns empty SearchArena, vectors are allocated by the first search.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns SearchArena allocated up front for VertexBuffer of `nodes` nodes and expected
+    /// largest number of `frontier` entries, so searches do not reallocate on the way.
+    pub fn with_capacity(nodes: usize, frontier: usize) -> Self {
+        Self {
+            costs: vec![f64::INFINITY; nodes],
+            parents: vec![None; nodes],
+            visited: vec![false; nodes],
+            touched: Vec::with_capacity(frontier),
+            frontier: BinaryHeap::with_capacity(frontier),
+        }
+    }
+
+    /// Clears labels of the previous search and fits vectors to given node count.
+    fn reset(&mut self, nodes: usize) {
+        for index in self.touched.drain(..) {
+            self.costs[index] = f64::INFINITY;
+            self.parents[index] = None;
+            self.visited[index] = false;
+        }
+        self.frontier.clear();
+        self.costs.resize(nodes, f64::INFINITY);
+        self.parents.resize(nodes, None);
+        self.visited.resize(nodes, false);
+    }
+
+    fn label(&mut self, index: usize, cost: f64, parent: Option<(usize, EdgeId)>) {
+        if self.costs[index] == f64::INFINITY {
+            self.touched.push(index);
+        }
+        self.costs[index] = cost;
+        self.parents[index] = parent;
+    }
+}
+
+/// Point-to-point Dijkstra search with binary heap frontier, settled nodes are never expanded twice,
+/// so a search takes O((V + E) log V) and stops as soon as finish is settled.
+struct Dijkstra {
+    arena: SearchArena,
     finish_index: usize,
-    processed: Vec<usize>,
-    cheapest_vertex_index: usize,
+    settled: usize,
 }
 
 impl Dijkstra {
-    pub fn new(start_index: usize, finish_index: usize) -> Self {
-        let mut costs = HashMap::new();
-        let mut processed = Vec::new();
-        let mut parents = HashMap::new();
-        costs.insert(start_index, 0.0_f64);
-        costs.insert(finish_index, MAX);
-        processed.push(start_index);
-        parents.insert(finish_index, None);
-        Self {
-            costs: costs,
-            parents: parents,
-            start_index: start_index,
-            finish_index: finish_index,
-            processed: processed,
-            cheapest_vertex_index: start_index,
-        }
-    }
-
-    pub fn calculate_path(&mut self, vertex: &VertexBuffer) -> Vec<SphereConnection> {
-        self.search_for_shortest_path_in_vertex(vertex);
-        let mut result: Vec<SphereConnection> = Vec::new();
-        let mut actual_index_from_parent: usize = self.finish_index;
-        let mut current_start_point: SpherePoint;
-        let mut current_end_point: SpherePoint = vertex.vector
-            [self.finish_index]
-            .coordinates
-            .clone();
-        while actual_index_from_parent != self.start_index {
-            actual_index_from_parent = self.parents[&actual_index_from_parent].unwrap(); // all parent are Some(_) as they are walked trough
-            current_start_point = vertex.vector[actual_index_from_parent]
-                .coordinates
-                .clone();
-            result.push(SphereConnection::new(current_start_point.clone(), current_end_point.clone()));
-            current_end_point = current_start_point.clone();
+    pub fn new(start_index: usize, finish_index: usize, vertex: &VertexBuffer) -> Self {
+        Self::with_arena(start_index, finish_index, vertex, SearchArena::new())
+    }
+
+    pub fn with_arena(start_index: usize, finish_index: usize, vertex: &VertexBuffer, mut arena: SearchArena) -> Self {
+        arena.reset(vertex.len());
+        arena.label(start_index, 0.0, None);
+        arena.frontier.push(FrontierEntry::new(start_index, 0.0));
+        Self {arena, finish_index, settled: 0}
+    }
+
+    /// Returns connections of the shortest path in travel order, None when finish is not reachable.
+    pub fn calculate_path(&mut self, vertex: &VertexBuffer) -> Option<Vec<SphereConnection>> {
+        let vertex_indices = self.calculate_vertex_indices(vertex)?;
+        Some(vertex_indices.windows(2)
+            .map(|pair| SphereConnection::new(vertex.vector[pair[0]].coordinates.clone(), vertex.vector[pair[1]].coordinates.clone()))
+            .collect())
+    }
+
+    /// Returns EdgeIds of the shortest path in travel order, which tells apart parallel connections.
+    /// None when finish is not reachable.
+    pub fn calculate_edge_ids(&mut self, vertex: &VertexBuffer) -> Option<Vec<EdgeId>> {
+        if !self.search_for_shortest_path_in_vertex(vertex) {
+            return None;
+        }
+        let mut result: Vec<EdgeId> = Vec::new();
+        let mut current = self.finish_index;
+        // every settled node but start has a parent
+        while let Some((parent, edge_id)) = self.arena.parents[current] {
+            result.push(edge_id);
+            current = parent;
         }
         result.reverse();
-        result
-    }
-
-    fn search_for_shortest_path_in_vertex(&mut self, vertex: &VertexBuffer) {
-        while !self.processed.contains(&self.finish_index) {
-            let mut vertex_index: usize;
-            let iteration_max: usize = vertex.vector[self.cheapest_vertex_index]
-                .graphs
-                .len();
-            for graph_index in 0..iteration_max {
-                vertex_index = vertex.vector[self.cheapest_vertex_index].graphs[graph_index].vertex_index;
-                if !self.processed.contains(&vertex_index) {
-                    let _parent_cost: f64 = self.costs[&self.cheapest_vertex_index];
-                    let _graph_cost: f64 = vertex.vector[self.cheapest_vertex_index].graphs[graph_index].cost;
-                    let _child_cost: f64 = _parent_cost + _graph_cost;
-                    if self.costs.contains_key(&vertex_index) {
-                        if self.costs[&vertex_index] > _child_cost {
-                            *self.costs.get_mut(&vertex_index).unwrap() = _child_cost;
-                            *self.parents.get_mut(&vertex_index).unwrap() = Some(self.cheapest_vertex_index);
-                        }
-                    } else {
-                        self.costs.insert(vertex_index, _child_cost);
-                        self.parents.insert(vertex_index, Some(self.cheapest_vertex_index));
-                    }
-                }
+        Some(result)
+    }
+
+    /// Returns final cost labels, which are those of visited nodes.
+    pub fn settled_costs(&self) -> NodeMap<usize, f64> {
+        self.arena.touched.iter()
+            .filter(|index| self.arena.visited[**index])
+            .map(|index| (*index, self.arena.costs[*index]))
+            .collect()
+    }
+
+    fn calculate_vertex_indices(&mut self, vertex: &VertexBuffer) -> Option<Vec<usize>> {
+        if !self.search_for_shortest_path_in_vertex(vertex) {
+            return None;
+        }
+        let mut result: Vec<usize> = vec![self.finish_index];
+        let mut current = self.finish_index;
+        while let Some((parent, _)) = self.arena.parents[current] {
+            result.push(parent);
+            current = parent;
+        }
+        result.reverse();
+        Some(result)
+    }
+
+    /// Settles nodes in order of cost until finish is settled, returns false when frontier runs out first.
+    fn search_for_shortest_path_in_vertex(&mut self, vertex: &VertexBuffer) -> bool {
+        while !self.arena.visited[self.finish_index] {
+            let entry = match self.arena.frontier.pop() {
+                Some(entry) => entry,
+                None => return false,
+            };
+            // stale entries of nodes reached again at lower cost stay in the heap
+            if self.arena.visited[entry.vertex_index] {
+                continue;
             }
-            let mut min_cost = std::f64::MAX;
-            let mut min_value_index: Option<usize> = None;
-            for (k, v) in &self.costs {
-                if !self.processed.contains(k) {
-                    if min_cost > *v {
-                        min_cost = *v;
-                        min_value_index = Some(*k);
-                    }
+            self.arena.visited[entry.vertex_index] = true;
+            self.settled += 1;
+            for graph in &vertex.vector[entry.vertex_index].graphs {
+                let child = from_node_index(graph.vertex_index);
+                if self.arena.visited[child] {
+                    continue;
+                }
+                let child_cost = entry.cost + graph.cost;
+                if self.arena.costs[child] > child_cost {
+                    self.arena.label(child, child_cost, Some((entry.vertex_index, from_node_index(graph.edge_id))));
+                    self.arena.frontier.push(FrontierEntry::new(child, child_cost));
                 }
-            }
-            if let Some(x) = min_value_index {
-                self.cheapest_vertex_index = x;
-                self.processed.push(self.cheapest_vertex_index);
             }
         }
+        true
     }
 }
 
@@ -106,28 +191,218 @@ impl Dijkstra {
 /// 
 /// This function finds the closest point on precalculated &VertexBuffer to given start and finish points
 /// and starts shortest path calcualtion from this points.
-///
-
+/// Returns None when finish is not reachable from start, and also when both points snap to the same node,
+/// use `route::find_route` to get an empty path with snapping segments in that case.
 pub fn find_shortest_path(start: &SpherePoint, finish: &SpherePoint, vertex: &VertexBuffer) 
 -> Option<Vec<SphereConnection>> {
-    if start == finish || vertex.vector.len() == 0 {
+    let (start_index, finish_index) = snap_query(start, finish, vertex)?;
+    let mut dijkstra = Dijkstra::new(start_index, finish_index, vertex);
+    dijkstra.calculate_path(vertex)
+}
+
+/// Returns Path which is the shortest path between two given points, see `find_shortest_path`.
+///
+/// Unlike `find_shortest_path` the result keeps vertex indices, EdgeIds and costs of the path,
+/// so it tells which of parallel connections between two nodes was used. Like it, returns None
+/// when finish is not reachable or both points snap to the same node, `find_path_with_policy`
+/// tells such queries apart.
+pub fn find_path(start: &SpherePoint, finish: &SpherePoint, vertex: &VertexBuffer) -> Option<Path> {
+    let (start_index, finish_index) = snap_query(start, finish, vertex)?;
+    let mut dijkstra = Dijkstra::new(start_index, finish_index, vertex);
+    Path::from_edge_ids(start_index, dijkstra.calculate_edge_ids(vertex)?, vertex)
+}
+
+/// Returns Path like `find_path`, keeping labels of the search in given SearchArena, so queries
+/// run one after another reuse its storage, see SearchArena.
+pub fn find_path_in(arena: &mut SearchArena, start: &SpherePoint, finish: &SpherePoint, vertex: &VertexBuffer) -> Option<Path> {
+    let (start_index, finish_index) = snap_query(start, finish, vertex)?;
+    let mut dijkstra = Dijkstra::with_arena(start_index, finish_index, vertex, std::mem::take(arena));
+    let edge_ids = dijkstra.calculate_edge_ids(vertex);
+    *arena = dijkstra.arena;
+    Path::from_edge_ids(start_index, edge_ids?, vertex)
+}
+
+/// Returns Path like `find_path` and passes QueryMetrics of the query to given Instrumentation,
+/// once for every call, also when no path is found.
+///
+/// ```
+/// use path_navigator::components::*;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::dijkstra::find_path_instrumented;
+/// use path_navigator::instrumentation::QueryMetrics;
+/// use path_navigator::vertex::VertexBuffer;
+///
+/// let connections = vec![
+///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+///     SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 2.0)),
+/// ];
+/// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+/// let mut records: Vec<QueryMetrics> = Vec::new();
+/// let path = find_path_instrumented(&SpherePoint::new(0.0, 0.0), &SpherePoint::new(0.0, 2.0), &vertex, &mut records);
+/// assert_eq!(path.unwrap().len(), 2);
+/// assert!(records[0].found);
+/// assert_eq!(records[0].nodes_expanded, 3);
+/// ```
+pub fn find_path_instrumented(start: &SpherePoint, finish: &SpherePoint, vertex: &VertexBuffer,
+    instrumentation: &mut dyn Instrumentation) -> Option<Path> {
+    let started = Instant::now();
+    let query = snap_query(start, finish, vertex);
+    let mut metrics = QueryMetrics {snap: started.elapsed(), ..QueryMetrics::default()};
+    let path = query.and_then(|(start_index, finish_index)| {
+        let mut dijkstra = Dijkstra::new(start_index, finish_index, vertex);
+        let searched = Instant::now();
+        let reached = dijkstra.search_for_shortest_path_in_vertex(vertex);
+        metrics.search = searched.elapsed();
+        metrics.nodes_expanded = dijkstra.settled;
+        if !reached {
+            return None;
+        }
+        let reconstructed = Instant::now();
+        let path = Path::from_edge_ids(start_index, dijkstra.calculate_edge_ids(vertex)?, vertex);
+        metrics.reconstruction = reconstructed.elapsed();
+        path
+    });
+    metrics.found = path.is_some();
+    instrumentation.record(&metrics);
+    path
+}
+
+/// Answer of `find_path_with_policy` to a degenerate query, whose start and finish are equal or
+/// snap to the same node.
+///
+/// * `NoPath` - None, like `find_path`, the default
+/// * `EmptyPath` - Path of the single node without connections, of zero cost
+/// * `Error` - fail with `InvalidParameter`
+/// * `Loop` - the cheapest cycle through the node like `cycles::shortest_cycle_through`, None when
+///   the node lies on no cycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DegenerateQuery {
+    #[default]
+    NoPath,
+    EmptyPath,
+    Error,
+    Loop,
+}
+
+/// Returns Path which is the shortest path between two given points like `find_path`, answering
+/// queries whose points snap to the same node as given by DegenerateQuery. Other queries give None
+/// only when finish is not reachable, or VertexBuffer is empty.
+///
+/// ```
+/// use path_navigator::components::*;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::dijkstra::*;
+/// use path_navigator::vertex::VertexBuffer;
+///
+/// let connections = vec![SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0))];
+/// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+/// let (start, finish) = (SpherePoint::new(0.0, 0.0), SpherePoint::new(0.1, 0.1));
+/// let empty = find_path_with_policy(&start, &finish, &vertex, DegenerateQuery::EmptyPath).unwrap().unwrap();
+/// assert_eq!((empty.cost(), empty.vertex_indices), (0.0, vec![0]));
+/// assert!(find_path_with_policy(&start, &finish, &vertex, DegenerateQuery::Error).is_err());
+/// assert!(find_path_with_policy(&start, &finish, &vertex, DegenerateQuery::Loop).unwrap().is_none());
+/// ```
+pub fn find_path_with_policy(start: &SpherePoint, finish: &SpherePoint, vertex: &VertexBuffer, degenerate: DegenerateQuery)
+-> Result<Option<Path>> {
+    if vertex.is_empty() {
+        return Ok(None);
+    }
+    let start_index = get_closest_point(start, vertex);
+    let finish_index = get_closest_point(finish, vertex);
+    if start_index != finish_index {
+        let edge_ids = search_edge_ids(start_index, finish_index, vertex, |_, _, graph| Some(graph.cost));
+        return Ok(edge_ids.and_then(|edge_ids| Path::from_edge_ids(start_index, edge_ids, vertex)));
+    }
+    match degenerate {
+        DegenerateQuery::NoPath => Ok(None),
+        DegenerateQuery::EmptyPath => Ok(Path::from_edge_ids(start_index, Vec::new(), vertex)),
+        DegenerateQuery::Error => Err(Error::from_kind(ErrorKind::InvalidParameter)),
+        DegenerateQuery::Loop => Ok(shortest_cycle_through(start_index, vertex)),
+    }
+}
+
+/// # CostLabels
+/// Final cost labels of a point-to-point search: costs from the start node to every node the search
+/// settled before reaching finish. Nodes not settled have no label, their cost is at least the finish cost.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CostLabels {
+    pub start_index: usize,
+    costs: NodeMap<usize, f64>,
+}
+
+impl CostLabels {
+    /// Returns cost from the start node to given node, None when the search did not settle it.
+    pub fn cost(&self, vertex_index: usize) -> Option<f64> {
+        self.costs.get(&vertex_index).copied()
+    }
+
+    /// Returns number of settled nodes.
+    pub fn len(&self) -> usize {
+        self.costs.len()
+    }
+
+    /// Returns true when no node was settled.
+    pub fn is_empty(&self) -> bool {
+        self.costs.is_empty()
+    }
+}
+
+/// Returns Path like `find_path` together with CostLabels of the search,
+/// so costs of nodes explored on the way can be looked up afterwards.
+///
+/// ```
+/// use path_navigator::components::*;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::dijkstra::find_path_with_labels;
+/// use path_navigator::vertex::VertexBuffer;
+///
+/// let connections = vec![
+///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+///     SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 2.0)),
+/// ];
+/// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+/// let (path, labels) = find_path_with_labels(&SpherePoint::new(0.0, 0.0), &SpherePoint::new(0.0, 2.0), &vertex).unwrap();
+/// assert_eq!(labels.cost(1), Some(path.costs[0]));
+/// assert_eq!(labels.cost(2), Some(path.cost()));
+/// ```
+pub fn find_path_with_labels(start: &SpherePoint, finish: &SpherePoint, vertex: &VertexBuffer) -> Option<(Path, CostLabels)> {
+    let (start_index, finish_index) = snap_query(start, finish, vertex)?;
+    let mut dijkstra = Dijkstra::new(start_index, finish_index, vertex);
+    let path = Path::from_edge_ids(start_index, dijkstra.calculate_edge_ids(vertex)?, vertex)?;
+    Some((path, CostLabels {start_index, costs: dijkstra.settled_costs()}))
+}
+
+/// Snaps start and finish of a query to VertexBuffer, None when there is nothing to search for.
+pub(crate) fn snap_query(start: &SpherePoint, finish: &SpherePoint, vertex: &VertexBuffer) -> Option<(usize, usize)> {
+    if start == finish || vertex.is_empty() {
         return None;
     }
-    let start_index: usize = get_closest_point(&start, &vertex);
-    let finish_index: usize = get_closest_point(&finish, &vertex);
+    let start_index: usize = get_closest_point(start, vertex);
+    let finish_index: usize = get_closest_point(finish, vertex);
     if start_index == finish_index {
         return None;
     }
-    let mut dijkstra = Dijkstra::new(start_index, finish_index);
-    Some(dijkstra.calculate_path(vertex))
+    Some((start_index, finish_index))
 }
 
-fn get_closest_point(point: &SpherePoint, vertex: &VertexBuffer) -> usize {
+pub(crate) fn get_closest_point(point: &SpherePoint, vertex: &VertexBuffer) -> usize {
     let mut index: usize = 0;
-    let mut distance: f64 = INFINITY;
+    let mut distance: f64 = f64::INFINITY;
     let radius = get_radius_km(&vertex.celestial_object);
     vertex.vector.iter().enumerate().for_each(|(i, sphere_point)| {
         let connection = SphereConnection::new(point.clone(), sphere_point.coordinates.clone());
+        // great-circle distance is never shorter than latitude difference, then short connections are
+        // pruned by their approximate distance, so only candidates which may be closer are computed exactly
+        #[cfg(feature = "fast-math")]
+        {
+            if radius * (point.lat - sphere_point.coordinates.lat).abs().to_radians() >= distance {
+                return;
+            }
+            let approximation = connection.approximate_cost(radius);
+            if approximation.is_some_and(|approximation| approximation * (1.0 - APPROXIMATION_TOLERANCE) >= distance) {
+                return;
+            }
+        }
         let local_distance = connection.cost(radius);
         if local_distance < distance {
             distance = local_distance;
@@ -137,11 +412,803 @@ fn get_closest_point(point: &SpherePoint, vertex: &VertexBuffer) -> usize {
     index
 }
 
+/// Entry of the search frontier. Ordering is reversed so `BinaryHeap` pops the cheapest vertex first.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct FrontierEntry {
+    pub vertex_index: usize,
+    pub cost: f64,
+}
+
+impl FrontierEntry {
+    pub fn new(vertex_index: usize, cost: f64) -> Self {
+        Self {vertex_index, cost}
+    }
+}
+
+impl Eq for FrontierEntry {}
+
+impl Ord for FrontierEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost)
+            .then_with(|| other.vertex_index.cmp(&self.vertex_index))
+    }
+}
+
+impl PartialOrd for FrontierEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Depot serving given graph node together with travel cost from the depot to the node.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServiceArea {
+    pub depot: usize,
+    pub cost: f64,
+}
+
+impl ServiceArea {
+    pub fn new(depot: usize, cost: f64) -> Self {
+        Self {depot, cost}
+    }
+}
+
+/// Returns HashMap<usize, ServiceArea> assigning every reachable node of VertexBuffer to its nearest depot.
+///
+/// # Arguments:
+/// * `depots` which is &[SpherePoint] - depot locations, `ServiceArea::depot` is an index into this slice
+/// * `vertex` which is &VertexBuffer - precalculated vertex for avaliable paths on given geometry
+///
+/// # Remarks:
+///
+/// Every depot is snapped to the closest point on VertexBuffer and all depots are searched at once
+/// (multi-source Dijkstra), so each node is processed only one time regardless of depot count.
+/// When several depots snap to the same node the one with the lowest index serves it.
+/// Nodes not reachable from any depot are not present in the result.
+///
+/// ```
+/// use path_navigator::components::*;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::dijkstra::find_service_areas;
+/// use path_navigator::vertex::VertexBuffer;
+///
+/// let connections = vec![
+///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+///     SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 2.0)),
+/// ];
+/// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+/// let depots = vec![SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 2.0)];
+/// let areas = find_service_areas(&depots, &vertex);
+/// assert_eq!(areas[&0].depot, 0);
+/// assert_eq!(areas[&2].depot, 1);
+/// ```
+pub fn find_service_areas(depots: &[SpherePoint], vertex: &VertexBuffer) -> HashMap<usize, ServiceArea> {
+    if depots.is_empty() || vertex.is_empty() {
+        return HashMap::new();
+    }
+    let sources: Vec<usize> = depots.iter().map(|point| get_closest_point(point, vertex)).collect();
+    let labels = search_from_sources(&sources, vertex);
+    labels.settled_costs()
+        .filter_map(|(vertex_index, cost)| labels.sources[vertex_index].map(|depot| (vertex_index, ServiceArea::new(depot, cost))))
+        .collect()
+}
+
+/// # ShortestPathTree
+/// Result of one-to-many search: cheapest cost from root node to every reachable node
+/// and the parent connection through which each node (except root) is reached.
+///
+/// ## Exporting tree
+/// ```
+/// use path_navigator::components::*;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::dijkstra::shortest_path_tree;
+/// use path_navigator::vertex::VertexBuffer;
+///
+/// let connections = vec![
+///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(1.0, 0.0)),
+/// ];
+/// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+/// let tree = shortest_path_tree(&SpherePoint::new(0.0, 0.0), &vertex).unwrap();
+/// let tree_vertex = tree.to_vertex_buffer(&vertex).unwrap();
+/// let geojson = tree.to_geojson(&vertex);
+/// assert_eq!(tree_vertex.len(), 3);
+/// assert!(geojson.starts_with("{\"type\":\"FeatureCollection\""));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShortestPathTree {
+    pub root: usize,
+    pub costs: HashMap<usize, f64>,
+    pub parents: HashMap<usize, (usize, EdgeId)>,
+}
+
+impl ShortestPathTree {
+    /// Returns path from root to given node following parent connections, None for unreachable node.
+    pub fn path_to(&self, node: usize, vertex: &VertexBuffer) -> Option<Path> {
+        if !self.costs.contains_key(&node) {
+            return None;
+        }
+        let mut edge_ids: Vec<EdgeId> = Vec::new();
+        let mut current = node;
+        while let Some((parent, edge_id)) = self.parents.get(&current) {
+            edge_ids.push(*edge_id);
+            current = *parent;
+        }
+        edge_ids.reverse();
+        Path::from_edge_ids(self.root, edge_ids, vertex)
+    }
+
+    /// Returns tree connections directed from parent to child, ordered by child cost.
+    pub fn to_connections(&self, vertex: &VertexBuffer) -> Vec<SphereConnection> {
+        self.sorted_children().iter()
+            .map(|child| SphereConnection::new(
+                vertex.vector[self.parents[child].0].coordinates.clone(),
+                vertex.vector[*child].coordinates.clone(),
+            ))
+            .collect()
+    }
+
+    /// Returns tree as a new VertexBuffer on the same celestial object.
+    /// Fails with `DataItemIncorrect` when tree has no connections.
+    pub fn to_vertex_buffer(&self, vertex: &VertexBuffer) -> Result<VertexBuffer> {
+        VertexBuffer::new(self.to_connections(vertex), vertex.celestial_object.clone())
+    }
+
+    /// Returns tree as GeoJSON FeatureCollection with one LineString per parent connection,
+    /// carrying `vertex_index`, `parent_index`, `edge_id` and `cost` (from root) properties.
+    pub fn to_geojson(&self, vertex: &VertexBuffer) -> String {
+        let features: Vec<String> = self.sorted_children().iter()
+            .map(|child| {
+                let (parent, edge_id) = self.parents[child];
+                geojson::line_string_feature(
+                    &[vertex.vector[parent].coordinates.clone(), vertex.vector[*child].coordinates.clone()],
+                    &[
+                        ("vertex_index", child.to_string()),
+                        ("parent_index", parent.to_string()),
+                        ("edge_id", edge_id.to_string()),
+                        ("cost", geojson::number(self.costs[child])),
+                    ],
+                )
+            })
+            .collect();
+        geojson::feature_collection(&features)
+    }
+
+    fn sorted_children(&self) -> Vec<usize> {
+        let mut children: Vec<usize> = self.parents.keys().copied().collect();
+        children.sort_by(|a, b| self.costs[a].total_cmp(&self.costs[b]).then(a.cmp(b)));
+        children
+    }
+}
+
+/// Returns ShortestPathTree rooted at the closest point on VertexBuffer to `origin`,
+/// None for empty VertexBuffer.
+pub fn shortest_path_tree(origin: &SpherePoint, vertex: &VertexBuffer) -> Option<ShortestPathTree> {
+    if vertex.is_empty() {
+        return None;
+    }
+    let root = get_closest_point(origin, vertex);
+    let labels = search_from_sources(&[root], vertex);
+    Some(labels.into_tree(root))
+}
+
+/// Returns ShortestPathTree rooted at the closest point on VertexBuffer to `origin`, grown only until
+/// closest points to all `targets` are settled. Costs and paths to targets are the same as in full
+/// `shortest_path_tree`, other nodes are present only when settled before the last target.
+/// None for empty VertexBuffer.
+///
+/// ```
+/// use path_navigator::components::*;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::dijkstra::targeted_tree;
+/// use path_navigator::vertex::VertexBuffer;
+///
+/// let connections = vec![
+///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+///     SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 2.0)),
+///     SphereConnection::new(SpherePoint::new(0.0, 2.0), SpherePoint::new(0.0, 3.0)),
+/// ];
+/// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+/// let tree = targeted_tree(&SpherePoint::new(0.0, 0.0), &[SpherePoint::new(0.0, 1.0)], &vertex).unwrap();
+/// assert_eq!(tree.path_to(1, &vertex).unwrap().vertex_indices, vec![0, 1]);
+/// assert!(tree.path_to(3, &vertex).is_none());
+/// ```
+pub fn targeted_tree(origin: &SpherePoint, targets: &[SpherePoint], vertex: &VertexBuffer) -> Option<ShortestPathTree> {
+    if vertex.is_empty() {
+        return None;
+    }
+    let root = get_closest_point(origin, vertex);
+    let target_indices: Vec<usize> = targets.iter().map(|point| get_closest_point(point, vertex)).collect();
+    let labels = search_targets(&[root], &target_indices, vertex);
+    Some(labels.into_tree(root))
+}
+
+/// Runs point-to-point search like `find_path` and records its progress for visualization, as GeoJSON
+/// FeatureCollections of Points with `vertex_index`, `state` and `cost` (from start) properties.
+/// State is `settled` for nodes with final cost and `frontier` for nodes reached with tentative cost.
+///
+/// # Arguments:
+/// * `start` which is &SpherePoint - start of the search, snapped to the closest point
+/// * `finish` which is &SpherePoint - finish of the search, snapped to the closest point
+/// * `vertex` which is &VertexBuffer - precalculated vertex for avaliable paths on given geometry
+/// * `interval` which is usize - number of settled nodes between snapshots
+///
+/// # Remarks:
+///
+/// Snapshot is taken after every `interval` settled nodes and once more when the search settles
+/// finish or runs out of nodes, so the last snapshot always shows the final state. Snapshots hold the
+/// whole explored part of the graph, so keep the interval large on big graphs.
+/// Fails with `InvalidParameter` for zero interval and `DataItemIncomplete` for empty VertexBuffer.
+///
+/// ```
+/// use path_navigator::components::*;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::dijkstra::trace_search;
+/// use path_navigator::vertex::VertexBuffer;
+///
+/// let connections = vec![
+///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+///     SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 2.0)),
+/// ];
+/// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+/// let snapshots = trace_search(&SpherePoint::new(0.0, 0.0), &SpherePoint::new(0.0, 2.0), &vertex, 1).unwrap();
+/// assert_eq!(snapshots.len(), 3);
+/// assert!(snapshots[0].contains("\"state\":\"frontier\""));
+/// ```
+pub fn trace_search(start: &SpherePoint, finish: &SpherePoint, vertex: &VertexBuffer, interval: usize) -> Result<Vec<String>> {
+    if interval == 0 {
+        return Err(Error::from_kind(ErrorKind::InvalidParameter));
+    }
+    if vertex.is_empty() {
+        return Err(Error::from_kind(ErrorKind::DataItemIncomplete));
+    }
+    let start_index = get_closest_point(start, vertex);
+    let finish_index = get_closest_point(finish, vertex);
+    let mut costs: Vec<Option<f64>> = vec![None; vertex.len()];
+    let mut tentative: Vec<f64> = vec![f64::INFINITY; vertex.len()];
+    // nodes with a tentative cost, which are the ones shown in snapshots
+    let mut reached: Vec<usize> = Vec::new();
+    let mut settled: usize = 0;
+    let mut frontier: BinaryHeap<FrontierEntry> = BinaryHeap::new();
+    let mut snapshots: Vec<String> = Vec::new();
+    let snapshot = |costs: &[Option<f64>], tentative: &[f64], reached: &[usize]| {
+        let mut nodes: Vec<usize> = reached.to_vec();
+        nodes.sort_unstable();
+        let features: Vec<String> = nodes.iter()
+            .map(|index| {
+                let (state, cost) = match costs[*index] {
+                    Some(cost) => ("settled", cost),
+                    None => ("frontier", tentative[*index]),
+                };
+                geojson::point_feature(&vertex.vector[*index].coordinates, &[
+                    ("vertex_index", index.to_string()),
+                    ("state", format!("\"{}\"", state)),
+                    ("cost", geojson::number(cost)),
+                ])
+            })
+            .collect();
+        geojson::feature_collection(&features)
+    };
+    tentative[start_index] = 0.0;
+    reached.push(start_index);
+    frontier.push(FrontierEntry::new(start_index, 0.0));
+    while let Some(entry) = frontier.pop() {
+        if costs[entry.vertex_index].is_some() {
+            continue;
+        }
+        costs[entry.vertex_index] = Some(entry.cost);
+        settled += 1;
+        if entry.vertex_index == finish_index {
+            break;
+        }
+        for graph in &vertex.vector[entry.vertex_index].graphs {
+            let child = from_node_index(graph.vertex_index);
+            let child_cost = entry.cost + graph.cost;
+            if costs[child].is_none() && tentative[child] > child_cost {
+                if tentative[child] == f64::INFINITY {
+                    reached.push(child);
+                }
+                tentative[child] = child_cost;
+                frontier.push(FrontierEntry::new(child, child_cost));
+            }
+        }
+        if settled.is_multiple_of(interval) {
+            snapshots.push(snapshot(&costs, &tentative, &reached));
+        }
+    }
+    snapshots.push(snapshot(&costs, &tentative, &reached));
+    Ok(snapshots)
+}
+
+/// Limits of `find_path_with_budget`, none by default.
+///
+/// * `max_cost` - search stops before settling a node more expensive than this
+/// * `max_settled` - search stops after settling this many nodes
+/// * `max_duration` - search stops after running this long
+/// * `partial` - when search stops early, return incomplete PartialPath toward finish instead of
+///   failing with `Aborted`, false by default
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct SearchBudget {
+    pub max_cost: Option<f64>,
+    pub max_settled: Option<usize>,
+    pub max_duration: Option<Duration>,
+    pub partial: bool,
+}
+
+/// Result of `find_path_with_budget`. Incomplete Path ends at the settled node closest to finish,
+/// `remaining_distance` is great-circle distance from its last node to finish in kilometers,
+/// 0.0 for complete Path.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartialPath {
+    pub path: Path,
+    pub is_complete: bool,
+    pub remaining_distance: f64,
+}
+
+/// Finds the cheapest Path like `find_path` within SearchBudget, for real-time planners which
+/// prefer a usable answer in time over the best one too late.
+///
+/// # Arguments:
+/// * `start` which is &SpherePoint - start point, snapped to the closest point on VertexBuffer
+/// * `finish` which is &SpherePoint - finish point, snapped to the closest point on VertexBuffer
+/// * `vertex` which is &VertexBuffer - precalculated vertex for avaliable paths on given geometry
+/// * `budget` which is &SearchBudget - limits of the search
+/// * `keep_going` which is FnMut() -> bool - called after every settled node, returning false cancels the search
+///
+/// # Remarks:
+///
+/// When search stops early the partial answer goes to the settled node with the lowest great-circle
+/// distance to finish, so it makes the most progress toward finish known so far, though not
+/// necessarily along the cheapest complete route. Without `SearchBudget::partial` early stop fails
+/// with `Aborted`. Returns None for empty VertexBuffer or unreachable finish, and Path without
+/// connections when start and finish snap to the same node. Fails with `InvalidParameter` for
+/// negative or not a number `max_cost`.
+///
+/// ```
+/// use path_navigator::components::*;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::dijkstra::*;
+/// use path_navigator::vertex::VertexBuffer;
+///
+/// let connections: Vec<SphereConnection> = (0..10)
+///     .map(|i| SphereConnection::new(SpherePoint::new(0.0, i as f64), SpherePoint::new(0.0, (i + 1) as f64)))
+///     .collect();
+/// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+/// let budget = SearchBudget {max_settled: Some(4), partial: true, ..SearchBudget::default()};
+/// let (start, finish) = (SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 10.0));
+/// let partial = find_path_with_budget(&start, &finish, &vertex, &budget, || true).unwrap().unwrap();
+/// assert!(!partial.is_complete);
+/// assert_eq!(partial.path.vertex_indices, vec![0, 1, 2, 3]);
+/// ```
+pub fn find_path_with_budget<F>(start: &SpherePoint, finish: &SpherePoint, vertex: &VertexBuffer, budget: &SearchBudget,
+    mut keep_going: F) -> Result<Option<PartialPath>>
+where F: FnMut() -> bool {
+    if budget.max_cost.is_some_and(|max_cost| max_cost.is_nan() || max_cost < 0.0) {
+        return Err(Error::from_kind(ErrorKind::InvalidParameter));
+    }
+    if vertex.is_empty() {
+        return Ok(None);
+    }
+    let started = Instant::now();
+    let start_index = get_closest_point(start, vertex);
+    let finish_index = get_closest_point(finish, vertex);
+    let max_cost = budget.max_cost.unwrap_or(f64::INFINITY);
+    let mut costs: Vec<Option<f64>> = vec![None; vertex.len()];
+    let mut parents: Vec<Option<(usize, EdgeId)>> = vec![None; vertex.len()];
+    let mut tentative: Vec<f64> = vec![f64::INFINITY; vertex.len()];
+    let mut settled: Vec<usize> = Vec::new();
+    let mut frontier: BinaryHeap<FrontierEntry> = BinaryHeap::new();
+    tentative[start_index] = 0.0;
+    frontier.push(FrontierEntry::new(start_index, 0.0));
+    let mut stopped = false;
+    while let Some(entry) = frontier.pop() {
+        if costs[entry.vertex_index].is_some() {
+            continue;
+        }
+        if entry.cost > max_cost {
+            stopped = true;
+            break;
+        }
+        costs[entry.vertex_index] = Some(entry.cost);
+        settled.push(entry.vertex_index);
+        if entry.vertex_index == finish_index {
+            break;
+        }
+        if budget.max_settled.is_some_and(|max_settled| settled.len() >= max_settled)
+            || budget.max_duration.is_some_and(|max_duration| started.elapsed() > max_duration)
+            || !keep_going() {
+            stopped = true;
+            break;
+        }
+        for graph in &vertex.vector[entry.vertex_index].graphs {
+            let child = from_node_index(graph.vertex_index);
+            let child_cost = entry.cost + graph.cost;
+            if costs[child].is_none() && tentative[child] > child_cost {
+                tentative[child] = child_cost;
+                parents[child] = Some((entry.vertex_index, from_node_index(graph.edge_id)));
+                frontier.push(FrontierEntry::new(child, child_cost));
+            }
+        }
+    }
+    let is_complete = costs[finish_index].is_some();
+    if !is_complete && !stopped {
+        return Ok(None);
+    }
+    if !is_complete && !budget.partial {
+        return Err(Error::from_kind(ErrorKind::Aborted));
+    }
+    let to_finish = |node: usize| vertex.great_circle_length(&SphereConnection::new(
+        vertex.vector[node].coordinates.clone(),
+        vertex.vector[finish_index].coordinates.clone(),
+    ));
+    let (end_index, remaining_distance) = if is_complete {
+        (finish_index, 0.0)
+    } else {
+        settled.iter()
+            .filter_map(|node| costs[*node].map(|cost| (*node, to_finish(*node), cost)))
+            .min_by(|a, b| a.1.total_cmp(&b.1).then(a.2.total_cmp(&b.2)).then(a.0.cmp(&b.0)))
+            .map(|(node, distance, _)| (node, distance))
+            .unwrap_or((start_index, to_finish(start_index)))
+    };
+    let mut edge_ids: Vec<EdgeId> = Vec::new();
+    let mut current = end_index;
+    // parents of settled nodes are final
+    while let Some((parent, edge_id)) = parents[current] {
+        edge_ids.push(edge_id);
+        current = parent;
+    }
+    edge_ids.reverse();
+    Ok(Path::from_edge_ids(start_index, edge_ids, vertex).map(|path| PartialPath {path, is_complete, remaining_distance}))
+}
+
+/// Node of `find_path_with_max_hops` reached with given hops, from the state at `parent` over its EdgeId.
+#[derive(Clone, Copy)]
+struct HopState {
+    node: usize,
+    hops: usize,
+    parent: Option<(usize, EdgeId)>,
+}
+
+/// Returns the cheapest Path between two given points using at most `max_hops` connections, for
+/// networks where every hop has a fixed overhead, like a transfer or a battery swap.
+///
+/// # Arguments:
+/// * `start` which is &SpherePoint - start point, snapped to the closest point on VertexBuffer
+/// * `finish` which is &SpherePoint - finish point, snapped to the closest point on VertexBuffer
+/// * `vertex` which is &VertexBuffer - precalculated vertex for avaliable paths on given geometry
+/// * `max_hops` which is usize - maximal number of connections of the path
+///
+/// # Remarks:
+///
+/// Search runs over states of node and number of hops taken to it, so the cheapest path may be
+/// ignored for a dearer one with fewer hops. A state is pruned when its node was already settled
+/// with no more hops, as it can not lead anywhere cheaper, so the search visits each node at most
+/// `max_hops + 1` times. `max_hops` above number of nodes less one does not limit the path.
+/// Returns None like `find_path`, and also when finish needs more hops.
+///
+/// ```
+/// use path_navigator::components::*;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::dijkstra::find_path_with_max_hops;
+/// use path_navigator::vertex::VertexBuffer;
+///
+/// let connections = vec![
+///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+///     SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 2.0)),
+///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(1.0, 2.0)),
+///     SphereConnection::new(SpherePoint::new(1.0, 2.0), SpherePoint::new(0.0, 2.0)),
+/// ];
+/// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+/// let (start, finish) = (SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 2.0));
+/// assert_eq!(find_path_with_max_hops(&start, &finish, &vertex, 2).unwrap().edge_ids, vec![0, 1]);
+/// assert!(find_path_with_max_hops(&start, &finish, &vertex, 1).is_none());
+/// ```
+pub fn find_path_with_max_hops(start: &SpherePoint, finish: &SpherePoint, vertex: &VertexBuffer, max_hops: usize)
+-> Option<Path> {
+    let (start_index, finish_index) = snap_query(start, finish, vertex)?;
+    // a simple path never takes more hops than there are nodes less one
+    let max_hops = max_hops.min(vertex.len() - 1);
+    // frontier entries point into `states` rather than nodes, as a node may be reached with different hops
+    let mut states: Vec<HopState> = vec![HopState {node: start_index, hops: 0, parent: None}];
+    let mut fewest_hops: Vec<Option<usize>> = vec![None; vertex.len()];
+    let mut frontier: BinaryHeap<FrontierEntry> = BinaryHeap::new();
+    frontier.push(FrontierEntry::new(0, 0.0));
+    let mut reached: Option<usize> = None;
+    while let Some(entry) = frontier.pop() {
+        let HopState {node, hops, ..} = states[entry.vertex_index];
+        if fewest_hops[node].is_some_and(|settled_hops| settled_hops <= hops) {
+            continue;
+        }
+        fewest_hops[node] = Some(hops);
+        if node == finish_index {
+            reached = Some(entry.vertex_index);
+            break;
+        }
+        if hops == max_hops {
+            continue;
+        }
+        for graph in &vertex.vector[node].graphs {
+            let child = from_node_index(graph.vertex_index);
+            if fewest_hops[child].is_none_or(|settled_hops| settled_hops > hops + 1) {
+                states.push(HopState {node: child, hops: hops + 1, parent: Some((entry.vertex_index, from_node_index(graph.edge_id)))});
+                frontier.push(FrontierEntry::new(states.len() - 1, entry.cost + graph.cost));
+            }
+        }
+    }
+    let mut edge_ids: Vec<EdgeId> = Vec::new();
+    let mut current = reached?;
+    while let Some((parent, edge_id)) = states[current].parent {
+        edge_ids.push(edge_id);
+        current = parent;
+    }
+    edge_ids.reverse();
+    Path::from_edge_ids(start_index, edge_ids, vertex)
+}
+
+/// Returns cost of the cheapest path between closest points of VertexBuffer to given points when it
+/// is at most `max_cost`, None when finish is not reachable within it. Meant for screening many
+/// origin-destination pairs for feasibility: the search keeps no parents and builds no Path, and
+/// it stops as soon as the frontier gets dearer than `max_cost`.
+///
+/// # Remarks:
+///
+/// Points snapping to the same node are reachable at cost 0.0. Empty VertexBuffer reaches nothing.
+/// Fails with `InvalidParameter` for negative or not a number `max_cost`, infinity means no limit.
+///
+/// ```
+/// use path_navigator::components::*;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::dijkstra::is_reachable;
+/// use path_navigator::vertex::VertexBuffer;
+///
+/// let connections = vec![SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0))];
+/// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+/// let (start, finish) = (SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0));
+/// assert!(is_reachable(&start, &finish, &vertex, 200.0).unwrap().is_some());
+/// assert!(is_reachable(&start, &finish, &vertex, 100.0).unwrap().is_none());
+/// ```
+pub fn is_reachable(start: &SpherePoint, finish: &SpherePoint, vertex: &VertexBuffer, max_cost: f64) -> Result<Option<f64>> {
+    if max_cost.is_nan() || max_cost < 0.0 {
+        return Err(Error::from_kind(ErrorKind::InvalidParameter));
+    }
+    if vertex.is_empty() {
+        return Ok(None);
+    }
+    let start_index = get_closest_point(start, vertex);
+    let finish_index = get_closest_point(finish, vertex);
+    let mut settled: Vec<bool> = vec![false; vertex.len()];
+    let mut tentative: Vec<f64> = vec![f64::INFINITY; vertex.len()];
+    let mut frontier: BinaryHeap<FrontierEntry> = BinaryHeap::new();
+    tentative[start_index] = 0.0;
+    frontier.push(FrontierEntry::new(start_index, 0.0));
+    while let Some(entry) = frontier.pop() {
+        if entry.cost > max_cost {
+            break;
+        }
+        if entry.vertex_index == finish_index {
+            return Ok(Some(entry.cost));
+        }
+        if settled[entry.vertex_index] {
+            continue;
+        }
+        settled[entry.vertex_index] = true;
+        for graph in &vertex.vector[entry.vertex_index].graphs {
+            let child = from_node_index(graph.vertex_index);
+            let child_cost = entry.cost + graph.cost;
+            if child_cost <= max_cost && !settled[child] && tentative[child] > child_cost {
+                tentative[child] = child_cost;
+                frontier.push(FrontierEntry::new(child, child_cost));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Point-to-point heap Dijkstra with connection costs given by `cost`, called with the node a
+/// connection leaves, position of the relation in its `graphs` and the relation itself.
+/// Connections with `None` cost are skipped. Returns EdgeIds of the cheapest path in travel order,
+/// None when finish is not reachable.
+pub(crate) fn search_edge_ids<F>(start_index: usize, finish_index: usize, vertex: &VertexBuffer, cost: F) -> Option<Vec<EdgeId>>
+where F: Fn(usize, usize, &GraphRelation) -> Option<f64> {
+    search_edge_ids_with_extra(start_index, finish_index, vertex, &NodeMap::default(), cost).0
+}
+
+/// Like `search_edge_ids`, with `extra` relations leaving nodes besides their `graphs`, taken at their
+/// own costs. Used for query-time connections which are not stored in VertexBuffer.
+/// Returns number of settled nodes too.
+pub(crate) fn search_edge_ids_with_extra<F>(start_index: usize, finish_index: usize, vertex: &VertexBuffer,
+    extra: &NodeMap<usize, Vec<GraphRelation>>, cost: F) -> (Option<Vec<EdgeId>>, usize)
+where F: Fn(usize, usize, &GraphRelation) -> Option<f64> {
+    // labels are indexed by node, extra relations only lead to nodes of VertexBuffer
+    let mut settled: Vec<bool> = vec![false; vertex.len()];
+    let mut parents: Vec<Option<(usize, EdgeId)>> = vec![None; vertex.len()];
+    let mut tentative: Vec<f64> = vec![f64::INFINITY; vertex.len()];
+    let mut frontier: BinaryHeap<FrontierEntry> = BinaryHeap::new();
+    let mut explored = 0;
+    tentative[start_index] = 0.0;
+    frontier.push(FrontierEntry::new(start_index, 0.0));
+    while let Some(entry) = frontier.pop() {
+        if settled[entry.vertex_index] {
+            continue;
+        }
+        settled[entry.vertex_index] = true;
+        explored += 1;
+        if entry.vertex_index == finish_index {
+            break;
+        }
+        let stored = vertex.vector[entry.vertex_index].graphs.iter().enumerate()
+            .map(|(relation, graph)| (graph, cost(entry.vertex_index, relation, graph)));
+        let injected = extra.get(&entry.vertex_index).into_iter().flatten().map(|graph| (graph, Some(graph.cost)));
+        for (graph, graph_cost) in stored.chain(injected) {
+            let child = from_node_index(graph.vertex_index);
+            if settled[child] {
+                continue;
+            }
+            let child_cost = match graph_cost {
+                Some(graph_cost) => entry.cost + graph_cost,
+                None => continue,
+            };
+            if tentative[child] > child_cost {
+                tentative[child] = child_cost;
+                parents[child] = Some((entry.vertex_index, from_node_index(graph.edge_id)));
+                frontier.push(FrontierEntry::new(child, child_cost));
+            }
+        }
+    }
+    if !settled[finish_index] {
+        return (None, explored);
+    }
+    let mut edge_ids: Vec<EdgeId> = Vec::new();
+    let mut current = finish_index;
+    while let Some((parent, edge_id)) = parents[current] {
+        edge_ids.push(edge_id);
+        current = parent;
+    }
+    edge_ids.reverse();
+    (Some(edge_ids), explored)
+}
+
+/// Final labels of a search settling every node reachable from any of the sources, indexed by node.
+/// Nodes which were not settled have no labels.
+pub(crate) struct SearchLabels {
+    pub costs: Vec<Option<f64>>,
+    pub parents: Vec<Option<(usize, EdgeId)>>,
+    pub sources: Vec<Option<usize>>,
+    /// settled nodes in order of settling
+    pub settled: Vec<usize>,
+}
+
+impl SearchLabels {
+    /// Returns settled nodes with their costs in order of settling.
+    pub fn settled_costs(&self) -> impl Iterator<Item = (usize, f64)> + '_ {
+        self.settled.iter().filter_map(move |node| self.costs[*node].map(|cost| (*node, cost)))
+    }
+
+    /// Returns ShortestPathTree of settled nodes grown from `root`.
+    pub fn into_tree(self, root: usize) -> ShortestPathTree {
+        let costs = self.settled_costs().collect();
+        let parents = self.settled.iter()
+            .filter_map(|node| self.parents[*node].map(|parent| (*node, parent)))
+            .collect();
+        ShortestPathTree {root, costs, parents}
+    }
+}
+
+/// Multi-source Dijkstra, `SearchLabels::sources` maps each node to index of the source it is reached from.
+/// When several sources share a node the one with the lowest index owns it.
+pub(crate) fn search_from_sources(sources: &[usize], vertex: &VertexBuffer) -> SearchLabels {
+    search_until_settled(sources, Stop::Never, vertex)
+}
+
+/// Multi-source Dijkstra as `search_from_sources` which stops as soon as all `targets` are settled,
+/// or when all of them turn out to be unreachable. Labels of nodes settled so far are final.
+pub(crate) fn search_targets(sources: &[usize], targets: &[usize], vertex: &VertexBuffer) -> SearchLabels {
+    search_until_settled(sources, Stop::AllOf(targets), vertex)
+}
+
+/// Multi-source Dijkstra as `search_targets` which stops at the first settled target and returns it,
+/// None when no target is reachable.
+pub(crate) fn search_first_target(sources: &[usize], targets: &[usize], vertex: &VertexBuffer)
+-> (SearchLabels, Option<usize>) {
+    let labels = search_until_settled(sources, Stop::AnyOf(targets), vertex);
+    let mut reached: Vec<(f64, usize)> = targets.iter()
+        .filter_map(|target| labels.costs.get(*target).copied().flatten().map(|cost| (cost, *target)))
+        .collect();
+    reached.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+    let first = reached.first().map(|(_, target)| *target);
+    (labels, first)
+}
+
+/// Multi-source Dijkstra as `search_from_sources` which settles only nodes with cost up to `budget`.
+pub(crate) fn search_within(sources: &[usize], budget: f64, vertex: &VertexBuffer) -> SearchLabels {
+    search_until_settled(sources, Stop::Within(budget), vertex)
+}
+
+enum Stop<'a> {
+    Never,
+    AllOf(&'a [usize]),
+    AnyOf(&'a [usize]),
+    Within(f64),
+}
+
+fn search_until_settled(sources: &[usize], stop: Stop, vertex: &VertexBuffer) -> SearchLabels {
+    let nodes = vertex.len();
+    let mut is_target: Vec<bool> = vec![false; nodes];
+    let (mut remaining, any_of) = match stop {
+        Stop::Never | Stop::Within(_) => (None, false),
+        Stop::AllOf(targets) | Stop::AnyOf(targets) => {
+            // targets outside of VertexBuffer are never settled, but still keep the search going
+            let mut distinct = targets.to_vec();
+            distinct.sort_unstable();
+            distinct.dedup();
+            distinct.iter().filter(|target| **target < nodes).for_each(|target| is_target[*target] = true);
+            (Some(distinct.len()), matches!(stop, Stop::AnyOf(_)))
+        }
+    };
+    let mut labels = SearchLabels {
+        costs: vec![None; nodes],
+        parents: vec![None; nodes],
+        sources: vec![None; nodes],
+        settled: Vec::new(),
+    };
+    let mut tentative: Vec<f64> = vec![f64::INFINITY; nodes];
+    let mut frontier: BinaryHeap<FrontierEntry> = BinaryHeap::new();
+    for (source, vertex_index) in sources.iter().enumerate() {
+        if labels.sources[*vertex_index].is_none() {
+            tentative[*vertex_index] = 0.0;
+            labels.sources[*vertex_index] = Some(source);
+            frontier.push(FrontierEntry::new(*vertex_index, 0.0));
+        }
+    }
+    let budget = if let Stop::Within(budget) = stop { budget } else { f64::INFINITY };
+    while let Some(entry) = frontier.pop() {
+        if labels.costs[entry.vertex_index].is_some() {
+            continue;
+        }
+        if entry.cost > budget {
+            break;
+        }
+        labels.costs[entry.vertex_index] = Some(entry.cost);
+        labels.settled.push(entry.vertex_index);
+        if let Some(remaining) = remaining.as_mut() {
+            let target = is_target[entry.vertex_index];
+            if target {
+                *remaining -= 1;
+            }
+            if *remaining == 0 || (any_of && target) {
+                break;
+            }
+        }
+        let source = labels.sources[entry.vertex_index];
+        for graph in &vertex.vector[entry.vertex_index].graphs {
+            let child = from_node_index(graph.vertex_index);
+            if labels.costs[child].is_some() {
+                continue;
+            }
+            let child_cost = entry.cost + graph.cost;
+            if tentative[child] > child_cost {
+                tentative[child] = child_cost;
+                labels.parents[child] = Some((entry.vertex_index, from_node_index(graph.edge_id)));
+                labels.sources[child] = source;
+                frontier.push(FrontierEntry::new(child, child_cost));
+            }
+        }
+    }
+    // nodes left on the frontier keep no labels
+    for node in 0..nodes {
+        if labels.costs[node].is_none() {
+            labels.parents[node] = None;
+            labels.sources[node] = None;
+        }
+    }
+    labels
+}
+
 #[cfg(test)]
 mod djikstra_tests {
     use super::*;
     use crate::vertex::VertexBuffer;
-    use crate::components::{SphereConnection, SpherePoint};
+    use crate::components::{SphereBoundingBox, SphereConnection, SpherePoint};
     use crate::data::CelestialObject;
 
     #[test]
@@ -207,6 +1274,7 @@ mod djikstra_tests {
     }
 
     #[test]
+    #[allow(clippy::vec_init_then_push, clippy::redundant_pattern_matching, clippy::bool_assert_comparison)]
     fn test_shortest_path_not_possible_to_find() {
         //  when:
         let mut path: Vec<SphereConnection> = Vec::new();
@@ -228,4 +1296,365 @@ mod djikstra_tests {
         };
         assert_eq!(is_path_calculated, false);
     }
+
+    #[test]
+    fn test_find_path() {
+        // given:
+        let connections = vec![
+            SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+            SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 2.0)),
+            SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(5.0, 1.0)),
+            SphereConnection::new(SpherePoint::new(5.0, 1.0), SpherePoint::new(0.0, 2.0)),
+        ];
+        let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        let start = SpherePoint::new(0.0, 0.0);
+        let finish = SpherePoint::new(0.0, 2.0);
+        // when:
+        let path = find_path(&start, &finish, &vertex).unwrap();
+        // then:
+        assert_eq!(path.vertex_indices, vec![0, 1, 2]);
+        assert_eq!(path.edge_ids, vec![0, 1]);
+        assert_eq!(path.connections, find_shortest_path(&start, &finish, &vertex).unwrap());
+    }
+
+    #[test]
+    fn test_find_path_over_parallel_connections() {
+        // given:
+        let ferry = SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0));
+        let bridge = SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 0.0));
+        let options = BuildOptions {duplicates: EdgePolicy::KeepAll, ..BuildOptions::default()};
+        let mut vertex = VertexBuffer::with_options(vec![ferry, bridge], CelestialObject::EARTH, &options).unwrap();
+        // when:
+        vertex.update_costs(&[(0, 500.0)]).unwrap();
+        let path = find_path(&SpherePoint::new(0.0, 0.0), &SpherePoint::new(0.0, 1.0), &vertex).unwrap();
+        // then:
+        assert_eq!(vertex.edges_between(0, 1), vec![0, 1]);
+        assert_eq!(path.edge_ids, vec![1]);
+        assert!(path.cost() < 500.0);
+    }
+
+    #[test]
+    fn test_service_areas() {
+        // given:
+        let mut connections: Vec<SphereConnection> = Vec::new();
+        for i in 0..10 {
+            connections.push(SphereConnection::new(SpherePoint::new(0.0, i as f64), SpherePoint::new(0.0, i as f64 + 1.0)));
+        }
+        let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        let depots = vec![SpherePoint::new(0.1, 0.0), SpherePoint::new(0.0, 8.9)];
+        // when:
+        let areas = find_service_areas(&depots, &vertex);
+        // then:
+        let radius = get_radius_km(&CelestialObject::EARTH);
+        let one_degree = SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)).cost(radius);
+        assert_eq!(areas.len(), vertex.len());
+        for (index, node) in vertex.vector.iter().enumerate() {
+            let area = &areas[&index];
+            let expected_depot = if node.coordinates.lng < 4.5 { 0 } else { 1 };
+            let depot_lng = if expected_depot == 0 { 0.0 } else { 9.0 };
+            assert_eq!(area.depot, expected_depot);
+            assert!(relative_eq!(area.cost, (node.coordinates.lng - depot_lng).abs() * one_degree, max_relative = 1e-9));
+        }
+    }
+
+    #[test]
+    fn test_service_areas_without_depots() {
+        // given:
+        let path = vec![SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(1.0, 1.0))];
+        let vertex = VertexBuffer::new(path, CelestialObject::EARTH).unwrap();
+        // when, then:
+        assert!(find_service_areas(&[], &vertex).is_empty());
+    }
+
+    #[test]
+    fn test_cost_labels_after_search() {
+        // given: a line 0 - 1 - 2 - 3 and a branch 1 - 4 far away
+        let connections = vec![
+            SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+            SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 2.0)),
+            SphereConnection::new(SpherePoint::new(0.0, 2.0), SpherePoint::new(0.0, 3.0)),
+            SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(9.0, 1.0)),
+        ];
+        let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        // when
+        let (path, labels) = find_path_with_labels(&SpherePoint::new(0.0, 0.0), &SpherePoint::new(0.0, 3.0), &vertex).unwrap();
+        // then
+        assert_eq!(path.vertex_indices, vec![0, 1, 2, 3]);
+        assert_eq!(labels.start_index, 0);
+        assert_eq!(labels.cost(0), Some(0.0));
+        assert!(relative_eq!(labels.cost(2).unwrap(), path.costs[0] + path.costs[1], max_relative = 1e-12));
+        assert_eq!(labels.cost(3), Some(path.cost()));
+        assert_eq!(labels.cost(4), None);
+        assert_eq!(labels.len(), 4);
+    }
+
+    #[test]
+    fn test_trace_search_snapshots() {
+        // given: a line 0 - 1 - 2 - 3 - 4 searched from node 2, which settles nodes 2, 1, 3, 0 and 4
+        let longitudes = [0.0, 1.5, 2.0, 3.0, 5.0];
+        let connections: Vec<SphereConnection> = longitudes.windows(2)
+            .map(|pair| SphereConnection::new(SpherePoint::new(0.0, pair[0]), SpherePoint::new(0.0, pair[1])))
+            .collect();
+        let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        // when
+        let snapshots = trace_search(&SpherePoint::new(0.0, 2.0), &SpherePoint::new(0.0, 5.0), &vertex, 2).unwrap();
+        // then
+        let count = |snapshot: &str, state: &str| snapshot.matches(&format!("\"state\":\"{}\"", state)).count();
+        assert_eq!(snapshots.len(), 3);
+        assert_eq!((count(&snapshots[0], "settled"), count(&snapshots[0], "frontier")), (2, 2));
+        assert!(snapshots[0].starts_with("{\"type\":\"FeatureCollection\""));
+        assert!(snapshots[0].contains("\"geometry\":{\"type\":\"Point\",\"coordinates\":[2,0]},\"properties\":{\"vertex_index\":2,\"state\":\"settled\",\"cost\":0}"));
+        assert_eq!((count(&snapshots[1], "settled"), count(&snapshots[1], "frontier")), (4, 1));
+        assert_eq!((count(&snapshots[2], "settled"), count(&snapshots[2], "frontier")), (5, 0));
+        assert!(trace_search(&SpherePoint::new(0.0, 2.0), &SpherePoint::new(0.0, 5.0), &vertex, 0).is_err());
+    }
+
+    #[test]
+    fn test_targeted_search_stops_at_last_target() {
+        // given
+        let connections: Vec<SphereConnection> = (0..10)
+            .map(|i| SphereConnection::new(SpherePoint::new(0.0, i as f64), SpherePoint::new(0.0, i as f64 + 1.0)))
+            .collect();
+        let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        let full = search_from_sources(&[0], &vertex);
+        // when
+        let labels = search_targets(&[0], &[3, 5], &vertex);
+        let unreachable = search_targets(&[0], &[3, 42], &vertex);
+        // then
+        assert_eq!(labels.settled.len(), 6);
+        assert_eq!(labels.costs[5], full.costs[5]);
+        assert_eq!(labels.parents[3], full.parents[3]);
+        assert_eq!(labels.parents[6], None);
+        assert_eq!(unreachable.settled.len(), 11);
+    }
+
+    #[test]
+    fn test_partial_path_on_exceeded_budget() {
+        // given: a line 0 - ... - 10 along the equator with dead end 11 leading back west from node 1
+        let mut connections: Vec<SphereConnection> = (0..10)
+            .map(|i| SphereConnection::new(SpherePoint::new(0.0, i as f64), SpherePoint::new(0.0, (i + 1) as f64)))
+            .collect();
+        connections.push(SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.5, 0.5)));
+        let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        let (start, finish) = (SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 10.0));
+        let partial = SearchBudget {partial: true, ..SearchBudget::default()};
+        let by_cost = SearchBudget {max_cost: Some(3.5 * 111.2), ..partial};
+        let mut calls = 0;
+        // when
+        let cheap = find_path_with_budget(&start, &finish, &vertex, &by_cost, || true).unwrap().unwrap();
+        let cancelled = find_path_with_budget(&start, &finish, &vertex, &partial, || { calls += 1; calls < 2 }).unwrap().unwrap();
+        let generous = find_path_with_budget(&start, &finish, &vertex, &SearchBudget {max_cost: Some(1e5), ..partial}, || true).unwrap().unwrap();
+        // then
+        assert_eq!(cheap.path.vertex_indices, vec![0, 1, 2, 3]);
+        assert!(!cheap.is_complete);
+        assert!(relative_eq!(cheap.remaining_distance, 7.0 * 111.19, max_relative = 1e-3));
+        assert_eq!(cancelled.path.vertex_indices, vec![0, 1]);
+        assert!(generous.is_complete && generous.remaining_distance == 0.0);
+        assert_eq!(generous.path, find_path(&start, &finish, &vertex).unwrap());
+        let strict = SearchBudget {max_settled: Some(2), ..SearchBudget::default()};
+        assert!(matches!(find_path_with_budget(&start, &finish, &vertex, &strict, || true).unwrap_err().kind(), ErrorKind::Aborted));
+        assert!(find_path_with_budget(&start, &finish, &vertex, &SearchBudget {max_cost: Some(-1.0), ..partial}, || true).is_err());
+    }
+
+    #[test]
+    fn test_path_with_max_hops() {
+        // given: a line of 4 connections 0 - 4, a dearer direct connection 0 - 2 which reaches node 2
+        // with fewer hops after it was settled on the line, and a dearer detour 0 - 5 - 4
+        let point = |lat: f64, lng: f64| SpherePoint::new(lat, lng);
+        let mut connections: Vec<SphereConnection> = (0..4)
+            .map(|i| SphereConnection::new(point(0.0, i as f64), point(0.0, (i + 1) as f64)))
+            .collect();
+        connections.push(SphereConnection::new(point(0.0, 0.0), point(1.5, 2.0)));
+        connections.push(SphereConnection::new(point(1.5, 2.0), point(0.0, 4.0)));
+        connections.push(SphereConnection::new(point(0.0, 0.0), point(0.0, 2.0)));
+        let mut vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        vertex.update_costs(&[(6, 250.0)]).unwrap();
+        let (start, finish) = (point(0.0, 0.0), point(0.0, 4.0));
+        // when
+        let unbounded = find_path_with_max_hops(&start, &finish, &vertex, 4).unwrap();
+        let three_hops = find_path_with_max_hops(&start, &finish, &vertex, 3).unwrap();
+        let two_hops = find_path_with_max_hops(&start, &finish, &vertex, 2).unwrap();
+        let huge = find_path_with_max_hops(&start, &finish, &vertex, usize::MAX / 2).unwrap();
+        let max = find_path_with_max_hops(&start, &finish, &vertex, usize::MAX).unwrap();
+        // then
+        assert_eq!(unbounded.edge_ids, vec![0, 1, 2, 3]);
+        assert_eq!(huge, unbounded);
+        assert_eq!(max, unbounded);
+        assert_eq!(unbounded, find_path(&start, &finish, &vertex).unwrap());
+        assert_eq!(three_hops.edge_ids, vec![6, 2, 3]);
+        assert_eq!(two_hops.edge_ids, vec![4, 5]);
+        assert!(unbounded.cost() < three_hops.cost() && three_hops.cost() < two_hops.cost());
+        assert!(find_path_with_max_hops(&start, &finish, &vertex, 1).is_none());
+        assert!(find_path_with_max_hops(&start, &start, &vertex, 3).is_none());
+    }
+
+    #[test]
+    fn test_is_reachable_within_cost() {
+        // given: a line 0 - 1 - 2 and separate connection 3 - 4
+        let connections = vec![
+            SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+            SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 2.0)),
+            SphereConnection::new(SpherePoint::new(5.0, 5.0), SpherePoint::new(5.0, 6.0)),
+        ];
+        let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        let (start, finish) = (SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 2.0));
+        let expected = find_path(&start, &finish, &vertex).unwrap().cost();
+        // when
+        let generous = is_reachable(&start, &finish, &vertex, f64::INFINITY).unwrap();
+        let exact = is_reachable(&start, &finish, &vertex, expected).unwrap();
+        let tight = is_reachable(&start, &finish, &vertex, expected * 0.99).unwrap();
+        // then
+        assert!(relative_eq!(generous.unwrap(), expected, max_relative = 1e-12));
+        assert_eq!(exact, generous);
+        assert_eq!(tight, None);
+        assert_eq!(is_reachable(&start, &SpherePoint::new(5.0, 6.0), &vertex, f64::INFINITY).unwrap(), None);
+        assert_eq!(is_reachable(&start, &SpherePoint::new(0.1, 0.1), &vertex, 0.0).unwrap(), Some(0.0));
+        assert!(is_reachable(&start, &finish, &vertex, f64::NAN).is_err());
+    }
+
+    #[test]
+    fn test_degenerate_query_policies() {
+        // given: a triangle 0 - 1 - 2 with a tail 2 - 3
+        let connections = vec![
+            SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+            SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(1.0, 1.0)),
+            SphereConnection::new(SpherePoint::new(1.0, 1.0), SpherePoint::new(0.0, 0.0)),
+            SphereConnection::new(SpherePoint::new(1.0, 1.0), SpherePoint::new(2.0, 2.0)),
+        ];
+        let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        let on_ring = SpherePoint::new(0.0, 0.0);
+        let on_tail = SpherePoint::new(2.0, 2.0);
+        let find = |point: &SpherePoint, degenerate: DegenerateQuery| find_path_with_policy(point, point, &vertex, degenerate);
+        // when
+        let loop_on_ring = find(&on_ring, DegenerateQuery::Loop).unwrap().unwrap();
+        let empty_on_tail = find(&on_tail, DegenerateQuery::EmptyPath).unwrap().unwrap();
+        // then
+        assert!(loop_on_ring.is_closed() && loop_on_ring.len() == 3);
+        assert_eq!(loop_on_ring.vertex_indices.first(), Some(&0));
+        assert_eq!(empty_on_tail.vertex_indices, vec![3]);
+        assert!(empty_on_tail.edge_ids.is_empty() && empty_on_tail.cost() == 0.0);
+        assert!(find(&on_tail, DegenerateQuery::Loop).unwrap().is_none());
+        assert!(find(&on_ring, DegenerateQuery::default()).unwrap().is_none());
+        assert!(matches!(find(&on_ring, DegenerateQuery::Error).unwrap_err().kind(), ErrorKind::InvalidParameter));
+        let regular = find_path_with_policy(&on_ring, &on_tail, &vertex, DegenerateQuery::Error).unwrap().unwrap();
+        assert_eq!(regular, find_path(&on_ring, &on_tail, &vertex).unwrap());
+    }
+
+    #[test]
+    fn test_shortest_path_tree() {
+        // given:
+        let connections = vec![
+            SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+            SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 2.0)),
+            SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(5.0, 1.0)),
+            SphereConnection::new(SpherePoint::new(5.0, 1.0), SpherePoint::new(0.0, 2.0)),
+        ];
+        let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        // when:
+        let tree = shortest_path_tree(&SpherePoint::new(0.1, 0.1), &vertex).unwrap();
+        // then:
+        assert_eq!(tree.root, 0);
+        assert_eq!(tree.costs.len(), 4);
+        assert_eq!(tree.parents[&2].0, 1);
+        assert_eq!(tree.parents[&3].0, 0);
+        assert_eq!(tree.path_to(2, &vertex).unwrap().vertex_indices, vec![0, 1, 2]);
+        let tree_vertex = tree.to_vertex_buffer(&vertex).unwrap();
+        assert_eq!(tree_vertex.edge_count(), 3);
+        assert_eq!(tree.to_geojson(&vertex).matches("LineString").count(), 3);
+    }
+
+    #[test]
+    fn test_closest_point_matches_exact_scan() {
+        // given: dense nodes around the antimeridian, where short connections wrap longitude
+        let bbox = SphereBoundingBox::new(60.0, 178.0, 62.0, -178.0);
+        let vertex = crate::vertex::generators::random(300, 3.0, &bbox, 11).unwrap();
+        let radius = get_radius_km(&vertex.celestial_object);
+        let mut rng = crate::rng::SplitMix64::new(5);
+        for _ in 0..200 {
+            let query = SpherePoint::new(59.0 + 4.0 * rng.next_f64(), 176.0 + 8.0 * rng.next_f64() - if rng.next_f64() < 0.5 { 360.0 } else { 0.0 });
+            let exact = |index: usize| SphereConnection::new(query.clone(), vertex.vector[index].coordinates.clone()).cost(radius);
+            // when
+            let closest = get_closest_point(&query, &vertex);
+            // then
+            let expected = (0..vertex.len()).min_by(|a, b| exact(*a).total_cmp(&exact(*b))).unwrap();
+            assert_eq!(exact(closest), exact(expected));
+        }
+    }
+
+    #[test]
+    fn test_heap_search_on_random_network() {
+        // given: random network with an island far away
+        let bbox = SphereBoundingBox::new(40.0, 10.0, 44.0, 14.0);
+        let mut connections = crate::vertex::generators::random(2000, 3.0, &bbox, 3).unwrap().to_connections();
+        connections.push(SphereConnection::new(SpherePoint::new(-30.0, 100.0), SpherePoint::new(-30.0, 101.0)));
+        let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        let start = vertex.vector[0].coordinates.clone();
+        let island = SpherePoint::new(-30.0, 100.0);
+        let mut rng = crate::rng::SplitMix64::new(8);
+        for _ in 0..20 {
+            let finish_index = rng.below(vertex.len() - 2);
+            let finish = vertex.vector[finish_index].coordinates.clone();
+            // when
+            let path = find_path(&start, &finish, &vertex);
+            // then
+            let expected = search_edge_ids(0, finish_index, &vertex, |_, _, graph| Some(graph.cost))
+                .and_then(|edge_ids| Path::from_edge_ids(0, edge_ids, &vertex));
+            assert_eq!(path.is_some(), expected.is_some() && finish_index != 0);
+            if let (Some(path), Some(expected)) = (path, expected) {
+                assert!(relative_eq!(path.cost(), expected.cost(), max_relative = 1e-12));
+            }
+        }
+        assert!(find_path(&start, &island, &vertex).is_none());
+        assert!(find_shortest_path(&start, &island, &vertex).is_none());
+        assert!(find_path_with_labels(&start, &island, &vertex).is_none());
+    }
+
+    #[test]
+    fn test_search_arena_reuse() {
+        // given: random network with an island far away, arena sized for a smaller graph
+        let bbox = SphereBoundingBox::new(40.0, 10.0, 44.0, 14.0);
+        let mut connections = crate::vertex::generators::random(500, 3.0, &bbox, 5).unwrap().to_connections();
+        connections.push(SphereConnection::new(SpherePoint::new(-30.0, 100.0), SpherePoint::new(-30.0, 101.0)));
+        let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        let mut arena = SearchArena::with_capacity(10, 4);
+        let mut rng = crate::rng::SplitMix64::new(2);
+        for query in 0..30 {
+            let start = vertex.vector[rng.below(vertex.len())].coordinates.clone();
+            let finish = if query % 5 == 0 {
+                SpherePoint::new(-30.0, 101.0)
+            } else {
+                vertex.vector[rng.below(vertex.len())].coordinates.clone()
+            };
+            // when
+            let path = find_path_in(&mut arena, &start, &finish, &vertex);
+            // then: labels of earlier queries do not leak into later ones
+            assert_eq!(path, find_path(&start, &finish, &vertex));
+        }
+        assert_eq!(arena.costs.len(), vertex.len());
+    }
+
+    #[test]
+    fn test_instrumented_queries() {
+        // given: a line 0 - 1 - 2 and separate connection 3 - 4
+        let connections = vec![
+            SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+            SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 2.0)),
+            SphereConnection::new(SpherePoint::new(5.0, 5.0), SpherePoint::new(5.0, 6.0)),
+        ];
+        let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        let (start, finish, island) = (SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 2.0), SpherePoint::new(5.0, 6.0));
+        let mut records: Vec<QueryMetrics> = Vec::new();
+        // when
+        let path = find_path_instrumented(&start, &finish, &vertex, &mut records);
+        let unreachable = find_path_instrumented(&start, &island, &vertex, &mut records);
+        let degenerate = find_path_instrumented(&start, &start, &vertex, &mut records);
+        // then
+        assert_eq!(path, find_path(&start, &finish, &vertex));
+        assert!(unreachable.is_none() && degenerate.is_none());
+        assert_eq!(records.iter().map(|metrics| (metrics.found, metrics.nodes_expanded)).collect::<Vec<_>>(),
+            vec![(true, 3), (false, 3), (false, 0)]);
+        assert_eq!(records[1].reconstruction, Duration::ZERO);
+        assert_eq!(records[2].search, Duration::ZERO);
+        assert!(records[0].total() >= records[0].search);
+    }
 }
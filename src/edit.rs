@@ -0,0 +1,397 @@
+use crate::errors::*;
+use crate::vertex::{coordinates_key, BuildOptions, Direction, EdgeId, EdgePolicy, VertexBuffer};
+use crate::components::{SphereConnection, SpherePoint};
+use crate::hashing::{from_node_index, NodeSet};
+
+/// Single recorded change of EditSession.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EditOperation {
+    AddConnection(SphereConnection),
+    RemoveEdge(EdgeId),
+}
+
+/// Result of `EditSession::commit`: where stored items moved, as VertexBuffer is rebuilt.
+///
+/// * `edge_ids` - new EdgeId of every EdgeId from before the commit, None for removed connections
+/// * `vertex_indices` - new index of every node from before the commit, None for nodes left without connections
+/// * `added` - EdgeIds of added connections, in order of adding
+#[derive(Debug, Clone, PartialEq)]
+pub struct EditCommit {
+    pub edge_ids: Vec<Option<EdgeId>>,
+    pub vertex_indices: Vec<Option<usize>>,
+    pub added: Vec<EdgeId>,
+}
+
+/// # EditSession
+/// Transactional edits of VertexBuffer for interactive editors: additions and removals of connections
+/// are only recorded, can be undone and redone, and are applied all at once by `commit`.
+///
+/// # Remarks:
+///
+/// Commit rebuilds VertexBuffer, so EdgeIds and node indices change, EditCommit tells how to remap
+/// them. Costs, variances, directions, metadata and shapes of kept connections and nodes are carried over,
+/// parallel connections and self-loops are kept. Added connections are two-way and cost their great-circle
+/// length in kilometers.
+///
+/// ```
+/// use path_navigator::components::*;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::edit::EditSession;
+/// use path_navigator::vertex::VertexBuffer;
+///
+/// let connections = vec![
+///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+///     SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 2.0)),
+/// ];
+/// let mut vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+/// let mut session = EditSession::new(&vertex);
+/// session.remove_edge(0).unwrap();
+/// session.add_connection(SphereConnection::new(SpherePoint::new(0.0, 2.0), SpherePoint::new(1.0, 2.0)));
+/// session.undo();
+/// let commit = session.commit(&mut vertex).unwrap();
+/// assert_eq!(vertex.edge_count(), 1);
+/// assert_eq!(commit.edge_ids, vec![None, Some(0)]);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct EditSession {
+    // stored connections of VertexBuffer the session was started on
+    base: Vec<(SphereConnection, Direction)>,
+    operations: Vec<EditOperation>,
+    undone: Vec<EditOperation>,
+}
+
+impl EditSession {
+    /// Starts a session over given VertexBuffer, which should not be changed until commit.
+    pub fn new(vertex: &VertexBuffer) -> Self {
+        Self {base: vertex.to_directed_connections(), operations: Vec::new(), undone: Vec::new()}
+    }
+
+    /// Returns operations which commit would apply, in order.
+    pub fn operations(&self) -> &[EditOperation] {
+        &self.operations
+    }
+
+    /// Records connection to be added.
+    pub fn add_connection(&mut self, connection: SphereConnection) {
+        self.record(EditOperation::AddConnection(connection));
+    }
+
+    /// Records stored connection to be removed. Fails with `DataItemIncorrect` for unknown EdgeId
+    /// or connection already removed in this session.
+    pub fn remove_edge(&mut self, edge_id: EdgeId) -> Result<()> {
+        if edge_id >= self.base.len() || self.operations.contains(&EditOperation::RemoveEdge(edge_id)) {
+            return Err(Error::from_kind(ErrorKind::DataItemIncorrect));
+        }
+        self.record(EditOperation::RemoveEdge(edge_id));
+        Ok(())
+    }
+
+    /// Reverts the last recorded operation, false when there is nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        match self.operations.pop() {
+            Some(operation) => {
+                self.undone.push(operation);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Records again the last undone operation, false when there is nothing to redo.
+    /// Recording a new operation drops operations which could be redone.
+    pub fn redo(&mut self) -> bool {
+        match self.undone.pop() {
+            Some(operation) => {
+                self.operations.push(operation);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Applies recorded operations to VertexBuffer at once. On error VertexBuffer is left unchanged,
+    /// `DataItemIncorrect` means that it is not the VertexBuffer the session was started on, that
+    /// the edits leave it without connections or that a connection misses its GraphRelation.
+    pub fn commit(self, vertex: &mut VertexBuffer) -> Result<EditCommit> {
+        if vertex.to_directed_connections() != self.base {
+            return Err(Error::from_kind(ErrorKind::DataItemIncorrect));
+        }
+        let edge_count = self.base.len();
+        let mut removed: Vec<bool> = vec![false; edge_count];
+        let mut additions: Vec<(SphereConnection, Direction)> = Vec::new();
+        for operation in &self.operations {
+            match operation {
+                EditOperation::AddConnection(connection) => additions.push((connection.clone(), Direction::TwoWay)),
+                EditOperation::RemoveEdge(edge_id) => removed[*edge_id] = true,
+            }
+        }
+        let kept: Vec<EdgeId> = (0..edge_count).filter(|edge_id| !removed[*edge_id]).collect();
+        let additions_count = additions.len();
+        let connections: Vec<(SphereConnection, Direction)> = kept.iter().map(|edge_id| self.base[*edge_id].clone())
+            .chain(additions)
+            .collect();
+        let options = BuildOptions {duplicates: EdgePolicy::KeepAll, self_loops: EdgePolicy::KeepAll, ..BuildOptions::default()};
+        let mut rebuilt = VertexBuffer::with_directions(connections, vertex.celestial_object.clone(), &options)?;
+        let vertex_indices: Vec<Option<usize>> = vertex.vector.iter().map(|node| rebuilt.index_of(&node.coordinates)).collect();
+        carry_over(vertex, &mut rebuilt, &kept, &vertex_indices, <[SpherePoint]>::to_vec)?;
+        let mut edge_ids: Vec<Option<EdgeId>> = vec![None; edge_count];
+        kept.iter().enumerate().for_each(|(new_id, old_id)| edge_ids[*old_id] = Some(new_id));
+        let added = (kept.len()..kept.len() + additions_count).collect();
+        rebuilt.set_version(vertex.version() + 1);
+        *vertex = rebuilt;
+        Ok(EditCommit {edge_ids, vertex_indices, added})
+    }
+
+    fn record(&mut self, operation: EditOperation) {
+        self.operations.push(operation);
+        self.undone.clear();
+    }
+}
+
+/// Result of `snap_to_grid`.
+///
+/// * `merged_nodes` - number of nodes snapped onto the same grid point as a node of lower index
+/// * `degenerate_edges` - EdgeIds from before snapping of connections whose both ends fell onto the same
+///   grid point, they are removed
+/// * `edge_ids` - new EdgeId of every EdgeId from before snapping, None for degenerate connections
+/// * `vertex_indices` - new index of every node from before snapping, None for nodes left without connections
+#[derive(Debug, Clone, PartialEq)]
+pub struct GridSnap {
+    pub merged_nodes: usize,
+    pub degenerate_edges: Vec<EdgeId>,
+    pub edge_ids: Vec<Option<EdgeId>>,
+    pub vertex_indices: Vec<Option<usize>>,
+}
+
+/// Rounds coordinates of all nodes and edge shapes of VertexBuffer to multiples of `grid` degrees, as
+/// a cleanup of noisy imports where the same junction comes with slightly different coordinates.
+///
+/// # Arguments:
+/// * `vertex` which is &mut VertexBuffer - graph to clean up, rebuilt like by `EditSession::commit`
+/// * `grid` which is f64 - grid step in degrees, like 1e-6 which is about 11 cm on Earth
+///
+/// # Remarks:
+///
+/// Nodes snapped onto the same grid point are merged, connections between them become self-loops and
+/// are removed, connections which already were self-loops are kept. Costs, variances, directions, metadata
+/// and shapes of kept connections are carried over, for merged nodes with the same metadata key the value of the
+/// node with the highest index wins. On error VertexBuffer is left unchanged: `InvalidParameter` for not
+/// positive or not finite `grid`, `DataItemIncorrect` when no connection would be left.
+///
+/// ```
+/// use path_navigator::components::*;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::edit::snap_to_grid;
+/// use path_navigator::vertex::VertexBuffer;
+///
+/// let connections = vec![
+///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 0.01)),
+///     SphereConnection::new(SpherePoint::new(0.0000001, 0.0100002), SpherePoint::new(0.0, 0.02)),
+/// ];
+/// let mut vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+/// let snap = snap_to_grid(&mut vertex, 1e-6).unwrap();
+/// assert_eq!(snap.merged_nodes, 1);
+/// assert_eq!(vertex.len(), 3);
+/// assert_eq!(vertex.edge(1), Some((1, 2)));
+/// ```
+pub fn snap_to_grid(vertex: &mut VertexBuffer, grid: f64) -> Result<GridSnap> {
+    if !grid.is_finite() || grid <= 0.0 {
+        return Err(Error::from_kind(ErrorKind::InvalidParameter));
+    }
+    let round = |point: &SpherePoint| SpherePoint::new((point.lat / grid).round() * grid, (point.lng / grid).round() * grid);
+    let snapped: Vec<SpherePoint> = vertex.vector.iter().map(|node| round(&node.coordinates)).collect();
+    let distinct: NodeSet<(u64, u64)> = snapped.iter().map(coordinates_key).collect();
+    let merged_nodes = snapped.len() - distinct.len();
+    let mut kept: Vec<EdgeId> = Vec::new();
+    let mut degenerate_edges: Vec<EdgeId> = Vec::new();
+    for edge_id in 0..vertex.edge_count() {
+        let (from, to) = vertex.edge(edge_id).unwrap();
+        if from != to && snapped[from] == snapped[to] {
+            degenerate_edges.push(edge_id);
+        } else {
+            kept.push(edge_id);
+        }
+    }
+    let connections: Vec<(SphereConnection, Direction)> = kept.iter()
+        .map(|edge_id| {
+            let (from, to) = vertex.edge(*edge_id).unwrap();
+            let direction = vertex.edge_direction(*edge_id).unwrap_or(Direction::TwoWay);
+            (SphereConnection::new(snapped[from].clone(), snapped[to].clone()), direction)
+        })
+        .collect();
+    let options = BuildOptions {duplicates: EdgePolicy::KeepAll, self_loops: EdgePolicy::KeepAll, ..BuildOptions::default()};
+    let mut rebuilt = VertexBuffer::with_directions(connections, vertex.celestial_object.clone(), &options)?;
+    let vertex_indices: Vec<Option<usize>> = snapped.iter().map(|point| rebuilt.index_of(point)).collect();
+    carry_over(vertex, &mut rebuilt, &kept, &vertex_indices, |shape| {
+        let mut points: Vec<SpherePoint> = shape.iter().map(&round).collect();
+        points.dedup();
+        points
+    })?;
+    let mut edge_ids: Vec<Option<EdgeId>> = vec![None; vertex.edge_count()];
+    kept.iter().enumerate().for_each(|(new_id, old_id)| edge_ids[*old_id] = Some(new_id));
+    rebuilt.set_version(vertex.version() + 1);
+    *vertex = rebuilt;
+    Ok(GridSnap {merged_nodes, degenerate_edges, edge_ids, vertex_indices})
+}
+
+/// Copies costs, variances, metadata and shapes to VertexBuffer rebuilt from `kept` connections of `vertex`,
+/// in their order, `vertex_indices` maps nodes of `vertex` to nodes of the rebuilt one.
+fn carry_over<F>(vertex: &VertexBuffer, rebuilt: &mut VertexBuffer, kept: &[EdgeId], vertex_indices: &[Option<usize>],
+    shape_of: F) -> Result<()>
+where F: Fn(&[SpherePoint]) -> Vec<SpherePoint> {
+    let relation = |edge_id: EdgeId| {
+        let (from, _) = vertex.edge(edge_id).ok_or_else(|| Error::from_kind(ErrorKind::DataItemIncorrect))?;
+        vertex.vector[from].graphs.iter()
+            .find(|graph| from_node_index(graph.edge_id) == edge_id)
+            .ok_or_else(|| Error::from_kind(ErrorKind::DataItemIncorrect))
+    };
+    let costs: Vec<(EdgeId, f64)> = kept.iter().enumerate()
+        .map(|(new_id, old_id)| Ok((new_id, relation(*old_id)?.cost)))
+        .collect::<Result<_>>()?;
+    rebuilt.update_costs(&costs)?;
+    for (new_id, old_id) in kept.iter().enumerate() {
+        let variance = relation(*old_id)?.variance;
+        if variance > 0.0 {
+            rebuilt.set_edge_variance(new_id, variance)?;
+        }
+        if let Some(metadata) = vertex.edge_metadata(*old_id) {
+            for (key, value) in metadata {
+                rebuilt.set_edge_metadata(new_id, key, value)?;
+            }
+        }
+        if let Some(shape) = vertex.edge_shape(*old_id) {
+            rebuilt.set_edge_shape(new_id, shape_of(shape))?;
+        }
+    }
+    for (old_index, new_index) in vertex_indices.iter().enumerate() {
+        if let (Some(new_index), Some(metadata)) = (new_index, vertex.node_metadata(old_index)) {
+            for (key, value) in metadata {
+                rebuilt.set_node_metadata(*new_index, key, value)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod edit_tests {
+    use super::*;
+    use crate::components::SpherePoint;
+    use crate::data::CelestialObject;
+
+    #[test]
+    fn test_edit_session_undo_redo_commit() {
+        // given: a line 0 - 1 - 2 - 3 with a named node and tagged connections
+        let connections: Vec<SphereConnection> = (0..3)
+            .map(|i| SphereConnection::new(SpherePoint::new(0.0, i as f64), SpherePoint::new(0.0, i as f64 + 1.0)))
+            .collect();
+        let mut vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        vertex.update_costs(&[(2, 7.0)]).unwrap();
+        vertex.set_edge_metadata(2, "class", "ferry").unwrap();
+        vertex.set_node_metadata(3, "name", "harbor").unwrap();
+        let bypass = SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(1.0, 1.0));
+        let mut session = EditSession::new(&vertex);
+        // when
+        session.remove_edge(0).unwrap();
+        session.remove_edge(1).unwrap();
+        assert!(session.undo());
+        session.add_connection(bypass.clone());
+        assert!(!session.redo());
+        assert!(session.remove_edge(0).is_err());
+        let commit = session.clone().commit(&mut vertex).unwrap();
+        // then
+        assert_eq!(session.operations(), &[EditOperation::RemoveEdge(0), EditOperation::AddConnection(bypass)]);
+        assert_eq!(commit.edge_ids, vec![None, Some(0), Some(1)]);
+        assert_eq!(commit.added, vec![2]);
+        assert_eq!(commit.vertex_indices, vec![Some(3), Some(0), Some(1), Some(2)]);
+        assert_eq!(vertex.edge_count(), 3);
+        assert_eq!(vertex.vector[1].graphs.iter().find(|graph| graph.edge_id == 1).unwrap().cost, 7.0);
+        assert_eq!(vertex.edge_metadata(1).unwrap()["class"], "ferry");
+        assert_eq!(vertex.node_metadata(2).unwrap()["name"], "harbor");
+        assert!(session.commit(&mut vertex).is_err());
+    }
+
+    #[test]
+    fn test_edit_session_keeps_one_way_connections() {
+        // given
+        let mut ring = crate::fixtures::one_way_ring();
+        let shortcut = SphereConnection::new(ring.vector[0].coordinates.clone(), ring.vector[2].coordinates.clone());
+        let mut session = EditSession::new(&ring);
+        // when
+        session.remove_edge(3).unwrap();
+        session.add_connection(shortcut);
+        let commit = session.commit(&mut ring).unwrap();
+        // then
+        assert_eq!(commit.added, vec![3]);
+        assert_eq!((0..3).map(|edge_id| ring.edge_direction(edge_id)).collect::<Vec<_>>(), vec![Some(Direction::OneWay); 3]);
+        assert_eq!(ring.edge_direction(3), Some(Direction::TwoWay));
+        assert!(ring.find_edge(1, 0).is_none());
+        assert!(ring.find_edge(2, 0).is_some());
+    }
+
+    #[test]
+    fn test_edit_session_commit_refuses_missing_relation() {
+        // given: a ring whose first connection lost its relation
+        let mut ring = crate::fixtures::one_way_ring();
+        let (from, _) = ring.edge(0).unwrap();
+        ring.vector[from].graphs.retain(|rel| rel.edge_id != 0);
+        let mut session = EditSession::new(&ring);
+        // when
+        session.remove_edge(3).unwrap();
+        let commit = session.commit(&mut ring);
+        // then
+        assert!(commit.is_err());
+        assert_eq!(ring.edge_count(), 4);
+    }
+
+    #[test]
+    fn test_snap_to_grid_merges_nodes() {
+        // given: a square 0 - 1 - 2 - 4 where node 3 is node 2 shifted by noise, joined to it by a short
+        // connection, and a shaped connection 4 - 3
+        let point = |lat: f64, lng: f64| SpherePoint::new(lat, lng);
+        let connections = vec![
+            SphereConnection::new(point(0.0, 0.0), point(0.0, 0.001)),
+            SphereConnection::new(point(0.0, 0.001), point(0.001, 0.001)),
+            SphereConnection::new(point(0.001, 0.001), point(0.0010004, 0.0009997)),
+            SphereConnection::new(point(0.001, 0.0), point(0.0010004, 0.0009997)),
+            SphereConnection::new(point(0.001, 0.0), point(0.0, 0.0)),
+        ];
+        let mut vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        vertex.update_costs(&[(3, 0.5)]).unwrap();
+        vertex.set_edge_shape(3, vec![point(0.001002, 0.000499), point(0.000998, 0.000501)]).unwrap();
+        vertex.set_node_metadata(3, "name", "junction").unwrap();
+        let version = vertex.version();
+        // when
+        let fine = snap_to_grid(&mut vertex.clone(), 1e-7).unwrap();
+        let snap = snap_to_grid(&mut vertex, 1e-5).unwrap();
+        // then
+        assert_eq!((fine.merged_nodes, fine.degenerate_edges.len()), (0, 0));
+        assert_eq!(snap.merged_nodes, 1);
+        assert_eq!(snap.degenerate_edges, vec![2]);
+        assert_eq!(snap.edge_ids, vec![Some(0), Some(1), None, Some(2), Some(3)]);
+        assert_eq!(snap.vertex_indices, vec![Some(0), Some(1), Some(2), Some(2), Some(3)]);
+        assert_eq!(vertex.len(), 4);
+        assert_eq!(vertex.edge(2), Some((3, 2)));
+        assert_eq!(vertex.edge_cost(2), Some(0.5));
+        let shape = vertex.edge_shape(2).unwrap();
+        assert_eq!(shape.len(), 1);
+        assert!(relative_eq!(shape[0].lat, 0.001, max_relative = 1e-12) && relative_eq!(shape[0].lng, 0.0005, max_relative = 1e-12));
+        assert_eq!(vertex.node_metadata(2).unwrap()["name"], "junction");
+        assert_eq!(vertex.version(), version + 1);
+        assert!(snap_to_grid(&mut vertex, 0.0).is_err());
+        assert!(snap_to_grid(&mut vertex, 1.0).is_err());
+        assert_eq!(vertex.len(), 4);
+    }
+
+    #[test]
+    fn test_snap_to_grid_keeps_one_way_connections() {
+        // given
+        let mut ring = crate::fixtures::one_way_ring();
+        // when
+        let snap = snap_to_grid(&mut ring, 0.5).unwrap();
+        // then
+        assert_eq!(snap.merged_nodes, 0);
+        assert!(ring.is_directed());
+        assert!(ring.find_edge(0, 1).is_some() && ring.find_edge(1, 0).is_none());
+    }
+}
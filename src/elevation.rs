@@ -0,0 +1,149 @@
+use crate::components::{SphereConnection, SpherePoint};
+use crate::data::get_radius_km;
+use crate::flight_plan::Waypoint;
+use crate::path::Path;
+use crate::vertex::VertexBuffer;
+
+/// Source of terrain elevation, like a digital elevation model, in meters above a reference level.
+/// Closures of `Fn(&SpherePoint) -> Option<f64>` are ElevationProviders too.
+pub trait ElevationProvider {
+    /// Returns elevation at point in meters, None where it is not known.
+    fn elevation_m(&self, point: &SpherePoint) -> Option<f64>;
+}
+
+impl<F: Fn(&SpherePoint) -> Option<f64>> ElevationProvider for F {
+    fn elevation_m(&self, point: &SpherePoint) -> Option<f64> {
+        self(point)
+    }
+}
+
+/// Returns grade of connection travelled from start to finish, rise over great-circle run,
+/// positive uphill, so 0.1 is 10 %. None when elevation of either end is unknown or the connection
+/// has zero length.
+///
+/// # Arguments:
+/// * `connection` which is &SphereConnection - travelled connection
+/// * `elevation` which is &dyn ElevationProvider - elevations of both ends
+/// * `radius` which is f64 - radius of celestial object in kilometers, see `data::get_radius_km`
+///
+/// ```
+/// use path_navigator::components::*;
+/// use path_navigator::data::*;
+/// use path_navigator::elevation::grade;
+///
+/// let climb = SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 0.01));
+/// let terrain = |point: &SpherePoint| Some(point.lng * 10000.0);
+/// let radius = get_radius_km(&CelestialObject::EARTH);
+/// assert!((grade(&climb, &terrain, radius).unwrap() - 0.09).abs() < 0.001);
+/// ```
+pub fn grade(connection: &SphereConnection, elevation: &dyn ElevationProvider, radius: f64) -> Option<f64> {
+    let rise = elevation.elevation_m(&connection.finish)? - elevation.elevation_m(&connection.start)?;
+    let run = connection.cost(radius) * 1000.0;
+    if run > 0.0 { Some(rise / run) } else { None }
+}
+
+/// Length of a route on the surface and along terrain, see `path_length` and `waypoints_length`.
+///
+/// * `length_2d_km` - great-circle length in kilometers, as if the route was flat
+/// * `length_3d_km` - length in kilometers with altitude changes, never shorter than `length_2d_km`
+/// * `flat_segments` - number of segments with unknown elevation or altitude of an end, counted as flat
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PathLength {
+    pub length_2d_km: f64,
+    pub length_3d_km: f64,
+    pub flat_segments: usize,
+}
+
+impl PathLength {
+    fn of(segments: impl Iterator<Item = (SphereConnection, Option<f64>)>, radius: f64) -> Self {
+        let mut length = PathLength {length_2d_km: 0.0, length_3d_km: 0.0, flat_segments: 0};
+        for (connection, rise_m) in segments {
+            let run = connection.cost(radius);
+            length.length_2d_km += run;
+            match rise_m {
+                Some(rise_m) => length.length_3d_km += run.hypot(rise_m / 1000.0),
+                None => {
+                    length.length_3d_km += run;
+                    length.flat_segments += 1;
+                }
+            }
+        }
+        length
+    }
+}
+
+/// Returns PathLength of Path over terrain given by ElevationProvider, following edge shapes, see
+/// `Path::geometry`. Every segment is treated as straight slope between elevations of its ends, so
+/// terrain between shape points is not sampled. VertexBuffer has to be the one Path was found on.
+///
+/// ```
+/// use path_navigator::components::*;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::elevation::path_length;
+/// use path_navigator::path::Path;
+/// use path_navigator::vertex::VertexBuffer;
+///
+/// let connections = vec![SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 0.01))];
+/// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+/// let path = Path::from_vertex_indices(vec![0, 1], &vertex).unwrap();
+/// let ridge = |point: &SpherePoint| Some(point.lng * 100_000.0);
+/// let length = path_length(&path, &vertex, &ridge);
+/// assert!(length.length_3d_km > length.length_2d_km * 1.3);
+/// ```
+pub fn path_length(path: &Path, vertex: &VertexBuffer, elevation: &dyn ElevationProvider) -> PathLength {
+    let points = path.geometry(vertex);
+    let segments = points.windows(2).map(|pair| {
+        let rise_m = elevation.elevation_m(&pair[0]).zip(elevation.elevation_m(&pair[1])).map(|(from, to)| to - from);
+        (SphereConnection::new(pair[0].clone(), pair[1].clone()), rise_m)
+    });
+    PathLength::of(segments, get_radius_km(&vertex.celestial_object))
+}
+
+/// Returns PathLength of 3D waypoints, like ones of `LayeredGraph::waypoints`, with altitude changes
+/// between them, on celestial object of given radius in kilometers.
+pub fn waypoints_length(waypoints: &[Waypoint], radius: f64) -> PathLength {
+    let segments = waypoints.windows(2).map(|pair| {
+        let rise_m = pair[0].altitude_m.zip(pair[1].altitude_m).map(|(from, to)| to - from);
+        (SphereConnection::new(pair[0].point.clone(), pair[1].point.clone()), rise_m)
+    });
+    PathLength::of(segments, radius)
+}
+
+#[cfg(test)]
+mod elevation_tests {
+    use super::*;
+    use crate::data::CelestialObject;
+
+    #[test]
+    fn test_3d_lengths() {
+        // given: a trail climbing 300 m over 1 km of run with a shaped first connection, then unknown terrain
+        let point = |lat: f64, lng: f64| SpherePoint::new(lat, lng);
+        let radius = get_radius_km(&CelestialObject::EARTH);
+        let step = (1.0 / radius).to_degrees();
+        let connections = vec![
+            SphereConnection::new(point(0.0, 0.0), point(0.0, step)),
+            SphereConnection::new(point(0.0, step), point(0.0, 2.0 * step)),
+        ];
+        let mut vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        vertex.set_edge_shape(0, vec![point(0.0, step / 2.0)]).unwrap();
+        let path = Path::from_vertex_indices(vec![0, 1, 2], &vertex).unwrap();
+        let terrain = |point: &SpherePoint| if point.lng <= step * 1.01 { Some(point.lng / step * 300.0) } else { None };
+        let flight = vec![
+            Waypoint::new(point(0.0, 0.0), Some(0.0)),
+            Waypoint::new(point(0.0, 0.0), Some(120.0)),
+            Waypoint::new(point(0.0, step), Some(120.0)),
+            Waypoint::new(point(0.0, 2.0 * step), None),
+        ];
+        // when
+        let trail = path_length(&path, &vertex, &terrain);
+        let drone = waypoints_length(&flight, radius);
+        // then
+        assert!(relative_eq!(trail.length_2d_km, 2.0, max_relative = 1e-9));
+        assert!(relative_eq!(trail.length_3d_km, 1.0 + 0.3_f64.hypot(1.0), max_relative = 1e-9));
+        assert_eq!(trail.flat_segments, 1);
+        assert!(relative_eq!(drone.length_2d_km, 2.0, max_relative = 1e-9));
+        assert!(relative_eq!(drone.length_3d_km, 2.12, max_relative = 1e-9));
+        assert_eq!(drone.flat_segments, 1);
+        assert_eq!(waypoints_length(&[], radius).length_3d_km, 0.0);
+    }
+}
@@ -1,7 +1,30 @@
+// error-chain 0.11 expands foreign links to deprecated std::error::Error methods
+#![allow(deprecated)]
+
 error_chain! {
+    foreign_links {
+        Io(::std::io::Error);
+    }
     errors {
         InvalidParameter { description("invalid parameter") }
         DataItemIncomplete { description("data item is incomplete") }
         DataItemIncorrect { description("data set is incorrect") }
+        Aborted { description("operation was aborted") }
+        NegativeCost(edge_id: usize, cost: f64) {
+            description("negative connection cost")
+            display("connection {} has negative cost {}, shortest path search requires non-negative costs", edge_id, cost)
+        }
+        EdgeTooLong(connection_index: usize, length_km: f64) {
+            description("connection longer than allowed")
+            display("input connection {} is {} km long, which exceeds maximal edge length", connection_index, length_km)
+        }
+        BodyMismatch(expected_radius_km: f64, found_radius_km: f64) {
+            description("graphs over different celestial objects")
+            display("graph over body of radius {} km cannot be combined with graph over body of radius {} km", expected_radius_km, found_radius_km)
+        }
+        SwappedCoordinates(item: usize) {
+            description("coordinates look swapped")
+            display("item {} has latitude out of range which would be a valid longitude, check coordinate order", item)
+        }
     }
 }
@@ -0,0 +1,106 @@
+use crate::errors::*;
+use crate::vertex::VertexBuffer;
+use crate::components::SpherePoint;
+use crate::dijkstra::{get_closest_point, search_first_target};
+use crate::path::Path;
+
+/// Facility closest to a query by network cost, `facility` is index of the facility in `Facilities::points`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NearestFacility {
+    pub facility: usize,
+    pub cost: f64,
+    pub path: Path,
+}
+
+/// # Facilities
+/// Set of facility locations, like hospitals or depots, snapped to closest points on VertexBuffer
+/// once, so they can be queried for the one nearest to any origin.
+///
+/// ```
+/// use path_navigator::components::*;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::facility::Facilities;
+/// use path_navigator::vertex::VertexBuffer;
+///
+/// let connections = vec![
+///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+///     SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 3.0)),
+/// ];
+/// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+/// let hospitals = Facilities::new(vec![SpherePoint::new(0.0, 3.0), SpherePoint::new(0.0, 0.0)], &vertex).unwrap();
+/// let nearest = hospitals.nearest_facility(&SpherePoint::new(0.0, 1.2), &vertex).unwrap();
+/// assert_eq!(nearest.facility, 1);
+/// assert_eq!(nearest.path.vertex_indices, vec![1, 0]);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Facilities {
+    pub points: Vec<SpherePoint>,
+    pub vertex_indices: Vec<usize>,
+}
+
+impl Facilities {
+    /// Snaps given facility points to VertexBuffer.
+    /// Fails with `DataItemIncomplete` for no facilities or empty VertexBuffer.
+    pub fn new(points: Vec<SpherePoint>, vertex: &VertexBuffer) -> Result<Self> {
+        if points.is_empty() || vertex.is_empty() {
+            return Err(Error::from_kind(ErrorKind::DataItemIncomplete));
+        }
+        let vertex_indices = points.iter().map(|point| get_closest_point(point, vertex)).collect();
+        Ok(Self {points, vertex_indices})
+    }
+
+    /// Returns facility with the lowest network cost from the closest point on VertexBuffer to
+    /// `origin`, with path to it. When several facilities snap to the same node, the one with the
+    /// lowest index is returned, of facilities at different nodes equally close any one may be.
+    /// None when no facility is reachable.
+    ///
+    /// # Remarks:
+    ///
+    /// Search stops as soon as the first facility node is settled, so nearby facilities are found
+    /// without exploring the rest of the graph. VertexBuffer has to be the one facilities were snapped to.
+    pub fn nearest_facility(&self, origin: &SpherePoint, vertex: &VertexBuffer) -> Option<NearestFacility> {
+        if vertex.is_empty() {
+            return None;
+        }
+        let root = get_closest_point(origin, vertex);
+        let (labels, reached) = search_first_target(&[root], &self.vertex_indices, vertex);
+        let vertex_index = reached?;
+        let facility = self.vertex_indices.iter().position(|index| *index == vertex_index).unwrap();
+        let cost = labels.costs[vertex_index]?;
+        let path = labels.into_tree(root).path_to(vertex_index, vertex)?;
+        Some(NearestFacility {facility, cost, path})
+    }
+}
+
+#[cfg(test)]
+mod facility_tests {
+    use super::*;
+    use crate::components::SphereConnection;
+    use crate::data::CelestialObject;
+
+    #[test]
+    fn test_nearest_facility_by_network_cost() {
+        // given: facility 0 is close as the crow flies but only reachable around a long detour
+        let connections = vec![
+            SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 2.0)),
+            SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(3.0, 0.0)),
+            SphereConnection::new(SpherePoint::new(3.0, 0.0), SpherePoint::new(3.0, 0.5)),
+            SphereConnection::new(SpherePoint::new(3.0, 0.5), SpherePoint::new(0.5, 0.5)),
+            SphereConnection::new(SpherePoint::new(10.0, 10.0), SpherePoint::new(10.0, 11.0)),
+        ];
+        let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        let points = vec![SpherePoint::new(0.5, 0.5), SpherePoint::new(0.0, 2.0)];
+        let facilities = Facilities::new(points, &vertex).unwrap();
+        let isolated = Facilities::new(vec![SpherePoint::new(10.0, 11.0)], &vertex).unwrap();
+        // when
+        let nearest = facilities.nearest_facility(&SpherePoint::new(0.0, 0.0), &vertex).unwrap();
+        let at_facility = facilities.nearest_facility(&SpherePoint::new(0.5, 0.5), &vertex).unwrap();
+        // then
+        assert_eq!(nearest.facility, 1);
+        assert_eq!(nearest.path.vertex_indices, vec![0, 1]);
+        assert!(relative_eq!(nearest.cost, nearest.path.cost(), max_relative = 1e-12));
+        assert_eq!((at_facility.facility, at_facility.cost), (0, 0.0));
+        assert!(isolated.nearest_facility(&SpherePoint::new(0.0, 0.0), &vertex).is_none());
+        assert!(Facilities::new(Vec::new(), &vertex).is_err());
+    }
+}
@@ -0,0 +1,11 @@
+use crate::components::{SphereConnection, SpherePoint};
+use crate::data::CelestialObject;
+use crate::vertex::VertexBuffer;
+
+/// One-way ring 0 -> 1 -> 2 -> 3 -> 0 around a one degree square at the equator on Earth, connections
+/// of about 111 km, so going back to the previous node costs the other three connections.
+pub(crate) fn one_way_ring() -> VertexBuffer {
+    let corners = [SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0), SpherePoint::new(1.0, 1.0), SpherePoint::new(1.0, 0.0)];
+    let connections = (0..4).map(|i| SphereConnection::new(corners[i].clone(), corners[(i + 1) % 4].clone())).collect();
+    VertexBuffer::new_directed(connections, CelestialObject::EARTH).unwrap()
+}
@@ -0,0 +1,139 @@
+use crate::errors::*;
+use crate::components::SpherePoint;
+use std::io::Write;
+
+const FEET_PER_METER: f64 = 1.0 / 0.3048;
+
+/// Point of a flight plan, `altitude_m` is altitude above the surface in meters, None for 2D routes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Waypoint {
+    pub point: SpherePoint,
+    pub altitude_m: Option<f64>,
+}
+
+impl Waypoint {
+    pub fn new(point: SpherePoint, altitude_m: Option<f64>) -> Self {
+        Self {point, altitude_m}
+    }
+}
+
+/// Output format of `write_flight_plan`.
+///
+/// * `Fms` - X-Plane 11 FMS flight plan of latitude / longitude waypoints named `WP001`, `WP002` and so on,
+///   altitudes in feet
+/// * `MavlinkMission` - QGroundControl `QGC WPL 110` mission, the first waypoint is home position and the
+///   rest are `MAV_CMD_NAV_WAYPOINT` items with altitudes in meters relative to home
+///
+/// Waypoints without altitude are written at altitude 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlightPlanFormat {
+    Fms,
+    MavlinkMission,
+}
+
+/// Writes waypoints, like ones of `Route::waypoints` or `LayeredGraph::waypoints`, as flight plan in given format.
+///
+/// # Arguments:
+/// * `waypoints` which is &[Waypoint] - points in travel order
+/// * `format` which is FlightPlanFormat - format of flight plan
+/// * `writer` which is &mut Write - destination of flight plan
+///
+/// # Remarks:
+///
+/// Fails with `DataItemIncomplete` for no waypoints and with `DataItemIncorrect` for not finite altitude.
+///
+/// ```
+/// use path_navigator::components::SpherePoint;
+/// use path_navigator::flight_plan::*;
+///
+/// let waypoints = vec![
+///     Waypoint::new(SpherePoint::new(52.1, 21.0), Some(0.0)),
+///     Waypoint::new(SpherePoint::new(52.2, 21.1), Some(120.0)),
+/// ];
+/// let mut mission: Vec<u8> = Vec::new();
+/// write_flight_plan(&waypoints, FlightPlanFormat::MavlinkMission, &mut mission).unwrap();
+/// let mission = String::from_utf8(mission).unwrap();
+/// assert_eq!(mission.lines().nth(2), Some("1\t0\t3\t16\t0\t0\t0\t0\t52.20000000\t21.10000000\t120.000000\t1"));
+/// ```
+pub fn write_flight_plan<W: Write>(waypoints: &[Waypoint], format: FlightPlanFormat, writer: &mut W) -> Result<()> {
+    if waypoints.is_empty() {
+        return Err(Error::from_kind(ErrorKind::DataItemIncomplete));
+    }
+    if waypoints.iter().any(|waypoint| waypoint.altitude_m.is_some_and(|altitude| !altitude.is_finite())) {
+        return Err(Error::from_kind(ErrorKind::DataItemIncorrect));
+    }
+    match format {
+        FlightPlanFormat::Fms => {
+            let name = |position: usize| format!("WP{:03}", position + 1);
+            writeln!(writer, "I\n1100 Version\nCYCLE 1")?;
+            writeln!(writer, "DEP {}\nDES {}", name(0), name(waypoints.len() - 1))?;
+            writeln!(writer, "NUMENR {}", waypoints.len())?;
+            for (position, waypoint) in waypoints.iter().enumerate() {
+                writeln!(writer, "28 {} DRCT {:.6} {:.6} {:.6}", name(position),
+                    waypoint.altitude_m.unwrap_or(0.0) * FEET_PER_METER, waypoint.point.lat, waypoint.point.lng)?;
+            }
+        }
+        FlightPlanFormat::MavlinkMission => {
+            writeln!(writer, "QGC WPL 110")?;
+            for (position, waypoint) in waypoints.iter().enumerate() {
+                // home is in global frame and current, mission items are relative to home altitude
+                let (current, frame) = if position == 0 { (1, 0) } else { (0, 3) };
+                writeln!(writer, "{}\t{}\t{}\t16\t0\t0\t0\t0\t{:.8}\t{:.8}\t{:.6}\t1", position, current, frame,
+                    waypoint.point.lat, waypoint.point.lng, waypoint.altitude_m.unwrap_or(0.0))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod flight_plan_tests {
+    use super::*;
+    use crate::components::SphereConnection;
+    use crate::data::CelestialObject;
+    use crate::layers::{AltitudeLayer, LayeredGraph};
+    use crate::route::find_route;
+    use crate::vertex::VertexBuffer;
+
+    #[test]
+    fn test_flight_plans_of_routes() {
+        // given: a ground corridor and the same corridor flown 100 m higher
+        let corridor = || VertexBuffer::new(vec![
+            SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 0.01)),
+            SphereConnection::new(SpherePoint::new(0.0, 0.01), SpherePoint::new(0.01, 0.01)),
+        ], CelestialObject::EARTH).unwrap();
+        let ground = corridor();
+        let mut airspace = LayeredGraph::new(vec![AltitudeLayer::new(0.0, corridor()), AltitudeLayer::new(0.1, corridor())], 1.0).unwrap();
+        airspace.connect_stacked_nodes();
+        let route = find_route(&SpherePoint::new(0.0, -0.001), &SpherePoint::new(0.01, 0.01), &ground).unwrap();
+        let flight = airspace.find_path(0, &SpherePoint::new(0.0, 0.0), 1, &SpherePoint::new(0.01, 0.01)).unwrap();
+        let write = |waypoints: &[Waypoint], format: FlightPlanFormat| {
+            let mut output: Vec<u8> = Vec::new();
+            write_flight_plan(waypoints, format, &mut output).unwrap();
+            String::from_utf8(output).unwrap()
+        };
+        // when
+        let route_waypoints = route.waypoints(&ground);
+        let flight_waypoints = airspace.waypoints(&flight);
+        let fms = write(&route_waypoints, FlightPlanFormat::Fms);
+        let mission = write(&flight_waypoints, FlightPlanFormat::MavlinkMission);
+        // then: snapped finish is not repeated
+        assert_eq!(route_waypoints.len(), 4);
+        assert_eq!(route_waypoints[0], Waypoint::new(SpherePoint::new(0.0, -0.001), None));
+        assert_eq!(fms, concat!(
+            "I\n1100 Version\nCYCLE 1\nDEP WP001\nDES WP004\nNUMENR 4\n",
+            "28 WP001 DRCT 0.000000 0.000000 -0.001000\n",
+            "28 WP002 DRCT 0.000000 0.000000 0.000000\n",
+            "28 WP003 DRCT 0.000000 0.000000 0.010000\n",
+            "28 WP004 DRCT 0.000000 0.010000 0.010000\n",
+        ));
+        assert_eq!(flight_waypoints.first().unwrap().altitude_m, Some(0.0));
+        assert_eq!(flight_waypoints.last().unwrap().altitude_m, Some(100.0));
+        assert_eq!(mission.lines().count(), flight_waypoints.len() + 1);
+        assert!(mission.lines().last().unwrap().ends_with("\t0.01000000\t0.01000000\t100.000000\t1"));
+        let mut output: Vec<u8> = Vec::new();
+        assert!(write_flight_plan(&[], FlightPlanFormat::Fms, &mut output).is_err());
+        let broken = vec![Waypoint::new(SpherePoint::new(0.0, 0.0), Some(f64::NAN))];
+        assert!(write_flight_plan(&broken, FlightPlanFormat::MavlinkMission, &mut output).is_err());
+    }
+}
@@ -0,0 +1,309 @@
+use crate::errors::*;
+use crate::vertex::{BuildOptions, EdgeId, EdgePolicy, VertexBuffer};
+use crate::components::{SphereConnection, SpherePoint};
+use crate::data::CelestialObject;
+use std::io::{BufRead, Write};
+
+const METERS_PER_KM: f64 = 1000.0;
+const KM_PER_NAUTICAL_MILE: f64 = 1.852;
+
+/// Order of coordinates in text formats.
+///
+/// * `LngLat` - longitude first, like GeoJSON and WKT prescribe
+/// * `LatLng` - latitude first, like most spreadsheets and navigation software
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoordinateOrder {
+    LngLat,
+    LatLng,
+}
+
+/// Unit of connection costs in text formats, VertexBuffer keeps them in kilometers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DistanceUnit {
+    Kilometers,
+    Meters,
+    NauticalMiles,
+}
+
+impl DistanceUnit {
+    /// Returns given kilometers in this unit.
+    pub fn from_km(&self, km: f64) -> f64 {
+        match self {
+            DistanceUnit::Kilometers => km,
+            DistanceUnit::Meters => km * METERS_PER_KM,
+            DistanceUnit::NauticalMiles => km / KM_PER_NAUTICAL_MILE,
+        }
+    }
+
+    /// Returns given value of this unit in kilometers.
+    pub fn to_km(&self, value: f64) -> f64 {
+        match self {
+            DistanceUnit::Kilometers => value,
+            DistanceUnit::Meters => value / METERS_PER_KM,
+            DistanceUnit::NauticalMiles => value * KM_PER_NAUTICAL_MILE,
+        }
+    }
+}
+
+/// Options of CSV and WKT readers and writers, `IoConfig::default()` is longitude first and kilometers.
+///
+/// * `coordinate_order` - CoordinateOrder of read and written positions
+/// * `unit` - DistanceUnit of read and written costs
+#[derive(Debug, Clone, PartialEq)]
+pub struct IoConfig {
+    pub coordinate_order: CoordinateOrder,
+    pub unit: DistanceUnit,
+}
+
+impl Default for IoConfig {
+    fn default() -> Self {
+        Self {coordinate_order: CoordinateOrder::LngLat, unit: DistanceUnit::Kilometers}
+    }
+}
+
+impl IoConfig {
+    fn column_names(&self) -> (&'static str, &'static str) {
+        match self.coordinate_order {
+            CoordinateOrder::LngLat => ("lng", "lat"),
+            CoordinateOrder::LatLng => ("lat", "lng"),
+        }
+    }
+
+    fn ordered(&self, point: &SpherePoint) -> (f64, f64) {
+        match self.coordinate_order {
+            CoordinateOrder::LngLat => (point.lng, point.lat),
+            CoordinateOrder::LatLng => (point.lat, point.lng),
+        }
+    }
+
+    /// Returns point of two values in configured order. Fails with `SwappedCoordinates` naming `item` when
+    /// latitude is out of range but would be a valid longitude, with `DataItemIncorrect` for other values out of range.
+    pub(crate) fn point(&self, first: f64, second: f64, item: usize) -> Result<SpherePoint> {
+        let (lat, lng) = match self.coordinate_order {
+            CoordinateOrder::LngLat => (second, first),
+            CoordinateOrder::LatLng => (first, second),
+        };
+        if !lat.is_finite() || !lng.is_finite() || lng.abs() > 180.0 {
+            return Err(Error::from_kind(ErrorKind::DataItemIncorrect));
+        }
+        if lat.abs() > 90.0 {
+            let kind = if lng.abs() <= 90.0 { ErrorKind::SwappedCoordinates(item) } else { ErrorKind::DataItemIncorrect };
+            return Err(Error::from_kind(kind));
+        }
+        Ok(SpherePoint::new(lat, lng))
+    }
+}
+
+/// Writes connections of VertexBuffer as CSV, one line per EdgeId in order with both ends in configured
+/// CoordinateOrder and cost in configured DistanceUnit, under header like `start_lng,start_lat,finish_lng,finish_lat,cost`.
+///
+/// ```
+/// use path_navigator::components::*;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::formats::*;
+/// use path_navigator::vertex::VertexBuffer;
+///
+/// let connections = vec![SphereConnection::new(SpherePoint::new(54.0, 18.0), SpherePoint::new(54.0, 18.5))];
+/// let mut vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+/// vertex.update_costs(&[(0, 1.5)]).unwrap();
+/// let config = IoConfig {coordinate_order: CoordinateOrder::LatLng, unit: DistanceUnit::Meters};
+/// let mut csv: Vec<u8> = Vec::new();
+/// write_csv(&vertex, &config, &mut csv).unwrap();
+/// assert_eq!(String::from_utf8(csv).unwrap(), "start_lat,start_lng,finish_lat,finish_lng,cost\n54,18,54,18.5,1500\n");
+/// ```
+pub fn write_csv<W: Write>(vertex: &VertexBuffer, config: &IoConfig, writer: &mut W) -> Result<()> {
+    let (first, second) = config.column_names();
+    writeln!(writer, "start_{},start_{},finish_{},finish_{},cost", first, second, first, second)?;
+    for edge_id in 0..vertex.edge_count() {
+        let (from, to) = vertex.edge(edge_id).unwrap();
+        let (start_first, start_second) = config.ordered(&vertex.vector[from].coordinates);
+        let (finish_first, finish_second) = config.ordered(&vertex.vector[to].coordinates);
+        let cost = config.unit.from_km(vertex.edge_cost(edge_id).unwrap());
+        writeln!(writer, "{},{},{},{},{}", start_first, start_second, finish_first, finish_second, cost)?;
+    }
+    Ok(())
+}
+
+/// Reads VertexBuffer from CSV written by `write_csv`, or any CSV with header line and rows of four
+/// coordinates of connection ends in configured CoordinateOrder, optionally followed by cost in configured
+/// DistanceUnit. Connections without cost cost their great-circle length, repeated connections are kept
+/// as parallel ones, so EdgeIds follow rows.
+///
+/// # Remarks:
+///
+/// Fails with `SwappedCoordinates` carrying row number, header being row 0, when latitude of a row is out
+/// of range but would be a valid longitude, and with `DataItemIncorrect` for other malformed rows.
+///
+/// ```
+/// use path_navigator::components::SpherePoint;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::errors::ErrorKind;
+/// use path_navigator::formats::*;
+///
+/// let csv = "lat_1,lng_1,lat_2,lng_2\n-33.9,151.2,-33.8,151.3\n";
+/// let config = IoConfig {coordinate_order: CoordinateOrder::LatLng, ..IoConfig::default()};
+/// let vertex = read_csv(csv.as_bytes(), CelestialObject::EARTH, &config).unwrap();
+/// assert_eq!(vertex.index_of(&SpherePoint::new(-33.8, 151.3)), Some(1));
+/// let swapped = read_csv(csv.as_bytes(), CelestialObject::EARTH, &IoConfig::default());
+/// assert!(matches!(swapped.unwrap_err().kind(), ErrorKind::SwappedCoordinates(1)));
+/// ```
+pub fn read_csv<R: BufRead>(reader: R, celestial_object: CelestialObject, config: &IoConfig) -> Result<VertexBuffer> {
+    let mut connections: Vec<SphereConnection> = Vec::new();
+    let mut costs: Vec<(EdgeId, f64)> = Vec::new();
+    for (row, line) in reader.lines().enumerate() {
+        let line = line?;
+        if row == 0 || line.trim().is_empty() {
+            continue;
+        }
+        let values = line.split(',')
+            .map(|value| value.trim().parse::<f64>().map_err(|_| Error::from_kind(ErrorKind::DataItemIncorrect)))
+            .collect::<Result<Vec<f64>>>()?;
+        if values.len() != 4 && values.len() != 5 {
+            return Err(Error::from_kind(ErrorKind::DataItemIncorrect));
+        }
+        let start = config.point(values[0], values[1], row)?;
+        let finish = config.point(values[2], values[3], row)?;
+        if let Some(cost) = values.get(4) {
+            costs.push((connections.len(), config.unit.to_km(*cost)));
+        }
+        connections.push(SphereConnection::new(start, finish));
+    }
+    let options = BuildOptions {duplicates: EdgePolicy::KeepAll, ..BuildOptions::default()};
+    let mut vertex = VertexBuffer::with_options(connections, celestial_object, &options)?;
+    vertex.update_costs(&costs)?;
+    Ok(vertex)
+}
+
+/// Returns connections of VertexBuffer as WKT MULTILINESTRING with one two-point line per EdgeId in order,
+/// positions in configured CoordinateOrder.
+///
+/// ```
+/// use path_navigator::components::*;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::formats::*;
+/// use path_navigator::vertex::VertexBuffer;
+///
+/// let connections = vec![SphereConnection::new(SpherePoint::new(54.0, 18.0), SpherePoint::new(54.0, 18.5))];
+/// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+/// assert_eq!(to_wkt(&vertex, &IoConfig::default()), "MULTILINESTRING ((18 54, 18.5 54))");
+/// ```
+pub fn to_wkt(vertex: &VertexBuffer, config: &IoConfig) -> String {
+    let lines: Vec<String> = (0..vertex.edge_count())
+        .map(|edge_id| {
+            let (from, to) = vertex.edge(edge_id).unwrap();
+            let (start_first, start_second) = config.ordered(&vertex.vector[from].coordinates);
+            let (finish_first, finish_second) = config.ordered(&vertex.vector[to].coordinates);
+            format!("({} {}, {} {})", start_first, start_second, finish_first, finish_second)
+        })
+        .collect();
+    format!("MULTILINESTRING ({})", lines.join(", "))
+}
+
+/// Reads VertexBuffer from WKT LINESTRING or MULTILINESTRING with positions in configured CoordinateOrder,
+/// every pair of consecutive positions becomes a connection costing its great-circle length, built like
+/// by `VertexBuffer::new`.
+///
+/// # Remarks:
+///
+/// Fails with `SwappedCoordinates` carrying position number, counted from 0 over the whole text, when
+/// latitude is out of range but would be a valid longitude, and with `DataItemIncorrect` for other
+/// malformed text. For the usual longitude first order put `IoConfig::default()`.
+///
+/// ```
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::formats::*;
+///
+/// let vertex = read_wkt("LINESTRING (18 54, 18.5 54, 18.5 54.5)", CelestialObject::EARTH, &IoConfig::default()).unwrap();
+/// assert_eq!(vertex.edge_count(), 2);
+/// ```
+pub fn read_wkt(text: &str, celestial_object: CelestialObject, config: &IoConfig) -> Result<VertexBuffer> {
+    let text = text.trim();
+    let upper = text.to_ascii_uppercase();
+    let body = if upper.starts_with("MULTILINESTRING") {
+        strip_parentheses(&text["MULTILINESTRING".len()..])?
+    } else if upper.starts_with("LINESTRING") {
+        &text["LINESTRING".len()..]
+    } else {
+        return Err(Error::from_kind(ErrorKind::DataItemIncorrect));
+    };
+    let mut connections: Vec<SphereConnection> = Vec::new();
+    let mut item = 0;
+    let mut rest = body.trim();
+    while !rest.is_empty() {
+        let end = rest.find(')').ok_or_else(|| Error::from_kind(ErrorKind::DataItemIncorrect))?;
+        let mut points: Vec<SpherePoint> = Vec::new();
+        for position in strip_parentheses(&rest[..=end])?.split(',') {
+            let values = position.split_whitespace()
+                .map(|value| value.parse::<f64>().map_err(|_| Error::from_kind(ErrorKind::DataItemIncorrect)))
+                .collect::<Result<Vec<f64>>>()?;
+            if values.len() != 2 {
+                return Err(Error::from_kind(ErrorKind::DataItemIncorrect));
+            }
+            points.push(config.point(values[0], values[1], item)?);
+            item += 1;
+        }
+        connections.extend(points.windows(2).map(|pair| SphereConnection::new(pair[0].clone(), pair[1].clone())));
+        rest = rest[end + 1..].trim_start().trim_start_matches(',').trim_start();
+    }
+    VertexBuffer::new(connections, celestial_object)
+}
+
+fn strip_parentheses(text: &str) -> Result<&str> {
+    let text = text.trim();
+    if text.len() < 2 || !text.starts_with('(') || !text.ends_with(')') {
+        return Err(Error::from_kind(ErrorKind::DataItemIncorrect));
+    }
+    Ok(&text[1..text.len() - 1])
+}
+
+#[cfg(test)]
+mod formats_tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trips_in_every_order_and_unit() {
+        // given
+        let connections = vec![
+            SphereConnection::new(SpherePoint::new(54.35, 18.65), SpherePoint::new(54.52, 18.53)),
+            SphereConnection::new(SpherePoint::new(54.52, 18.53), SpherePoint::new(-33.9, 151.2)),
+        ];
+        let mut vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        vertex.update_costs(&[(0, 18.52)]).unwrap();
+        let orders = [CoordinateOrder::LngLat, CoordinateOrder::LatLng];
+        let units = [DistanceUnit::Kilometers, DistanceUnit::Meters, DistanceUnit::NauticalMiles];
+        for (coordinate_order, unit) in orders.iter().flat_map(|order| units.iter().map(move |unit| (*order, *unit))) {
+            let config = IoConfig {coordinate_order, unit};
+            // when
+            let mut csv: Vec<u8> = Vec::new();
+            write_csv(&vertex, &config, &mut csv).unwrap();
+            let from_csv = read_csv(csv.as_slice(), CelestialObject::EARTH, &config).unwrap();
+            let from_wkt = read_wkt(&to_wkt(&vertex, &config), CelestialObject::EARTH, &config).unwrap();
+            // then
+            assert_eq!(from_csv.to_connections(), vertex.to_connections());
+            assert_eq!(from_wkt.to_connections(), vertex.to_connections());
+            assert!(relative_eq!(from_csv.edge_cost(0).unwrap(), 18.52, max_relative = 1e-12));
+            assert_eq!(from_csv.edge_cost(1), vertex.edge_cost(1));
+        }
+        let mut csv: Vec<u8> = Vec::new();
+        write_csv(&vertex, &IoConfig {unit: DistanceUnit::NauticalMiles, ..IoConfig::default()}, &mut csv).unwrap();
+        assert!(String::from_utf8(csv).unwrap().lines().nth(1).unwrap().ends_with(",10"));
+    }
+
+    #[test]
+    fn test_swapped_coordinates_are_reported() {
+        // given: Sydney written latitude first in longitude first WKT, and longitude first CSV read as latitude first
+        let wkt = "MULTILINESTRING ((18.65 54.35, 18.53 54.52), (18.53 54.52, -33.9 151.2))";
+        let csv = "start_lng,start_lat,finish_lng,finish_lat\n18.65,54.35,18.53,54.52\n151.2,-33.9,18.53,54.52\n";
+        let lat_first = IoConfig {coordinate_order: CoordinateOrder::LatLng, ..IoConfig::default()};
+        // when
+        let wkt_error = read_wkt(wkt, CelestialObject::EARTH, &IoConfig::default()).unwrap_err();
+        let csv_error = read_csv(csv.as_bytes(), CelestialObject::EARTH, &lat_first).unwrap_err();
+        let out_of_range = read_wkt("LINESTRING (0 0, 200 100)", CelestialObject::EARTH, &IoConfig::default()).unwrap_err();
+        // then
+        assert!(matches!(wkt_error.kind(), ErrorKind::SwappedCoordinates(3)));
+        assert!(matches!(csv_error.kind(), ErrorKind::SwappedCoordinates(2)));
+        assert!(matches!(out_of_range.kind(), ErrorKind::DataItemIncorrect));
+        assert!(read_wkt("POINT (0 0)", CelestialObject::EARTH, &IoConfig::default()).is_err());
+        assert!(read_csv("header\n1,2,3\n".as_bytes(), CelestialObject::EARTH, &IoConfig::default()).is_err());
+    }
+}
@@ -0,0 +1,107 @@
+use std::collections::{HashMap, HashSet};
+#[cfg(any(test, feature = "fxhash"))]
+use std::hash::{BuildHasherDefault, Hasher};
+
+/// Hasher of the Firefox and rustc compilers (FxHash): one multiply and rotate per word, much faster
+/// than SipHash on integer keys like node indices and coordinate bits but not resistant to hash
+/// flooding, so it is only used with `fxhash` feature.
+#[cfg(any(test, feature = "fxhash"))]
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct FxHasher {
+    hash: u64,
+}
+
+#[cfg(any(test, feature = "fxhash"))]
+const SEED: u64 = 0x51_7c_c1_b7_27_22_0a_95;
+
+#[cfg(any(test, feature = "fxhash"))]
+impl FxHasher {
+    fn add_to_hash(&mut self, word: u64) {
+        self.hash = (self.hash.rotate_left(5) ^ word).wrapping_mul(SEED);
+    }
+}
+
+#[cfg(any(test, feature = "fxhash"))]
+impl Hasher for FxHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        let mut chunks = bytes.chunks_exact(8);
+        for chunk in &mut chunks {
+            let mut word = [0_u8; 8];
+            word.copy_from_slice(chunk);
+            self.add_to_hash(u64::from_le_bytes(word));
+        }
+        for byte in chunks.remainder() {
+            self.add_to_hash(u64::from(*byte));
+        }
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.add_to_hash(value);
+    }
+
+    fn write_usize(&mut self, value: usize) {
+        self.add_to_hash(value as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+}
+
+/// Hasher of maps keyed by node indices and coordinates, FxHash with `fxhash` feature and
+/// the standard SipHash otherwise.
+#[cfg(feature = "fxhash")]
+pub(crate) type NodeHasher = BuildHasherDefault<FxHasher>;
+#[cfg(not(feature = "fxhash"))]
+pub(crate) type NodeHasher = std::collections::hash_map::RandomState;
+
+pub(crate) type NodeMap<K, V> = HashMap<K, V, NodeHasher>;
+pub(crate) type NodeSet<K> = HashSet<K, NodeHasher>;
+
+/// Integer type nodes and EdgeIds are stored with in connection list and GraphRelations of VertexBuffer,
+/// `u32` with `u32-indices` feature which halves their memory on 64-bit platforms and limits graphs to
+/// `u32::MAX` nodes and connections.
+#[cfg(feature = "u32-indices")]
+pub type NodeIndex = u32;
+#[cfg(not(feature = "u32-indices"))]
+pub type NodeIndex = usize;
+
+/// Converts node index or EdgeId to NodeIndex, VertexBuffer checks that it fits before adding nodes and connections.
+#[cfg(feature = "u32-indices")]
+pub(crate) fn to_node_index(index: usize) -> NodeIndex {
+    index as NodeIndex
+}
+#[cfg(not(feature = "u32-indices"))]
+pub(crate) fn to_node_index(index: usize) -> NodeIndex {
+    index
+}
+
+#[cfg(feature = "u32-indices")]
+pub(crate) fn from_node_index(index: NodeIndex) -> usize {
+    index as usize
+}
+#[cfg(not(feature = "u32-indices"))]
+pub(crate) fn from_node_index(index: NodeIndex) -> usize {
+    index
+}
+
+#[cfg(test)]
+mod hashing_tests {
+    use super::*;
+    use std::hash::BuildHasher;
+
+    #[test]
+    fn test_fx_hasher() {
+        // given
+        let build = BuildHasherDefault::<FxHasher>::default();
+        // when
+        let hash = |value: (u64, u64)| build.hash_one(value);
+        // then
+        assert_eq!(hash((1, 2)), hash((1, 2)));
+        assert_ne!(hash((1, 2)), hash((2, 1)));
+        let mut map: HashMap<usize, usize, BuildHasherDefault<FxHasher>> = HashMap::default();
+        map.extend((0..1000).map(|i| (i, i * 2)));
+        assert_eq!(map.len(), 1000);
+        assert_eq!(map[&777], 1554);
+    }
+}
@@ -0,0 +1,217 @@
+use crate::vertex::{EdgeId, VertexBuffer};
+use crate::dijkstra::search_edge_ids;
+use crate::path::Path;
+use crate::rng::SplitMix64;
+use crate::hashing::from_node_index;
+
+/// Options of `health`.
+///
+/// * `sample_size` - number of random node pairs searched, 100 by default, 0 skips sampling
+/// * `seed` - seed of sampled pairs, the same seed samples the same pairs of graphs of the same size
+#[derive(Debug, Clone, PartialEq)]
+pub struct HealthOptions {
+    pub sample_size: usize,
+    pub seed: u64,
+}
+
+impl Default for HealthOptions {
+    fn default() -> Self {
+        Self {sample_size: 100, seed: 0}
+    }
+}
+
+/// Inconsistency found by `health`, usually left by direct changes of `VertexBuffer::vector`.
+///
+/// * `NodeNotIndexed` - node is not found by its coordinates, `VertexBuffer::index_of` gives other node or none
+/// * `EdgeOutOfRange` - connection refers to a node which does not exist
+/// * `RelationMismatch` - GraphRelation of connection is missing in its start node or joins other nodes than the connection
+/// * `InvalidCost` - connection cost is negative or NaN, see `VertexBuffer::validate_costs`
+/// * `BrokenPath` - search between sampled nodes gave connections which do not form a path
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HealthIssue {
+    NodeNotIndexed(usize),
+    EdgeOutOfRange(EdgeId),
+    RelationMismatch(EdgeId),
+    InvalidCost(EdgeId),
+    BrokenPath(usize, usize),
+}
+
+/// Result of `health`.
+///
+/// * `version` - `VertexBuffer::version` checked, the number of cost updates and edits since the graph was built
+/// * `issues` - HealthIssues found, at most one per node, connection or sampled pair
+/// * `isolated_nodes` - number of nodes without any GraphRelation
+/// * `sampled_pairs` - number of searched node pairs
+/// * `reachable_pairs` - number of searched node pairs with a path between them
+#[derive(Debug, Clone, PartialEq)]
+pub struct HealthReport {
+    pub version: u64,
+    pub issues: Vec<HealthIssue>,
+    pub isolated_nodes: usize,
+    pub sampled_pairs: usize,
+    pub reachable_pairs: usize,
+}
+
+impl HealthReport {
+    /// Returns true when no HealthIssue was found.
+    pub fn is_healthy(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Returns share of sampled pairs which are reachable, 1.0 when nothing was sampled.
+    pub fn reachable_share(&self) -> f64 {
+        if self.sampled_pairs == 0 { 1.0 } else { self.reachable_pairs as f64 / self.sampled_pairs as f64 }
+    }
+
+    /// Returns true when graph should be rebuilt from source data: it has HealthIssues, or compared to
+    /// `baseline` report of the freshly built graph, its reachable share dropped by more than `tolerance`
+    /// or it has more isolated nodes, as many closures or edits pile up.
+    pub fn needs_rebuild(&self, baseline: &HealthReport, tolerance: f64) -> bool {
+        !self.is_healthy() || self.reachable_share() < baseline.reachable_share() - tolerance
+            || self.isolated_nodes > baseline.isolated_nodes
+    }
+}
+
+/// Checks VertexBuffer for use by long running services which change it incrementally, so they can decide
+/// when to rebuild it.
+///
+/// # Arguments:
+/// * `vertex` which is &VertexBuffer - graph to check
+/// * `options` which is &HealthOptions - number of sampled pairs and their seed
+///
+/// # Remarks:
+///
+/// Every node is looked up by its coordinates and every connection is checked against GraphRelations of
+/// its ends, which takes time linear in graph size. Sampling is skipped when issues were found, as searches
+/// may not be trusted then. Reachable share of sampled pairs falls as edits split the network, compare
+/// reports of the same options with `HealthReport::needs_rebuild`.
+///
+/// ```
+/// use path_navigator::components::*;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::edit::EditSession;
+/// use path_navigator::health::*;
+/// use path_navigator::vertex::VertexBuffer;
+///
+/// let connections = vec![
+///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+///     SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 2.0)),
+/// ];
+/// let mut vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+/// let baseline = health(&vertex, &HealthOptions::default());
+/// assert!(baseline.is_healthy());
+/// let mut session = EditSession::new(&vertex);
+/// session.remove_edge(1).unwrap();
+/// session.add_connection(SphereConnection::new(SpherePoint::new(1.0, 1.0), SpherePoint::new(1.0, 2.0)));
+/// session.commit(&mut vertex).unwrap();
+/// let report = health(&vertex, &HealthOptions::default());
+/// assert_eq!(report.version, 1);
+/// assert!(report.needs_rebuild(&baseline, 0.1));
+/// ```
+pub fn health(vertex: &VertexBuffer, options: &HealthOptions) -> HealthReport {
+    let mut issues: Vec<HealthIssue> = Vec::new();
+    for (index, node) in vertex.vector.iter().enumerate() {
+        if vertex.index_of(&node.coordinates) != Some(index) {
+            issues.push(HealthIssue::NodeNotIndexed(index));
+        }
+    }
+    let mut relation_counts: Vec<usize> = vec![0; vertex.edge_count()];
+    let mut relation_issues: Vec<Option<HealthIssue>> = vec![None; vertex.edge_count()];
+    for (index, node) in vertex.vector.iter().enumerate() {
+        for graph in &node.graphs {
+            let (child, edge_id) = (from_node_index(graph.vertex_index), from_node_index(graph.edge_id));
+            let issue = match vertex.edge(edge_id) {
+                None => Some(HealthIssue::RelationMismatch(edge_id)),
+                Some(ends) if ends != (index, child) && ends != (child, index) => {
+                    Some(HealthIssue::RelationMismatch(edge_id))
+                }
+                Some(_) if graph.cost.is_nan() || graph.cost < 0.0 => Some(HealthIssue::InvalidCost(edge_id)),
+                Some(_) => None,
+            };
+            if let Some(issue) = issue {
+                match relation_issues.get_mut(edge_id) {
+                    Some(recorded) => *recorded = recorded.or(Some(issue)),
+                    None => issues.push(issue),
+                }
+            }
+            if let Some(count) = relation_counts.get_mut(edge_id) {
+                *count += 1;
+            }
+        }
+    }
+    for edge_id in 0..vertex.edge_count() {
+        let (from, to) = vertex.edge(edge_id).unwrap();
+        if from >= vertex.len() || to >= vertex.len() {
+            issues.push(HealthIssue::EdgeOutOfRange(edge_id));
+        } else if let Some(issue) = relation_issues[edge_id] {
+            issues.push(issue);
+        } else if relation_counts[edge_id] == 0 || !vertex.vector[from].graphs.iter().any(|graph| from_node_index(graph.edge_id) == edge_id) {
+            issues.push(HealthIssue::RelationMismatch(edge_id));
+        }
+    }
+    let isolated_nodes = vertex.vector.iter().filter(|node| node.graphs.is_empty()).count();
+    let mut reachable_pairs = 0;
+    let mut sampled_pairs = 0;
+    if vertex.len() > 1 && issues.is_empty() {
+        let mut rng = SplitMix64::new(options.seed);
+        for _ in 0..options.sample_size {
+            let (start, finish) = (rng.below(vertex.len()), rng.below(vertex.len()));
+            sampled_pairs += 1;
+            let edge_ids = search_edge_ids(start, finish, vertex, |_, _, graph| Some(graph.cost));
+            if let Some(edge_ids) = edge_ids {
+                reachable_pairs += 1;
+                if start != finish && Path::from_edge_ids(start, edge_ids, vertex).is_none_or(|path| path.vertex_indices.last() != Some(&finish)) {
+                    issues.push(HealthIssue::BrokenPath(start, finish));
+                }
+            }
+        }
+    }
+    HealthReport {version: vertex.version(), issues, isolated_nodes, sampled_pairs, reachable_pairs}
+}
+
+#[cfg(test)]
+mod health_tests {
+    use super::*;
+    use crate::components::{SphereConnection, SpherePoint};
+    use crate::data::CelestialObject;
+    use crate::edit::EditSession;
+
+    #[test]
+    fn test_health_finds_broken_indices_and_degradation() {
+        // given: a ring of six nodes
+        let ring: Vec<SphereConnection> = (0..6)
+            .map(|i| SphereConnection::new(SpherePoint::new(0.0, i as f64), SpherePoint::new(0.0, ((i + 1) % 6) as f64)))
+            .collect();
+        let vertex = VertexBuffer::new(ring, CelestialObject::EARTH).unwrap();
+        let options = HealthOptions {sample_size: 50, seed: 3};
+        let baseline = health(&vertex, &options);
+        let mut closed = vertex.clone();
+        let mut session = EditSession::new(&closed);
+        session.remove_edge(0).unwrap();
+        session.remove_edge(3).unwrap();
+        session.commit(&mut closed).unwrap();
+        let mut moved = vertex.clone();
+        moved.vector[2].coordinates = SpherePoint::new(1.0, 2.0);
+        let mut negative = vertex.clone();
+        negative.vector[4].graphs[0].cost = -1.0;
+        let mut rewired = vertex.clone();
+        rewired.vector[1].graphs[0].vertex_index = 4;
+        // when
+        let closed_report = health(&closed, &options);
+        let moved_report = health(&moved, &options);
+        let negative_report = health(&negative, &options);
+        let rewired_report = health(&rewired, &options);
+        // then
+        assert!(baseline.is_healthy());
+        assert_eq!((baseline.sampled_pairs, baseline.reachable_pairs), (50, 50));
+        assert!(!baseline.needs_rebuild(&baseline, 0.0));
+        assert!(closed_report.is_healthy());
+        assert_eq!(closed_report.version, 1);
+        assert!(closed_report.reachable_share() < 0.9);
+        assert!(closed_report.needs_rebuild(&baseline, 0.05));
+        assert_eq!(moved_report.issues, vec![HealthIssue::NodeNotIndexed(2)]);
+        assert_eq!(moved_report.sampled_pairs, 0);
+        assert_eq!(negative_report.issues, vec![HealthIssue::InvalidCost(from_node_index(vertex.vector[4].graphs[0].edge_id))]);
+        assert_eq!(rewired_report.issues, vec![HealthIssue::RelationMismatch(from_node_index(vertex.vector[1].graphs[0].edge_id))]);
+    }
+}
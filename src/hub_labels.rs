@@ -0,0 +1,259 @@
+use crate::errors::*;
+use crate::vertex::VertexBuffer;
+use crate::dijkstra::FrontierEntry;
+use crate::hashing::{from_node_index, NodeMap};
+use crate::io::binary::{read_f64, read_header, read_usize, write_f64, write_header, write_u64};
+use std::collections::BinaryHeap;
+use std::io::{Read, Write};
+
+const MAGIC: &[u8; 4] = b"PNHL";
+const FORMAT_VERSION: u32 = 2;
+
+/// # HubLabels
+/// Two-hop cover of VertexBuffer for read-heavy deployments: every node keeps a label, a list of
+/// hubs with exact costs to them, such that any two connected nodes share a hub on their shortest path.
+/// On graphs with one-way connections every node keeps one label of costs from hubs and one of costs to them.
+/// Cost query is then a merge of two sorted lists and takes microseconds instead of a graph search.
+///
+/// # Remarks:
+///
+/// Labels are built with pruned landmark labeling, running a shortest path search from nodes in
+/// descending degree order (the order a node contraction would use) and pruning every node whose
+/// cost is already covered by earlier hubs. Building is much more expensive than single query and
+/// labels only reflect costs at build time, so they suit static graphs; rebuild after `update_costs`.
+///
+/// ```
+/// use path_navigator::components::*;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::hub_labels::HubLabels;
+/// use path_navigator::vertex::VertexBuffer;
+///
+/// let connections = vec![
+///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+///     SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 2.0)),
+/// ];
+/// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+/// let labels = HubLabels::build(&vertex);
+/// let mut saved: Vec<u8> = Vec::new();
+/// labels.save(&mut saved).unwrap();
+/// let loaded = HubLabels::load(&mut saved.as_slice()).unwrap();
+/// assert_eq!(loaded.cost(0, 2), labels.cost(0, 2));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct HubLabels {
+    // forward[node] holds (hub rank, cost from hub to node) sorted by hub rank
+    forward: Vec<Vec<(usize, f64)>>,
+    // backward[node] holds (hub rank, cost from node to hub), empty for two-way graphs, where both are equal
+    backward: Vec<Vec<(usize, f64)>>,
+}
+
+/// Label entries of every node, (hub rank, cost) sorted by hub rank.
+type Labels = Vec<Vec<(usize, f64)>>;
+
+impl HubLabels {
+    /// Builds labels of all nodes of VertexBuffer. Graphs with one-way connections get separate
+    /// labels of costs from hubs and to hubs, the latter found over `VertexBuffer::reversed`.
+    pub fn build(vertex: &VertexBuffer) -> Self {
+        let mut order: Vec<usize> = (0..vertex.len()).collect();
+        order.sort_by(|a, b| vertex.vector[*b].graphs.len().cmp(&vertex.vector[*a].graphs.len()).then(a.cmp(b)));
+        let reversed = if vertex.is_directed() { Some(vertex.reversed()) } else { None };
+        let mut forward: Labels = vec![Vec::new(); vertex.len()];
+        let mut backward: Labels = if reversed.is_some() { vec![Vec::new(); vertex.len()] } else { Vec::new() };
+        // costs between the current hub and hubs of its label, indexed by hub rank
+        let mut hub_costs: Vec<f64> = vec![f64::INFINITY; vertex.len()];
+        for (rank, hub) in order.iter().enumerate() {
+            let hub_label = if reversed.is_some() { backward[*hub].clone() } else { forward[*hub].clone() };
+            label_from_hub(vertex, *hub, rank, &hub_label, &mut forward, &mut hub_costs);
+            if let Some(reversed) = &reversed {
+                let hub_label = forward[*hub].clone();
+                label_from_hub(reversed, *hub, rank, &hub_label, &mut backward, &mut hub_costs);
+            }
+        }
+        Self {forward, backward}
+    }
+
+    /// Returns number of labelled nodes.
+    pub fn len(&self) -> usize {
+        self.forward.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.forward.is_empty()
+    }
+
+    /// Returns total number of label entries, useful for estimating memory use.
+    pub fn size(&self) -> usize {
+        self.forward.iter().chain(&self.backward).map(|label| label.len()).sum()
+    }
+
+    /// Returns cost of the shortest path from one node to another, None when either node is unknown
+    /// or `to` is not reachable from `from`.
+    pub fn cost(&self, from: usize, to: usize) -> Option<f64> {
+        let first = if self.backward.is_empty() { self.forward.get(from)? } else { self.backward.get(from)? };
+        let second = self.forward.get(to)?;
+        let (mut i, mut j) = (0, 0);
+        let mut best = f64::INFINITY;
+        while i < first.len() && j < second.len() {
+            match first[i].0.cmp(&second[j].0) {
+                std::cmp::Ordering::Less => i += 1,
+                std::cmp::Ordering::Greater => j += 1,
+                std::cmp::Ordering::Equal => {
+                    best = best.min(first[i].1 + second[j].1);
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+        if best.is_finite() { Some(best) } else { None }
+    }
+
+    /// Writes labels in compact little-endian binary format.
+    pub fn save<W: Write>(&self, writer: &mut W) -> Result<()> {
+        write_header(writer, MAGIC, FORMAT_VERSION)?;
+        for labels in [&self.forward, &self.backward] {
+            write_u64(writer, labels.len() as u64)?;
+            for label in labels {
+                write_u64(writer, label.len() as u64)?;
+                for (rank, cost) in label {
+                    write_u64(writer, *rank as u64)?;
+                    write_f64(writer, *cost)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads labels written by `save`, fails with `DataItemIncorrect` for data in other format.
+    pub fn load<R: Read>(reader: &mut R) -> Result<Self> {
+        read_header(reader, MAGIC, FORMAT_VERSION)?;
+        let forward = read_labels(reader)?;
+        let backward = read_labels(reader)?;
+        if !backward.is_empty() && backward.len() != forward.len() {
+            return Err(Error::from_kind(ErrorKind::DataItemIncorrect));
+        }
+        Ok(Self {forward, backward})
+    }
+}
+
+/// Runs pruned search from hub of given rank over `vertex` and adds the hub to `labels` of every node
+/// whose cost is not already covered by earlier hubs. `hub_label` holds costs of the opposite direction
+/// between the hub and earlier hubs, `hub_costs` is left filled with infinity.
+fn label_from_hub(vertex: &VertexBuffer, hub: usize, rank: usize, hub_label: &[(usize, f64)], labels: &mut Labels,
+    hub_costs: &mut [f64]) {
+    for (other_rank, cost) in hub_label {
+        hub_costs[*other_rank] = *cost;
+    }
+    let mut settled: NodeMap<usize, f64> = NodeMap::default();
+    let mut frontier: BinaryHeap<FrontierEntry> = BinaryHeap::new();
+    frontier.push(FrontierEntry::new(hub, 0.0));
+    while let Some(entry) = frontier.pop() {
+        if settled.contains_key(&entry.vertex_index) {
+            continue;
+        }
+        settled.insert(entry.vertex_index, entry.cost);
+        let covered = labels[entry.vertex_index].iter()
+            .any(|(other_rank, cost)| hub_costs[*other_rank] + cost <= entry.cost);
+        if covered {
+            continue;
+        }
+        labels[entry.vertex_index].push((rank, entry.cost));
+        for graph in &vertex.vector[entry.vertex_index].graphs {
+            let child = from_node_index(graph.vertex_index);
+            if !settled.contains_key(&child) {
+                frontier.push(FrontierEntry::new(child, entry.cost + graph.cost));
+            }
+        }
+    }
+    for (other_rank, _) in hub_label {
+        hub_costs[*other_rank] = f64::INFINITY;
+    }
+}
+
+fn read_labels<R: Read>(reader: &mut R) -> Result<Labels> {
+    let node_count = read_usize(reader)?;
+    let mut labels: Labels = Vec::new();
+    for _ in 0..node_count {
+        let label_len = read_usize(reader)?;
+        let mut label: Vec<(usize, f64)> = Vec::new();
+        for _ in 0..label_len {
+            let rank = read_usize(reader)?;
+            let cost = read_f64(reader)?;
+            if rank >= node_count || label.last().is_some_and(|(previous, _)| *previous >= rank) {
+                return Err(Error::from_kind(ErrorKind::DataItemIncorrect));
+            }
+            label.push((rank, cost));
+        }
+        labels.push(label);
+    }
+    Ok(labels)
+}
+
+#[cfg(test)]
+mod hub_labels_tests {
+    use super::*;
+    use crate::components::SphereBoundingBox;
+    use crate::dijkstra::search_from_sources;
+    use crate::vertex::generators::random;
+
+    #[test]
+    fn test_hub_label_costs_match_dijkstra() {
+        // given
+        let bbox = SphereBoundingBox::new(40.0, -10.0, 60.0, 30.0);
+        let vertex = random(120, 3.0, &bbox, 21).unwrap();
+        // when
+        let labels = HubLabels::build(&vertex);
+        // then
+        for from in (0..vertex.len()).step_by(7) {
+            let expected = search_from_sources(&[from], &vertex).costs;
+            for (to, expected) in expected.iter().enumerate() {
+                assert!(relative_eq!(labels.cost(from, to).unwrap(), expected.unwrap(), max_relative = 1e-9));
+            }
+        }
+        assert!(labels.size() < vertex.len() * vertex.len() / 2);
+        assert_eq!(labels.cost(0, vertex.len()), None);
+    }
+
+    #[test]
+    fn test_hub_labels_on_one_way_ring() {
+        // given
+        let ring = crate::fixtures::one_way_ring();
+        let directed = VertexBuffer::new_directed(random(80, 3.0, &SphereBoundingBox::new(40.0, -10.0, 60.0, 30.0), 5).unwrap().to_connections(),
+            crate::data::CelestialObject::EARTH).unwrap();
+        // when
+        let labels = HubLabels::build(&ring);
+        let directed_labels = HubLabels::build(&directed);
+        // then
+        let side = |edge_id| ring.edge_cost(edge_id).unwrap();
+        assert!(relative_eq!(labels.cost(0, 1).unwrap(), 111.19, max_relative = 1e-3));
+        assert!(relative_eq!(labels.cost(1, 0).unwrap(), side(1) + side(2) + side(3), max_relative = 1e-9));
+        assert!(relative_eq!(labels.cost(3, 2).unwrap(), side(3) + side(0) + side(1), max_relative = 1e-9));
+        for from in 0..directed.len() {
+            let expected = search_from_sources(&[from], &directed).costs;
+            for (to, expected) in expected.iter().enumerate() {
+                match (directed_labels.cost(from, to), *expected) {
+                    (Some(cost), Some(expected)) => assert!(relative_eq!(cost, expected, max_relative = 1e-9)),
+                    (cost, expected) => assert_eq!(cost.is_some(), expected.is_some()),
+                }
+            }
+        }
+        let mut saved: Vec<u8> = Vec::new();
+        labels.save(&mut saved).unwrap();
+        assert_eq!(HubLabels::load(&mut saved.as_slice()).unwrap(), labels);
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        // given
+        let bbox = SphereBoundingBox::new(40.0, -10.0, 60.0, 30.0);
+        let labels = HubLabels::build(&random(30, 2.5, &bbox, 4).unwrap());
+        let mut saved: Vec<u8> = Vec::new();
+        // when
+        labels.save(&mut saved).unwrap();
+        let loaded = HubLabels::load(&mut saved.as_slice()).unwrap();
+        // then
+        assert_eq!(loaded, labels);
+        saved[0] = b'X';
+        assert!(HubLabels::load(&mut saved.as_slice()).is_err());
+        assert!(HubLabels::load(&mut &saved[..10]).is_err());
+    }
+}
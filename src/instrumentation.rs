@@ -0,0 +1,59 @@
+use std::time::Duration;
+
+/// Timings and work of a single query, passed to Instrumentation once the query is answered.
+///
+/// * `snap` - time spent snapping start and finish to the closest points on VertexBuffer
+/// * `search` - time spent settling nodes, zero when the query was answered without search
+/// * `reconstruction` - time spent walking parents back from finish and building the Path
+/// * `nodes_expanded` - number of nodes settled by the search
+/// * `found` - whether the query was answered with a path
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct QueryMetrics {
+    pub snap: Duration,
+    pub search: Duration,
+    pub reconstruction: Duration,
+    pub nodes_expanded: usize,
+    pub found: bool,
+}
+
+impl QueryMetrics {
+    /// Returns time of the whole query.
+    pub fn total(&self) -> Duration {
+        self.snap + self.search + self.reconstruction
+    }
+}
+
+/// Receiver of QueryMetrics of instrumented queries, like `dijkstra::find_path_instrumented`, so
+/// services can export them to a metrics system of their choice. Closures of `FnMut(&QueryMetrics)`
+/// and `Vec<QueryMetrics>`, which keeps every record, are Instrumentations too.
+///
+/// ```
+/// use path_navigator::components::*;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::dijkstra::find_path_instrumented;
+/// use path_navigator::instrumentation::QueryMetrics;
+/// use path_navigator::vertex::VertexBuffer;
+///
+/// let connections = vec![SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0))];
+/// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+/// let mut expanded = 0;
+/// let mut counter = |metrics: &QueryMetrics| expanded += metrics.nodes_expanded;
+/// find_path_instrumented(&SpherePoint::new(0.0, 0.0), &SpherePoint::new(0.0, 1.0), &vertex, &mut counter);
+/// assert_eq!(expanded, 2);
+/// ```
+pub trait Instrumentation {
+    /// Receives metrics of an answered query.
+    fn record(&mut self, metrics: &QueryMetrics);
+}
+
+impl<F: FnMut(&QueryMetrics)> Instrumentation for F {
+    fn record(&mut self, metrics: &QueryMetrics) {
+        self(metrics)
+    }
+}
+
+impl Instrumentation for Vec<QueryMetrics> {
+    fn record(&mut self, metrics: &QueryMetrics) {
+        self.push(*metrics)
+    }
+}
@@ -0,0 +1,2 @@
+pub(crate) mod binary;
+pub mod geojson;
@@ -0,0 +1,65 @@
+use crate::errors::*;
+use std::convert::TryFrom;
+use std::io::{Read, Write};
+
+/// Writes magic bytes and format version which start every binary format of the crate.
+pub(crate) fn write_header<W: Write>(writer: &mut W, magic: &[u8; 4], version: u32) -> Result<()> {
+    writer.write_all(magic)?;
+    writer.write_all(&version.to_le_bytes())?;
+    Ok(())
+}
+
+/// Reads header written by `write_header`, fails with `DataItemIncorrect` for other magic or version.
+pub(crate) fn read_header<R: Read>(reader: &mut R, magic: &[u8; 4], version: u32) -> Result<()> {
+    let mut found_magic = [0u8; 4];
+    reader.read_exact(&mut found_magic)?;
+    let mut found_version = [0u8; 4];
+    reader.read_exact(&mut found_version)?;
+    if &found_magic != magic || u32::from_le_bytes(found_version) != version {
+        return Err(Error::from_kind(ErrorKind::DataItemIncorrect));
+    }
+    Ok(())
+}
+
+pub(crate) fn write_u64<W: Write>(writer: &mut W, value: u64) -> Result<()> {
+    writer.write_all(&value.to_le_bytes())?;
+    Ok(())
+}
+
+pub(crate) fn read_u64<R: Read>(reader: &mut R) -> Result<u64> {
+    let mut bytes = [0u8; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+pub(crate) fn write_f64<W: Write>(writer: &mut W, value: f64) -> Result<()> {
+    writer.write_all(&value.to_le_bytes())?;
+    Ok(())
+}
+
+pub(crate) fn read_f64<R: Read>(reader: &mut R) -> Result<f64> {
+    Ok(f64::from_bits(read_u64(reader)?))
+}
+
+/// Reads length or index, fails with `DataItemIncorrect` when it does not fit into usize.
+pub(crate) fn read_usize<R: Read>(reader: &mut R) -> Result<usize> {
+    usize::try_from(read_u64(reader)?).map_err(|_| Error::from_kind(ErrorKind::DataItemIncorrect))
+}
+
+/// Writes text as its byte length followed by UTF-8 bytes.
+pub(crate) fn write_string<W: Write>(writer: &mut W, value: &str) -> Result<()> {
+    write_u64(writer, value.len() as u64)?;
+    writer.write_all(value.as_bytes())?;
+    Ok(())
+}
+
+/// Reads text written by `write_string`, fails with `DataItemIncorrect` for invalid UTF-8.
+pub(crate) fn read_string<R: Read>(reader: &mut R) -> Result<String> {
+    let len = read_u64(reader)?;
+    let mut bytes: Vec<u8> = Vec::new();
+    reader.take(len).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 != len {
+        return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+    }
+    String::from_utf8(bytes).map_err(|_| Error::from_kind(ErrorKind::DataItemIncorrect))
+}
@@ -0,0 +1,357 @@
+use crate::errors::*;
+use crate::components::SpherePoint;
+use crate::route::Route;
+use crate::vertex::VertexBuffer;
+
+/// Reads VertexBuffer on Earth from GeoJSON FeatureCollection of LineString features,
+/// see `VertexBuffer::from_geojson`.
+///
+/// ```
+/// use path_navigator::io::geojson;
+///
+/// let text = r#"{"type": "FeatureCollection", "features": [{"type": "Feature", "properties": {},
+///     "geometry": {"type": "LineString", "coordinates": [[21.02, 52.23], [21.02, 52.24]]}}]}"#;
+/// assert_eq!(geojson::read_vertex_buffer(text).unwrap().edge_count(), 1);
+/// ```
+pub fn read_vertex_buffer(text: &str) -> Result<VertexBuffer> {
+    VertexBuffer::from_geojson(text)
+}
+
+/// Writes Route as GeoJSON Feature with LineString geometry, see `Route::to_geojson`.
+pub fn write_route(route: &Route) -> String {
+    route.to_geojson()
+}
+
+/// Writes Routes as GeoJSON FeatureCollection of their `Route::to_geojson` features, in order,
+/// which `read_vertex_buffer` reads back as a VertexBuffer of routed connections.
+pub fn write_routes(routes: &[Route]) -> String {
+    let features: Vec<String> = routes.iter().map(Route::to_geojson).collect();
+    feature_collection(&features)
+}
+
+/// Formats number as JSON value, non-finite numbers have no JSON representation and become `null`.
+pub(crate) fn number(value: f64) -> String {
+    if value.is_finite() {
+        format!("{}", value)
+    } else {
+        "null".to_string()
+    }
+}
+
+/// Formats text as JSON string, escaping quotes, backslashes and control characters.
+pub(crate) fn string(value: &str) -> String {
+    let mut result = String::with_capacity(value.len() + 2);
+    result.push('"');
+    for character in value.chars() {
+        match character {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            character if (character as u32) < 0x20 => result.push_str(&format!("\\u{:04x}", character as u32)),
+            character => result.push(character),
+        }
+    }
+    result.push('"');
+    result
+}
+
+/// Formats point as GeoJSON position, which is `[longitude, latitude]`.
+pub(crate) fn position(point: &SpherePoint) -> String {
+    format!("[{},{}]", number(point.lng), number(point.lat))
+}
+
+fn properties(properties: &[(&str, String)]) -> String {
+    let members: Vec<String> = properties.iter()
+        .map(|(key, value)| format!("\"{}\":{}", key, value))
+        .collect();
+    format!("{{{}}}", members.join(","))
+}
+
+/// Formats GeoJSON Feature with LineString geometry, property values have to be formatted JSON values.
+pub(crate) fn line_string_feature(points: &[SpherePoint], feature_properties: &[(&str, String)]) -> String {
+    let positions: Vec<String> = points.iter().map(position).collect();
+    format!(
+        "{{\"type\":\"Feature\",\"geometry\":{{\"type\":\"LineString\",\"coordinates\":[{}]}},\"properties\":{}}}",
+        positions.join(","),
+        properties(feature_properties),
+    )
+}
+
+/// Formats GeoJSON Feature with Point geometry, property values have to be formatted JSON values.
+pub(crate) fn point_feature(point: &SpherePoint, feature_properties: &[(&str, String)]) -> String {
+    format!(
+        "{{\"type\":\"Feature\",\"geometry\":{{\"type\":\"Point\",\"coordinates\":{}}},\"properties\":{}}}",
+        position(point),
+        properties(feature_properties),
+    )
+}
+
+/// Formats GeoJSON FeatureCollection of already formatted features.
+pub(crate) fn feature_collection(features: &[String]) -> String {
+    format!("{{\"type\":\"FeatureCollection\",\"features\":[{}]}}", features.join(","))
+}
+
+/// Deepest nesting of arrays and objects `parse` accepts, deeper documents fail instead of
+/// overflowing the stack.
+const MAX_DEPTH: usize = 128;
+
+/// JSON value read by `parse`, object members are kept in document order.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// Returns value of object member, the first one when the key repeats.
+    pub(crate) fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(members) => members.iter().find(|(name, _)| name == key).map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_f64(&self) -> Option<f64> {
+        match self {
+            Json::Number(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+/// Reads JSON document, fails with `DataItemIncorrect` for malformed text.
+pub(crate) fn parse(text: &str) -> Result<Json> {
+    let mut parser = Parser {text, position: 0};
+    let value = parser.value(0)?;
+    parser.skip_whitespace();
+    if parser.position != text.len() {
+        return Err(Error::from_kind(ErrorKind::DataItemIncorrect));
+    }
+    Ok(value)
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn skip_whitespace(&mut self) {
+        let rest = &self.text[self.position..];
+        self.position += rest.len() - rest.trim_start_matches([' ', '\t', '\n', '\r']).len();
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.position).copied()
+    }
+
+    /// Skips whitespace and consumes expected byte.
+    fn expect(&mut self, expected: u8) -> Result<()> {
+        self.skip_whitespace();
+        if self.peek() != Some(expected) {
+            return Err(Error::from_kind(ErrorKind::DataItemIncorrect));
+        }
+        self.position += 1;
+        Ok(())
+    }
+
+    fn literal(&mut self, literal: &str, value: Json) -> Result<Json> {
+        if !self.text[self.position..].starts_with(literal) {
+            return Err(Error::from_kind(ErrorKind::DataItemIncorrect));
+        }
+        self.position += literal.len();
+        Ok(value)
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Json> {
+        if depth > MAX_DEPTH {
+            return Err(Error::from_kind(ErrorKind::DataItemIncorrect));
+        }
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'{') => self.object(depth),
+            Some(b'[') => self.array(depth),
+            Some(b'"') => self.string().map(Json::String),
+            Some(b'n') => self.literal("null", Json::Null),
+            Some(b't') => self.literal("true", Json::Bool(true)),
+            Some(b'f') => self.literal("false", Json::Bool(false)),
+            Some(b'-') | Some(b'0'..=b'9') => self.number(),
+            _ => Err(Error::from_kind(ErrorKind::DataItemIncorrect)),
+        }
+    }
+
+    fn object(&mut self, depth: usize) -> Result<Json> {
+        self.expect(b'{')?;
+        let mut members: Vec<(String, Json)> = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.position += 1;
+            return Ok(Json::Object(members));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.expect(b':')?;
+            members.push((key, self.value(depth + 1)?));
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(b'}') => {
+                    self.position += 1;
+                    return Ok(Json::Object(members));
+                }
+                _ => return Err(Error::from_kind(ErrorKind::DataItemIncorrect)),
+            }
+        }
+    }
+
+    fn array(&mut self, depth: usize) -> Result<Json> {
+        self.expect(b'[')?;
+        let mut values: Vec<Json> = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.position += 1;
+            return Ok(Json::Array(values));
+        }
+        loop {
+            values.push(self.value(depth + 1)?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(b']') => {
+                    self.position += 1;
+                    return Ok(Json::Array(values));
+                }
+                _ => return Err(Error::from_kind(ErrorKind::DataItemIncorrect)),
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Json> {
+        let rest = &self.text[self.position..];
+        let length = rest.find(|character: char| !matches!(character, '0'..='9' | '-' | '+' | '.' | 'e' | 'E'))
+            .unwrap_or(rest.len());
+        let value = rest[..length].parse::<f64>().map_err(|_| Error::from_kind(ErrorKind::DataItemIncorrect))?;
+        self.position += length;
+        Ok(Json::Number(value))
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let incorrect = || Error::from_kind(ErrorKind::DataItemIncorrect);
+        if self.peek() != Some(b'"') {
+            return Err(incorrect());
+        }
+        self.position += 1;
+        let mut result = String::new();
+        loop {
+            let character = self.text[self.position..].chars().next().ok_or_else(incorrect)?;
+            self.position += character.len_utf8();
+            match character {
+                '"' => return Ok(result),
+                '\\' => {
+                    let escaped = self.text[self.position..].chars().next().ok_or_else(incorrect)?;
+                    self.position += escaped.len_utf8();
+                    match escaped {
+                        '"' | '\\' | '/' => result.push(escaped),
+                        'b' => result.push('\u{8}'),
+                        'f' => result.push('\u{c}'),
+                        'n' => result.push('\n'),
+                        'r' => result.push('\r'),
+                        't' => result.push('\t'),
+                        'u' => result.push(self.unicode_escape()?),
+                        _ => return Err(incorrect()),
+                    }
+                }
+                character if (character as u32) < 0x20 => return Err(incorrect()),
+                character => result.push(character),
+            }
+        }
+    }
+
+    /// Reads code point of `\u` escape, joining UTF-16 surrogate pairs written as two escapes.
+    fn unicode_escape(&mut self) -> Result<char> {
+        let incorrect = || Error::from_kind(ErrorKind::DataItemIncorrect);
+        let high = self.hex_unit()?;
+        let code = if (0xd800..0xdc00).contains(&high) {
+            if !self.text[self.position..].starts_with("\\u") {
+                return Err(incorrect());
+            }
+            self.position += 2;
+            let low = self.hex_unit()?;
+            if !(0xdc00..0xe000).contains(&low) {
+                return Err(incorrect());
+            }
+            0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+        } else {
+            high
+        };
+        char::from_u32(code).ok_or_else(incorrect)
+    }
+
+    fn hex_unit(&mut self) -> Result<u32> {
+        let digits = self.text.get(self.position..self.position + 4)
+            .filter(|digits| digits.bytes().all(|byte| byte.is_ascii_hexdigit()))
+            .ok_or_else(|| Error::from_kind(ErrorKind::DataItemIncorrect))?;
+        self.position += 4;
+        Ok(u32::from_str_radix(digits, 16).unwrap())
+    }
+}
+
+#[cfg(test)]
+mod geojson_tests {
+    use super::*;
+
+    #[test]
+    fn test_feature_collection() {
+        // given
+        let points = vec![SpherePoint::new(54.35, 18.6667), SpherePoint::new(52.25, 21.0)];
+        // when
+        let line = line_string_feature(&points, &[("cost", number(f64::INFINITY)), ("name", "\"Vistula\"".to_string())]);
+        let collection = feature_collection(&[line]);
+        // then
+        assert_eq!(string("a \"quoted\"\\path\n"), "\"a \\\"quoted\\\"\\\\path\\n\"");
+        assert_eq!(collection, concat!(
+            "{\"type\":\"FeatureCollection\",\"features\":[",
+            "{\"type\":\"Feature\",\"geometry\":{\"type\":\"LineString\",\"coordinates\":[[18.6667,54.35],[21,52.25]]},",
+            "\"properties\":{\"cost\":null,\"name\":\"Vistula\"}}]}",
+        ));
+    }
+
+    #[test]
+    fn test_parse() {
+        // given
+        let text = " {\"a\": [1, -2.5e1, true, null], \"b\": {\"c\": \"\\u00e9\\\"\\ud83d\\ude00\"}, \"a\": 3} ";
+        // when
+        let json = parse(text).unwrap();
+        // then
+        let values = json.get("a").unwrap().as_array().unwrap();
+        assert_eq!(values, &[Json::Number(1.0), Json::Number(-25.0), Json::Bool(true), Json::Null][..]);
+        assert_eq!(json.get("b").and_then(|b| b.get("c")).and_then(Json::as_str), Some("\u{e9}\"\u{1f600}"));
+        assert_eq!(parse(&string("a \"quoted\"\\path\n")).unwrap(), Json::String("a \"quoted\"\\path\n".to_string()));
+        assert_eq!(parse("[]").unwrap(), Json::Array(Vec::new()));
+        assert!(parse("{\"a\": 1,}").is_err());
+        assert!(parse("[1] 2").is_err());
+        assert!(parse("\"\\ud83d\"").is_err());
+        assert!(parse(&"[".repeat(MAX_DEPTH + 2)).is_err());
+    }
+}
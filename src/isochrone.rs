@@ -0,0 +1,142 @@
+use crate::errors::*;
+use crate::vertex::VertexBuffer;
+use crate::components::{SpherePoint, SpherePolygon};
+use crate::dijkstra::{get_closest_point, search_within};
+use crate::hashing::NodeMap;
+
+/// Shape of `isochrones` result.
+///
+/// * `Union` - single Isochrone of the area reachable from any origin
+/// * `PerOrigin` - one Isochrone per origin, in order of origins
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IsochroneOutput {
+    Union,
+    PerOrigin,
+}
+
+/// Area reachable within a cost budget.
+///
+/// * `origins` - positions of origins in the query this area is reachable from
+/// * `vertex_indices` - reachable nodes in ascending order
+/// * `overlap` - reachable nodes which are reachable from more than one origin, in ascending order
+/// * `polygon` - convex hull of reachable nodes, None when they do not span an area, like a single node
+#[derive(Debug, Clone, PartialEq)]
+pub struct Isochrone {
+    pub origins: Vec<usize>,
+    pub vertex_indices: Vec<usize>,
+    pub overlap: Vec<usize>,
+    pub polygon: Option<SpherePolygon>,
+}
+
+/// Returns areas reachable from given origins within `budget` network cost.
+///
+/// # Arguments:
+/// * `origins` which is &[SpherePoint] - origins, like depots, snapped to the closest points on VertexBuffer
+/// * `budget` which is f64 - the highest cost of reachable nodes
+/// * `vertex` which is &VertexBuffer - precalculated vertex for avaliable paths on given geometry
+/// * `output` which is IsochroneOutput - union of all areas or one area per origin
+///
+/// # Remarks:
+///
+/// Every origin is searched on its own up to the budget, so overlaps of coverage are known in both
+/// outputs. Polygons are convex hulls and cover gaps between reachable roads, nodes tell exactly what
+/// is reachable. Fails with `InvalidParameter` for negative or not finite budget and with
+/// `DataItemIncomplete` for no origins or empty VertexBuffer.
+///
+/// ```
+/// use path_navigator::components::*;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::isochrone::*;
+/// use path_navigator::vertex::VertexBuffer;
+///
+/// let connections = vec![
+///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+///     SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 2.0)),
+/// ];
+/// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+/// let depots = vec![SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 2.0)];
+/// let areas = isochrones(&depots, 120.0, &vertex, IsochroneOutput::PerOrigin).unwrap();
+/// assert_eq!(areas[0].vertex_indices, vec![0, 1]);
+/// assert_eq!(areas[1].overlap, vec![1]);
+/// ```
+pub fn isochrones(origins: &[SpherePoint], budget: f64, vertex: &VertexBuffer, output: IsochroneOutput) -> Result<Vec<Isochrone>> {
+    if !budget.is_finite() || budget < 0.0 {
+        return Err(Error::from_kind(ErrorKind::InvalidParameter));
+    }
+    if origins.is_empty() || vertex.is_empty() {
+        return Err(Error::from_kind(ErrorKind::DataItemIncomplete));
+    }
+    let reached: Vec<Vec<usize>> = origins.iter()
+        .map(|origin| {
+            let mut nodes: Vec<usize> = search_within(&[get_closest_point(origin, vertex)], budget, vertex).settled;
+            nodes.sort_unstable();
+            nodes
+        })
+        .collect();
+    let mut reach_counts: NodeMap<usize, usize> = NodeMap::default();
+    reached.iter().flatten().for_each(|node| *reach_counts.entry(*node).or_insert(0) += 1);
+    let isochrone = |origins: Vec<usize>, vertex_indices: Vec<usize>| {
+        let overlap = vertex_indices.iter().copied().filter(|node| reach_counts[node] > 1).collect();
+        let points: Vec<SpherePoint> = vertex_indices.iter().map(|node| vertex.vector[*node].coordinates.clone()).collect();
+        Isochrone {origins, vertex_indices, overlap, polygon: SpherePolygon::convex_hull(&points)}
+    };
+    Ok(match output {
+        IsochroneOutput::PerOrigin => reached.into_iter()
+            .enumerate()
+            .map(|(origin, vertex_indices)| isochrone(vec![origin], vertex_indices))
+            .collect(),
+        IsochroneOutput::Union => {
+            let mut vertex_indices: Vec<usize> = reach_counts.keys().copied().collect();
+            vertex_indices.sort_unstable();
+            vec![isochrone((0..origins.len()).collect(), vertex_indices)]
+        }
+    })
+}
+
+#[cfg(test)]
+mod isochrone_tests {
+    use super::*;
+    use crate::components::SphereConnection;
+    use crate::data::CelestialObject;
+
+    #[test]
+    fn test_isochrones_of_two_depots() {
+        // given: a grid 3 x 5 with depots at both ends of the middle row
+        let point = |row: usize, column: usize| SpherePoint::new(row as f64, column as f64);
+        let mut connections: Vec<SphereConnection> = Vec::new();
+        for row in 0..3 {
+            for column in 0..5 {
+                if column < 4 {
+                    connections.push(SphereConnection::new(point(row, column), point(row, column + 1)));
+                }
+                if row < 2 {
+                    connections.push(SphereConnection::new(point(row, column), point(row + 1, column)));
+                }
+            }
+        }
+        let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        let depots = vec![point(1, 0), point(1, 4)];
+        let budget = 2.5 * 111.2;
+        // when
+        let per_origin = isochrones(&depots, budget, &vertex, IsochroneOutput::PerOrigin).unwrap();
+        let union = isochrones(&depots, budget, &vertex, IsochroneOutput::Union).unwrap();
+        // then
+        let middle = vertex.index_of(&point(1, 2)).unwrap();
+        assert_eq!(per_origin.len(), 2);
+        assert_eq!(per_origin[0].origins, vec![0]);
+        assert_eq!(per_origin[0].overlap, vec![middle]);
+        assert_eq!(per_origin[1].overlap, vec![middle]);
+        assert!(!per_origin[0].vertex_indices.contains(&vertex.index_of(&point(0, 2)).unwrap()));
+        let left = per_origin[0].polygon.as_ref().unwrap();
+        assert!(left.contains(&SpherePoint::new(1.0, 0.5)) && !left.contains(&SpherePoint::new(1.0, 3.5)));
+        assert_eq!(union.len(), 1);
+        assert_eq!(union[0].origins, vec![0, 1]);
+        assert_eq!(union[0].vertex_indices.len(), 2 * per_origin[0].vertex_indices.len() - 1);
+        assert_eq!(union[0].overlap, vec![middle]);
+        // corners only, points along meridians and the equator are collinear, along parallel 2 inside
+        assert_eq!(union[0].polygon.as_ref().unwrap().vertices.len(), 4);
+        let single = isochrones(&depots[..1], 0.0, &vertex, IsochroneOutput::Union).unwrap();
+        assert_eq!((single[0].vertex_indices.len(), single[0].polygon.is_none()), (1, true));
+        assert!(isochrones(&depots, -1.0, &vertex, IsochroneOutput::Union).is_err());
+    }
+}
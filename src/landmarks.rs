@@ -0,0 +1,228 @@
+use crate::errors::*;
+use crate::vertex::VertexBuffer;
+use crate::dijkstra::search_from_sources;
+use crate::io::binary::{read_f64, read_header, read_u64, read_usize, write_f64, write_header, write_u64};
+use std::io::{Read, Write};
+
+const MAGIC: &[u8; 4] = b"PNLM";
+const FORMAT_VERSION: u32 = 2;
+
+/// # Landmarks
+/// Exact costs from a few landmark nodes to every node, giving lower bounds of network cost between
+/// any two nodes without a graph search, the estimator of ALT (A*, landmarks, triangle inequality).
+///
+/// # Remarks:
+///
+/// For landmark L and nodes a, b triangle inequality gives `cost(a, b) >= cost(L, b) - cost(L, a)` and
+/// `cost(a, b) >= cost(a, L) - cost(b, L)`, the estimate is the best of these bounds. Costs to landmarks
+/// are found over `VertexBuffer::reversed` when the graph has one-way connections, on two-way graphs
+/// they equal costs from landmarks and are not stored again. Landmarks are picked one by one as the node furthest
+/// from those picked so far, so they lie on the edges of the graph where bounds are tight. Costs
+/// are taken at build time, rebuild after `update_costs`.
+///
+/// ```
+/// use path_navigator::components::*;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::landmarks::Landmarks;
+/// use path_navigator::vertex::VertexBuffer;
+///
+/// let connections = vec![
+///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+///     SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 2.0)),
+/// ];
+/// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+/// let landmarks = Landmarks::build(&vertex, 1).unwrap();
+/// assert_eq!(landmarks.vertex_indices, vec![2]);
+/// assert_eq!(landmarks.estimated_min_cost(0, 2), vertex.vector[0].graphs[0].cost * 2.0);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Landmarks {
+    pub vertex_indices: Vec<usize>,
+    // costs[landmark][node], None for nodes not reachable from the landmark
+    costs: Vec<Vec<Option<f64>>>,
+    // costs_to[landmark][node], None for nodes the landmark is not reachable from, empty for two-way graphs
+    costs_to: Vec<Vec<Option<f64>>>,
+}
+
+impl Landmarks {
+    /// Picks `count` landmarks, or all nodes when VertexBuffer is smaller, and computes costs from them.
+    /// Fails with `InvalidParameter` for zero count and `DataItemIncomplete` for empty VertexBuffer.
+    pub fn build(vertex: &VertexBuffer, count: usize) -> Result<Self> {
+        if count == 0 {
+            return Err(Error::from_kind(ErrorKind::InvalidParameter));
+        }
+        if vertex.is_empty() {
+            return Err(Error::from_kind(ErrorKind::DataItemIncomplete));
+        }
+        let costs_over = |graph: &VertexBuffer, source: usize| -> Vec<Option<f64>> {
+            search_from_sources(&[source], graph).costs
+        };
+        let costs_from = |source: usize| costs_over(vertex, source);
+        let reversed = if vertex.is_directed() { Some(vertex.reversed()) } else { None };
+        // cost from the closest landmark picked so far, unreachable nodes are the furthest
+        let mut closest: Vec<f64> = costs_from(0).iter().map(|cost| cost.unwrap_or(f64::INFINITY)).collect();
+        let mut vertex_indices: Vec<usize> = Vec::new();
+        let mut costs: Vec<Vec<Option<f64>>> = Vec::new();
+        let mut costs_to: Vec<Vec<Option<f64>>> = Vec::new();
+        while vertex_indices.len() < count.min(vertex.len()) {
+            let landmark = (0..vertex.len())
+                .filter(|node| !vertex_indices.contains(node))
+                .max_by(|a, b| closest[*a].total_cmp(&closest[*b]).then(b.cmp(a)))
+                .unwrap();
+            let landmark_costs = costs_from(landmark);
+            for (node, cost) in landmark_costs.iter().enumerate() {
+                closest[node] = closest[node].min(cost.unwrap_or(f64::INFINITY));
+            }
+            if let Some(reversed) = &reversed {
+                costs_to.push(costs_over(reversed, landmark));
+            }
+            vertex_indices.push(landmark);
+            costs.push(landmark_costs);
+        }
+        Ok(Self {vertex_indices, costs, costs_to})
+    }
+
+    /// Returns lower bound of network cost from one node to another, never higher than the cost of
+    /// the shortest path. Infinity when landmarks prove `to` is not reachable from `from`,
+    /// 0.0 for unknown nodes.
+    pub fn estimated_min_cost(&self, from: usize, to: usize) -> f64 {
+        let costs_to = if self.costs_to.is_empty() { &self.costs } else { &self.costs_to };
+        let known = from < self.node_count() && to < self.node_count();
+        self.costs.iter().zip(costs_to)
+            .map(|(costs, costs_to)| {
+                // landmark reaching `from` but not `to`, or reached from `to` but not from `from`, separates them
+                let from_landmark = match (costs.get(from).copied().flatten(), costs.get(to).copied().flatten()) {
+                    (Some(from_cost), Some(to_cost)) => to_cost - from_cost,
+                    (Some(_), None) if known => f64::INFINITY,
+                    _ => 0.0,
+                };
+                let to_landmark = match (costs_to.get(from).copied().flatten(), costs_to.get(to).copied().flatten()) {
+                    (Some(from_cost), Some(to_cost)) => from_cost - to_cost,
+                    (None, Some(_)) if known => f64::INFINITY,
+                    _ => 0.0,
+                };
+                from_landmark.max(to_landmark)
+            })
+            .fold(0.0, f64::max)
+    }
+
+    /// Returns number of nodes landmark costs were computed for.
+    pub fn node_count(&self) -> usize {
+        self.costs.first().map_or(0, |costs| costs.len())
+    }
+
+    /// Writes landmarks and their costs in compact little-endian binary format,
+    /// unreachable nodes are stored as NaN.
+    pub fn save<W: Write>(&self, writer: &mut W) -> Result<()> {
+        write_header(writer, MAGIC, FORMAT_VERSION)?;
+        write_u64(writer, self.vertex_indices.len() as u64)?;
+        write_u64(writer, self.node_count() as u64)?;
+        write_u64(writer, !self.costs_to.is_empty() as u64)?;
+        for (landmark, costs) in self.vertex_indices.iter().zip(&self.costs) {
+            write_u64(writer, *landmark as u64)?;
+            for cost in costs {
+                write_f64(writer, cost.unwrap_or(f64::NAN))?;
+            }
+        }
+        for cost in self.costs_to.iter().flatten() {
+            write_f64(writer, cost.unwrap_or(f64::NAN))?;
+        }
+        Ok(())
+    }
+
+    /// Reads landmarks written by `save`, fails with `DataItemIncorrect` for data in other format.
+    pub fn load<R: Read>(reader: &mut R) -> Result<Self> {
+        read_header(reader, MAGIC, FORMAT_VERSION)?;
+        let landmark_count = read_usize(reader)?;
+        let node_count = read_usize(reader)?;
+        let is_directed = match read_u64(reader)? {
+            0 => false,
+            1 => true,
+            _ => return Err(Error::from_kind(ErrorKind::DataItemIncorrect)),
+        };
+        let read_costs = |reader: &mut R| (0..node_count)
+            .map(|_| read_f64(reader).map(|cost| if cost.is_nan() { None } else { Some(cost) }))
+            .collect::<Result<Vec<Option<f64>>>>();
+        let mut vertex_indices: Vec<usize> = Vec::new();
+        let mut costs: Vec<Vec<Option<f64>>> = Vec::new();
+        for _ in 0..landmark_count {
+            let landmark = read_usize(reader)?;
+            if landmark >= node_count {
+                return Err(Error::from_kind(ErrorKind::DataItemIncorrect));
+            }
+            vertex_indices.push(landmark);
+            costs.push(read_costs(reader)?);
+        }
+        let costs_to = if is_directed {
+            (0..landmark_count).map(|_| read_costs(reader)).collect::<Result<Vec<Vec<Option<f64>>>>>()?
+        } else {
+            Vec::new()
+        };
+        Ok(Self {vertex_indices, costs, costs_to})
+    }
+}
+
+#[cfg(test)]
+mod landmarks_tests {
+    use super::*;
+    use crate::components::{SphereConnection, SpherePoint};
+    use crate::data::CelestialObject;
+    use crate::dijkstra::find_path;
+
+    #[test]
+    fn test_estimates_are_lower_bounds() {
+        // given: a grid 4 x 4 with a detour-forcing missing connection and a separate island
+        let point = |row: usize, column: usize| SpherePoint::new(row as f64, column as f64);
+        let mut connections: Vec<SphereConnection> = Vec::new();
+        for row in 0..4 {
+            for column in 0..4 {
+                if column < 3 && !(row == 1 && column == 1) {
+                    connections.push(SphereConnection::new(point(row, column), point(row, column + 1)));
+                }
+                if row < 3 {
+                    connections.push(SphereConnection::new(point(row, column), point(row + 1, column)));
+                }
+            }
+        }
+        connections.push(SphereConnection::new(SpherePoint::new(20.0, 20.0), SpherePoint::new(20.0, 21.0)));
+        let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        // when
+        let landmarks = Landmarks::build(&vertex, 4).unwrap();
+        // then
+        assert_eq!(landmarks.vertex_indices.len(), 4);
+        let island = vertex.index_of(&SpherePoint::new(20.0, 20.0)).unwrap();
+        assert!(landmarks.vertex_indices.contains(&island) || landmarks.vertex_indices.contains(&(island + 1)));
+        for a in 0..16 {
+            for b in 0..16 {
+                let exact = find_path(&vertex.vector[a].coordinates, &vertex.vector[b].coordinates, &vertex)
+                    .map_or(0.0, |path| path.cost());
+                assert!(landmarks.estimated_min_cost(a, b) <= exact * (1.0 + 1e-12));
+            }
+        }
+        let corners = (vertex.index_of(&point(0, 0)).unwrap(), vertex.index_of(&point(3, 3)).unwrap());
+        assert!(landmarks.estimated_min_cost(corners.0, corners.1) > 0.0);
+        assert_eq!(landmarks.estimated_min_cost(0, island), f64::INFINITY);
+        assert_eq!(landmarks.estimated_min_cost(0, 42), 0.0);
+        assert!(Landmarks::build(&vertex, 0).is_err());
+    }
+
+    #[test]
+    fn test_estimates_on_one_way_ring() {
+        // given
+        let ring = crate::fixtures::one_way_ring();
+        // when
+        let landmarks = Landmarks::build(&ring, 4).unwrap();
+        let mut saved: Vec<u8> = Vec::new();
+        landmarks.save(&mut saved).unwrap();
+        let loaded = Landmarks::load(&mut saved.as_slice()).unwrap();
+        // then
+        for a in 0..4 {
+            for b in 0..4 {
+                let exact = find_path(&ring.vector[a].coordinates, &ring.vector[b].coordinates, &ring).map_or(0.0, |path| path.cost());
+                assert!(landmarks.estimated_min_cost(a, b) <= exact * (1.0 + 1e-12));
+            }
+        }
+        assert!(landmarks.estimated_min_cost(1, 0) > ring.edge_cost(0).unwrap());
+        assert_eq!(loaded, landmarks);
+    }
+}
@@ -0,0 +1,249 @@
+use crate::errors::*;
+use crate::vertex::VertexBuffer;
+use crate::components::{SphereConnection, SpherePoint};
+use crate::data::get_radius_km;
+use crate::dijkstra::{get_closest_point, FrontierEntry};
+use crate::flight_plan::Waypoint;
+use crate::hashing::{from_node_index, NodeMap};
+use std::collections::BinaryHeap;
+
+/// VertexBuffer of connections flown at the same altitude above the surface, in kilometers.
+#[derive(Debug, Clone)]
+pub struct AltitudeLayer {
+    pub altitude_km: f64,
+    pub vertex: VertexBuffer,
+}
+
+impl AltitudeLayer {
+    pub fn new(altitude_km: f64, vertex: VertexBuffer) -> Self {
+        Self {altitude_km, vertex}
+    }
+}
+
+/// Node of LayeredGraph, `vertex_index` is index of node in VertexBuffer of `layer`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct LayerNode {
+    pub layer: usize,
+    pub vertex_index: usize,
+}
+
+impl LayerNode {
+    pub fn new(layer: usize, vertex_index: usize) -> Self {
+        Self {layer, vertex_index}
+    }
+}
+
+/// Cheapest route through LayeredGraph with visited nodes in travel order and its total cost.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayeredPath {
+    pub nodes: Vec<LayerNode>,
+    pub cost: f64,
+}
+
+/// # LayeredGraph
+/// 3D airspace network made of altitude layers stacked over the same celestial object and
+/// joined by climb and descend connectors.
+///
+/// # Remarks:
+///
+/// Horizontal cost in a layer is connection cost scaled by `(radius + altitude) / radius`, which is
+/// the great-circle length at that altitude for haversine costs. Connector cost is the horizontal
+/// distance between its ends at their mean altitude plus `vertical_weight` times altitude change,
+/// so `vertical_weight` above 1.0 makes climbing more expensive than flying level.
+///
+/// ```
+/// use path_navigator::components::*;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::layers::*;
+/// use path_navigator::vertex::VertexBuffer;
+///
+/// let low = VertexBuffer::new(vec![
+///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+/// ], CelestialObject::EARTH).unwrap();
+/// let high = VertexBuffer::new(vec![
+///     SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 5.0)),
+/// ], CelestialObject::EARTH).unwrap();
+/// let mut graph = LayeredGraph::new(vec![AltitudeLayer::new(0.1, low), AltitudeLayer::new(0.5, high)], 3.0).unwrap();
+/// assert_eq!(graph.connect_stacked_nodes(), 1);
+/// let path = graph.find_path(0, &SpherePoint::new(0.0, 0.0), 1, &SpherePoint::new(0.0, 5.0)).unwrap();
+/// assert_eq!(path.nodes, vec![LayerNode::new(0, 0), LayerNode::new(0, 1), LayerNode::new(1, 0), LayerNode::new(1, 1)]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct LayeredGraph {
+    pub layers: Vec<AltitudeLayer>,
+    pub vertical_weight: f64,
+    radius: f64,
+    connectors: NodeMap<LayerNode, Vec<(LayerNode, f64)>>,
+}
+
+impl LayeredGraph {
+    /// Creates LayeredGraph without connectors. Fails with `DataItemIncorrect` for no layers,
+    /// with `BodyMismatch` for layers over different celestial objects and with `InvalidParameter`
+    /// for negative or not finite `vertical_weight` or altitudes.
+    pub fn new(layers: Vec<AltitudeLayer>, vertical_weight: f64) -> Result<Self> {
+        let radius = match layers.first() {
+            Some(layer) => get_radius_km(&layer.vertex.celestial_object),
+            None => return Err(Error::from_kind(ErrorKind::DataItemIncorrect)),
+        };
+        for layer in &layers[1..] {
+            layers[0].vertex.check_same_body(&layer.vertex)?;
+        }
+        let is_invalid = |value: f64| !value.is_finite() || value < 0.0;
+        if is_invalid(vertical_weight) || layers.iter().any(|layer| is_invalid(layer.altitude_km)) {
+            return Err(Error::from_kind(ErrorKind::InvalidParameter));
+        }
+        Ok(Self {layers, vertical_weight, radius, connectors: NodeMap::default()})
+    }
+
+    /// Adds climb or descend connector usable in both directions.
+    /// Fails with `DataItemIncorrect` when any node does not exist or both are in the same layer.
+    pub fn add_connector(&mut self, from: LayerNode, to: LayerNode) -> Result<()> {
+        if from.layer == to.layer || !self.contains(&from) || !self.contains(&to) {
+            return Err(Error::from_kind(ErrorKind::DataItemIncorrect));
+        }
+        let from_layer = &self.layers[from.layer];
+        let to_layer = &self.layers[to.layer];
+        let mean_altitude = (from_layer.altitude_km + to_layer.altitude_km) / 2.0;
+        let horizontal = SphereConnection::new(
+            from_layer.vertex.vector[from.vertex_index].coordinates.clone(),
+            to_layer.vertex.vector[to.vertex_index].coordinates.clone(),
+        ).cost(self.radius + mean_altitude);
+        let cost = horizontal + self.vertical_weight * (from_layer.altitude_km - to_layer.altitude_km).abs();
+        self.connectors.entry(from).or_default().push((to, cost));
+        self.connectors.entry(to).or_default().push((from, cost));
+        Ok(())
+    }
+
+    /// Connects every node with the node at the same coordinates in the next layer, returns number of added connectors.
+    pub fn connect_stacked_nodes(&mut self) -> usize {
+        let mut pairs: Vec<(LayerNode, LayerNode)> = Vec::new();
+        for layer in 1..self.layers.len() {
+            for (vertex_index, node) in self.layers[layer - 1].vertex.vector.iter().enumerate() {
+                if let Some(upper_index) = self.layers[layer].vertex.index_of(&node.coordinates) {
+                    pairs.push((LayerNode::new(layer - 1, vertex_index), LayerNode::new(layer, upper_index)));
+                }
+            }
+        }
+        for (from, to) in &pairs {
+            self.add_connector(*from, *to).unwrap(); // both nodes exist in different layers
+        }
+        pairs.len()
+    }
+
+    /// Returns the cheapest route from point in one layer to point in another one, points are snapped
+    /// to the closest nodes of their layers. None when a layer does not exist or finish is not reachable.
+    pub fn find_path(&self, start_layer: usize, start: &SpherePoint, finish_layer: usize, finish: &SpherePoint)
+    -> Option<LayeredPath> {
+        let start_node = LayerNode::new(start_layer, get_closest_point(start, &self.layers.get(start_layer)?.vertex));
+        let finish_node = LayerNode::new(finish_layer, get_closest_point(finish, &self.layers.get(finish_layer)?.vertex));
+        // flat index of LayerNode is vertex_index shifted by sizes of lower layers
+        let offsets: Vec<usize> = self.layers.iter()
+            .scan(0, |offset, layer| {
+                let current = *offset;
+                *offset += layer.vertex.len();
+                Some(current)
+            })
+            .collect();
+        let flat = |node: &LayerNode| offsets[node.layer] + node.vertex_index;
+        let mut nodes: NodeMap<usize, LayerNode> = NodeMap::default();
+        let mut costs: NodeMap<usize, f64> = NodeMap::default();
+        let mut parents: NodeMap<usize, usize> = NodeMap::default();
+        let mut frontier: BinaryHeap<FrontierEntry> = BinaryHeap::new();
+        nodes.insert(flat(&start_node), start_node);
+        frontier.push(FrontierEntry::new(flat(&start_node), 0.0));
+        let mut tentative: NodeMap<usize, f64> = NodeMap::default();
+        while let Some(entry) = frontier.pop() {
+            if costs.contains_key(&entry.vertex_index) {
+                continue;
+            }
+            costs.insert(entry.vertex_index, entry.cost);
+            let node = nodes[&entry.vertex_index];
+            if node == finish_node {
+                break;
+            }
+            let layer = &self.layers[node.layer];
+            let scale = (self.radius + layer.altitude_km) / self.radius;
+            let horizontal = layer.vertex.vector[node.vertex_index].graphs.iter()
+                .map(|graph| (LayerNode::new(node.layer, from_node_index(graph.vertex_index)), graph.cost * scale));
+            let vertical = self.connectors.get(&node).into_iter().flatten().copied();
+            for (next, cost) in horizontal.chain(vertical) {
+                let next_index = flat(&next);
+                let next_cost = entry.cost + cost;
+                if !costs.contains_key(&next_index) && tentative.get(&next_index).is_none_or(|cost| *cost > next_cost) {
+                    tentative.insert(next_index, next_cost);
+                    nodes.insert(next_index, next);
+                    parents.insert(next_index, entry.vertex_index);
+                    frontier.push(FrontierEntry::new(next_index, next_cost));
+                }
+            }
+        }
+        let cost = *costs.get(&flat(&finish_node))?;
+        let mut path: Vec<LayerNode> = vec![finish_node];
+        let mut current = flat(&finish_node);
+        while let Some(parent) = parents.get(&current) {
+            path.push(nodes[parent]);
+            current = *parent;
+        }
+        path.reverse();
+        Some(LayeredPath {nodes: path, cost})
+    }
+
+    /// Returns Waypoints of LayeredPath found on this graph with altitudes of their layers, for
+    /// `flight_plan::write_flight_plan`. Climbs between stacked nodes are two Waypoints at the same point.
+    pub fn waypoints(&self, path: &LayeredPath) -> Vec<Waypoint> {
+        path.nodes.iter()
+            .filter_map(|node| {
+                let layer = self.layers.get(node.layer)?;
+                let point = layer.vertex.vector.get(node.vertex_index)?.coordinates.clone();
+                Some(Waypoint::new(point, Some(layer.altitude_km * 1000.0)))
+            })
+            .collect()
+    }
+
+    fn contains(&self, node: &LayerNode) -> bool {
+        self.layers.get(node.layer).is_some_and(|layer| node.vertex_index < layer.vertex.len())
+    }
+}
+
+#[cfg(test)]
+mod layers_tests {
+    use super::*;
+    use crate::data::CelestialObject;
+
+    fn corridor(points: &[(f64, f64)]) -> VertexBuffer {
+        let connections: Vec<SphereConnection> = points.windows(2)
+            .map(|pair| SphereConnection::new(SpherePoint::new(pair[0].0, pair[0].1), SpherePoint::new(pair[1].0, pair[1].1)))
+            .collect();
+        VertexBuffer::new(connections, CelestialObject::EARTH).unwrap()
+    }
+
+    #[test]
+    fn test_climb_only_when_it_pays_off() {
+        // given: low layer detours around an obstacle, high layer flies straight over it
+        let low = corridor(&[(0.0, 0.0), (0.5, 0.3), (1.0, 0.0)]);
+        let high = corridor(&[(0.0, 0.0), (1.0, 0.0)]);
+        let layers = |vertical_weight: f64| {
+            let mut graph = LayeredGraph::new(vec![
+                AltitudeLayer::new(0.1, low.clone()),
+                AltitudeLayer::new(1.0, high.clone()),
+            ], vertical_weight).unwrap();
+            graph.connect_stacked_nodes();
+            graph
+        };
+        let start = SpherePoint::new(0.0, 0.0);
+        let finish = SpherePoint::new(1.0, 0.0);
+        // when
+        let cheap_climb = layers(1.0).find_path(0, &start, 0, &finish).unwrap();
+        let expensive_climb = layers(100.0).find_path(0, &start, 0, &finish).unwrap();
+        // then
+        assert_eq!(cheap_climb.nodes.iter().map(|node| node.layer).collect::<Vec<usize>>(), vec![0, 1, 1, 0]);
+        assert_eq!(expensive_climb.nodes.iter().map(|node| node.layer).collect::<Vec<usize>>(), vec![0, 0, 0]);
+        assert!(cheap_climb.cost < expensive_climb.cost);
+        assert!(LayeredGraph::new(Vec::new(), 1.0).is_err());
+        let mut on_mars = high.clone();
+        on_mars.celestial_object = CelestialObject::MARS;
+        let mixed = LayeredGraph::new(vec![AltitudeLayer::new(0.1, low.clone()), AltitudeLayer::new(1.0, on_mars)], 1.0);
+        assert!(matches!(mixed.unwrap_err().kind(), ErrorKind::BodyMismatch(..)));
+        assert!(layers(1.0).add_connector(LayerNode::new(0, 0), LayerNode::new(0, 1)).is_err());
+    }
+}
@@ -1,15 +1,53 @@
 #[macro_use] extern crate error_chain;
+#[cfg(test)]
 #[macro_use] extern crate approx;
 
-use dbg;
 use std::cmp::PartialEq;
 
-mod errors;
+mod hashing;
+mod rng;
+#[cfg(test)]
+mod fixtures;
 
+pub mod errors;
 pub mod vertex;
 pub mod data;
 pub mod components;
 pub mod dijkstra;
+pub mod astar;
+pub mod search;
+pub mod instrumentation;
+pub mod path;
+pub mod stochastic;
+pub mod spatial;
+pub mod matching;
+pub mod clustering;
+pub mod analysis;
+pub mod health;
+pub mod hub_labels;
+pub mod landmarks;
+pub mod bundle;
+pub mod cache;
+pub mod partition;
+pub mod arc_flags;
+pub mod matrix;
+pub mod facility;
+pub mod isochrone;
+pub mod assignment;
+pub mod cycles;
+pub mod cost;
+pub mod edit;
+pub mod elevation;
+pub mod layers;
+pub mod timetable;
+pub mod route;
+pub mod flight_plan;
+pub mod formats;
+pub mod io;
+#[cfg(feature = "scenarios")]
+pub mod scenarios;
+#[cfg(feature = "tokio")]
+pub mod async_api;
 
 #[cfg(test)]
 mod lib_tests {
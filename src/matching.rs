@@ -0,0 +1,210 @@
+use crate::errors::*;
+use crate::vertex::{EdgeId, VertexBuffer};
+use crate::components::{SphereConnection, SpherePoint};
+use crate::dijkstra::search_edge_ids;
+use crate::path::Path;
+use crate::spatial::SpatialIndex;
+use std::collections::BTreeMap;
+
+/// Time a matched trace spent on one connection, in the units of trace timestamps.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EdgeTraversal {
+    pub edge_id: EdgeId,
+    pub entry_time: f64,
+    pub exit_time: f64,
+}
+
+impl EdgeTraversal {
+    /// Returns time spent on the connection.
+    pub fn duration(&self) -> f64 {
+        self.exit_time - self.entry_time
+    }
+}
+
+/// Result of `match_timed_trace`, Path along the network with traversal of every connection on it,
+/// so `traversals[i]` belongs to `path.edge_ids[i]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimedMatch {
+    pub path: Path,
+    pub traversals: Vec<EdgeTraversal>,
+}
+
+/// Matches trace of noisy positions, like GPS fixes, to the network: every position is snapped to
+/// the closest node and consecutive distinct nodes are joined by the cheapest path between them.
+/// Returns Path without connections when all positions snap to one node, None for no positions,
+/// empty VertexBuffer or when some consecutive nodes are not connected.
+///
+/// ```
+/// use path_navigator::components::*;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::matching::match_trace;
+/// use path_navigator::vertex::VertexBuffer;
+///
+/// let connections = vec![
+///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+///     SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 2.0)),
+/// ];
+/// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+/// let trace = vec![SpherePoint::new(0.01, 0.0), SpherePoint::new(-0.02, 0.1), SpherePoint::new(0.0, 1.9)];
+/// let path = match_trace(&trace, &vertex).unwrap();
+/// assert_eq!(path.vertex_indices, vec![0, 1, 2]);
+/// ```
+pub fn match_trace(points: &[SpherePoint], vertex: &VertexBuffer) -> Option<Path> {
+    let index = SpatialIndex::new(vertex);
+    let nodes: Vec<usize> = points.iter().map(|point| index.nearest(point)).collect::<Option<_>>()?;
+    let (start_index, legs) = route_legs(&nodes, vertex)?;
+    Path::from_edge_ids(start_index, legs.into_iter().flatten().collect(), vertex)
+}
+
+/// Matches timestamped trace to the network like `match_trace` and estimates when every connection
+/// on the matched Path was entered and left, for speed estimation from historical traces.
+///
+/// # Arguments:
+/// * `fixes` which is &[(SpherePoint, f64)] - positions with timestamps, like seconds, in trace order
+/// * `vertex` which is &VertexBuffer - precalculated vertex for avaliable paths on given geometry
+///
+/// # Remarks:
+///
+/// Trace leaves a node at the last fix snapped to it and arrives at the next node at the first fix
+/// snapped there, time in between is split among connections joining them in proportion to their
+/// great-circle length, so speed is assumed constant between fixes. Time spent at nodes is not
+/// assigned to any connection.
+///
+/// Fails with `InvalidParameter` for not finite or decreasing timestamps.
+///
+/// ```
+/// use path_navigator::components::*;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::matching::match_timed_trace;
+/// use path_navigator::vertex::VertexBuffer;
+///
+/// let connections = vec![
+///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+///     SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 3.0)),
+/// ];
+/// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+/// let fixes = vec![(SpherePoint::new(0.0, 0.0), 0.0), (SpherePoint::new(0.0, 3.0), 900.0)];
+/// let matched = match_timed_trace(&fixes, &vertex).unwrap().unwrap();
+/// assert_eq!(matched.traversals.len(), 2);
+/// assert!((matched.traversals[0].duration() - 300.0).abs() < 1e-9);
+/// ```
+pub fn match_timed_trace(fixes: &[(SpherePoint, f64)], vertex: &VertexBuffer) -> Result<Option<TimedMatch>> {
+    if fixes.iter().any(|(_, time)| !time.is_finite()) || fixes.windows(2).any(|pair| pair[1].1 < pair[0].1) {
+        return Err(Error::from_kind(ErrorKind::InvalidParameter));
+    }
+    let index = SpatialIndex::new(vertex);
+    let nodes: Vec<usize> = match fixes.iter().map(|(point, _)| index.nearest(point)).collect::<Option<_>>() {
+        Some(nodes) => nodes,
+        None => return Ok(None),
+    };
+    let (start_index, legs) = match route_legs(&nodes, vertex) {
+        Some(routed) => routed,
+        None => return Ok(None),
+    };
+    // departure from the last fix of every run of fixes at one node, arrival at the first fix of the next run
+    let mut runs: Vec<(f64, f64)> = Vec::new();
+    for (position, node) in nodes.iter().enumerate() {
+        let time = fixes[position].1;
+        match runs.last_mut() {
+            Some(run) if position > 0 && nodes[position - 1] == *node => run.1 = time,
+            _ => runs.push((time, time)),
+        }
+    }
+    let mut traversals: Vec<EdgeTraversal> = Vec::new();
+    for (leg, edge_ids) in legs.iter().enumerate() {
+        let (departure, arrival) = (runs[leg].1, runs[leg + 1].0);
+        let lengths: Vec<f64> = edge_ids.iter()
+            .map(|edge_id| {
+                let (from, to) = vertex.edge(*edge_id).unwrap();
+                vertex.great_circle_length(&SphereConnection::new(vertex.vector[from].coordinates.clone(), vertex.vector[to].coordinates.clone()))
+            })
+            .collect();
+        let total: f64 = lengths.iter().sum();
+        let mut entry_time = departure;
+        let mut covered = 0.0;
+        for (position, (edge_id, length)) in edge_ids.iter().zip(&lengths).enumerate() {
+            covered += length;
+            let share = if total > 0.0 { covered / total } else { (position + 1) as f64 / edge_ids.len() as f64 };
+            let exit_time = if position + 1 == edge_ids.len() { arrival } else { departure + (arrival - departure) * share };
+            traversals.push(EdgeTraversal {edge_id: *edge_id, entry_time, exit_time});
+            entry_time = exit_time;
+        }
+    }
+    let edge_ids = traversals.iter().map(|traversal| traversal.edge_id).collect();
+    Ok(Path::from_edge_ids(start_index, edge_ids, vertex).map(|path| TimedMatch {path, traversals}))
+}
+
+/// Returns mean traversal duration of every connection seen in given matches, ordered by EdgeId,
+/// which can be passed to `VertexBuffer::update_costs` to route by observed travel times.
+///
+/// ```
+/// use path_navigator::matching::{mean_durations, EdgeTraversal, TimedMatch};
+/// use path_navigator::path::Path;
+///
+/// let path = Path {vertex_indices: vec![0], edge_ids: Vec::new(), connections: Vec::new(), costs: Vec::new()};
+/// let traversal = |entry_time: f64, exit_time: f64| EdgeTraversal {edge_id: 7, entry_time, exit_time};
+/// let first = TimedMatch {path: path.clone(), traversals: vec![traversal(0.0, 10.0)]};
+/// let second = TimedMatch {path, traversals: vec![traversal(100.0, 120.0)]};
+/// assert_eq!(mean_durations(&[first, second]), vec![(7, 15.0)]);
+/// ```
+pub fn mean_durations(matches: &[TimedMatch]) -> Vec<(EdgeId, f64)> {
+    let mut sums: BTreeMap<EdgeId, (f64, usize)> = BTreeMap::new();
+    for traversal in matches.iter().flat_map(|matched| matched.traversals.iter()) {
+        let sum = sums.entry(traversal.edge_id).or_insert((0.0, 0));
+        sum.0 += traversal.duration();
+        sum.1 += 1;
+    }
+    sums.into_iter().map(|(edge_id, (sum, count))| (edge_id, sum / count as f64)).collect()
+}
+
+/// Joins consecutive distinct nodes by the cheapest paths, returns the first node and EdgeIds of every leg.
+fn route_legs(nodes: &[usize], vertex: &VertexBuffer) -> Option<(usize, Vec<Vec<EdgeId>>)> {
+    let start_index = *nodes.first()?;
+    let mut distinct = nodes.to_vec();
+    distinct.dedup();
+    let legs = distinct.windows(2)
+        .map(|pair| search_edge_ids(pair[0], pair[1], vertex, |_, _, graph| Some(graph.cost)))
+        .collect::<Option<Vec<Vec<EdgeId>>>>()?;
+    Some((start_index, legs))
+}
+
+#[cfg(test)]
+mod matching_tests {
+    use super::*;
+    use crate::data::CelestialObject;
+
+    #[test]
+    fn test_timed_trace_traversals() {
+        // given: line 0 - 1 - 2 - 3 with connections of 1, 1 and 2 degrees, isolated connection 4 - 5,
+        // the vehicle waits at node 1 from 100 to 160 and skips node 2 between fixes
+        let connections = vec![
+            SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+            SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 2.0)),
+            SphereConnection::new(SpherePoint::new(0.0, 2.0), SpherePoint::new(0.0, 4.0)),
+            SphereConnection::new(SpherePoint::new(10.0, 10.0), SpherePoint::new(10.0, 11.0)),
+        ];
+        let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        let fixes = vec![
+            (SpherePoint::new(0.01, 0.0), 0.0),
+            (SpherePoint::new(0.0, 0.98), 100.0),
+            (SpherePoint::new(-0.01, 1.01), 160.0),
+            (SpherePoint::new(0.0, 4.0), 460.0),
+        ];
+        // when
+        let matched = match_timed_trace(&fixes, &vertex).unwrap().unwrap();
+        // then
+        assert_eq!(matched.path.vertex_indices, vec![0, 1, 2, 3]);
+        assert_eq!(matched.path.edge_ids, matched.traversals.iter().map(|traversal| traversal.edge_id).collect::<Vec<_>>());
+        let durations: Vec<f64> = matched.traversals.iter().map(|traversal| traversal.duration()).collect();
+        assert!(relative_eq!(durations[0], 100.0, max_relative = 1e-12));
+        assert!(relative_eq!(durations[1], 100.0, max_relative = 1e-9));
+        assert!(relative_eq!(durations[2], 200.0, max_relative = 1e-9));
+        assert_eq!((matched.traversals[1].entry_time, matched.traversals[2].exit_time), (160.0, 460.0));
+        assert_eq!(mean_durations(&[matched.clone(), matched])[0], (0, 100.0));
+        let unreachable = vec![(SpherePoint::new(0.0, 0.0), 0.0), (SpherePoint::new(10.0, 10.0), 1.0)];
+        assert!(match_timed_trace(&unreachable, &vertex).unwrap().is_none());
+        let backwards = vec![(SpherePoint::new(0.0, 0.0), 5.0), (SpherePoint::new(0.0, 1.0), 1.0)];
+        assert!(match_timed_trace(&backwards, &vertex).is_err());
+        assert_eq!(match_trace(&[SpherePoint::new(0.0, 0.1)], &vertex).unwrap().len(), 0);
+    }
+}
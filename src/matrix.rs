@@ -0,0 +1,226 @@
+use crate::errors::*;
+use crate::vertex::VertexBuffer;
+use crate::components::SpherePoint;
+use crate::dijkstra::{get_closest_point, search_targets};
+use crate::io::geojson;
+use std::io::Write;
+
+/// Output format of CostMatrix.
+///
+/// * `Csv` - header `origin` followed by destination node indices, then one line per origin with its
+///   node index and costs, unreachable destinations are empty cells
+/// * `Json` - object with `origins` and `destinations` node indices and `costs` rows, unreachable
+///   destinations are `null`
+/// * `OsrmDurations`, `OsrmDistances` - OSRM table service response with costs in `durations` or
+///   `distances` rows and snapped `sources` and `destinations` locations
+///
+/// Costs are written as they are, OSRM clients expect seconds and meters, so pick connection costs
+/// in these units when matrix is meant for them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatrixFormat {
+    Csv,
+    Json,
+    OsrmDurations,
+    OsrmDistances,
+}
+
+/// # CostMatrix
+/// Network costs between every origin and every destination, `costs[origin][destination]` is None
+/// when destination is not reachable from origin. `origins` and `destinations` hold closest points
+/// on VertexBuffer the query points were snapped to.
+///
+/// ```
+/// use path_navigator::components::*;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::matrix::cost_matrix;
+/// use path_navigator::vertex::VertexBuffer;
+///
+/// let connections = vec![
+///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+///     SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 2.0)),
+/// ];
+/// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+/// let points = vec![SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 2.0)];
+/// let matrix = cost_matrix(&points, &points, &vertex);
+/// assert_eq!(matrix.cost(0, 0), Some(0.0));
+/// assert_eq!(matrix.cost(0, 1), matrix.cost(1, 0));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct CostMatrix {
+    pub origins: Vec<usize>,
+    pub destinations: Vec<usize>,
+    pub costs: Vec<Vec<Option<f64>>>,
+}
+
+impl CostMatrix {
+    /// Returns cost from origin to destination given by their positions in the query.
+    pub fn cost(&self, origin: usize, destination: usize) -> Option<f64> {
+        *self.costs.get(origin)?.get(destination)?
+    }
+
+    /// Writes matrix in given MatrixFormat, VertexBuffer has to be the one matrix was computed on.
+    pub fn write<W: Write>(&self, vertex: &VertexBuffer, format: MatrixFormat, writer: &mut W) -> Result<()> {
+        write_rows(&self.origins, &self.destinations, self.costs.iter().cloned(), vertex, format, writer)
+    }
+}
+
+/// Returns CostMatrix between given points.
+///
+/// # Arguments:
+/// * `origins` which is &[SpherePoint] - row points, snapped to the closest points on VertexBuffer
+/// * `destinations` which is &[SpherePoint] - column points, snapped to the closest points on VertexBuffer
+/// * `vertex` which is &VertexBuffer - precalculated vertex for avaliable paths on given geometry
+///
+/// # Remarks:
+///
+/// Every row is one search from the origin which stops as soon as all destinations are settled,
+/// so rows of nearby destinations are much cheaper than full one-to-many searches.
+/// Empty VertexBuffer gives matrix without rows.
+pub fn cost_matrix(origins: &[SpherePoint], destinations: &[SpherePoint], vertex: &VertexBuffer) -> CostMatrix {
+    if vertex.is_empty() {
+        return CostMatrix {origins: Vec::new(), destinations: Vec::new(), costs: Vec::new()};
+    }
+    let origins: Vec<usize> = origins.iter().map(|point| get_closest_point(point, vertex)).collect();
+    let destinations: Vec<usize> = destinations.iter().map(|point| get_closest_point(point, vertex)).collect();
+    let costs = origins.iter().map(|origin| row(*origin, &destinations, vertex)).collect();
+    CostMatrix {origins, destinations, costs}
+}
+
+/// Computes CostMatrix between given points like `cost_matrix` and writes it in given MatrixFormat.
+/// Every row is written as soon as it is computed and dropped afterwards, so matrices too large
+/// to be kept in memory can be streamed to a file. Empty VertexBuffer writes matrix without rows.
+///
+/// ```
+/// use path_navigator::components::*;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::matrix::*;
+/// use path_navigator::vertex::VertexBuffer;
+///
+/// let connections = vec![SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0))];
+/// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+/// let points = vec![SpherePoint::new(0.0, 0.0)];
+/// let mut csv: Vec<u8> = Vec::new();
+/// write_cost_matrix(&points, &points, &vertex, MatrixFormat::Csv, &mut csv).unwrap();
+/// assert_eq!(String::from_utf8(csv).unwrap(), "origin,0\n0,0\n");
+/// ```
+pub fn write_cost_matrix<W: Write>(origins: &[SpherePoint], destinations: &[SpherePoint], vertex: &VertexBuffer,
+    format: MatrixFormat, writer: &mut W) -> Result<()> {
+    let snap = |points: &[SpherePoint]| -> Vec<usize> {
+        if vertex.is_empty() {
+            return Vec::new();
+        }
+        points.iter().map(|point| get_closest_point(point, vertex)).collect()
+    };
+    let origins = snap(origins);
+    let destinations = snap(destinations);
+    let rows = origins.iter().map(|origin| row(*origin, &destinations, vertex));
+    write_rows(&origins, &destinations, rows, vertex, format, writer)
+}
+
+fn row(origin: usize, destinations: &[usize], vertex: &VertexBuffer) -> Vec<Option<f64>> {
+    let labels = search_targets(&[origin], destinations, vertex);
+    destinations.iter().map(|destination| labels.costs[*destination]).collect()
+}
+
+fn write_rows<W, I>(origins: &[usize], destinations: &[usize], rows: I, vertex: &VertexBuffer, format: MatrixFormat,
+    writer: &mut W) -> Result<()>
+where W: Write, I: Iterator<Item = Vec<Option<f64>>> {
+    let json_row = |row: &[Option<f64>]| {
+        let values: Vec<String> = row.iter().map(|cost| cost.map_or("null".to_string(), geojson::number)).collect();
+        format!("[{}]", values.join(","))
+    };
+    let indices = |nodes: &[usize]| nodes.iter().map(|node| node.to_string()).collect::<Vec<String>>().join(",");
+    match format {
+        MatrixFormat::Csv => {
+            writeln!(writer, "origin{}", destinations.iter().map(|node| format!(",{}", node)).collect::<String>())?;
+            for (origin, row) in origins.iter().zip(rows) {
+                let cells: String = row.iter().map(|cost| cost.map_or(",".to_string(), |cost| format!(",{}", cost))).collect();
+                writeln!(writer, "{}{}", origin, cells)?;
+            }
+        }
+        MatrixFormat::Json => {
+            write!(writer, "{{\"origins\":[{}],\"destinations\":[{}],\"costs\":[", indices(origins), indices(destinations))?;
+            for (position, row) in rows.enumerate() {
+                write!(writer, "{}{}", if position > 0 { "," } else { "" }, json_row(&row))?;
+            }
+            write!(writer, "]}}")?;
+        }
+        MatrixFormat::OsrmDurations | MatrixFormat::OsrmDistances => {
+            let field = if format == MatrixFormat::OsrmDurations { "durations" } else { "distances" };
+            write!(writer, "{{\"code\":\"Ok\",\"{}\":[", field)?;
+            for (position, row) in rows.enumerate() {
+                write!(writer, "{}{}", if position > 0 { "," } else { "" }, json_row(&row))?;
+            }
+            let waypoints = |nodes: &[usize]| nodes.iter()
+                .map(|node| format!("{{\"location\":{}}}", geojson::position(&vertex.vector[*node].coordinates)))
+                .collect::<Vec<String>>()
+                .join(",");
+            write!(writer, "],\"sources\":[{}],\"destinations\":[{}]}}", waypoints(origins), waypoints(destinations))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod matrix_tests {
+    use super::*;
+    use crate::components::SphereConnection;
+    use crate::data::CelestialObject;
+    use crate::dijkstra::find_path;
+
+    #[test]
+    fn test_matrix_matches_point_to_point_costs() {
+        // given: a line 0 - 1 - 2 and separate connection 3 - 4
+        let connections = vec![
+            SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+            SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(1.0, 2.0)),
+            SphereConnection::new(SpherePoint::new(5.0, 5.0), SpherePoint::new(5.0, 6.0)),
+        ];
+        let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        let origins = vec![SpherePoint::new(0.0, 0.0), SpherePoint::new(5.0, 5.0)];
+        let destinations = vec![SpherePoint::new(1.0, 2.0), SpherePoint::new(0.0, 1.0), SpherePoint::new(5.0, 6.0)];
+        // when
+        let matrix = cost_matrix(&origins, &destinations, &vertex);
+        // then
+        let expected = find_path(&origins[0], &destinations[0], &vertex).unwrap().cost();
+        assert_eq!(matrix.origins, vec![0, 3]);
+        assert!(relative_eq!(matrix.cost(0, 0).unwrap(), expected, max_relative = 1e-12));
+        assert!(matrix.cost(0, 1).unwrap() < expected);
+        assert_eq!(matrix.cost(0, 2), None);
+        assert_eq!(matrix.cost(1, 0), None);
+        assert!(matrix.cost(1, 2).is_some());
+        assert_eq!(matrix.cost(2, 0), None);
+    }
+
+    #[test]
+    fn test_matrix_formats() {
+        // given
+        let connections = vec![
+            SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+            SphereConnection::new(SpherePoint::new(5.0, 5.0), SpherePoint::new(5.0, 6.0)),
+        ];
+        let mut vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        vertex.update_costs(&[(0, 2.5), (1, 2.5)]).unwrap();
+        let origins = vec![SpherePoint::new(0.0, 0.0)];
+        let destinations = vec![SpherePoint::new(0.0, 1.0), SpherePoint::new(5.0, 6.0)];
+        let write = |format: MatrixFormat| {
+            let mut output: Vec<u8> = Vec::new();
+            write_cost_matrix(&origins, &destinations, &vertex, format, &mut output).unwrap();
+            String::from_utf8(output).unwrap()
+        };
+        // when
+        let csv = write(MatrixFormat::Csv);
+        let json = write(MatrixFormat::Json);
+        let osrm = write(MatrixFormat::OsrmDurations);
+        let mut stored: Vec<u8> = Vec::new();
+        cost_matrix(&origins, &destinations, &vertex).write(&vertex, MatrixFormat::Json, &mut stored).unwrap();
+        // then
+        assert_eq!(csv, "origin,1,3\n0,2.5,\n");
+        assert_eq!(json, "{\"origins\":[0],\"destinations\":[1,3],\"costs\":[[2.5,null]]}");
+        assert_eq!(osrm, concat!(
+            "{\"code\":\"Ok\",\"durations\":[[2.5,null]],\"sources\":[{\"location\":[0,0]}],",
+            "\"destinations\":[{\"location\":[1,0]},{\"location\":[6,5]}]}",
+        ));
+        assert_eq!(String::from_utf8(stored).unwrap(), json);
+    }
+}
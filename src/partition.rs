@@ -0,0 +1,150 @@
+use crate::errors::*;
+use crate::vertex::*;
+use crate::components::{Vector3, dot, norm, normalize};
+
+/// Split of VertexBuffer nodes into balanced regions.
+///
+/// * `partitions` - partition id of every node, in order of nodes
+/// * `boundary_nodes` - per partition, ascending indices of its nodes with a connection to other partition
+/// * `cross_edges` - ascending EdgeIds of connections joining nodes of different partitions
+#[derive(Debug, Clone, PartialEq)]
+pub struct Partitioning {
+    pub partitions: Vec<usize>,
+    pub boundary_nodes: Vec<Vec<usize>>,
+    pub cross_edges: Vec<EdgeId>,
+}
+
+impl Partitioning {
+    /// Returns number of partitions.
+    pub fn count(&self) -> usize {
+        self.boundary_nodes.len()
+    }
+
+    /// Returns ascending indices of nodes of given partition.
+    pub fn members(&self, partition: usize) -> Vec<usize> {
+        (0..self.partitions.len())
+            .filter(|index| self.partitions[*index] == partition)
+            .collect()
+    }
+}
+
+/// Splits nodes of VertexBuffer into `k` regions of nearly equal sizes.
+///
+/// # Remarks:
+///
+/// Inertial recursive bisection: node set is sorted along its principal axis, the direction
+/// of the largest spread of node unit vectors, and cut perpendicular to it at the size ratio of the
+/// requested partition counts. Regions are geometrically compact, which keeps cross-partition
+/// connections few on road-like networks, though not minimal as with multilevel partitioners.
+///
+/// Fails with `InvalidParameter` when `k` is 0 or greater than number of nodes.
+///
+/// ```
+/// use path_navigator::components::SphereBoundingBox;
+/// use path_navigator::partition::partition;
+/// use path_navigator::vertex::generators::random;
+///
+/// let vertex = random(100, 3.0, &SphereBoundingBox::new(49.0, 14.0, 54.8, 24.1), 1).unwrap();
+/// let partitioning = partition(&vertex, 4).unwrap();
+/// assert!((0..4).all(|p| partitioning.members(p).len() == 25));
+/// ```
+pub fn partition(vertex: &VertexBuffer, k: usize) -> Result<Partitioning> {
+    if k == 0 || k > vertex.len() {
+        return Err(Error::from_kind(ErrorKind::InvalidParameter));
+    }
+    let vectors: Vec<Vector3> = vertex.vector.iter().map(|node| node.coordinates.to_vector()).collect();
+    let mut partitions: Vec<usize> = vec![0; vertex.len()];
+    let mut nodes: Vec<usize> = (0..vertex.len()).collect();
+    bisect(&mut nodes, &vectors, 0, k, &mut partitions);
+    let cross_edges: Vec<EdgeId> = (0..vertex.edge_count())
+        .filter(|edge_id| {
+            let (from, to) = vertex.edge(*edge_id).unwrap();
+            partitions[from] != partitions[to]
+        })
+        .collect();
+    // both ends of a crossing connection are boundary nodes, also when it is one-way
+    let mut is_boundary: Vec<bool> = vec![false; vertex.len()];
+    for edge_id in &cross_edges {
+        let (from, to) = vertex.edge(*edge_id).unwrap();
+        is_boundary[from] = true;
+        is_boundary[to] = true;
+    }
+    let mut boundary_nodes: Vec<Vec<usize>> = vec![Vec::new(); k];
+    for index in (0..vertex.len()).filter(|index| is_boundary[*index]) {
+        boundary_nodes[partitions[index]].push(index);
+    }
+    Ok(Partitioning {partitions, boundary_nodes, cross_edges})
+}
+
+fn bisect(nodes: &mut [usize], vectors: &[Vector3], first_partition: usize, k: usize, partitions: &mut [usize]) {
+    if k == 1 {
+        nodes.iter().for_each(|node| partitions[*node] = first_partition);
+        return;
+    }
+    let axis = principal_axis(nodes, vectors);
+    nodes.sort_by(|a, b| dot(&vectors[*a], &axis).total_cmp(&dot(&vectors[*b], &axis)).then(a.cmp(b)));
+    let lower_k = k / 2;
+    let split = nodes.len() * lower_k / k;
+    let (lower, upper) = nodes.split_at_mut(split);
+    bisect(lower, vectors, first_partition, lower_k, partitions);
+    bisect(upper, vectors, first_partition + lower_k, k - lower_k, partitions);
+}
+
+/// Returns dominant eigenvector of covariance of node vectors, found by power iteration.
+fn principal_axis(nodes: &[usize], vectors: &[Vector3]) -> Vector3 {
+    let count = nodes.len() as f64;
+    let mut mean: Vector3 = [0.0; 3];
+    for node in nodes {
+        (0..3).for_each(|axis| mean[axis] += vectors[*node][axis] / count);
+    }
+    let mut covariance = [[0.0; 3]; 3];
+    for node in nodes {
+        let offset: Vector3 = [vectors[*node][0] - mean[0], vectors[*node][1] - mean[1], vectors[*node][2] - mean[2]];
+        for row in 0..3 {
+            for column in 0..3 {
+                covariance[row][column] += offset[row] * offset[column];
+            }
+        }
+    }
+    let mut axis: Vector3 = [1.0, 0.5, 0.25];
+    for _ in 0..64 {
+        let next: Vector3 = [dot(&covariance[0], &axis), dot(&covariance[1], &axis), dot(&covariance[2], &axis)];
+        if norm(&next) == 0.0 {
+            break;
+        }
+        axis = normalize(&next);
+    }
+    axis
+}
+
+#[cfg(test)]
+mod partition_tests {
+    use super::*;
+    use crate::components::{SphereBoundingBox, SpherePoint};
+    use crate::data::CelestialObject;
+    use crate::vertex::generators::{k_nearest, random};
+
+    #[test]
+    fn test_partition_is_balanced_and_consistent() {
+        // given: geometric graph, as random connections of `random` would cross any cut
+        let points: Vec<SpherePoint> = random(203, 2.0, &SphereBoundingBox::new(-5.0, 175.0, 5.0, -175.0), 9).unwrap()
+            .vector.into_iter()
+            .map(|node| node.coordinates)
+            .collect();
+        let vertex = k_nearest(&points, 3, CelestialObject::EARTH).unwrap();
+        // when
+        let partitioning = partition(&vertex, 5).unwrap();
+        // then
+        assert_eq!(partitioning.count(), 5);
+        let mut sizes: Vec<usize> = (0..5).map(|p| partitioning.members(p).len()).collect();
+        sizes.sort();
+        assert_eq!(sizes, vec![40, 40, 41, 41, 41]);
+        for edge_id in &partitioning.cross_edges {
+            let (from, to) = vertex.edge(*edge_id).unwrap();
+            assert!(partitioning.boundary_nodes[partitioning.partitions[from]].contains(&from));
+            assert!(partitioning.boundary_nodes[partitioning.partitions[to]].contains(&to));
+        }
+        assert!(partitioning.cross_edges.len() < vertex.edge_count() / 5);
+        assert!(partition(&vertex, 0).is_err());
+    }
+}
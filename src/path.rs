@@ -0,0 +1,900 @@
+use crate::errors::*;
+use crate::vertex::*;
+use crate::components::{is_clear, offset_polyline, simplify_polyline, smooth_polyline, SphereConnection, SpherePoint, SpherePolygon};
+use crate::data::get_radius_km;
+use crate::hashing::{from_node_index, NodeMap, NodeSet};
+use std::ops::Range;
+
+/// Level of detail of Path geometry, see `Path::simplify`.
+///
+/// * `Full` - every node of Path
+/// * `Simplified` - Douglas-Peucker simplification keeping geometry within `tolerance_km` of full one
+/// * `Junctions` - only start, finish and nodes joined to more or less than two other nodes, by
+///   connections in either direction, so chains of nodes between junctions become single connections
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Granularity {
+    Full,
+    Simplified {tolerance_km: f64},
+    Junctions,
+}
+
+/// Part of Path between consecutive distance markers, see `Path::distance_markers`.
+///
+/// * `start`, `finish` - points where segment starts and finishes, markers or ends of Path
+/// * `start_distance` - great-circle distance along Path to `start` in kilometers
+/// * `distance` - great-circle length of segment in kilometers
+/// * `cost` - cost of segment, connections cut by a marker contribute cost proportional to the length covered
+/// * `edge_ids` - EdgeIds of connections segment runs along, in travel order
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathSegment {
+    pub start: SpherePoint,
+    pub finish: SpherePoint,
+    pub start_distance: f64,
+    pub distance: f64,
+    pub cost: f64,
+    pub edge_ids: Vec<EdgeId>,
+}
+
+/// Markers every fixed distance along Path with segments between them, see `Path::distance_markers`.
+/// There is one segment more than markers, none for Path without connections.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DistanceMarkers {
+    pub markers: Vec<SpherePoint>,
+    pub segments: Vec<PathSegment>,
+}
+
+/// # Path
+/// Path through VertexBuffer, stored as ordered indices of visited nodes alongside with
+/// EdgeId, SphereConnection and cost of every traversed connection.
+/// Connections are oriented in travel direction, so `connections[i]` joins
+/// `vertex_indices[i]` with `vertex_indices[i + 1]`.
+///
+/// ## Route identity
+/// Paths computed on the same VertexBuffer can be grouped by `fingerprint`, which depends only on
+/// ordered node indices, so it is not affected by float noise in coordinates or costs.
+/// `similarity` measures how much two paths overlap.
+///
+/// ```
+/// use path_navigator::components::*;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::path::Path;
+/// use path_navigator::vertex::VertexBuffer;
+///
+/// let connections = vec![
+///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+///     SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 2.0)),
+/// ];
+/// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+/// let path = Path::from_vertex_indices(vec![0, 1, 2], &vertex).unwrap();
+/// let same_path = Path::from_vertex_indices(vec![0, 1, 2], &vertex).unwrap();
+/// assert_eq!(path.fingerprint(), same_path.fingerprint());
+/// assert_eq!(path.similarity(&same_path), 1.0);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Path {
+    pub vertex_indices: Vec<usize>,
+    pub edge_ids: Vec<EdgeId>,
+    pub connections: Vec<SphereConnection>,
+    pub costs: Vec<f64>,
+}
+
+impl Path {
+    /// Builds Path visiting given nodes in order, taking the cheapest connection between each
+    /// pair of consecutive nodes. Returns None when any node is not in VertexBuffer or
+    /// consecutive nodes are not connected.
+    pub fn from_vertex_indices(vertex_indices: Vec<usize>, vertex: &VertexBuffer) -> Option<Self> {
+        if vertex_indices.iter().any(|index| *index >= vertex.len()) {
+            return None;
+        }
+        let mut edge_ids: Vec<EdgeId> = Vec::new();
+        let mut connections: Vec<SphereConnection> = Vec::new();
+        let mut costs: Vec<f64> = Vec::new();
+        for pair in vertex_indices.windows(2) {
+            let relation = vertex.vector[pair[0]].graphs.iter()
+                .filter(|rel| from_node_index(rel.vertex_index) == pair[1])
+                .min_by(|a, b| a.cost.total_cmp(&b.cost))?;
+            edge_ids.push(from_node_index(relation.edge_id));
+            connections.push(SphereConnection::new(
+                vertex.vector[pair[0]].coordinates.clone(),
+                vertex.vector[pair[1]].coordinates.clone(),
+            ));
+            costs.push(relation.cost);
+        }
+        Some(Self {vertex_indices, edge_ids, connections, costs})
+    }
+
+    /// Builds Path starting at `start_index` and following connections with given EdgeIds in order,
+    /// which selects exactly one of parallel connections. Returns None when start is not in VertexBuffer,
+    /// any EdgeId is unknown or connection does not continue from the node reached so far.
+    pub fn from_edge_ids(start_index: usize, edge_ids: Vec<EdgeId>, vertex: &VertexBuffer) -> Option<Self> {
+        if start_index >= vertex.len() {
+            return None;
+        }
+        let mut vertex_indices: Vec<usize> = vec![start_index];
+        let mut connections: Vec<SphereConnection> = Vec::new();
+        let mut costs: Vec<f64> = Vec::new();
+        for edge_id in &edge_ids {
+            let current = *vertex_indices.last().unwrap();
+            let relation = vertex.vector[current].graphs.iter().find(|rel| from_node_index(rel.edge_id) == *edge_id)?;
+            connections.push(SphereConnection::new(
+                vertex.vector[current].coordinates.clone(),
+                vertex.vector[from_node_index(relation.vertex_index)].coordinates.clone(),
+            ));
+            costs.push(relation.cost);
+            vertex_indices.push(from_node_index(relation.vertex_index));
+        }
+        Some(Self {vertex_indices, edge_ids, connections, costs})
+    }
+
+    /// Returns total cost of all connections on Path.
+    pub fn cost(&self) -> f64 {
+        self.costs.iter().sum()
+    }
+
+    /// Returns number of connections on Path.
+    pub fn len(&self) -> usize {
+        self.connections.len()
+    }
+
+    /// Returns true when Path has no connections.
+    pub fn is_empty(&self) -> bool {
+        self.connections.is_empty()
+    }
+
+    /// Returns true when Path has connections and finishes at the node it starts from, like a cycle.
+    pub fn is_closed(&self) -> bool {
+        !self.is_empty() && self.vertex_indices.first() == self.vertex_indices.last()
+    }
+
+    /// Returns points of Path in travel order, empty for Path without connections.
+    pub fn points(&self) -> Vec<SpherePoint> {
+        match self.connections.first() {
+            Some(first) => std::iter::once(first.start.clone())
+                .chain(self.connections.iter().map(|connection| connection.finish.clone()))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Returns points of Path in travel order like `points`, with shapes of connections set by
+    /// `VertexBuffer::set_edge_shape` between their nodes, reversed for connections travelled against
+    /// their direction, so renderers draw original geometry instead of chords. Connections of Path are
+    /// always oriented in travel direction. VertexBuffer has to be the one Path was found on.
+    ///
+    /// ```
+    /// use path_navigator::components::*;
+    /// use path_navigator::data::CelestialObject;
+    /// use path_navigator::path::Path;
+    /// use path_navigator::vertex::VertexBuffer;
+    ///
+    /// let connections = vec![SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 2.0))];
+    /// let mut vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+    /// vertex.set_edge_shape(0, vec![SpherePoint::new(0.5, 0.5), SpherePoint::new(0.5, 1.5)]).unwrap();
+    /// let path = Path::from_vertex_indices(vec![1, 0], &vertex).unwrap();
+    /// assert_eq!(path.geometry(&vertex)[1], SpherePoint::new(0.5, 1.5));
+    /// ```
+    pub fn geometry(&self, vertex: &VertexBuffer) -> Vec<SpherePoint> {
+        let mut points: Vec<SpherePoint> = Vec::new();
+        for (position, connection) in self.connections.iter().enumerate() {
+            if position == 0 {
+                points.push(connection.start.clone());
+            }
+            let edge_id = self.edge_ids[position];
+            if let Some(shape) = vertex.edge_shape(edge_id) {
+                let is_forward = vertex.edge(edge_id).is_some_and(|(from, _)| from == self.vertex_indices[position]);
+                if is_forward {
+                    points.extend(shape.iter().cloned());
+                } else {
+                    points.extend(shape.iter().rev().cloned());
+                }
+            }
+            points.push(connection.finish.clone());
+        }
+        points
+    }
+
+    /// Returns Path following this one and then `other`, for joining legs of a route.
+    /// Fails with `DataItemIncorrect` when `other` does not start at the node this Path finishes at.
+    ///
+    /// ```
+    /// use path_navigator::components::*;
+    /// use path_navigator::data::CelestialObject;
+    /// use path_navigator::path::Path;
+    /// use path_navigator::vertex::VertexBuffer;
+    ///
+    /// let connections = vec![
+    ///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+    ///     SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 2.0)),
+    /// ];
+    /// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+    /// let first = Path::from_vertex_indices(vec![0, 1], &vertex).unwrap();
+    /// let second = Path::from_vertex_indices(vec![1, 2], &vertex).unwrap();
+    /// assert_eq!(first.concat(&second).unwrap(), Path::from_vertex_indices(vec![0, 1, 2], &vertex).unwrap());
+    /// assert!(second.concat(&first).is_err());
+    /// ```
+    pub fn concat(&self, other: &Path) -> Result<Path> {
+        if self.vertex_indices.last() != other.vertex_indices.first() {
+            return Err(Error::from_kind(ErrorKind::DataItemIncorrect));
+        }
+        let mut joined = self.clone();
+        joined.vertex_indices.extend_from_slice(&other.vertex_indices[1..]);
+        joined.edge_ids.extend_from_slice(&other.edge_ids);
+        joined.connections.extend_from_slice(&other.connections);
+        joined.costs.extend_from_slice(&other.costs);
+        Ok(joined)
+    }
+
+    /// Returns Path with connections in `range`, given by their positions on Path, replaced by
+    /// `replacement`, like a detour around a closed road. Empty range inserts a round trip.
+    ///
+    /// # Remarks:
+    ///
+    /// Replacement has to start at the node the range starts at and finish at the node it finishes at,
+    /// otherwise fails with `DataItemIncorrect`. Fails with `InvalidParameter` for range reaching past
+    /// the last connection or decreasing.
+    ///
+    /// ```
+    /// use path_navigator::components::*;
+    /// use path_navigator::data::CelestialObject;
+    /// use path_navigator::path::Path;
+    /// use path_navigator::vertex::VertexBuffer;
+    ///
+    /// let connections = vec![
+    ///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+    ///     SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 2.0)),
+    ///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.5, 1.0)),
+    ///     SphereConnection::new(SpherePoint::new(0.5, 1.0), SpherePoint::new(0.0, 2.0)),
+    /// ];
+    /// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+    /// let route = Path::from_vertex_indices(vec![0, 1, 2], &vertex).unwrap();
+    /// let detour = Path::from_vertex_indices(vec![0, 3, 2], &vertex).unwrap();
+    /// assert_eq!(route.splice(0..2, &detour).unwrap(), detour);
+    /// assert!(route.splice(0..1, &detour).is_err());
+    /// ```
+    pub fn splice(&self, range: Range<usize>, replacement: &Path) -> Result<Path> {
+        if range.start > range.end || range.end > self.len() {
+            return Err(Error::from_kind(ErrorKind::InvalidParameter));
+        }
+        if replacement.vertex_indices.first() != self.vertex_indices.get(range.start)
+            || replacement.vertex_indices.last() != self.vertex_indices.get(range.end) {
+            return Err(Error::from_kind(ErrorKind::DataItemIncorrect));
+        }
+        let head = self.slice(0..range.start);
+        let tail = self.slice(range.end..self.len());
+        head.concat(replacement)?.concat(&tail)
+    }
+
+    /// Returns the part of Path from its start to its node closest to `point`, like the part already
+    /// travelled when re-routing from the current position. When the closest node is visited more than
+    /// once the first visit is taken. Path without nodes is returned as it is.
+    ///
+    /// ```
+    /// use path_navigator::components::*;
+    /// use path_navigator::data::CelestialObject;
+    /// use path_navigator::path::Path;
+    /// use path_navigator::vertex::VertexBuffer;
+    ///
+    /// let connections = vec![
+    ///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+    ///     SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 2.0)),
+    /// ];
+    /// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+    /// let route = Path::from_vertex_indices(vec![0, 1, 2], &vertex).unwrap();
+    /// assert_eq!(route.truncate_at(&SpherePoint::new(0.1, 1.2)).vertex_indices, vec![0, 1]);
+    /// ```
+    pub fn truncate_at(&self, point: &SpherePoint) -> Path {
+        let angle = |position: &usize| {
+            let node = match position {
+                0 => self.connections.first().map(|connection| &connection.start),
+                _ => self.connections.get(position - 1).map(|connection| &connection.finish),
+            };
+            node.map_or(f64::INFINITY, |node| SphereConnection::new(node.clone(), point.clone()).central_angle())
+        };
+        let closest = (0..self.vertex_indices.len())
+            .map(|position| (position, angle(&position)))
+            .min_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)))
+            .map_or(0, |(position, _)| position);
+        self.slice(0..closest)
+    }
+
+    /// Returns part of Path made of connections at given positions, with nodes they join.
+    fn slice(&self, range: Range<usize>) -> Path {
+        Path {
+            vertex_indices: self.vertex_indices.get(range.start..=range.end).map_or_else(Vec::new, <[usize]>::to_vec),
+            edge_ids: self.edge_ids[range.clone()].to_vec(),
+            connections: self.connections[range.clone()].to_vec(),
+            costs: self.costs[range].to_vec(),
+        }
+    }
+
+    /// Returns polyline parallel to Path at given distance, to the right of travel direction for
+    /// positive `distance_km` and to the left for negative, for separated outbound and return corridors.
+    ///
+    /// # Remarks:
+    ///
+    /// Points are moved along great circles perpendicular to connections, so the offset follows
+    /// curvature of the sphere. Corners keep the distance from both connections (miter), except
+    /// very sharp ones which get one point per connection (bevel). When the offset is larger than
+    /// a tight inner turn is wide the polyline would cross itself, such loops are cut out at
+    /// the crossing point. Offset connections are great-circle arcs, which for long connections
+    /// are slightly closer to Path in the middle than at the ends.
+    ///
+    /// ```
+    /// use path_navigator::components::*;
+    /// use path_navigator::data::CelestialObject;
+    /// use path_navigator::path::Path;
+    /// use path_navigator::vertex::VertexBuffer;
+    ///
+    /// let connections = vec![SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0))];
+    /// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+    /// let path = Path::from_vertex_indices(vec![0, 1], &vertex).unwrap();
+    /// let outbound = path.offset(10.0, 6371.0);
+    /// assert!(outbound.iter().all(|point| (point.lat + 0.0899).abs() < 1e-4));
+    /// ```
+    pub fn offset(&self, distance_km: f64, radius: f64) -> Vec<SpherePoint> {
+        offset_polyline(&self.points(), distance_km / radius)
+    }
+
+    /// Returns points of Path with waypoints skipped wherever the great-circle arc past them is clear
+    /// of obstacles, an any-angle trajectory for vehicles free to leave the network between nodes,
+    /// like drones. Returns `points` when no waypoint can be skipped.
+    ///
+    /// # Remarks:
+    ///
+    /// Waypoints are pulled like in Theta* post-processing: walking along Path, a waypoint is kept only
+    /// when the arc from the last kept one to the next waypoint is blocked, so it takes one `is_clear`
+    /// check per waypoint and never makes the trajectory longer, by the triangle inequality. The result
+    /// is not the shortest trajectory around obstacles, only the shortest one through Path waypoints
+    /// found greedily. The first and last points are always kept.
+    ///
+    /// ```
+    /// use path_navigator::components::*;
+    /// use path_navigator::data::CelestialObject;
+    /// use path_navigator::path::Path;
+    /// use path_navigator::vertex::VertexBuffer;
+    ///
+    /// let connections = vec![
+    ///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+    ///     SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(1.0, 1.0)),
+    /// ];
+    /// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+    /// let path = Path::from_vertex_indices(vec![0, 1, 2], &vertex).unwrap();
+    /// assert_eq!(path.shortcut(&[]), vec![SpherePoint::new(0.0, 0.0), SpherePoint::new(1.0, 1.0)]);
+    /// ```
+    pub fn shortcut(&self, obstacles: &[SpherePolygon]) -> Vec<SpherePoint> {
+        let points = self.points();
+        if points.len() <= 2 {
+            return points;
+        }
+        let mut kept: Vec<SpherePoint> = vec![points[0].clone()];
+        for position in 1..points.len() - 1 {
+            if !is_clear(kept.last().unwrap(), &points[position + 1], obstacles) {
+                kept.push(points[position].clone());
+            }
+        }
+        kept.push(points[points.len() - 1].clone());
+        kept
+    }
+
+    /// Returns points of Path with sharp corners replaced by smooth curves, giving flyable or sailable
+    /// trajectory instead of jagged graph geometry. Curves are great-circle interpolated quadratic
+    /// Bézier curves which pass at most `max_deviation_km` from the corner they replace, on tight
+    /// corners between short connections they stay closer. Returns `points` for zero deviation.
+    ///
+    /// ```
+    /// use path_navigator::components::*;
+    /// use path_navigator::data::CelestialObject;
+    /// use path_navigator::path::Path;
+    /// use path_navigator::vertex::VertexBuffer;
+    ///
+    /// let connections = vec![
+    ///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+    ///     SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(1.0, 1.0)),
+    /// ];
+    /// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+    /// let path = Path::from_vertex_indices(vec![0, 1, 2], &vertex).unwrap();
+    /// let trajectory = path.smooth(5.0, 6371.0);
+    /// assert_eq!(trajectory.len(), 21);
+    /// assert!(!trajectory.contains(&SpherePoint::new(0.0, 1.0)));
+    /// ```
+    pub fn smooth(&self, max_deviation_km: f64, radius: f64) -> Vec<SpherePoint> {
+        smooth_polyline(&self.points(), max_deviation_km / radius)
+    }
+
+    /// Cuts Path into segments of fixed length, for progress waypoints, fuel planning or report tables.
+    ///
+    /// # Arguments:
+    /// * `interval_km` which is f64 - distance between markers in kilometers
+    /// * `radius` which is f64 - radius of celestial object in kilometers, see `data::get_radius_km`
+    ///
+    /// # Remarks:
+    ///
+    /// Markers are placed on great-circle arcs of connections every `interval_km` from the start,
+    /// a marker falling on the finish is left out, so the last segment is usually shorter.
+    /// Fails with `InvalidParameter` for not positive or not finite interval.
+    ///
+    /// ```
+    /// use path_navigator::components::*;
+    /// use path_navigator::data::{get_radius_km, CelestialObject};
+    /// use path_navigator::path::Path;
+    /// use path_navigator::vertex::VertexBuffer;
+    ///
+    /// let connections = vec![SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0))];
+    /// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+    /// let path = Path::from_vertex_indices(vec![0, 1], &vertex).unwrap();
+    /// let every_50_km = path.distance_markers(50.0, get_radius_km(&CelestialObject::EARTH)).unwrap();
+    /// assert_eq!(every_50_km.markers.len(), 2);
+    /// assert_eq!(every_50_km.segments.len(), 3);
+    /// assert!((every_50_km.segments[2].distance - 11.19).abs() < 0.01);
+    /// ```
+    pub fn distance_markers(&self, interval_km: f64, radius: f64) -> Result<DistanceMarkers> {
+        if !interval_km.is_finite() || interval_km <= 0.0 {
+            return Err(Error::from_kind(ErrorKind::InvalidParameter));
+        }
+        let lengths: Vec<f64> = self.connections.iter().map(|connection| connection.cost(radius)).collect();
+        let total: f64 = lengths.iter().sum();
+        let mut markers: Vec<SpherePoint> = Vec::new();
+        let mut segments: Vec<PathSegment> = Vec::new();
+        let first = match self.connections.first() {
+            Some(first) => first.start.clone(),
+            None => return Ok(DistanceMarkers {markers, segments}),
+        };
+        let new_segment = |start: SpherePoint, start_distance: f64| PathSegment {
+            start: start.clone(), finish: start, start_distance, distance: 0.0, cost: 0.0, edge_ids: Vec::new(),
+        };
+        let mut current = new_segment(first, 0.0);
+        let mut connection_start = 0.0;
+        for (((connection, edge_id), cost), length) in self.connections.iter().zip(&self.edge_ids).zip(&self.costs).zip(&lengths) {
+            let connection_finish = connection_start + length;
+            current.edge_ids.push(*edge_id);
+            let mut covered = connection_start;
+            let mut marker = (markers.len() + 1) as f64 * interval_km;
+            while *length > 0.0 && marker <= connection_finish && marker < total {
+                let point = connection.interpolate((marker - connection_start) / length);
+                current.cost += cost * (marker - covered) / length;
+                current.distance = marker - current.start_distance;
+                current.finish = point.clone();
+                segments.push(current);
+                markers.push(point.clone());
+                current = new_segment(point, marker);
+                if marker < connection_finish {
+                    current.edge_ids.push(*edge_id);
+                }
+                covered = marker;
+                marker = (markers.len() + 1) as f64 * interval_km;
+            }
+            current.cost += if *length > 0.0 { cost * (connection_finish - covered) / length } else { *cost };
+            connection_start = connection_finish;
+        }
+        current.distance = total - current.start_distance;
+        current.finish = self.connections.last().unwrap().finish.clone();
+        segments.push(current);
+        Ok(DistanceMarkers {markers, segments})
+    }
+
+    /// Returns connections of Path at given Granularity, for clients which do not need thousands of
+    /// short connections. Connections are oriented in travel direction and join consecutive kept
+    /// nodes, so they are chords, not graph connections. VertexBuffer has to be the one Path was found on.
+    ///
+    /// ```
+    /// use path_navigator::components::*;
+    /// use path_navigator::data::CelestialObject;
+    /// use path_navigator::path::{Granularity, Path};
+    /// use path_navigator::vertex::VertexBuffer;
+    ///
+    /// let connections: Vec<SphereConnection> = (0..10)
+    ///     .map(|i| SphereConnection::new(SpherePoint::new(0.0, i as f64 * 0.1), SpherePoint::new(0.0, (i + 1) as f64 * 0.1)))
+    ///     .collect();
+    /// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+    /// let path = Path::from_vertex_indices((0..11).collect(), &vertex).unwrap();
+    /// assert_eq!(path.simplify(Granularity::Full, &vertex).len(), 10);
+    /// assert_eq!(path.simplify(Granularity::Simplified {tolerance_km: 0.1}, &vertex).len(), 1);
+    /// assert_eq!(path.simplify(Granularity::Junctions, &vertex).len(), 1);
+    /// ```
+    pub fn simplify(&self, granularity: Granularity, vertex: &VertexBuffer) -> Vec<SphereConnection> {
+        let points = match granularity {
+            Granularity::Full => return self.connections.clone(),
+            Granularity::Simplified {tolerance_km} => {
+                simplify_polyline(&self.points(), tolerance_km / get_radius_km(&vertex.celestial_object))
+            }
+            Granularity::Junctions => {
+                let on_path: NodeSet<usize> = self.vertex_indices.iter().copied().collect();
+                let mut neighbours: NodeMap<usize, NodeSet<usize>> = NodeMap::default();
+                for edge_id in 0..vertex.edge_count() {
+                    let (from, to) = vertex.edge(edge_id).unwrap();
+                    for (node, other) in [(from, to), (to, from)] {
+                        if node != other && on_path.contains(&node) {
+                            neighbours.entry(node).or_default().insert(other);
+                        }
+                    }
+                }
+                let is_junction = |index: &usize| neighbours.get(index).map_or(0, NodeSet::len) != 2;
+                let last = self.vertex_indices.len().saturating_sub(1);
+                self.vertex_indices.iter().enumerate()
+                    .filter(|(position, index)| *position == 0 || *position == last || is_junction(index))
+                    .map(|(_, index)| vertex.vector[*index].coordinates.clone())
+                    .collect()
+            }
+        };
+        points.windows(2).map(|pair| SphereConnection::new(pair[0].clone(), pair[1].clone())).collect()
+    }
+
+    /// Returns stable 64 bit fingerprint of ordered node indices (FNV-1a),
+    /// identical for paths visiting the same nodes in the same order.
+    pub fn fingerprint(&self) -> u64 {
+        const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+        const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+        self.vertex_indices.iter()
+            .flat_map(|index| (*index as u64).to_le_bytes())
+            .fold(FNV_OFFSET_BASIS, |hash, byte| (hash ^ byte as u64).wrapping_mul(FNV_PRIME))
+    }
+
+    /// Returns ratio of cost shared by both paths to the cost of the more expensive one, from 0.0 for
+    /// disjoint paths to 1.0 for identical ones. Connections are matched by EdgeId regardless of travel
+    /// direction, so both paths are expected to come from the same VertexBuffer.
+    /// For haversine costs this is the shared-length ratio.
+    pub fn similarity(&self, other: &Path) -> f64 {
+        let longest = self.cost().max(other.cost());
+        if longest == 0.0 {
+            return if self.vertex_indices == other.vertex_indices { 1.0 } else { 0.0 };
+        }
+        let other_edges: NodeSet<EdgeId> = other.edge_ids.iter().copied().collect();
+        let shared: f64 = self.edge_ids.iter()
+            .zip(self.costs.iter())
+            .filter(|(edge_id, _)| other_edges.contains(edge_id))
+            .map(|(_, cost)| cost)
+            .sum();
+        (shared / longest).min(1.0)
+    }
+}
+
+/// Returns the smallest great-circle distance in kilometers from point to any connection of Path,
+/// measured across the track, like how far an aircraft strayed from its filed route. Connections are
+/// followed along their shapes, see `Path::geometry`. None for Path without connections.
+///
+/// # Arguments:
+/// * `point` which is &SpherePoint - current position
+/// * `path` which is &Path - route to measure the distance to
+/// * `vertex` which is &VertexBuffer - VertexBuffer the Path was found on
+///
+/// ```
+/// use path_navigator::components::*;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::path::{path_distance_from, Path};
+/// use path_navigator::vertex::VertexBuffer;
+///
+/// let connections = vec![
+///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+///     SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(1.0, 1.0)),
+/// ];
+/// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+/// let route = Path::from_vertex_indices(vec![0, 1, 2], &vertex).unwrap();
+/// let strayed = path_distance_from(&SpherePoint::new(0.05, 0.5), &route, &vertex).unwrap();
+/// assert!(strayed > 5.0);
+/// ```
+pub fn path_distance_from(point: &SpherePoint, path: &Path, vertex: &VertexBuffer) -> Option<f64> {
+    path.geometry(vertex).windows(2)
+        .map(|pair| SphereConnection::new(pair[0].clone(), pair[1].clone()).angular_distance_to(point))
+        .min_by(|a, b| a.total_cmp(b))
+        .map(|angle| angle * get_radius_km(&vertex.celestial_object))
+}
+
+/// Removes paths visiting the same nodes in the same order as an earlier path, keeping the first one.
+pub fn deduplicate(paths: Vec<Path>) -> Vec<Path> {
+    let mut seen: NodeMap<u64, Vec<usize>> = NodeMap::default();
+    let mut result: Vec<Path> = Vec::new();
+    for path in paths {
+        let same_fingerprint = seen.entry(path.fingerprint()).or_default();
+        if same_fingerprint.iter().any(|i| result[*i].vertex_indices == path.vertex_indices) {
+            continue;
+        }
+        same_fingerprint.push(result.len());
+        result.push(path);
+    }
+    result
+}
+
+#[cfg(test)]
+mod path_tests {
+    use super::*;
+    use crate::data::{get_radius_km, CelestialObject};
+
+    fn ladder_vertex() -> VertexBuffer {
+        // two parallel lines of nodes: 0 - 1 - 2 - 3 and 4 - 5 - 6 - 7, joined at both ends
+        let mut connections: Vec<SphereConnection> = Vec::new();
+        for lat in [0.0, 1.0] {
+            for i in 0..3 {
+                connections.push(SphereConnection::new(SpherePoint::new(lat, i as f64), SpherePoint::new(lat, i as f64 + 1.0)));
+            }
+        }
+        connections.push(SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(1.0, 0.0)));
+        connections.push(SphereConnection::new(SpherePoint::new(0.0, 3.0), SpherePoint::new(1.0, 3.0)));
+        VertexBuffer::new(connections, CelestialObject::EARTH).unwrap()
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        // given
+        let vertex = ladder_vertex();
+        // when
+        let path = Path::from_vertex_indices(vec![0, 1, 2, 3], &vertex).unwrap();
+        let same_path = Path::from_vertex_indices(vec![0, 1, 2, 3], &vertex).unwrap();
+        let reversed_path = Path::from_vertex_indices(vec![3, 2, 1, 0], &vertex).unwrap();
+        // then
+        assert_eq!(path.fingerprint(), same_path.fingerprint());
+        assert_ne!(path.fingerprint(), reversed_path.fingerprint());
+        assert_eq!(deduplicate(vec![path, reversed_path, same_path]).len(), 2);
+        assert!(Path::from_vertex_indices(vec![0, 2], &vertex).is_none());
+    }
+
+    #[test]
+    fn test_concat_splice_and_truncate() {
+        // given
+        let vertex = ladder_vertex();
+        let bottom = Path::from_vertex_indices(vec![0, 1, 2, 3], &vertex).unwrap();
+        let top = Path::from_vertex_indices(vec![0, 4, 5, 6, 7, 3], &vertex).unwrap();
+        let back = Path::from_vertex_indices(vec![3, 2], &vertex).unwrap();
+        let around = Path::from_vertex_indices(vec![1, 0, 4, 5, 6, 7, 3], &vertex).unwrap();
+        // when
+        let round_trip = bottom.concat(&back).unwrap();
+        let detour = bottom.splice(1..3, &around).unwrap();
+        let swapped = bottom.splice(0..3, &top).unwrap();
+        let inserted = bottom.splice(1..1, &Path::from_vertex_indices(vec![1, 0, 1], &vertex).unwrap()).unwrap();
+        let travelled = round_trip.truncate_at(&SpherePoint::new(0.1, 2.1));
+        // then
+        assert_eq!(round_trip.vertex_indices, vec![0, 1, 2, 3, 2]);
+        assert!(relative_eq!(round_trip.cost(), bottom.cost() + back.cost(), max_relative = 1e-12));
+        assert_eq!(detour.vertex_indices, vec![0, 1, 0, 4, 5, 6, 7, 3]);
+        assert_eq!(detour.connections.len(), detour.costs.len());
+        assert_eq!(detour, Path::from_edge_ids(0, detour.edge_ids.clone(), &vertex).unwrap());
+        assert_eq!(swapped, top);
+        assert_eq!(inserted.vertex_indices, vec![0, 1, 0, 1, 2, 3]);
+        assert_eq!(travelled.vertex_indices, vec![0, 1, 2]);
+        assert_eq!(bottom.truncate_at(&SpherePoint::new(0.0, -5.0)).vertex_indices, vec![0]);
+        assert!(bottom.concat(&top).is_err());
+        assert!(bottom.splice(1..2, &top).is_err());
+        assert!(bottom.splice(2..4, &back).is_err());
+    }
+
+    #[test]
+    fn test_path_distance_from() {
+        // given
+        let vertex = ladder_vertex();
+        let route = Path::from_vertex_indices(vec![0, 1, 2, 3, 7], &vertex).unwrap();
+        let radius = get_radius_km(&CelestialObject::EARTH);
+        let degree = radius * 1f64.to_radians();
+        // when
+        let beside = path_distance_from(&SpherePoint::new(-0.1, 1.5), &route, &vertex).unwrap();
+        let beyond_end = path_distance_from(&SpherePoint::new(1.5, 3.0), &route, &vertex).unwrap();
+        let on_route = path_distance_from(&SpherePoint::new(0.5, 3.0), &route, &vertex).unwrap();
+        let mut shaped = vertex.clone();
+        shaped.set_edge_shape(shaped.find_edge(1, 2).unwrap(), vec![SpherePoint::new(-0.1, 1.5)]).unwrap();
+        let on_shape = path_distance_from(&SpherePoint::new(-0.1, 1.5), &route, &shaped).unwrap();
+        // then
+        assert!(relative_eq!(beside, 0.1 * degree, max_relative = 1e-6));
+        assert!(relative_eq!(beyond_end, 0.5 * degree, max_relative = 1e-9));
+        assert!(on_route < 1e-9);
+        assert!(on_shape < 1e-9);
+        let standing = Path::from_vertex_indices(vec![0], &vertex).unwrap();
+        assert_eq!(path_distance_from(&SpherePoint::new(0.0, 0.0), &standing, &vertex), None);
+    }
+
+    #[test]
+    fn test_similarity() {
+        // given
+        let vertex = ladder_vertex();
+        let bottom = Path::from_vertex_indices(vec![0, 1, 2, 3], &vertex).unwrap();
+        let mixed = Path::from_vertex_indices(vec![0, 1, 2, 3, 7], &vertex).unwrap();
+        let top = Path::from_vertex_indices(vec![0, 4, 5, 6, 7, 3], &vertex).unwrap();
+        // when, then
+        assert_eq!(bottom.similarity(&bottom), 1.0);
+        assert_eq!(bottom.similarity(&top), 0.0);
+        assert!(relative_eq!(bottom.similarity(&mixed), bottom.cost() / mixed.cost(), max_relative = 1e-12));
+        assert_eq!(bottom.similarity(&mixed), mixed.similarity(&bottom));
+    }
+
+    #[test]
+    fn test_offset() {
+        // given: a hairpin going east, north and back west, and a sharp V turning back west-north-west
+        let radius = get_radius_km(&CelestialObject::EARTH);
+        let polyline_path = |points: &[SpherePoint]| {
+            let connections: Vec<SphereConnection> = points.windows(2)
+                .map(|pair| SphereConnection::new(pair[0].clone(), pair[1].clone()))
+                .collect();
+            let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+            Path::from_vertex_indices((0..points.len()).collect(), &vertex).unwrap()
+        };
+        let hairpin = polyline_path(&[
+            SpherePoint::new(0.0, 0.0),
+            SpherePoint::new(0.0, 0.2),
+            SpherePoint::new(0.05, 0.2),
+            SpherePoint::new(0.05, 0.0),
+        ]);
+        let v_turn = polyline_path(&[SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 0.2), SpherePoint::new(0.05, 0.0)]);
+        let distance_to = |path: &Path, point: &SpherePoint| path.connections.iter()
+            .map(|connection| connection.angular_distance_to(point) * radius)
+            .fold(f64::INFINITY, f64::min);
+        // when
+        let outer = hairpin.offset(2.0, radius);
+        let inner = v_turn.offset(-1.0, radius);
+        // then
+        assert_eq!(hairpin.points().len(), 4);
+        assert_eq!(outer.len(), 4);
+        assert!(relative_eq!(distance_to(&hairpin, &outer[0]), 2.0, max_relative = 1e-6));
+        assert!(relative_eq!(distance_to(&hairpin, &outer[3]), 2.0, max_relative = 1e-6));
+        assert!(relative_eq!(distance_to(&hairpin, &outer[1]), 2.0 * 2f64.sqrt(), max_relative = 1e-3));
+        assert!(outer[1].lat < 0.0 && outer[1].lng > 0.2);
+        assert_eq!(inner.len(), 3);
+        assert!(relative_eq!(distance_to(&v_turn, &inner[1]), 1.0, max_relative = 1e-4));
+        assert!(inner[1].lng < 0.2);
+    }
+
+    #[test]
+    fn test_simplify() {
+        // given: ladder path 0 - 1 - 2 - 3 - 7 - 6 - 5 - 4 with small bump at node 2,
+        // spurs make junctions of nodes 0 and 3
+        let mut connections: Vec<SphereConnection> = Vec::new();
+        let bottom = [SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0), SpherePoint::new(0.001, 2.0), SpherePoint::new(0.0, 3.0)];
+        for pair in bottom.windows(2) {
+            connections.push(SphereConnection::new(pair[0].clone(), pair[1].clone()));
+        }
+        for i in 0..3 {
+            connections.push(SphereConnection::new(SpherePoint::new(1.0, i as f64), SpherePoint::new(1.0, i as f64 + 1.0)));
+        }
+        connections.push(SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(1.0, 0.0)));
+        connections.push(SphereConnection::new(SpherePoint::new(0.0, 3.0), SpherePoint::new(1.0, 3.0)));
+        connections.push(SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(-1.0, 0.0)));
+        connections.push(SphereConnection::new(SpherePoint::new(0.0, 3.0), SpherePoint::new(0.0, 4.0)));
+        let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        let path = Path::from_vertex_indices(vec![0, 1, 2, 3, 7, 6, 5, 4], &vertex).unwrap();
+        // when
+        let full = path.simplify(Granularity::Full, &vertex);
+        let coarse = path.simplify(Granularity::Simplified {tolerance_km: 1.0}, &vertex);
+        let fine = path.simplify(Granularity::Simplified {tolerance_km: 0.08}, &vertex);
+        let junctions = path.simplify(Granularity::Junctions, &vertex);
+        // then
+        assert_eq!(full, path.connections);
+        assert_eq!(coarse.len(), 3);
+        assert_eq!(fine.len(), 4);
+        assert_eq!(fine[0].finish, SpherePoint::new(0.001, 2.0));
+        assert_eq!(junctions, vec![
+            SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 3.0)),
+            SphereConnection::new(SpherePoint::new(0.0, 3.0), SpherePoint::new(1.0, 0.0)),
+        ]);
+        assert_eq!(coarse.last().unwrap().finish, SpherePoint::new(1.0, 0.0));
+    }
+
+    #[test]
+    fn test_simplify_to_junctions_of_one_way_line() {
+        // given: one-way line 0 -> 1 -> 2 -> 3 with a parallel 1 -> 2 and a branch 2 -> 4
+        let point = |lng: f64| SpherePoint::new(0.0, lng);
+        let mut connections: Vec<SphereConnection> = (0..3).map(|i| SphereConnection::new(point(i as f64), point(i as f64 + 1.0))).collect();
+        connections.push(SphereConnection::new(point(1.0), point(2.0)));
+        connections.push(SphereConnection::new(point(2.0), SpherePoint::new(1.0, 2.0)));
+        let options = BuildOptions {direction: Direction::OneWay, duplicates: EdgePolicy::KeepAll, ..BuildOptions::default()};
+        let (vertex, _) = VertexBuffer::build(connections, CelestialObject::EARTH, &options).unwrap();
+        let path = Path::from_vertex_indices(vec![0, 1, 2, 3], &vertex).unwrap();
+        // when
+        let junctions = path.simplify(Granularity::Junctions, &vertex);
+        // then: node 1 with parallel connections is passed, node 2 with only two outgoing connections is kept
+        assert_eq!(junctions, vec![
+            SphereConnection::new(point(0.0), point(2.0)),
+            SphereConnection::new(point(2.0), point(3.0)),
+        ]);
+    }
+
+    #[test]
+    fn test_distance_markers() {
+        // given: connections 0 - 1 and 1 - 2 along the equator of 1 and 0.5 degree, the second one twice as expensive
+        let radius = get_radius_km(&CelestialObject::EARTH);
+        let degree = radius * 1f64.to_radians();
+        let connections = vec![
+            SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+            SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 1.5)),
+        ];
+        let mut vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        vertex.update_costs(&[(0, 10.0), (1, 10.0)]).unwrap();
+        let path = Path::from_vertex_indices(vec![0, 1, 2], &vertex).unwrap();
+        // when
+        let thirds = path.distance_markers(0.4 * degree, radius).unwrap();
+        let at_junction = path.distance_markers(degree, radius).unwrap();
+        let single = path.distance_markers(10.0 * degree, radius).unwrap();
+        // then
+        assert_eq!(thirds.markers.len(), 3);
+        assert_eq!(thirds.segments.len(), 4);
+        assert!(relative_eq!(thirds.markers[2].lng, 1.2, max_relative = 1e-9));
+        assert_eq!(thirds.segments[2].edge_ids, vec![0, 1]);
+        assert!(relative_eq!(thirds.segments[2].cost, 2.0 + 4.0, max_relative = 1e-9));
+        assert!(relative_eq!(thirds.segments[3].distance, 0.3 * degree, max_relative = 1e-9));
+        assert!(relative_eq!(thirds.segments.iter().map(|segment| segment.cost).sum::<f64>(), path.cost(), max_relative = 1e-12));
+        assert_eq!(thirds.segments[1].start, thirds.markers[0]);
+        assert_eq!(at_junction.segments.iter().map(|segment| segment.edge_ids.clone()).collect::<Vec<_>>(), vec![vec![0], vec![1]]);
+        assert_eq!(single.markers.len(), 0);
+        assert_eq!(single.segments[0].finish, SpherePoint::new(0.0, 1.5));
+        assert!(path.distance_markers(0.0, radius).is_err());
+    }
+
+    #[test]
+    fn test_smooth() {
+        // given: path east, then north, then slightly north-east
+        let points = vec![
+            SpherePoint::new(0.0, 0.0),
+            SpherePoint::new(0.0, 1.0),
+            SpherePoint::new(1.0, 1.0),
+            SpherePoint::new(2.0, 1.1),
+        ];
+        let connections: Vec<SphereConnection> = points.windows(2)
+            .map(|pair| SphereConnection::new(pair[0].clone(), pair[1].clone()))
+            .collect();
+        let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        let path = Path::from_vertex_indices(vec![0, 1, 2, 3], &vertex).unwrap();
+        let radius = get_radius_km(&CelestialObject::EARTH);
+        // when
+        let smooth = path.smooth(2.0, radius);
+        // then
+        let from_corner = |point: &SpherePoint| SphereConnection::new(points[1].clone(), point.clone()).cost(radius);
+        let deviation = smooth.iter().map(from_corner).fold(f64::INFINITY, f64::min);
+        assert_eq!(smooth.first(), points.first());
+        assert_eq!(smooth.last(), points.last());
+        assert_eq!(smooth.len(), 24);
+        assert!(relative_eq!(deviation, 2.0, max_relative = 1e-3));
+        assert!(smooth.iter().all(|point| path.connections.iter()
+            .map(|connection| connection.angular_distance_to(point) * radius)
+            .fold(f64::INFINITY, f64::min) <= 2.0 + 1e-6));
+        assert_eq!(path.smooth(0.0, radius), points);
+    }
+
+    #[test]
+    fn test_geometry_with_edge_shapes() {
+        // given: a line 0 - 1 - 2 where connection 1 - 2 keeps the shape of a curve, travelled backwards
+        let connections = vec![
+            SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+            SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 3.0)),
+        ];
+        let mut vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        let curve = vec![SpherePoint::new(0.5, 1.5), SpherePoint::new(0.5, 2.5)];
+        vertex.set_edge_shape(1, curve.clone()).unwrap();
+        // when
+        let path = Path::from_vertex_indices(vec![2, 1, 0], &vertex).unwrap();
+        let geometry = path.geometry(&vertex);
+        // then
+        assert!(path.connections.windows(2).all(|pair| pair[0].finish == pair[1].start));
+        assert_eq!(path.connections[0].start, vertex.vector[2].coordinates);
+        assert_eq!(geometry, vec![
+            SpherePoint::new(0.0, 3.0), curve[1].clone(), curve[0].clone(), SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 0.0),
+        ]);
+        assert_eq!(Path::from_vertex_indices(vec![1, 0], &vertex).unwrap().geometry(&vertex), path.slice(1..2).points());
+        vertex.set_edge_shape(1, Vec::new()).unwrap();
+        assert_eq!(path.geometry(&vertex), path.points());
+        assert!(vertex.set_edge_shape(2, curve).is_err());
+    }
+
+    #[test]
+    fn test_shortcut_around_obstacle() {
+        // given: a grid path going east along the equator and then north, with a no-go square
+        // sitting on the diagonal between its ends
+        let point = |lat: f64, lng: f64| SpherePoint::new(lat, lng);
+        let mut connections: Vec<SphereConnection> = (0..4)
+            .map(|i| SphereConnection::new(point(0.0, i as f64), point(0.0, (i + 1) as f64)))
+            .collect();
+        connections.extend((0..4).map(|i| SphereConnection::new(point(i as f64, 4.0), point((i + 1) as f64, 4.0))));
+        let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        let path = Path::from_vertex_indices((0..9).collect(), &vertex).unwrap();
+        let obstacles = vec![SpherePolygon::new(vec![point(1.5, 1.5), point(1.5, 2.5), point(2.5, 2.5), point(2.5, 1.5)])];
+        let radius = get_radius_km(&CelestialObject::EARTH);
+        let length = |points: &[SpherePoint]| points.windows(2)
+            .map(|pair| SphereConnection::new(pair[0].clone(), pair[1].clone()).cost(radius))
+            .sum::<f64>();
+        // when
+        let free = path.shortcut(&[]);
+        let avoiding = path.shortcut(&obstacles);
+        // then
+        assert_eq!(free, vec![point(0.0, 0.0), point(4.0, 4.0)]);
+        assert!(avoiding.len() > 2 && avoiding.len() < path.points().len());
+        assert_eq!((avoiding.first(), avoiding.last()), (Some(&point(0.0, 0.0)), Some(&point(4.0, 4.0))));
+        assert!(avoiding.windows(2).all(|pair| is_clear(&pair[0], &pair[1], &obstacles)));
+        assert!(length(&free) < length(&avoiding) && length(&avoiding) < path.cost());
+        let single = Path::from_vertex_indices(vec![0, 1], &vertex).unwrap();
+        assert_eq!(single.shortcut(&obstacles), single.points());
+    }
+}
@@ -0,0 +1,49 @@
+/// Small deterministic pseudo random number generator (SplitMix64), used where results have to be
+/// reproducible from a seed on every platform.
+#[derive(Debug, Clone)]
+pub(crate) struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        Self {state: seed}
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns uniformly distributed number from [0.0, 1.0).
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Returns uniformly distributed number from [0, bound), bound has to be greater than 0.
+    pub fn below(&mut self, bound: usize) -> usize {
+        (self.next_f64() * bound as f64) as usize
+    }
+}
+
+#[cfg(test)]
+mod rng_tests {
+    use super::*;
+
+    #[test]
+    fn test_sequence_is_reproducible() {
+        // given
+        let mut first = SplitMix64::new(7);
+        let mut second = SplitMix64::new(7);
+        // when, then
+        for _ in 0..100 {
+            let value = first.next_f64();
+            assert_eq!(value, second.next_f64());
+            assert!((0.0..1.0).contains(&value));
+        }
+        assert_eq!(SplitMix64::new(0).next_u64(), 0xe220_a839_7b1d_cdaf);
+    }
+}
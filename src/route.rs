@@ -0,0 +1,627 @@
+use crate::vertex::*;
+use crate::components::{SphereConnection, SpherePoint};
+use crate::data::get_radius_km;
+use crate::dijkstra::{get_closest_point, search_edge_ids_with_extra};
+use crate::flight_plan::Waypoint;
+use crate::hashing::{from_node_index, to_node_index, NodeMap};
+use crate::io::geojson;
+use crate::path::{Granularity, Path};
+
+/// Version of JSON written by `Route::to_json`.
+pub const ROUTE_JSON_VERSION: u32 = 1;
+
+/// How a query point was attached to VertexBuffer.
+///
+/// * `query` - coordinates given in the query
+/// * `vertex_index` - node the query point was snapped to
+/// * `edge_id` - connection of the route leaving (start) or entering (finish) that node, None for empty route
+/// * `distance` - great-circle distance between query point and node, in kilometers
+/// * `connection` - synthetic connection between query point and node in travel direction
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snap {
+    pub query: SpherePoint,
+    pub vertex_index: usize,
+    pub edge_id: Option<EdgeId>,
+    pub distance: f64,
+    pub connection: SphereConnection,
+}
+
+/// Total distance and cost of route connections sharing the same category, see `Route::breakdown`.
+/// `category` is None for connections without the category key in metadata.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CategoryTotal {
+    pub category: Option<String>,
+    pub distance: f64,
+    pub cost: f64,
+}
+
+/// # Route
+/// Result of a routing query: Path through VertexBuffer together with Snaps of start and finish,
+/// so callers can see how far from the requested coordinates the graph path starts and ends.
+/// Path holds vertex indices and costs of every connection, `explored` the number of nodes
+/// settled by the search, a measure of its work.
+///
+/// ```
+/// use path_navigator::components::*;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::route::find_route;
+/// use path_navigator::vertex::VertexBuffer;
+///
+/// let connections = vec![SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0))];
+/// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+/// let route = find_route(&SpherePoint::new(0.1, 0.0), &SpherePoint::new(0.0, 1.0), &vertex).unwrap();
+/// assert!(route.start.distance > 11.0);
+/// assert_eq!(route.finish.distance, 0.0);
+/// assert_eq!(route.connections().len(), 3);
+/// assert_eq!(route.points().len(), 3);
+/// assert_eq!(route.explored, 2);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Route {
+    pub path: Path,
+    pub start: Snap,
+    pub finish: Snap,
+    pub explored: usize,
+}
+
+impl Route {
+    /// Returns cost of graph path, without snapping segments.
+    pub fn cost(&self) -> f64 {
+        self.path.cost()
+    }
+
+    /// Returns distance of both snapping segments, in kilometers.
+    pub fn snap_distance(&self) -> f64 {
+        self.start.distance + self.finish.distance
+    }
+
+    /// Returns distance in kilometers and cost of graph path aggregated by value of `key` in connection
+    /// metadata, like "34 km motorway, 2 km ferry" for road class key. Categories are ordered by their
+    /// first connection on the route, snapping segments are not included.
+    /// VertexBuffer has to be the one route was found on.
+    ///
+    /// ```
+    /// use path_navigator::components::*;
+    /// use path_navigator::data::CelestialObject;
+    /// use path_navigator::route::find_route;
+    /// use path_navigator::vertex::VertexBuffer;
+    ///
+    /// let connections = vec![
+    ///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+    ///     SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 1.1)),
+    /// ];
+    /// let mut vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+    /// vertex.set_edge_metadata(0, "class", "motorway").unwrap();
+    /// vertex.set_edge_metadata(1, "class", "ferry").unwrap();
+    /// let route = find_route(&SpherePoint::new(0.0, 0.0), &SpherePoint::new(0.0, 1.1), &vertex).unwrap();
+    /// let breakdown = route.breakdown(&vertex, "class");
+    /// assert_eq!(breakdown[0].category.as_deref(), Some("motorway"));
+    /// assert!(breakdown[0].distance > 111.0 && breakdown[1].distance < 12.0);
+    /// ```
+    pub fn breakdown(&self, vertex: &VertexBuffer, key: &str) -> Vec<CategoryTotal> {
+        let radius = get_radius_km(&vertex.celestial_object);
+        let mut totals: Vec<CategoryTotal> = Vec::new();
+        for ((edge_id, connection), cost) in self.path.edge_ids.iter().zip(&self.path.connections).zip(&self.path.costs) {
+            let category = vertex.edge_metadata(*edge_id).and_then(|metadata| metadata.get(key)).cloned();
+            let position = match totals.iter().position(|total| total.category == category) {
+                Some(position) => position,
+                None => {
+                    totals.push(CategoryTotal {category, distance: 0.0, cost: 0.0});
+                    totals.len() - 1
+                }
+            };
+            totals[position].distance += connection.cost(radius);
+            totals[position].cost += cost;
+        }
+        totals
+    }
+
+    /// Returns Route as JSON object with stable layout, independent of crate internals.
+    /// VertexBuffer has to be the one route was found on.
+    ///
+    /// # Remarks:
+    ///
+    /// Layout of version 1, positions are `[longitude, latitude]` and distances are great-circle kilometers:
+    ///
+    /// * `version` - ROUTE_JSON_VERSION
+    /// * `start`, `finish` - snapping with `query` position, `vertex_index` of snapped node and `distance` to it
+    /// * `segments` - graph path connections in travel order with `from` and `to` positions, `edge_id`,
+    ///   `cost`, `distance` and `metadata` object of string values
+    /// * `totals` - `cost` and `distance` of graph path and `snap_distance` of both snapping segments
+    ///
+    /// Within a version members are never renamed, removed or changed in meaning, new members may be
+    /// added, so consumers should ignore members they do not know. Any other change bumps the version.
+    /// Not finite numbers are written as `null`.
+    ///
+    /// ```
+    /// use path_navigator::components::*;
+    /// use path_navigator::data::CelestialObject;
+    /// use path_navigator::route::find_route;
+    /// use path_navigator::vertex::VertexBuffer;
+    ///
+    /// let connections = vec![SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0))];
+    /// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+    /// let route = find_route(&SpherePoint::new(0.0, 0.0), &SpherePoint::new(0.0, 1.0), &vertex).unwrap();
+    /// let json = route.to_json(&vertex);
+    /// assert!(json.starts_with("{\"version\":1,\"start\":{\"query\":[0,0],\"vertex_index\":0,\"distance\":0}"));
+    /// ```
+    pub fn to_json(&self, vertex: &VertexBuffer) -> String {
+        let radius = get_radius_km(&vertex.celestial_object);
+        let snap = |snap: &Snap| format!(
+            "{{\"query\":{},\"vertex_index\":{},\"distance\":{}}}",
+            geojson::position(&snap.query), snap.vertex_index, geojson::number(snap.distance),
+        );
+        let mut distance = 0.0;
+        let segments: Vec<String> = self.path.edge_ids.iter().zip(&self.path.connections).zip(&self.path.costs)
+            .map(|((edge_id, connection), cost)| {
+                let length = connection.cost(radius);
+                distance += length;
+                let metadata: Vec<String> = vertex.edge_metadata(*edge_id).into_iter().flatten()
+                    .map(|(key, value)| format!("{}:{}", geojson::string(key), geojson::string(value)))
+                    .collect();
+                format!(
+                    "{{\"from\":{},\"to\":{},\"edge_id\":{},\"cost\":{},\"distance\":{},\"metadata\":{{{}}}}}",
+                    geojson::position(&connection.start), geojson::position(&connection.finish), edge_id,
+                    geojson::number(*cost), geojson::number(length), metadata.join(","),
+                )
+            })
+            .collect();
+        format!(
+            "{{\"version\":{},\"start\":{},\"finish\":{},\"segments\":[{}],\"totals\":{{\"cost\":{},\"distance\":{},\"snap_distance\":{}}}}}",
+            ROUTE_JSON_VERSION, snap(&self.start), snap(&self.finish), segments.join(","),
+            geojson::number(self.cost()), geojson::number(distance), geojson::number(self.snap_distance()),
+        )
+    }
+
+    /// Returns Waypoints from query start to query finish without altitude, for `flight_plan::write_flight_plan`.
+    /// Path follows edge shapes, see `Path::geometry`, and points repeated because of zero snap distance are
+    /// written once. VertexBuffer has to be the one route was found on.
+    pub fn waypoints(&self, vertex: &VertexBuffer) -> Vec<Waypoint> {
+        let mut points: Vec<SpherePoint> = vec![self.start.connection.start.clone(), self.start.connection.finish.clone()];
+        points.extend(self.path.geometry(vertex).into_iter().skip(1));
+        points.push(self.finish.connection.finish.clone());
+        points.dedup();
+        points.into_iter().map(|point| Waypoint::new(point, None)).collect()
+    }
+
+    /// Returns points from query start to query finish, through the nodes of graph path, in travel order.
+    /// Query points equal to the nodes they were snapped to are given once.
+    pub fn points(&self) -> Vec<SpherePoint> {
+        let mut points: Vec<SpherePoint> = vec![self.start.query.clone()];
+        points.extend(self.path.points());
+        points.push(self.finish.query.clone());
+        points.dedup();
+        points
+    }
+
+    /// Returns Route as GeoJSON Feature with LineString geometry of `points`, from query start to
+    /// query finish, ready for map libraries like Leaflet or Mapbox. Properties are `cost` of graph
+    /// path, `snap_distance` in kilometers and number of `explored` nodes.
+    ///
+    /// ```
+    /// use path_navigator::components::*;
+    /// use path_navigator::data::CelestialObject;
+    /// use path_navigator::route::find_route;
+    /// use path_navigator::vertex::VertexBuffer;
+    ///
+    /// let connections = vec![SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0))];
+    /// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+    /// let route = find_route(&SpherePoint::new(0.0, 0.0), &SpherePoint::new(0.0, 1.0), &vertex).unwrap();
+    /// assert!(route.to_geojson().contains("\"geometry\":{\"type\":\"LineString\",\"coordinates\":[[0,0],[1,0]]}"));
+    /// ```
+    pub fn to_geojson(&self) -> String {
+        geojson::line_string_feature(&self.points(), &[
+            ("cost", geojson::number(self.cost())),
+            ("snap_distance", geojson::number(self.snap_distance())),
+            ("explored", self.explored.to_string()),
+        ])
+    }
+
+    /// Returns connections from query start to query finish: the start snapping segment,
+    /// path connections and the finish snapping segment.
+    pub fn connections(&self) -> Vec<SphereConnection> {
+        let mut result: Vec<SphereConnection> = vec![self.start.connection.clone()];
+        result.extend(self.path.connections.iter().cloned());
+        result.push(self.finish.connection.clone());
+        result
+    }
+
+    /// Returns connections like `connections` with graph path at given Granularity, see `Path::simplify`.
+    pub fn connections_at(&self, granularity: Granularity, vertex: &VertexBuffer) -> Vec<SphereConnection> {
+        let mut result: Vec<SphereConnection> = vec![self.start.connection.clone()];
+        result.extend(self.path.simplify(granularity, vertex));
+        result.push(self.finish.connection.clone());
+        result
+    }
+}
+
+/// Returns Route which is the shortest path between two given points, see `dijkstra::find_path`,
+/// with details of snapping query points to VertexBuffer.
+///
+/// Unlike `find_shortest_path`, when both points snap to the same node the result is Route with
+/// empty Path of zero cost and both snapping segments, so None always means that no route exists.
+pub fn find_route(start: &SpherePoint, finish: &SpherePoint, vertex: &VertexBuffer) -> Option<Route> {
+    if vertex.is_empty() {
+        return None;
+    }
+    let start_index = get_closest_point(start, vertex);
+    let finish_index = get_closest_point(finish, vertex);
+    let (edge_ids, explored) = search_edge_ids_with_extra(start_index, finish_index, vertex, &NodeMap::default(),
+        |_, _, graph| Some(graph.cost));
+    let path = Path::from_edge_ids(start_index, edge_ids?, vertex)?;
+    Some(route_from_path(path, start, finish, explored, vertex))
+}
+
+/// Returns the cheapest Route between two given points which travels connection with given EdgeId,
+/// like a designated corridor or a segment to inspect, in either direction.
+///
+/// # Remarks:
+///
+/// Query points are snapped once and the route is stitched from two searches, to the connection
+/// and from it, for each travel direction of the connection, the cheaper direction wins. Stitched
+/// route may pass some nodes twice, for example when the connection is a dead end. `Route::explored`
+/// counts nodes settled by all four searches.
+/// None for unknown EdgeId or when the connection is not reachable from start or finish.
+///
+/// ```
+/// use path_navigator::components::*;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::route::find_route_through_edge;
+/// use path_navigator::vertex::VertexBuffer;
+///
+/// let connections = vec![
+///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 2.0)),
+///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(1.0, 1.0)),
+///     SphereConnection::new(SpherePoint::new(1.0, 1.0), SpherePoint::new(0.0, 2.0)),
+/// ];
+/// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+/// let route = find_route_through_edge(&SpherePoint::new(0.0, 0.0), &SpherePoint::new(0.0, 2.0), 2, &vertex).unwrap();
+/// assert_eq!(route.path.edge_ids, vec![1, 2]);
+/// ```
+pub fn find_route_through_edge(start: &SpherePoint, finish: &SpherePoint, edge_id: EdgeId, vertex: &VertexBuffer) -> Option<Route> {
+    let (from, to) = vertex.edge(edge_id)?;
+    let start_index = get_closest_point(start, vertex);
+    let finish_index = get_closest_point(finish, vertex);
+    let mut explored = 0;
+    let mut search = |a: usize, b: usize| {
+        let (edge_ids, settled) = search_edge_ids_with_extra(a, b, vertex, &NodeMap::default(), |_, _, graph| Some(graph.cost));
+        explored += settled;
+        edge_ids
+    };
+    let paths: Vec<Path> = [(from, to), (to, from)].iter()
+        .filter_map(|(entry, exit)| {
+            let mut edge_ids = search(start_index, *entry)?;
+            edge_ids.push(edge_id);
+            edge_ids.extend(search(*exit, finish_index)?);
+            Path::from_edge_ids(start_index, edge_ids, vertex)
+        })
+        .collect();
+    paths.into_iter()
+        .min_by(|a, b| a.cost().total_cmp(&b.cost()))
+        .map(|path| route_from_path(path, start, finish, explored, vertex))
+}
+
+/// Route found by `find_route_with_virtual`, `virtual_used` holds positions of the virtual connections
+/// used by the route, in travel order, empty when the route does not need any of them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VirtualRoute {
+    pub route: Route,
+    pub virtual_used: Vec<usize>,
+}
+
+/// Returns Route between two given points like `find_route`, on VertexBuffer extended with temporary
+/// connections existing only for this query, like a proposed new bridge.
+///
+/// # Arguments:
+/// * `start` which is &SpherePoint - start of the route, snapped to the closest point
+/// * `finish` which is &SpherePoint - finish of the route, snapped to the closest point
+/// * `virtual_connections` which is &[SphereConnection] - temporary connections, their ends are snapped to the closest points
+/// * `vertex` which is &VertexBuffer - precalculated vertex for avaliable paths on given geometry
+///
+/// # Remarks:
+///
+/// Virtual connections cost their great-circle length in kilometers between the snapped nodes and
+/// can be travelled both ways. On the route they get EdgeIds following stored connections,
+/// `vertex.edge_count()` plus position in `virtual_connections`, and carry no metadata.
+/// VertexBuffer is not changed.
+///
+/// ```
+/// use path_navigator::components::*;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::route::find_route_with_virtual;
+/// use path_navigator::vertex::VertexBuffer;
+///
+/// let connections = vec![
+///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(1.0, 0.0)),
+///     SphereConnection::new(SpherePoint::new(1.0, 0.0), SpherePoint::new(1.0, 1.0)),
+///     SphereConnection::new(SpherePoint::new(1.0, 1.0), SpherePoint::new(0.0, 1.0)),
+/// ];
+/// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+/// let bridge = SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0));
+/// let result = find_route_with_virtual(&SpherePoint::new(0.0, 0.0), &SpherePoint::new(0.0, 1.0), &[bridge], &vertex).unwrap();
+/// assert_eq!(result.virtual_used, vec![0]);
+/// assert_eq!(result.route.path.edge_ids, vec![3]);
+/// ```
+pub fn find_route_with_virtual(start: &SpherePoint, finish: &SpherePoint, virtual_connections: &[SphereConnection],
+    vertex: &VertexBuffer) -> Option<VirtualRoute> {
+    if vertex.is_empty() {
+        return None;
+    }
+    let radius = get_radius_km(&vertex.celestial_object);
+    let edge_count = vertex.edge_count();
+    let mut extra: NodeMap<usize, Vec<GraphRelation>> = NodeMap::default();
+    for (position, connection) in virtual_connections.iter().enumerate() {
+        let from = get_closest_point(&connection.start, vertex);
+        let to = get_closest_point(&connection.finish, vertex);
+        let cost = SphereConnection::new(vertex.vector[from].coordinates.clone(), vertex.vector[to].coordinates.clone()).cost(radius);
+        for (node, other) in [(from, to), (to, from)] {
+            let relation = GraphRelation {edge_id: to_node_index(edge_count + position), ..GraphRelation::new(other, cost)};
+            extra.entry(node).or_default().push(relation);
+        }
+    }
+    let start_index = get_closest_point(start, vertex);
+    let finish_index = get_closest_point(finish, vertex);
+    let (edge_ids, explored) = search_edge_ids_with_extra(start_index, finish_index, vertex, &extra, |_, _, graph| Some(graph.cost));
+    let edge_ids = edge_ids?;
+    let mut path = Path {vertex_indices: vec![start_index], edge_ids: Vec::new(), connections: Vec::new(), costs: Vec::new()};
+    for edge_id in edge_ids {
+        let current = *path.vertex_indices.last().unwrap();
+        let relation = vertex.vector[current].graphs.iter()
+            .chain(extra.get(&current).into_iter().flatten())
+            .find(|relation| from_node_index(relation.edge_id) == edge_id)?;
+        path.connections.push(SphereConnection::new(
+            vertex.vector[current].coordinates.clone(),
+            vertex.vector[from_node_index(relation.vertex_index)].coordinates.clone(),
+        ));
+        path.costs.push(relation.cost);
+        path.vertex_indices.push(from_node_index(relation.vertex_index));
+        path.edge_ids.push(edge_id);
+    }
+    let virtual_used = path.edge_ids.iter()
+        .filter(|edge_id| **edge_id >= edge_count)
+        .map(|edge_id| edge_id - edge_count)
+        .collect();
+    Some(VirtualRoute {route: route_from_path(path, start, finish, explored, vertex), virtual_used})
+}
+
+/// Wraps Path found for given query points by search settling `explored` nodes into Route.
+pub(crate) fn route_from_path(path: Path, start: &SpherePoint, finish: &SpherePoint, explored: usize, vertex: &VertexBuffer)
+-> Route {
+    let radius = get_radius_km(&vertex.celestial_object);
+    let first = *path.vertex_indices.first().unwrap(); // Path always has the start node
+    let last = *path.vertex_indices.last().unwrap();
+    let start_connection = SphereConnection::new(start.clone(), vertex.vector[first].coordinates.clone());
+    let finish_connection = SphereConnection::new(vertex.vector[last].coordinates.clone(), finish.clone());
+    Route {
+        start: Snap {
+            query: start.clone(),
+            vertex_index: first,
+            edge_id: path.edge_ids.first().copied(),
+            distance: start_connection.cost(radius),
+            connection: start_connection,
+        },
+        finish: Snap {
+            query: finish.clone(),
+            vertex_index: last,
+            edge_id: path.edge_ids.last().copied(),
+            distance: finish_connection.cost(radius),
+            connection: finish_connection,
+        },
+        path,
+        explored,
+    }
+}
+
+#[cfg(test)]
+mod route_tests {
+    use super::*;
+    use crate::data::CelestialObject;
+
+    #[test]
+    fn test_route_reports_snapping() {
+        // given
+        let connections = vec![
+            SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+            SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 2.0)),
+        ];
+        let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        let start = SpherePoint::new(-0.5, -0.2);
+        let finish = SpherePoint::new(0.3, 2.1);
+        // when
+        let route = find_route(&start, &finish, &vertex).unwrap();
+        // then
+        let radius = get_radius_km(&CelestialObject::EARTH);
+        assert_eq!(route.path.vertex_indices, vec![0, 1, 2]);
+        assert_eq!((route.start.vertex_index, route.start.edge_id), (0, Some(0)));
+        assert_eq!((route.finish.vertex_index, route.finish.edge_id), (2, Some(1)));
+        assert_eq!(route.start.connection, SphereConnection::new(start.clone(), SpherePoint::new(0.0, 0.0)));
+        assert!(relative_eq!(route.start.distance, route.start.connection.cost(radius), max_relative = 1e-12));
+        assert_eq!(route.connections().last().unwrap().finish, finish);
+        assert!(route.snap_distance() > route.start.distance);
+        assert_eq!(route.connections_at(Granularity::Junctions, &vertex).len(), 3);
+    }
+
+    #[test]
+    fn test_route_metadata() {
+        // given: a line 0 - 1 - 2 with a long branch 1 - 3 and a separate connection 4 - 5
+        let connections = vec![
+            SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+            SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 2.0)),
+            SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(5.0, 1.0)),
+            SphereConnection::new(SpherePoint::new(9.0, 9.0), SpherePoint::new(9.0, 10.0)),
+        ];
+        let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        let finish = SpherePoint::new(0.1, 2.0);
+        // when
+        let route = find_route(&SpherePoint::new(0.0, 0.0), &finish, &vertex).unwrap();
+        let through_branch = find_route_through_edge(&SpherePoint::new(0.0, 0.0), &finish, 2, &vertex).unwrap();
+        // then
+        let radius = get_radius_km(&CelestialObject::EARTH);
+        let length: f64 = route.path.connections.iter().map(|connection| connection.cost(radius)).sum();
+        assert!(relative_eq!(route.cost(), length, max_relative = 1e-12));
+        assert_eq!(route.path.vertex_indices, vec![0, 1, 2]);
+        assert_eq!(route.points(), vec![SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 2.0), finish]);
+        assert_eq!(route.explored, 3);
+        assert_eq!(through_branch.path.vertex_indices, vec![0, 1, 3, 1, 2]);
+        assert!(through_branch.explored > route.explored);
+    }
+
+    #[test]
+    fn test_route_geojson_round_trip() {
+        // given
+        let text = concat!(
+            "{\"type\": \"FeatureCollection\", \"features\": [",
+            "{\"type\": \"Feature\", \"properties\": {\"name\": \"Main\"},",
+            " \"geometry\": {\"type\": \"LineString\", \"coordinates\": [[21.0, 52.2], [21.1, 52.2], [21.1, 52.3]]}},",
+            "{\"type\": \"Feature\", \"properties\": null,",
+            " \"geometry\": {\"type\": \"LineString\", \"coordinates\": [[21.1, 52.3], [21.2, 52.3]]}}]}",
+        );
+        let vertex = VertexBuffer::from_geojson(text).unwrap();
+        let finish = SpherePoint::new(52.3, 21.21);
+        // when
+        let route = find_route(&SpherePoint::new(52.2, 21.0), &finish, &vertex).unwrap();
+        let feature = route.to_geojson();
+        // then
+        assert_eq!(route.path.vertex_indices, vec![0, 1, 2, 3]);
+        assert_eq!(vertex.edge_metadata(1).unwrap()["name"], "Main");
+        assert!(vertex.edge_metadata(2).is_none());
+        assert!(feature.starts_with(concat!(
+            "{\"type\":\"Feature\",\"geometry\":{\"type\":\"LineString\",",
+            "\"coordinates\":[[21,52.2],[21.1,52.2],[21.1,52.3],[21.2,52.3],[21.21,52.3]]},",
+        )));
+        assert!(feature.ends_with(&format!("\"explored\":{}}}}}", route.explored)));
+        let collection = crate::io::geojson::write_routes(std::slice::from_ref(&route));
+        let reloaded = crate::io::geojson::read_vertex_buffer(&collection).unwrap();
+        assert_eq!(reloaded.edge_count(), 4);
+        assert_eq!(reloaded.edge_metadata(0).unwrap()["explored"], route.explored.to_string());
+        let swapped = VertexBuffer::from_geojson(&text.replace("[21.1, 52.3], [21.2", "[52.3, 121.1], [21.2"));
+        assert!(matches!(swapped.unwrap_err().kind(), crate::errors::ErrorKind::SwappedCoordinates(3)));
+        assert!(VertexBuffer::from_geojson(&text.replace("LineString", "Point")).is_err());
+        assert!(VertexBuffer::from_geojson(&text[..text.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_route_breakdown() {
+        // given: motorway, unclassified and motorway again
+        let connections: Vec<SphereConnection> = (0..3)
+            .map(|i| SphereConnection::new(SpherePoint::new(0.0, i as f64), SpherePoint::new(0.0, i as f64 + 1.0)))
+            .collect();
+        let mut vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        vertex.set_edge_metadata(0, "class", "motorway").unwrap();
+        vertex.set_edge_metadata(2, "class", "motorway").unwrap();
+        vertex.update_costs(&[(1, 5.0)]).unwrap();
+        let route = find_route(&SpherePoint::new(0.0, 0.0), &SpherePoint::new(0.0, 3.0), &vertex).unwrap();
+        // when
+        let breakdown = route.breakdown(&vertex, "class");
+        // then
+        let degree = SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)).cost(get_radius_km(&CelestialObject::EARTH));
+        assert_eq!(breakdown.len(), 2);
+        assert_eq!(breakdown[0].category.as_deref(), Some("motorway"));
+        assert!(relative_eq!(breakdown[0].distance, 2.0 * degree, max_relative = 1e-12));
+        assert!(relative_eq!(breakdown[0].cost, 2.0 * degree, max_relative = 1e-12));
+        assert_eq!(breakdown[1], CategoryTotal {category: None, distance: degree, cost: 5.0});
+        assert!(route.breakdown(&vertex, "toll").iter().all(|total| total.category.is_none()));
+    }
+
+    #[test]
+    fn test_route_json_layout() {
+        // given
+        let connections = vec![
+            SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+            SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 2.0)),
+        ];
+        let mut vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        vertex.update_costs(&[(0, 1.5), (1, 2.0)]).unwrap();
+        vertex.set_edge_metadata(1, "name", "\"Old\" road").unwrap();
+        let route = find_route(&SpherePoint::new(0.0, 0.0), &SpherePoint::new(0.0, 2.0), &vertex).unwrap();
+        // when
+        let json = route.to_json(&vertex);
+        // then
+        let radius = get_radius_km(&CelestialObject::EARTH);
+        let (first, second) = (route.path.connections[0].cost(radius), route.path.connections[1].cost(radius));
+        assert_eq!(json, format!(concat!(
+            "{{\"version\":1,",
+            "\"start\":{{\"query\":[0,0],\"vertex_index\":0,\"distance\":0}},",
+            "\"finish\":{{\"query\":[2,0],\"vertex_index\":2,\"distance\":0}},",
+            "\"segments\":[",
+            "{{\"from\":[0,0],\"to\":[1,0],\"edge_id\":0,\"cost\":1.5,\"distance\":{},\"metadata\":{{}}}},",
+            "{{\"from\":[1,0],\"to\":[2,0],\"edge_id\":1,\"cost\":2,\"distance\":{},\"metadata\":{{\"name\":\"\\\"Old\\\" road\"}}}}],",
+            "\"totals\":{{\"cost\":3.5,\"distance\":{},\"snap_distance\":0}}}}",
+        ), first, second, first + second));
+    }
+
+    #[test]
+    fn test_route_through_mandatory_edge() {
+        // given: direct line 0 - 1 - 2, an inspection loop 1 - 3 - 4 - 1 and a dead end 2 - 5
+        let connections = vec![
+            SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+            SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 2.0)),
+            SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(1.0, 0.9)),
+            SphereConnection::new(SpherePoint::new(1.0, 0.9), SpherePoint::new(1.0, 1.1)),
+            SphereConnection::new(SpherePoint::new(1.0, 1.1), SpherePoint::new(0.0, 1.0)),
+            SphereConnection::new(SpherePoint::new(0.0, 2.0), SpherePoint::new(0.5, 2.0)),
+            SphereConnection::new(SpherePoint::new(9.0, 9.0), SpherePoint::new(9.0, 9.5)),
+        ];
+        let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        let (start, finish) = (SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 2.0));
+        // when
+        let on_route = find_route_through_edge(&start, &finish, 1, &vertex).unwrap();
+        let loop_reversed = find_route_through_edge(&start, &finish, 3, &vertex).unwrap();
+        let dead_end = find_route_through_edge(&start, &finish, 5, &vertex).unwrap();
+        // then
+        assert_eq!(on_route.path, find_route(&start, &finish, &vertex).unwrap().path);
+        assert_eq!(loop_reversed.path.edge_ids, vec![0, 2, 3, 4, 1]);
+        assert_eq!(dead_end.path.vertex_indices, vec![0, 1, 2, 5, 2]);
+        assert!(dead_end.cost() > on_route.cost());
+        assert!(find_route_through_edge(&start, &finish, 6, &vertex).is_none());
+        assert!(find_route_through_edge(&start, &finish, 42, &vertex).is_none());
+    }
+
+    #[test]
+    fn test_route_with_virtual_connections() {
+        // given: a river crossed only by a bridge far north, and two proposed bridges
+        let connections = vec![
+            SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(2.0, 0.0)),
+            SphereConnection::new(SpherePoint::new(2.0, 0.0), SpherePoint::new(2.0, 1.0)),
+            SphereConnection::new(SpherePoint::new(2.0, 1.0), SpherePoint::new(0.0, 1.0)),
+            SphereConnection::new(SpherePoint::new(5.0, 5.0), SpherePoint::new(5.0, 6.0)),
+        ];
+        let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        let useless = SphereConnection::new(SpherePoint::new(5.0, 5.0), SpherePoint::new(2.0, 1.0));
+        let bridge = SphereConnection::new(SpherePoint::new(0.01, 0.0), SpherePoint::new(0.0, 1.01));
+        let proposals = vec![useless, bridge];
+        let (start, finish) = (SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0));
+        // when
+        let without = find_route_with_virtual(&start, &finish, &proposals[..1], &vertex).unwrap();
+        let with = find_route_with_virtual(&start, &finish, &proposals, &vertex).unwrap();
+        // then
+        assert!(without.virtual_used.is_empty());
+        assert_eq!(without.route, find_route(&start, &finish, &vertex).unwrap());
+        assert_eq!(with.virtual_used, vec![1]);
+        assert_eq!(with.route.path.vertex_indices, vec![0, 3]);
+        assert_eq!(with.route.path.edge_ids, vec![5]);
+        assert!(with.route.cost() < without.route.cost());
+        assert_eq!(vertex.edge_count(), 4);
+        assert!(find_route_with_virtual(&start, &SpherePoint::new(5.0, 6.0), &proposals[1..], &vertex).is_none());
+    }
+
+    #[test]
+    fn test_route_between_points_snapped_to_same_node() {
+        // given
+        let connections = vec![SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0))];
+        let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        let start = SpherePoint::new(0.1, 0.9);
+        let finish = SpherePoint::new(-0.1, 1.1);
+        // when
+        let route = find_route(&start, &finish, &vertex).unwrap();
+        let same_point = find_route(&start, &start, &vertex).unwrap();
+        // then
+        assert!(route.path.is_empty());
+        assert_eq!(route.path.vertex_indices, vec![1]);
+        assert_eq!(route.cost(), 0.0);
+        assert_eq!(route.connections().len(), 2);
+        assert_eq!((route.start.edge_id, route.finish.edge_id), (None, None));
+        assert!(route.snap_distance() > 0.0);
+        assert_eq!(same_point.start.distance, same_point.finish.distance);
+    }
+}
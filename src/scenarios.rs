@@ -0,0 +1,215 @@
+use crate::errors::*;
+use crate::data::{get_radius_km, CelestialObject};
+use crate::components::{SphereConnection, SpherePoint};
+use crate::vertex::VertexBuffer;
+use crate::vertex::generators::k_nearest;
+use crate::rng::SplitMix64;
+
+/// Major hub airports as (latitude, longitude), every other airport of `air_network` belongs to one of them.
+const HUBS: &[(f64, f64)] = &[
+    (40.64, -73.78), // New York
+    (33.94, -118.41), // Los Angeles
+    (41.98, -87.90), // Chicago
+    (33.64, -84.43), // Atlanta
+    (19.44, -99.07), // Mexico City
+    (-23.43, -46.47), // Sao Paulo
+    (-34.82, -58.54), // Buenos Aires
+    (4.70, -74.15), // Bogota
+    (51.47, -0.45), // London
+    (49.01, 2.55), // Paris
+    (50.04, 8.56), // Frankfurt
+    (52.17, 20.97), // Warsaw
+    (41.26, 28.74), // Istanbul
+    (55.97, 37.41), // Moscow
+    (30.12, 31.41), // Cairo
+    (6.58, 3.32), // Lagos
+    (-1.32, 36.93), // Nairobi
+    (-26.14, 28.25), // Johannesburg
+    (25.25, 55.36), // Dubai
+    (28.57, 77.10), // Delhi
+    (1.36, 103.99), // Singapore
+    (22.31, 113.91), // Hong Kong
+    (40.08, 116.58), // Beijing
+    (35.55, 139.78), // Tokyo
+    (-33.95, 151.18), // Sydney
+    (-37.01, 174.79), // Auckland
+];
+
+/// Longest distance in degrees of a regional airport of `air_network` from its hub, along each axis.
+const REGION_SPREAD: f64 = 8.0;
+
+/// Returns the diagonal grid of dijkstra tests on given celestial object: two arms from (0, 0) along
+/// the equator and along the prime meridian to `size` degrees, both closed at (`size`, `size`), and
+/// a diagonal from (0, 0) to (`size`, `size`) with a node at every whole degree, so the shortest path
+/// between the corners is the diagonal. Fails with `InvalidParameter` for zero size and above 45.
+///
+/// ```
+/// use path_navigator::components::SpherePoint;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::dijkstra::find_path;
+/// use path_navigator::scenarios::diagonal_grid;
+///
+/// let vertex = diagonal_grid(10, CelestialObject::MERCURY).unwrap();
+/// let path = find_path(&SpherePoint::new(0.0, 0.0), &SpherePoint::new(10.0, 10.0), &vertex).unwrap();
+/// assert_eq!(path.len(), 10);
+/// ```
+pub fn diagonal_grid(size: usize, celestial_object: CelestialObject) -> Result<VertexBuffer> {
+    if size == 0 || size > 45 {
+        return Err(Error::from_kind(ErrorKind::InvalidParameter));
+    }
+    let corner = SpherePoint::new(size as f64, size as f64);
+    let chain = |point: &dyn Fn(usize) -> SpherePoint, closing: Option<&SpherePoint>| {
+        let mut chain: Vec<SphereConnection> = (1..=size).map(|i| SphereConnection::new(point(i - 1), point(i))).collect();
+        if let Some(closing) = closing {
+            chain.push(SphereConnection::new(point(size), closing.clone()));
+        }
+        chain
+    };
+    let mut connections = chain(&|i| SpherePoint::new(i as f64, 0.0), Some(&corner));
+    connections.extend(chain(&|i| SpherePoint::new(0.0, i as f64), Some(&corner)));
+    connections.extend(chain(&|i| SpherePoint::new(i as f64, i as f64), None));
+    VertexBuffer::new(connections, celestial_object)
+}
+
+/// Returns spiderweb street network of a city on Earth, radial avenues from the center crossed by
+/// ring roads.
+///
+/// # Arguments:
+/// * `center` which is &SpherePoint - city center, where all avenues meet
+/// * `rings` which is usize - number of ring roads, at least 1
+/// * `spokes` which is usize - number of avenues, evenly spread around the center, at least 3
+/// * `ring_spacing_km` which is f64 - distance between consecutive rings in kilometers
+///
+/// # Remarks:
+///
+/// Ring roads are chains of straight segments between neighbouring avenues. Nodes are placed on
+/// a local flat projection around the center, so the web is regular for city sized networks and
+/// gets distorted over hundreds of kilometers. Costs are great-circle kilometers. Node 0 is the
+/// center, followed by the nodes of every avenue from the center outwards.
+///
+/// Fails with `InvalidParameter` for too few rings or spokes, not positive spacing and center
+/// closer to a pole than the outer ring.
+///
+/// ```
+/// use path_navigator::components::SpherePoint;
+/// use path_navigator::scenarios::spiderweb_city;
+///
+/// let city = spiderweb_city(&SpherePoint::new(52.23, 21.01), 5, 8, 1.0).unwrap();
+/// assert_eq!(city.len(), 41);
+/// assert_eq!(city.edge_count(), 80);
+/// ```
+pub fn spiderweb_city(center: &SpherePoint, rings: usize, spokes: usize, ring_spacing_km: f64) -> Result<VertexBuffer> {
+    if rings == 0 || spokes < 3 || !ring_spacing_km.is_finite() || ring_spacing_km <= 0.0 {
+        return Err(Error::from_kind(ErrorKind::InvalidParameter));
+    }
+    let radius = get_radius_km(&CelestialObject::EARTH);
+    let outer_degrees = (rings as f64 * ring_spacing_km / radius).to_degrees();
+    if center.lat.abs() + outer_degrees >= 90.0 {
+        return Err(Error::from_kind(ErrorKind::InvalidParameter));
+    }
+    let node = |ring: usize, spoke: usize| {
+        if ring == 0 {
+            return center.clone();
+        }
+        let bearing = (spoke as f64 * 360.0 / spokes as f64).to_radians();
+        let distance = (ring as f64 * ring_spacing_km / radius).to_degrees();
+        SpherePoint::new(
+            center.lat + distance * bearing.cos(),
+            center.lng + distance * bearing.sin() / center.lat.to_radians().cos(),
+        )
+    };
+    let mut connections: Vec<SphereConnection> = Vec::new();
+    for spoke in 0..spokes {
+        for ring in 1..=rings {
+            connections.push(SphereConnection::new(node(ring - 1, spoke), node(ring, spoke)));
+        }
+    }
+    for ring in 1..=rings {
+        for spoke in 0..spokes {
+            connections.push(SphereConnection::new(node(ring, spoke), node(ring, (spoke + 1) % spokes)));
+        }
+    }
+    VertexBuffer::new(connections, CelestialObject::EARTH)
+}
+
+/// Returns reproducible intercontinental air network of `airports` airports on Earth.
+///
+/// # Remarks:
+///
+/// The network has 26 major hubs on all inhabited continents, each linked to its 3 nearest hubs,
+/// and regional airports placed randomly around the hubs. Every regional airport flies to its hub
+/// and to its 2 nearest airports, so regions are meshed too. Costs are great-circle kilometers.
+/// Hubs are the first points, so `airports` equal 26 gives the hub network alone. The same seed
+/// always produces the same network.
+///
+/// Fails with `InvalidParameter` for less airports than hubs.
+///
+/// ```
+/// use path_navigator::components::SpherePoint;
+/// use path_navigator::dijkstra::find_path;
+/// use path_navigator::scenarios::air_network;
+///
+/// let network = air_network(500, 7).unwrap();
+/// let (new_york, sydney) = (SpherePoint::new(40.64, -73.78), SpherePoint::new(-33.95, 151.18));
+/// assert!(network.len() <= 500);
+/// assert!(find_path(&new_york, &sydney, &network).is_some());
+/// ```
+pub fn air_network(airports: usize, seed: u64) -> Result<VertexBuffer> {
+    if airports < HUBS.len() {
+        return Err(Error::from_kind(ErrorKind::InvalidParameter));
+    }
+    let hubs: Vec<SpherePoint> = HUBS.iter().map(|(lat, lng)| SpherePoint::new(*lat, *lng)).collect();
+    let mut rng = SplitMix64::new(seed);
+    let mut points = hubs.clone();
+    let mut connections: Vec<SphereConnection> = Vec::new();
+    for _ in HUBS.len()..airports {
+        let hub = &hubs[rng.below(hubs.len())];
+        let lat = (hub.lat + REGION_SPREAD * (2.0 * rng.next_f64() - 1.0)).clamp(-85.0, 85.0);
+        let lng = hub.lng + REGION_SPREAD * (2.0 * rng.next_f64() - 1.0) / hub.lat.to_radians().cos();
+        let lng = if lng > 180.0 { lng - 360.0 } else if lng < -180.0 { lng + 360.0 } else { lng };
+        let airport = SpherePoint::new(lat, lng);
+        connections.push(SphereConnection::new(airport.clone(), hub.clone()));
+        points.push(airport);
+    }
+    connections.extend(k_nearest(&hubs, 3, CelestialObject::EARTH)?.to_connections());
+    if points.len() > hubs.len() {
+        connections.extend(k_nearest(&points, 2, CelestialObject::EARTH)?.to_connections());
+    }
+    VertexBuffer::new(connections, CelestialObject::EARTH)
+}
+
+#[cfg(test)]
+mod scenarios_tests {
+    use super::*;
+    use crate::components::SphereBoundingBox;
+    use crate::dijkstra::{find_path, is_reachable};
+
+    #[test]
+    fn test_scenario_networks() {
+        // given
+        let center = SpherePoint::new(48.85, 2.35);
+        let city = spiderweb_city(&center, 4, 6, 2.0).unwrap();
+        let grid = diagonal_grid(10, CelestialObject::MERCURY).unwrap();
+        let network = air_network(500, 7).unwrap();
+        let radius = get_radius_km(&CelestialObject::EARTH);
+        // when: crossing the city between opposite ends of an avenue
+        let crossing = find_path(&city.vector[4].coordinates, &city.vector[16].coordinates, &city).unwrap();
+        // then
+        assert_eq!((city.len(), city.edge_count()), (25, 48));
+        assert!(relative_eq!(crossing.cost(), 16.0, max_relative = 1e-3));
+        assert!(crossing.vertex_indices.contains(&0));
+        let outer = SphereConnection::new(center.clone(), city.vector[4].coordinates.clone()).cost(radius);
+        assert!(relative_eq!(outer, 8.0, max_relative = 1e-3));
+        assert_eq!(find_path(&SpherePoint::new(0.0, 0.0), &SpherePoint::new(10.0, 10.0), &grid).unwrap().len(), 10);
+        assert_eq!(network.to_connections(), air_network(500, 7).unwrap().to_connections());
+        assert!(network.len() > 480);
+        let europe = SphereBoundingBox::new(35.0, -10.0, 70.0, 40.0);
+        assert!(network.vector.iter().filter(|node| europe.contains(&node.coordinates)).count() > 50);
+        let (auckland, chicago) = (SpherePoint::new(-37.01, 174.79), SpherePoint::new(41.98, -87.90));
+        assert!(is_reachable(&auckland, &chicago, &network, f64::INFINITY).unwrap().is_some());
+        assert!(air_network(10, 7).is_err());
+        assert!(spiderweb_city(&center, 0, 6, 2.0).is_err());
+        assert!(spiderweb_city(&SpherePoint::new(89.0, 0.0), 4, 6, 200.0).is_err());
+        assert!(diagonal_grid(0, CelestialObject::EARTH).is_err());
+    }
+}
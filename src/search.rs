@@ -0,0 +1,416 @@
+use crate::vertex::{EdgeId, GraphRelation, VertexBuffer};
+use crate::components::{SphereConnection, SpherePoint};
+use crate::data::get_radius_km;
+use crate::dijkstra::{snap_query, FrontierEntry};
+use crate::path::Path;
+use crate::hashing::{from_node_index, to_node_index};
+use std::collections::BinaryHeap;
+
+/// Side of the search a node is expanded from, `Backward` only for searches growing from finish,
+/// like BidirectionalSearch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Forward,
+    Backward,
+}
+
+/// Strategy of a point-to-point search run by `find_path_with` over VertexBuffer, which is not
+/// changed during the search.
+///
+/// # Remarks:
+///
+/// `find_path_with` calls `init` once, then until `finished` asks `pick_next` for the next node
+/// to expand and calls `relax` with each of its relations. Nodes expanded from `Side::Forward` get
+/// their outgoing relations, from `Side::Backward` the incoming ones, turned around: `vertex_index`
+/// of the relation is then the node the connection leaves. Search ends when `pick_next` gives None
+/// and `edge_ids` returns the path found, in travel order from start.
+///
+/// DijkstraSearch, AStarSearch and BidirectionalSearch implement it, custom strategies can wrap them:
+///
+/// ```
+/// use path_navigator::components::*;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::search::*;
+/// use path_navigator::vertex::{EdgeId, GraphRelation, VertexBuffer};
+///
+/// // Dijkstra which never enters node 1
+/// struct Avoiding(DijkstraSearch);
+///
+/// impl GraphSearch for Avoiding {
+///     fn init(&mut self, start_index: usize, finish_index: usize, vertex: &VertexBuffer) {
+///         self.0.init(start_index, finish_index, vertex)
+///     }
+///     fn pick_next(&mut self) -> Option<(usize, Side)> {
+///         self.0.pick_next()
+///     }
+///     fn relax(&mut self, from: usize, relation: &GraphRelation, side: Side, vertex: &VertexBuffer) {
+///         if relation.vertex_index != 1 {
+///             self.0.relax(from, relation, side, vertex)
+///         }
+///     }
+///     fn finished(&self) -> bool {
+///         self.0.finished()
+///     }
+///     fn edge_ids(&self) -> Option<Vec<EdgeId>> {
+///         self.0.edge_ids()
+///     }
+/// }
+///
+/// let connections = vec![
+///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+///     SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 2.0)),
+///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(1.0, 1.0)),
+///     SphereConnection::new(SpherePoint::new(1.0, 1.0), SpherePoint::new(0.0, 2.0)),
+/// ];
+/// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+/// let (start, finish) = (SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 2.0));
+/// let direct = find_path_with(&mut DijkstraSearch::new(), &start, &finish, &vertex).unwrap();
+/// let detour = find_path_with(&mut Avoiding(DijkstraSearch::new()), &start, &finish, &vertex).unwrap();
+/// assert_eq!(direct.vertex_indices, vec![0, 1, 2]);
+/// assert_eq!(detour.vertex_indices, vec![0, 3, 2]);
+/// ```
+pub trait GraphSearch {
+    /// Prepares search from `start_index` to `finish_index`, forgetting any earlier search.
+    fn init(&mut self, start_index: usize, finish_index: usize, vertex: &VertexBuffer);
+    /// Returns next node to expand with the side it is expanded from, None when there is none left.
+    fn pick_next(&mut self) -> Option<(usize, Side)>;
+    /// Offers node of `relation`, reached from the expanded node `from`.
+    fn relax(&mut self, from: usize, relation: &GraphRelation, side: Side, vertex: &VertexBuffer);
+    /// Returns true when the search can stop.
+    fn finished(&self) -> bool;
+    /// Returns EdgeIds of the path found in travel order, None when finish was not reached.
+    fn edge_ids(&self) -> Option<Vec<EdgeId>>;
+}
+
+/// Returns Path between two given points found by given GraphSearch, None when finish is not
+/// reachable or both points snap to the same node, like `dijkstra::find_path`.
+pub fn find_path_with(search: &mut dyn GraphSearch, start: &SpherePoint, finish: &SpherePoint, vertex: &VertexBuffer)
+-> Option<Path> {
+    let (start_index, finish_index) = snap_query(start, finish, vertex)?;
+    search.init(start_index, finish_index, vertex);
+    // incoming relations are collected only for searches expanding backward
+    let mut incoming: Option<Vec<Vec<GraphRelation>>> = None;
+    while !search.finished() {
+        let (node, side) = match search.pick_next() {
+            Some(next) => next,
+            None => break,
+        };
+        let relations = match side {
+            Side::Forward => &vertex.vector[node].graphs,
+            Side::Backward => &incoming.get_or_insert_with(|| incoming_relations(vertex))[node],
+        };
+        for relation in relations {
+            search.relax(node, relation, side, vertex);
+        }
+    }
+    Path::from_edge_ids(start_index, search.edge_ids()?, vertex)
+}
+
+fn incoming_relations(vertex: &VertexBuffer) -> Vec<Vec<GraphRelation>> {
+    let mut incoming: Vec<Vec<GraphRelation>> = vec![Vec::new(); vertex.len()];
+    for (from, node) in vertex.vector.iter().enumerate() {
+        for graph in &node.graphs {
+            incoming[from_node_index(graph.vertex_index)].push(GraphRelation {vertex_index: to_node_index(from), ..graph.clone()});
+        }
+    }
+    incoming
+}
+
+/// Labels of one search direction indexed by node, frontier is ordered by priority which is
+/// the cost itself for Dijkstra and the cost with estimate for A*.
+#[derive(Debug, Clone, Default)]
+struct Labels {
+    costs: Vec<f64>,
+    parents: Vec<Option<(usize, EdgeId)>>,
+    settled: Vec<bool>,
+    frontier: BinaryHeap<FrontierEntry>,
+    settled_count: usize,
+}
+
+impl Labels {
+    fn reset(&mut self, nodes: usize, source: usize, priority: f64) {
+        self.costs = vec![f64::INFINITY; nodes];
+        self.parents = vec![None; nodes];
+        self.settled = vec![false; nodes];
+        self.frontier.clear();
+        self.settled_count = 0;
+        self.costs[source] = 0.0;
+        self.frontier.push(FrontierEntry::new(source, priority));
+    }
+
+    /// Settles and returns the node with the lowest priority, skipping stale entries.
+    fn pop(&mut self) -> Option<usize> {
+        while let Some(entry) = self.frontier.pop() {
+            if !self.settled[entry.vertex_index] {
+                self.settled[entry.vertex_index] = true;
+                self.settled_count += 1;
+                return Some(entry.vertex_index);
+            }
+        }
+        None
+    }
+
+    /// Returns the lowest priority in frontier, a lower bound even with stale entries, infinity when empty.
+    fn peek(&self) -> f64 {
+        self.frontier.peek().map_or(f64::INFINITY, |entry| entry.cost)
+    }
+
+    /// Labels node with cost when it is lower than its current one, returns whether it was.
+    fn offer(&mut self, node: usize, cost: f64, parent: (usize, EdgeId), priority: f64) -> bool {
+        if self.settled[node] || self.costs[node] <= cost {
+            return false;
+        }
+        self.costs[node] = cost;
+        self.parents[node] = Some(parent);
+        self.frontier.push(FrontierEntry::new(node, priority));
+        true
+    }
+
+    /// Returns EdgeIds from node to the source, in order of walking parents.
+    fn walk(&self, node: usize) -> Vec<EdgeId> {
+        let mut edge_ids: Vec<EdgeId> = Vec::new();
+        let mut current = node;
+        while let Some((parent, edge_id)) = self.parents[current] {
+            edge_ids.push(edge_id);
+            current = parent;
+        }
+        edge_ids
+    }
+}
+
+/// Plain Dijkstra as GraphSearch, settles nodes in order of cost from start until finish is settled.
+#[derive(Debug, Clone, Default)]
+pub struct DijkstraSearch {
+    labels: Labels,
+    finish_index: usize,
+}
+
+impl DijkstraSearch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns number of nodes settled by the last search.
+    pub fn settled_count(&self) -> usize {
+        self.labels.settled_count
+    }
+}
+
+impl GraphSearch for DijkstraSearch {
+    fn init(&mut self, start_index: usize, finish_index: usize, vertex: &VertexBuffer) {
+        self.labels.reset(vertex.len(), start_index, 0.0);
+        self.finish_index = finish_index;
+    }
+
+    fn pick_next(&mut self) -> Option<(usize, Side)> {
+        self.labels.pop().map(|node| (node, Side::Forward))
+    }
+
+    fn relax(&mut self, from: usize, relation: &GraphRelation, _: Side, _: &VertexBuffer) {
+        let cost = self.labels.costs[from] + relation.cost;
+        self.labels.offer(from_node_index(relation.vertex_index), cost, (from, from_node_index(relation.edge_id)), cost);
+    }
+
+    fn finished(&self) -> bool {
+        self.labels.settled[self.finish_index]
+    }
+
+    fn edge_ids(&self) -> Option<Vec<EdgeId>> {
+        if !self.finished() {
+            return None;
+        }
+        let mut edge_ids = self.labels.walk(self.finish_index);
+        edge_ids.reverse();
+        Some(edge_ids)
+    }
+}
+
+/// A* as GraphSearch, frontier is ordered by cost from start plus great-circle distance to finish
+/// in kilometers, see `astar::find_shortest_path_astar` for when it gives the cheapest path.
+#[derive(Debug, Clone, Default)]
+pub struct AStarSearch {
+    search: DijkstraSearch,
+    target: Option<SpherePoint>,
+    radius: f64,
+}
+
+impl AStarSearch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns number of nodes settled by the last search.
+    pub fn settled_count(&self) -> usize {
+        self.search.settled_count()
+    }
+
+    fn estimate(&self, node: usize, vertex: &VertexBuffer) -> f64 {
+        self.target.as_ref()
+            .map_or(0.0, |target| SphereConnection::new(vertex.vector[node].coordinates.clone(), target.clone()).cost(self.radius))
+    }
+}
+
+impl GraphSearch for AStarSearch {
+    fn init(&mut self, start_index: usize, finish_index: usize, vertex: &VertexBuffer) {
+        self.target = Some(vertex.vector[finish_index].coordinates.clone());
+        self.radius = get_radius_km(&vertex.celestial_object);
+        self.search.labels.reset(vertex.len(), start_index, self.estimate(start_index, vertex));
+        self.search.finish_index = finish_index;
+    }
+
+    fn pick_next(&mut self) -> Option<(usize, Side)> {
+        self.search.pick_next()
+    }
+
+    fn relax(&mut self, from: usize, relation: &GraphRelation, _: Side, vertex: &VertexBuffer) {
+        let cost = self.search.labels.costs[from] + relation.cost;
+        let priority = cost + self.estimate(from_node_index(relation.vertex_index), vertex);
+        self.search.labels.offer(from_node_index(relation.vertex_index), cost, (from, from_node_index(relation.edge_id)), priority);
+    }
+
+    fn finished(&self) -> bool {
+        self.search.finished()
+    }
+
+    fn edge_ids(&self) -> Option<Vec<EdgeId>> {
+        self.search.edge_ids()
+    }
+}
+
+/// Bidirectional Dijkstra as GraphSearch, grows one search from start over outgoing connections and
+/// one from finish over incoming ones, always expanding the side with the cheaper frontier, so on
+/// road-style graphs both together settle about half of the nodes plain Dijkstra does.
+///
+/// # Remarks:
+///
+/// Every relaxed node labelled by both sides gives a candidate path through it, search stops when
+/// the cheapest frontier costs of both sides together reach the cheapest candidate, which can not
+/// be improved by any node left.
+#[derive(Debug, Clone, Default)]
+pub struct BidirectionalSearch {
+    forward: Labels,
+    backward: Labels,
+    meeting: Option<(f64, usize)>,
+}
+
+impl BidirectionalSearch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns number of nodes settled by the last search, on both sides.
+    pub fn settled_count(&self) -> usize {
+        self.forward.settled_count + self.backward.settled_count
+    }
+}
+
+impl GraphSearch for BidirectionalSearch {
+    fn init(&mut self, start_index: usize, finish_index: usize, vertex: &VertexBuffer) {
+        self.forward.reset(vertex.len(), start_index, 0.0);
+        self.backward.reset(vertex.len(), finish_index, 0.0);
+        self.meeting = None;
+    }
+
+    fn pick_next(&mut self) -> Option<(usize, Side)> {
+        let forward_first = self.forward.peek() <= self.backward.peek();
+        let (first, second) = if forward_first { (Side::Forward, Side::Backward) } else { (Side::Backward, Side::Forward) };
+        let pop = |search: &mut Self, side: Side| match side {
+            Side::Forward => search.forward.pop(),
+            Side::Backward => search.backward.pop(),
+        };
+        pop(self, first).map(|node| (node, first)).or_else(|| pop(self, second).map(|node| (node, second)))
+    }
+
+    fn relax(&mut self, from: usize, relation: &GraphRelation, side: Side, _: &VertexBuffer) {
+        let (labels, other) = match side {
+            Side::Forward => (&mut self.forward, &self.backward),
+            Side::Backward => (&mut self.backward, &self.forward),
+        };
+        let node = from_node_index(relation.vertex_index);
+        let cost = labels.costs[from] + relation.cost;
+        if labels.offer(node, cost, (from, from_node_index(relation.edge_id)), cost) {
+            // nodes not labelled by the other side cost infinity through
+            let through = cost + other.costs[node];
+            if through < self.meeting.map_or(f64::INFINITY, |(best, _)| best) {
+                self.meeting = Some((through, node));
+            }
+        }
+    }
+
+    fn finished(&self) -> bool {
+        let best = self.meeting.map_or(f64::INFINITY, |(best, _)| best);
+        self.forward.peek() + self.backward.peek() >= best
+    }
+
+    fn edge_ids(&self) -> Option<Vec<EdgeId>> {
+        let (_, node) = self.meeting?;
+        let mut edge_ids = self.forward.walk(node);
+        edge_ids.reverse();
+        // parents of the backward side lead toward finish, so they are already in travel order
+        edge_ids.extend(self.backward.walk(node));
+        Some(edge_ids)
+    }
+}
+
+#[cfg(test)]
+mod search_tests {
+    use super::*;
+    use crate::components::SphereBoundingBox;
+    use crate::data::CelestialObject;
+    use crate::dijkstra::find_path;
+
+    #[test]
+    fn test_strategies_match_dijkstra() {
+        // given: random network with an island far away
+        let bbox = SphereBoundingBox::new(40.0, 10.0, 44.0, 14.0);
+        let mut connections = crate::vertex::generators::random(1000, 3.0, &bbox, 9).unwrap().to_connections();
+        connections.push(SphereConnection::new(SpherePoint::new(-30.0, 100.0), SpherePoint::new(-30.0, 101.0)));
+        let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        let (mut dijkstra, mut astar, mut bidirectional) = (DijkstraSearch::new(), AStarSearch::new(), BidirectionalSearch::new());
+        let mut rng = crate::rng::SplitMix64::new(4);
+        let (mut dijkstra_settled, mut astar_settled, mut bidirectional_settled) = (0, 0, 0);
+        for query in 0..20 {
+            let start = vertex.vector[rng.below(vertex.len() - 2)].coordinates.clone();
+            let finish = if query == 0 { SpherePoint::new(-30.0, 100.0) } else { vertex.vector[rng.below(vertex.len() - 2)].coordinates.clone() };
+            // when
+            let expected = find_path(&start, &finish, &vertex);
+            let paths = [
+                find_path_with(&mut dijkstra, &start, &finish, &vertex),
+                find_path_with(&mut astar, &start, &finish, &vertex),
+                find_path_with(&mut bidirectional, &start, &finish, &vertex),
+            ];
+            dijkstra_settled += dijkstra.settled_count();
+            astar_settled += astar.settled_count();
+            bidirectional_settled += bidirectional.settled_count();
+            // then
+            for path in paths.iter() {
+                assert_eq!(path.is_some(), expected.is_some());
+                if let (Some(path), Some(expected)) = (path, &expected) {
+                    assert!(relative_eq!(path.cost(), expected.cost(), max_relative = 1e-9));
+                    assert_eq!(path.vertex_indices.first(), expected.vertex_indices.first());
+                    assert_eq!(path.vertex_indices.last(), expected.vertex_indices.last());
+                }
+            }
+        }
+        assert!(astar_settled < dijkstra_settled);
+        assert!(bidirectional_settled < dijkstra_settled);
+    }
+
+    #[test]
+    fn test_bidirectional_search_on_one_way_ring() {
+        // given: one-way ring 0 -> 1 -> 2 -> 3 -> 0
+        let point = |lng: f64| SpherePoint::new(0.0, lng);
+        let connections = vec![
+            SphereConnection::new(point(0.0), point(1.0)),
+            SphereConnection::new(point(1.0), point(2.0)),
+            SphereConnection::new(point(2.0), point(3.0)),
+            SphereConnection::new(point(3.0), point(0.0)),
+        ];
+        let vertex = VertexBuffer::new_directed(connections, CelestialObject::EARTH).unwrap();
+        // when
+        let path = find_path_with(&mut BidirectionalSearch::new(), &point(1.0), &point(0.0), &vertex).unwrap();
+        // then
+        assert_eq!(path.vertex_indices, vec![1, 2, 3, 0]);
+        assert_eq!(path.edge_ids, vec![1, 2, 3]);
+    }
+}
@@ -0,0 +1,259 @@
+use crate::vertex::VertexBuffer;
+use crate::components::*;
+use crate::data::get_radius_km;
+use crate::errors::*;
+use crate::io::binary::{read_f64, read_header, read_usize, write_f64, write_header, write_u64};
+use std::io::{Read, Write};
+
+const MAGIC: &[u8; 4] = b"PNSI";
+const FORMAT_VERSION: u32 = 1;
+
+/// # SpatialIndex
+/// Nearest node index over VertexBuffer, kd-tree of node unit vectors in Earth-centered Cartesian
+/// coordinates. Chord length between unit vectors grows with great-circle distance, so the nearest
+/// node in 3D is the nearest node on the sphere, without special cases at poles or the antimeridian.
+///
+/// Index is built once in O(n log n) and answers every query in O(log n) on average, so it should
+/// be shared by all queries against the same VertexBuffer. It does not follow later changes of nodes.
+///
+/// ```
+/// use path_navigator::components::*;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::spatial::SpatialIndex;
+/// use path_navigator::vertex::VertexBuffer;
+///
+/// let connections = vec![
+///     SphereConnection::new(SpherePoint::new(0.0, 179.0), SpherePoint::new(0.0, -179.0)),
+///     SphereConnection::new(SpherePoint::new(0.0, -179.0), SpherePoint::new(0.0, -170.0)),
+/// ];
+/// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+/// let index = SpatialIndex::new(&vertex);
+/// assert_eq!(index.nearest(&SpherePoint::new(0.5, -179.9)), Some(1));
+/// ```
+#[derive(Debug, Clone)]
+pub struct SpatialIndex {
+    // nodes arranged as implicit kd-tree, median of every range is its root
+    nodes: Vec<(Vector3, usize)>,
+    radius: f64,
+}
+
+/// Query point assigned to its nearest node, `distance` is great-circle distance in kilometers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NodeAssignment {
+    pub vertex_index: usize,
+    pub distance: f64,
+}
+
+impl SpatialIndex {
+    pub fn new(vertex: &VertexBuffer) -> Self {
+        let points: Vec<SpherePoint> = vertex.vector.iter().map(|node| node.coordinates.clone()).collect();
+        Self::from_points(&points, get_radius_km(&vertex.celestial_object))
+    }
+
+    /// Builds index over arbitrary points on sphere of given radius in kilometers, not tied to any
+    /// VertexBuffer. Indices returned by queries are then positions in `points`.
+    pub fn from_points(points: &[SpherePoint], radius: f64) -> Self {
+        let mut nodes: Vec<(Vector3, usize)> = points.iter()
+            .enumerate()
+            .map(|(index, point)| (point.to_vector(), index))
+            .collect();
+        arrange(&mut nodes, 0);
+        Self {nodes, radius}
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Returns index of the node closest to point, the lowest index on ties, None for empty index.
+    pub fn nearest(&self, point: &SpherePoint) -> Option<usize> {
+        self.nearest_with_chord(&point.to_vector()).map(|(_, index)| index)
+    }
+
+    /// Assigns every point to its nearest node, result is in order of points.
+    /// Assigning to empty index gives empty Vec.
+    pub fn assign(&self, points: &[SpherePoint]) -> Vec<NodeAssignment> {
+        points.iter()
+            .filter_map(|point| {
+                let target = point.to_vector();
+                let (squared_chord, vertex_index) = self.nearest_with_chord(&target)?;
+                let central_angle = 2.0 * (squared_chord.sqrt() / 2.0).min(1.0).asin();
+                Some(NodeAssignment {vertex_index, distance: central_angle * self.radius})
+            })
+            .collect()
+    }
+
+    /// Writes index in compact little-endian binary format, with its kd-tree arrangement,
+    /// so loading does not sort nodes again.
+    pub fn save<W: Write>(&self, writer: &mut W) -> Result<()> {
+        write_header(writer, MAGIC, FORMAT_VERSION)?;
+        write_f64(writer, self.radius)?;
+        write_u64(writer, self.nodes.len() as u64)?;
+        for (vector, index) in &self.nodes {
+            for coordinate in vector {
+                write_f64(writer, *coordinate)?;
+            }
+            write_u64(writer, *index as u64)?;
+        }
+        Ok(())
+    }
+
+    /// Reads index written by `save`, fails with `DataItemIncorrect` for data in other format.
+    pub fn load<R: Read>(reader: &mut R) -> Result<Self> {
+        read_header(reader, MAGIC, FORMAT_VERSION)?;
+        let radius = read_f64(reader)?;
+        let node_count = read_usize(reader)?;
+        let mut nodes: Vec<(Vector3, usize)> = Vec::new();
+        for _ in 0..node_count {
+            let vector = [read_f64(reader)?, read_f64(reader)?, read_f64(reader)?];
+            let index = read_usize(reader)?;
+            if index >= node_count || vector.iter().any(|coordinate| !coordinate.is_finite()) {
+                return Err(Error::from_kind(ErrorKind::DataItemIncorrect));
+            }
+            nodes.push((vector, index));
+        }
+        if !radius.is_finite() || radius <= 0.0 {
+            return Err(Error::from_kind(ErrorKind::DataItemIncorrect));
+        }
+        Ok(Self {nodes, radius})
+    }
+
+    /// Returns radius of the celestial object index distances are computed for, in kilometers.
+    pub fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    fn nearest_with_chord(&self, target: &Vector3) -> Option<(f64, usize)> {
+        let mut best: Option<(f64, usize)> = None;
+        search(&self.nodes, 0, target, &mut best);
+        best
+    }
+}
+
+/// Assigns batch of points to their nearest nodes of VertexBuffer, see `SpatialIndex::assign`.
+pub fn assign_to_nodes(points: &[SpherePoint], vertex: &VertexBuffer) -> Vec<NodeAssignment> {
+    SpatialIndex::new(vertex).assign(points)
+}
+
+/// Joins two point sets by great-circle distance: assigns every point of `points` to its nearest
+/// point of `candidates`, with `vertex_index` being position in `candidates`. Pure geometry, no graph
+/// is involved. Result is in order of `points`, empty when there are no candidates.
+///
+/// # Remarks:
+///
+/// Candidates are indexed once with `SpatialIndex::from_points`, so the join takes O((n + m) log m)
+/// instead of comparing every pair. Ties go to the lowest candidate position.
+///
+/// ```
+/// use path_navigator::components::SpherePoint;
+/// use path_navigator::spatial::nearest_neighbors;
+///
+/// let stops = vec![SpherePoint::new(0.0, 0.0), SpherePoint::new(10.0, 10.0)];
+/// let stations = vec![SpherePoint::new(9.0, 9.0), SpherePoint::new(0.5, 0.0)];
+/// let joined = nearest_neighbors(&stops, &stations, 6371.0);
+/// assert_eq!(joined.iter().map(|neighbor| neighbor.vertex_index).collect::<Vec<usize>>(), vec![1, 0]);
+/// ```
+pub fn nearest_neighbors(points: &[SpherePoint], candidates: &[SpherePoint], radius: f64) -> Vec<NodeAssignment> {
+    SpatialIndex::from_points(candidates, radius).assign(points)
+}
+
+fn squared_chord(a: &Vector3, b: &Vector3) -> f64 {
+    (a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)
+}
+
+fn arrange(nodes: &mut [(Vector3, usize)], depth: usize) {
+    if nodes.len() <= 1 {
+        return;
+    }
+    let axis = depth % 3;
+    let middle = nodes.len() / 2;
+    nodes.select_nth_unstable_by(middle, |a, b| a.0[axis].total_cmp(&b.0[axis]));
+    let (lower, upper) = nodes.split_at_mut(middle);
+    arrange(lower, depth + 1);
+    arrange(&mut upper[1..], depth + 1);
+}
+
+fn search(nodes: &[(Vector3, usize)], depth: usize, target: &Vector3, best: &mut Option<(f64, usize)>) {
+    if nodes.is_empty() {
+        return;
+    }
+    let middle = nodes.len() / 2;
+    let (vector, index) = &nodes[middle];
+    let distance = squared_chord(vector, target);
+    let is_better = match best {
+        Some((best_distance, best_index)) => distance < *best_distance || (distance == *best_distance && index < best_index),
+        None => true,
+    };
+    if is_better {
+        *best = Some((distance, *index));
+    }
+    let axis = depth % 3;
+    let offset = target[axis] - vector[axis];
+    let (near, far) = if offset < 0.0 {
+        (&nodes[..middle], &nodes[middle + 1..])
+    } else {
+        (&nodes[middle + 1..], &nodes[..middle])
+    };
+    search(near, depth + 1, target, best);
+    if best.is_none_or(|(best_distance, _)| offset * offset <= best_distance) {
+        search(far, depth + 1, target, best);
+    }
+}
+
+#[cfg(test)]
+mod spatial_tests {
+    use super::*;
+    use crate::data::CelestialObject;
+    use crate::dijkstra::get_closest_point;
+    use crate::vertex::generators::random;
+
+    #[test]
+    fn test_assign_matches_linear_scan() {
+        // given
+        let bbox = SphereBoundingBox::new(-80.0, 150.0, 89.0, -150.0);
+        let vertex = random(300, 3.0, &bbox, 11).unwrap();
+        let points: Vec<SpherePoint> = (0..200)
+            .map(|i| SpherePoint::new(-85.0 + i as f64 * 0.87, 140.0 + i as f64 * 0.4))
+            .collect();
+        // when
+        let assignments = assign_to_nodes(&points, &vertex);
+        // then
+        assert_eq!(assignments.len(), points.len());
+        let radius = get_radius_km(&CelestialObject::EARTH);
+        for (point, assignment) in points.iter().zip(assignments.iter()) {
+            let expected = get_closest_point(point, &vertex);
+            let distance = SphereConnection::new(point.clone(), vertex.vector[expected].coordinates.clone()).cost(radius);
+            assert_eq!(assignment.vertex_index, expected);
+            assert!(relative_eq!(assignment.distance, distance, max_relative = 1e-9));
+        }
+    }
+
+    #[test]
+    fn test_nearest_neighbors_join() {
+        // given: candidates spread over both hemispheres and across the antimeridian
+        let radius = get_radius_km(&CelestialObject::MARS);
+        let candidates: Vec<SpherePoint> = (0..150)
+            .map(|i| SpherePoint::new(-70.0 + (i * 37 % 140) as f64, -180.0 + (i * 53 % 360) as f64))
+            .collect();
+        let points: Vec<SpherePoint> = (0..80)
+            .map(|i| SpherePoint::new(-80.0 + i as f64 * 2.0, 175.0 + i as f64 * 0.13))
+            .collect();
+        // when
+        let joined = nearest_neighbors(&points, &candidates, radius);
+        // then
+        assert_eq!(joined.len(), points.len());
+        for (point, neighbor) in points.iter().zip(joined.iter()) {
+            let distances: Vec<f64> = candidates.iter()
+                .map(|candidate| SphereConnection::new(point.clone(), candidate.clone()).cost(radius))
+                .collect();
+            let closest = distances.iter().cloned().fold(f64::INFINITY, f64::min);
+            assert!(relative_eq!(distances[neighbor.vertex_index], closest, max_relative = 1e-9));
+            assert!(relative_eq!(neighbor.distance, closest, max_relative = 1e-9));
+        }
+        assert!(nearest_neighbors(&points, &[], radius).is_empty());
+    }
+}
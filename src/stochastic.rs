@@ -0,0 +1,241 @@
+use crate::errors::*;
+use crate::vertex::*;
+use crate::components::{SphereConnection, SpherePoint};
+use crate::dijkstra::snap_query;
+use crate::hashing::from_node_index;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// Travel cost label of a partial path ending at `vertex_index`.
+/// Cost of every connection is modelled as independent normal distribution, so the
+/// distribution of partial path cost is convolution of them: means and variances are summed.
+#[derive(Debug, Clone)]
+struct Label {
+    vertex_index: usize,
+    mean: f64,
+    variance: f64,
+    parent: Option<usize>,
+    dominated: bool,
+}
+
+impl Label {
+    fn dominates(&self, mean: f64, variance: f64) -> bool {
+        self.mean <= mean && self.variance <= variance
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct LabelEntry {
+    label_index: usize,
+    objective: f64,
+}
+
+impl Eq for LabelEntry {}
+
+impl Ord for LabelEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.objective.total_cmp(&self.objective)
+            .then_with(|| other.label_index.cmp(&self.label_index))
+    }
+}
+
+impl PartialOrd for LabelEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Returns Vec<SphereConnection> minimizing chosen percentile of travel cost between two given points.
+///
+/// # Arguments:
+/// * `start` which is &SpherePoint - start sphere point representation on given geomentry
+/// * `finish` which is &SpherePoint - finish sphere point representation on given geomentry
+/// * `vertex` which is &VertexBuffer - precalculated vertex for avaliable paths on given geometry
+/// * `percentile` which is f64 - percentile of travel cost to minimize, from 0.5 (mean) up to but excluding 1.0
+///
+/// # Remarks:
+///
+/// Connection cost is the mean of travel cost and `GraphRelation::variance` its variance,
+/// see `VertexBuffer::set_edge_variance`. Path cost distribution is approximated by the normal
+/// distribution with summed means and variances, and path with the lowest `mean + z * sqrt(variance)`
+/// is returned, where `z` is the standard normal quantile of `percentile`.
+/// The search is label-setting and keeps all mean/variance Pareto optimal labels per node,
+/// as the percentile objective is not additive over connections.
+///
+/// Returns `InvalidParameter` error for percentile outside of [0.5, 1.0) and `Ok(None)` in the same
+/// cases as `find_shortest_path`.
+pub fn find_percentile_path(start: &SpherePoint, finish: &SpherePoint, vertex: &VertexBuffer, percentile: f64)
+-> Result<Option<Vec<SphereConnection>>> {
+    if !(0.5..1.0).contains(&percentile) {
+        return Err(Error::from_kind(ErrorKind::InvalidParameter));
+    }
+    let (start_index, finish_index) = match snap_query(start, finish, vertex) {
+        Some(indices) => indices,
+        None => return Ok(None),
+    };
+    let z = standard_normal_quantile(percentile);
+    let mut labels: Vec<Label> = vec![Label {
+        vertex_index: start_index,
+        mean: 0.0,
+        variance: 0.0,
+        parent: None,
+        dominated: false,
+    }];
+    let mut node_labels: Vec<Vec<usize>> = vec![Vec::new(); vertex.len()];
+    node_labels[start_index].push(0);
+    let mut frontier: BinaryHeap<LabelEntry> = BinaryHeap::new();
+    frontier.push(LabelEntry {label_index: 0, objective: 0.0});
+    while let Some(entry) = frontier.pop() {
+        let label = labels[entry.label_index].clone();
+        if label.dominated {
+            continue;
+        }
+        if label.vertex_index == finish_index {
+            return Ok(Some(reconstruct_path(&labels, entry.label_index, vertex)));
+        }
+        for graph in &vertex.vector[label.vertex_index].graphs {
+            let child = from_node_index(graph.vertex_index);
+            let mean = label.mean + graph.cost;
+            let variance = label.variance + graph.variance;
+            let existing = &node_labels[child];
+            if existing.iter().any(|i| labels[*i].dominates(mean, variance)) {
+                continue;
+            }
+            for i in existing {
+                if mean <= labels[*i].mean && variance <= labels[*i].variance {
+                    labels[*i].dominated = true;
+                }
+            }
+            let label_index = labels.len();
+            labels.push(Label {
+                vertex_index: child,
+                mean,
+                variance,
+                parent: Some(entry.label_index),
+                dominated: false,
+            });
+            node_labels[child].retain(|i| !labels[*i].dominated);
+            node_labels[child].push(label_index);
+            frontier.push(LabelEntry {label_index, objective: mean + z * variance.sqrt()});
+        }
+    }
+    Ok(None)
+}
+
+fn reconstruct_path(labels: &[Label], last: usize, vertex: &VertexBuffer) -> Vec<SphereConnection> {
+    let mut result: Vec<SphereConnection> = Vec::new();
+    let mut current = &labels[last];
+    while let Some(parent) = current.parent {
+        let parent_label = &labels[parent];
+        result.push(SphereConnection::new(
+            vertex.vector[parent_label.vertex_index].coordinates.clone(),
+            vertex.vector[current.vertex_index].coordinates.clone(),
+        ));
+        current = parent_label;
+    }
+    result.reverse();
+    result
+}
+
+/// Standard normal quantile (inverse cumulative distribution function).
+/// Rational approximation by Peter J. Acklam, relative error below 1.15e-9.
+fn standard_normal_quantile(p: f64) -> f64 {
+    const A: [f64; 6] = [-3.969683028665376e+01, 2.209460984245205e+02, -2.759285104469687e+02,
+        1.38357751867269e+02, -3.066479806614716e+01, 2.506628277459239e+00];
+    const B: [f64; 5] = [-5.447609879822406e+01, 1.615858368580409e+02, -1.556989798598866e+02,
+        6.680131188771972e+01, -1.328068155288572e+01];
+    const C: [f64; 6] = [-7.784894002430293e-03, -3.223964580411365e-01, -2.400758277161838e+00,
+        -2.549732539343734e+00, 4.374664141464968e+00, 2.938163982698783e+00];
+    const D: [f64; 4] = [7.784695709041462e-03, 3.224671290700398e-01, 2.445134137142996e+00,
+        3.754408661907416e+00];
+    let p_low = 0.02425;
+    if p < p_low {
+        let q = (-2.0 * p.ln()).sqrt();
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    } else if p <= 1.0 - p_low {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    } else {
+        let q = (-2.0 * (1.0 - p).ln()).sqrt();
+        -(((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    }
+}
+
+#[cfg(test)]
+mod stochastic_tests {
+    use super::*;
+    use crate::data::CelestialObject;
+
+    fn reliability_vertex() -> VertexBuffer {
+        // short but unreliable route over (0.1, 1.0), long but reliable route over (-1.0, 1.0)
+        let start = SpherePoint::new(0.0, 0.0);
+        let finish = SpherePoint::new(0.0, 2.0);
+        let unreliable = SpherePoint::new(0.1, 1.0);
+        let reliable = SpherePoint::new(-1.0, 1.0);
+        let connections = vec![
+            SphereConnection::new(start.clone(), unreliable.clone()),
+            SphereConnection::new(unreliable, finish.clone()),
+            SphereConnection::new(start, reliable.clone()),
+            SphereConnection::new(reliable, finish),
+        ];
+        let mut vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        vertex.set_edge_variance(0, 10_000.0).unwrap();
+        vertex.set_edge_variance(1, 10_000.0).unwrap();
+        vertex
+    }
+
+    #[test]
+    fn test_percentile_path_prefers_reliable_route() {
+        // given
+        let vertex = reliability_vertex();
+        let start = SpherePoint::new(0.0, 0.0);
+        let finish = SpherePoint::new(0.0, 2.0);
+        // when
+        let mean_path = find_percentile_path(&start, &finish, &vertex, 0.5).unwrap().unwrap();
+        let reliable_path = find_percentile_path(&start, &finish, &vertex, 0.9).unwrap().unwrap();
+        // then
+        assert_eq!(mean_path[0].finish, SpherePoint::new(0.1, 1.0));
+        assert_eq!(reliable_path[0].finish, SpherePoint::new(-1.0, 1.0));
+        assert_eq!(reliable_path[1].finish, finish);
+    }
+
+    #[test]
+    fn test_percentile_validation() {
+        // given
+        let vertex = reliability_vertex();
+        let start = SpherePoint::new(0.0, 0.0);
+        let finish = SpherePoint::new(0.0, 2.0);
+        // when, then
+        assert!(find_percentile_path(&start, &finish, &vertex, 0.3).is_err());
+        assert!(find_percentile_path(&start, &finish, &vertex, 1.0).is_err());
+        assert!(relative_eq!(standard_normal_quantile(0.975), 1.959964, max_relative = 1e-6));
+        assert!(vertex.clone().set_edge_variance(2, -1.0).is_err());
+        assert!(vertex.clone().set_edge_variance(4, 1.0).is_err());
+    }
+
+    #[test]
+    fn test_edge_variance_on_one_way_connections() {
+        // given: one-way connections a -> b and b -> a, and a two-way connection b - c
+        let (a, b, c) = (SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0), SpherePoint::new(1.0, 1.0));
+        let mut vertex = VertexBuffer::with_directions(vec![
+            (SphereConnection::new(a.clone(), b.clone()), Direction::OneWay),
+            (SphereConnection::new(b.clone(), a), Direction::OneWay),
+            (SphereConnection::new(b, c), Direction::TwoWay),
+        ], CelestialObject::EARTH, &BuildOptions::default()).unwrap();
+        // when
+        vertex.set_edge_variance(0, 100.0).unwrap();
+        vertex.set_edge_variance(1, 25.0).unwrap();
+        vertex.set_edge_variance(2, 9.0).unwrap();
+        // then
+        let variance = |node: usize, edge_id: EdgeId| vertex.vector[node].graphs.iter()
+            .find(|rel| from_node_index(rel.edge_id) == edge_id)
+            .map(|rel| rel.variance);
+        assert_eq!(variance(0, 0), Some(100.0));
+        assert_eq!(variance(1, 1), Some(25.0));
+        assert_eq!((variance(1, 2), variance(2, 2)), (Some(9.0), Some(9.0)));
+    }
+}
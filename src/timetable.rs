@@ -0,0 +1,201 @@
+use crate::errors::*;
+use crate::components::SpherePoint;
+use crate::dijkstra::FrontierEntry;
+use crate::hashing::NodeMap;
+use std::collections::BinaryHeap;
+
+/// Timetabled trip between two stops, times are in seconds from any common epoch.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScheduledConnection {
+    pub from: SpherePoint,
+    pub to: SpherePoint,
+    pub departure: u64,
+    pub arrival: u64,
+}
+
+impl ScheduledConnection {
+    pub fn new(from: SpherePoint, to: SpherePoint, departure: u64, arrival: u64) -> Self {
+        Self {from, to, departure, arrival}
+    }
+}
+
+/// Result of earliest arrival query: `rides` are indices of used ScheduledConnections in travel order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Journey {
+    pub departure: u64,
+    pub arrival: u64,
+    pub rides: Vec<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum EventEdge {
+    Ride(usize),
+    Wait,
+}
+
+/// # TimeExpandedGraph
+/// Graph of timetabled services where every node is an event, a departure or an arrival at
+/// a stop at given time. Ride edges join departure with arrival of the same trip, wait edges join
+/// consecutive events at the same stop, so any path follows time forward.
+///
+/// # Remarks:
+///
+/// Events at the same stop share coordinates, which VertexBuffer merges into one node, so the
+/// graph keeps own event nodes. Transfers take no time; to model minimum transfer time shift
+/// arrivals of ScheduledConnections accordingly.
+///
+/// ```
+/// use path_navigator::components::SpherePoint;
+/// use path_navigator::timetable::*;
+///
+/// let gdansk = SpherePoint::new(54.35, 18.65);
+/// let warsaw = SpherePoint::new(52.23, 21.01);
+/// let krakow = SpherePoint::new(50.06, 19.94);
+/// let graph = TimeExpandedGraph::build(vec![
+///     ScheduledConnection::new(gdansk.clone(), warsaw.clone(), 3_600, 14_400),
+///     ScheduledConnection::new(warsaw.clone(), krakow.clone(), 18_000, 27_000),
+///     ScheduledConnection::new(warsaw, krakow.clone(), 10_000, 19_000),
+/// ]).unwrap();
+/// let journey = graph.earliest_arrival(&gdansk, &krakow, 0).unwrap();
+/// assert_eq!(journey.arrival, 27_000);
+/// assert_eq!(journey.rides, vec![0, 1]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct TimeExpandedGraph {
+    pub connections: Vec<ScheduledConnection>,
+    pub stops: Vec<SpherePoint>,
+    // events[stop] holds (time, event node) sorted by time
+    events: Vec<Vec<(u64, usize)>>,
+    event_stops: Vec<usize>,
+    event_times: Vec<u64>,
+    edges: Vec<Vec<(usize, EventEdge)>>,
+}
+
+impl TimeExpandedGraph {
+    /// Builds graph of given connections. Fails with `DataItemIncorrect` for no connections
+    /// or connection arriving before it departs.
+    pub fn build(connections: Vec<ScheduledConnection>) -> Result<Self> {
+        if connections.is_empty() || connections.iter().any(|connection| connection.arrival < connection.departure) {
+            return Err(Error::from_kind(ErrorKind::DataItemIncorrect));
+        }
+        let mut stops: Vec<SpherePoint> = Vec::new();
+        let mut stop_indices: NodeMap<(u64, u64), usize> = NodeMap::default();
+        let mut stop_of = |point: &SpherePoint| {
+            *stop_indices.entry((point.lat.to_bits(), point.lng.to_bits())).or_insert_with(|| {
+                stops.push(point.clone());
+                stops.len() - 1
+            })
+        };
+        let trips: Vec<(usize, usize)> = connections.iter()
+            .map(|connection| (stop_of(&connection.from), stop_of(&connection.to)))
+            .collect();
+        let mut events: Vec<Vec<(u64, usize)>> = vec![Vec::new(); stops.len()];
+        let mut event_stops: Vec<usize> = Vec::new();
+        let mut event_times: Vec<u64> = Vec::new();
+        let mut rides: Vec<(usize, usize, usize)> = Vec::new();
+        for (index, (connection, (from, to))) in connections.iter().zip(trips.iter()).enumerate() {
+            let departure_event = event_stops.len();
+            event_stops.push(*from);
+            event_times.push(connection.departure);
+            events[*from].push((connection.departure, departure_event));
+            let arrival_event = event_stops.len();
+            event_stops.push(*to);
+            event_times.push(connection.arrival);
+            events[*to].push((connection.arrival, arrival_event));
+            rides.push((departure_event, arrival_event, index));
+        }
+        let mut edges: Vec<Vec<(usize, EventEdge)>> = vec![Vec::new(); event_stops.len()];
+        for (departure_event, arrival_event, index) in rides {
+            edges[departure_event].push((arrival_event, EventEdge::Ride(index)));
+        }
+        for stop_events in events.iter_mut() {
+            // events are created in pairs, even ids are departures; arrivals go first so a departure
+            // at the very arrival time can still be caught
+            stop_events.sort_by_key(|(time, event)| (*time, event % 2 == 0, *event));
+            for pair in stop_events.windows(2) {
+                edges[pair[0].1].push((pair[1].1, EventEdge::Wait));
+            }
+        }
+        Ok(Self {connections, stops, events, event_stops, event_times, edges})
+    }
+
+    /// Returns number of event nodes.
+    pub fn event_count(&self) -> usize {
+        self.event_stops.len()
+    }
+
+    /// Returns Journey reaching `to` stop as early as possible when starting from `from` stop not
+    /// earlier than `not_before`. When several journeys arrive equally early any of them may be returned.
+    /// None when a stop is unknown or not reachable.
+    pub fn earliest_arrival(&self, from: &SpherePoint, to: &SpherePoint, not_before: u64) -> Option<Journey> {
+        let from_stop = self.stops.iter().position(|stop| stop == from)?;
+        let to_stop = self.stops.iter().position(|stop| stop == to)?;
+        let first_event = self.events[from_stop].iter().find(|(time, _)| *time >= not_before)?.1;
+        let mut settled: NodeMap<usize, f64> = NodeMap::default();
+        let mut parents: NodeMap<usize, (usize, EventEdge)> = NodeMap::default();
+        let mut frontier: BinaryHeap<FrontierEntry> = BinaryHeap::new();
+        frontier.push(FrontierEntry::new(first_event, self.event_times[first_event] as f64));
+        let mut reached: Option<usize> = None;
+        while let Some(entry) = frontier.pop() {
+            if settled.contains_key(&entry.vertex_index) {
+                continue;
+            }
+            settled.insert(entry.vertex_index, entry.cost);
+            if self.event_stops[entry.vertex_index] == to_stop && entry.vertex_index != first_event {
+                reached = Some(entry.vertex_index);
+                break;
+            }
+            for (next, edge) in &self.edges[entry.vertex_index] {
+                if !settled.contains_key(next) && !parents.contains_key(next) {
+                    parents.insert(*next, (entry.vertex_index, edge.clone()));
+                    frontier.push(FrontierEntry::new(*next, self.event_times[*next] as f64));
+                }
+            }
+        }
+        let last_event = reached?;
+        let mut rides: Vec<usize> = Vec::new();
+        let mut current = last_event;
+        while let Some((parent, edge)) = parents.get(&current) {
+            if let EventEdge::Ride(index) = edge {
+                rides.push(*index);
+            }
+            current = *parent;
+        }
+        rides.reverse();
+        let departure = rides.first().map_or(not_before, |index| self.connections[*index].departure);
+        Some(Journey {departure, arrival: self.event_times[last_event], rides})
+    }
+}
+
+#[cfg(test)]
+mod timetable_tests {
+    use super::*;
+
+    #[test]
+    fn test_earliest_arrival_waits_for_connection() {
+        // given
+        let port_a = SpherePoint::new(0.0, 0.0);
+        let port_b = SpherePoint::new(0.0, 1.0);
+        let port_c = SpherePoint::new(0.0, 2.0);
+        let graph = TimeExpandedGraph::build(vec![
+            ScheduledConnection::new(port_a.clone(), port_c.clone(), 100, 900),
+            ScheduledConnection::new(port_a.clone(), port_b.clone(), 200, 300),
+            ScheduledConnection::new(port_b.clone(), port_c.clone(), 250, 400),
+            ScheduledConnection::new(port_b.clone(), port_c.clone(), 500, 600),
+            ScheduledConnection::new(port_c.clone(), port_a.clone(), 600, 700),
+        ]).unwrap();
+        // when
+        let early = graph.earliest_arrival(&port_a, &port_c, 0).unwrap();
+        let late = graph.earliest_arrival(&port_a, &port_c, 150).unwrap();
+        // then
+        let back = graph.earliest_arrival(&port_b, &port_a, 450).unwrap();
+        assert_eq!(graph.event_count(), 10);
+        assert_eq!(back.rides, vec![3, 4]);
+        assert_eq!(early.arrival, 600);
+        assert_eq!(early.rides, vec![1, 3]);
+        assert_eq!(late, Journey {departure: 200, arrival: 600, rides: vec![1, 3]});
+        assert!(graph.earliest_arrival(&port_a, &port_c, 201).is_none());
+        assert!(graph.earliest_arrival(&port_c, &port_a, 601).is_none());
+        assert!(TimeExpandedGraph::build(vec![ScheduledConnection::new(port_a, port_b, 10, 5)]).is_err());
+    }
+}
@@ -1,6 +1,21 @@
 use crate::errors::*;
 use crate::data::*;
 use crate::components::*;
+use crate::hashing::{from_node_index, to_node_index, NodeMap};
+pub use crate::hashing::NodeIndex;
+use crate::formats::IoConfig;
+use crate::io::geojson::{self, Json};
+use crate::io::binary::{read_f64, read_header, read_string, read_u64, read_usize, write_f64, write_header, write_string, write_u64};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::io::{Read, Write};
+use std::sync::atomic::{self, AtomicU64};
+use std::time::{Duration, Instant};
+
+pub mod generators;
+#[cfg(feature = "builtin-networks")]
+pub mod builtin;
 
 /// Vertex Buffer (VB).
 /// Vertex Buffer stores nodes of each connection alongside with relation to other nodes and travel
@@ -32,18 +47,29 @@ use crate::components::*;
 ///
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GraphRelation {
-    pub vertex_index: usize,
+    /// node the relation leads to
+    pub vertex_index: NodeIndex,
+    /// EdgeId of the connection, stored as NodeIndex to keep relations compact
+    pub edge_id: NodeIndex,
     pub cost: f64,
+    pub variance: f64,
 }
 
 impl GraphRelation {
     pub fn new(vertex_index: usize, cost: f64) -> Self {
-        Self {vertex_index, cost}
+        Self {vertex_index: to_node_index(vertex_index), edge_id: 0, cost, variance: 0.0}
     }
 }
 
+/// Identifier of a connection stored in VertexBuffer.
+/// Both GraphRelations created for a connection (one per direction) share the same EdgeId,
+/// identifiers are assigned in order of appending connections, starting from 0.
+pub type EdgeId = usize;
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct VertexSpherePoint {
     pub coordinates: SpherePoint,
     pub graphs: Vec<GraphRelation>,
@@ -54,10 +80,6 @@ impl VertexSpherePoint {
         let graphs = Vec::new();
         Self {coordinates, graphs}
     }
-
-    fn has_point(&self, other: &SpherePoint) -> bool {
-        self.coordinates == *other
-    }
 }
 
 impl PartialEq for VertexSpherePoint {
@@ -66,80 +88,1676 @@ impl PartialEq for VertexSpherePoint {
     }
 }
 
-#[derive(Debug, Clone)]
+/// Handling of a connection joining nodes already joined by earlier connection (duplicate, in any
+/// direction of two-way VertexBuffer, in the same direction of one-way) or starting and finishing
+/// at the same point (self-loop).
+///
+/// * `Reject` - building VertexBuffer fails with `DataItemIncorrect`
+/// * `KeepCheapest` - connection is kept as single connection with the lower of both costs
+/// * `KeepAll` - every connection is kept, duplicates become parallel connections with own EdgeId
+/// * `WarnAndSkip` - connection is skipped and reported in BuildReport
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgePolicy {
+    Reject,
+    KeepCheapest,
+    KeepAll,
+    WarnAndSkip,
+}
+
+/// Direction in which connections of VertexBuffer can be travelled.
+///
+/// * `TwoWay` - from start to finish and back, the default
+/// * `OneWay` - only from start to finish, like one-way streets or one-directional flight corridors,
+///   a connection and its reverse are separate connections with own EdgeIds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    TwoWay,
+    OneWay,
+}
+
+/// Options applied to connections while building VertexBuffer with `VertexBuffer::with_options`.
+/// `BuildOptions::default()` gives the same VertexBuffer as `VertexBuffer::new`.
+///
+/// * `max_segment_km` - connections with cost above this value are split into chains of equal
+///   segments with intermediate nodes placed along the great circle.
+/// * `duplicates` - EdgePolicy for repeated connections, `WarnAndSkip` by default.
+/// * `self_loops` - EdgePolicy for self-loops, `Reject` by default. Kept self-loops are stored
+///   as single GraphRelation of the node to itself, repeated ones follow `duplicates` policy.
+/// * `local_projection` - connection costs are Euclidean distances in LocalProjection with origin
+///   in the center of bounding box of all connections instead of haversine, `false` by default.
+///   Meant for graphs spanning a few kilometers.
+/// * `max_edge_km` - input connections with great-circle length above this value are most likely
+///   data errors, like a bad coordinate making a segment span an ocean, and follow `long_edges` policy.
+/// * `long_edges` - EdgePolicy for connections longer than `max_edge_km`, `WarnAndSkip` by default.
+///   `Reject` fails with `EdgeTooLong` naming the first such connection, `KeepCheapest` and `KeepAll`
+///   keep the connection and flag it in BuildReport.
+/// * `distance_formula` - DistanceFormula of connection costs, `Haversine` by default. `Vincenty` is
+///   recommended for graphs with sub-meter connections, like indoor or port-scale networks.
+/// * `oneway_key` - metadata key of one-way connections in `VertexBuffer::with_edge_metadata`, None by
+///   default. Values `yes`, `true` and `1` allow travel only in the direction of the connection, `-1` and
+///   `reverse` only against it, other values keep the connection two-way.
+/// * `cost_decimals` - number of decimal places connection costs are rounded to, at most
+///   `MAX_COST_DECIMALS`, None by default. Trigonometric functions may differ in the last bits between
+///   platforms, rounding makes costs, and so serialized graphs and fingerprints, bit-identical
+///   unless a cost falls right at the rounding boundary.
+/// * `antimeridian_km` - tolerance in kilometers for stitching networks split at the antimeridian, None by
+///   default. Every node east of Greenwich within this distance of the 180th meridian is joined with the
+///   closest node west of Greenwich within this distance of it, like nodes at +180 and -180 longitude of
+///   tiled data. Stitching connections cost their great-circle length and get EdgeIds after the input ones.
+/// * `direction` - Direction of all connections, `TwoWay` by default. Stitching connections are always
+///   two-way, mixed networks are built two-way with `oneway_key` or with `VertexBuffer::with_directions`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BuildOptions {
+    pub max_segment_km: Option<f64>,
+    pub duplicates: EdgePolicy,
+    pub self_loops: EdgePolicy,
+    pub local_projection: bool,
+    pub max_edge_km: Option<f64>,
+    pub long_edges: EdgePolicy,
+    pub distance_formula: DistanceFormula,
+    pub cost_decimals: Option<u32>,
+    pub oneway_key: Option<String>,
+    pub antimeridian_km: Option<f64>,
+    pub direction: Direction,
+}
+
+/// Highest `BuildOptions::cost_decimals`, finer rounding exceeds precision of f64 for costs above 1.0.
+pub const MAX_COST_DECIMALS: u32 = 15;
+
+impl Default for BuildOptions {
+    fn default() -> Self {
+        Self {
+            max_segment_km: None,
+            duplicates: EdgePolicy::WarnAndSkip,
+            self_loops: EdgePolicy::Reject,
+            local_projection: false,
+            max_edge_km: None,
+            long_edges: EdgePolicy::WarnAndSkip,
+            distance_formula: DistanceFormula::Haversine,
+            cost_decimals: None,
+            oneway_key: None,
+            antimeridian_km: None,
+            direction: Direction::TwoWay,
+        }
+    }
+}
+
+/// Kind of problematic input connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeIssue {
+    Duplicate,
+    SelfLoop,
+    TooLong,
+}
+
+/// What was done with problematic input connection.
+/// `Replaced` means that cost of the earlier connection was lowered to the cost of the duplicate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolicyAction {
+    Skipped,
+    Replaced,
+    KeptParallel,
+    Kept,
+}
+
+/// EdgePolicy applied to input connection, `connection_index` is an index into the input connections.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppliedPolicy {
+    pub connection_index: usize,
+    pub issue: EdgeIssue,
+    pub action: PolicyAction,
+}
+
+/// Report of building VertexBuffer with `VertexBuffer::build`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BuildReport {
+    pub applied: Vec<AppliedPolicy>,
+}
+
+/// Number of input connections between progress reports of `VertexBuffer::build_with_progress`.
+pub const PROGRESS_INTERVAL: usize = 1024;
+
+/// State of VertexBuffer build reported to progress callback.
+/// `edges_created` may exceed `connections_processed` when connections are densified.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BuildProgress {
+    pub connections_processed: usize,
+    pub connections_total: usize,
+    pub nodes_created: usize,
+    pub edges_created: usize,
+    pub elapsed: Duration,
+}
+
+/// User attributes of a connection, like road category, toll or speed limit, or of a node, like its
+/// name or kind, as key value pairs.
+pub type Metadata = BTreeMap<String, String>;
+
+/// Connection present in both compared VertexBuffers with different costs, see `VertexBuffer::structural_diff`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CostChange {
+    pub edge_id: EdgeId,
+    pub other_edge_id: EdgeId,
+    pub cost: f64,
+    pub other_cost: f64,
+}
+
+/// Differences between two VertexBuffers found by `VertexBuffer::structural_diff`. Removed items are
+/// indices and EdgeIds of the compared VertexBuffer, added ones of the other VertexBuffer, all ascending.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GraphDiff {
+    pub added_nodes: Vec<usize>,
+    pub removed_nodes: Vec<usize>,
+    pub added_edges: Vec<EdgeId>,
+    pub removed_edges: Vec<EdgeId>,
+    pub cost_changes: Vec<CostChange>,
+}
+
+impl GraphDiff {
+    /// Returns true when both VertexBuffers have the same nodes and connections with costs within tolerance.
+    pub fn is_empty(&self) -> bool {
+        self.added_nodes.is_empty() && self.removed_nodes.is_empty() && self.added_edges.is_empty()
+            && self.removed_edges.is_empty() && self.cost_changes.is_empty()
+    }
+}
+
+/// Outcome of snapping a point with `VertexBuffer::snap_batch`, distances are great-circle kilometers
+/// to the closest node.
+///
+/// * `Snapped` - closest node is within the maximal distance
+/// * `TooFar` - closest node is further than the maximal distance, it is still reported
+/// * `EmptyGraph` - VertexBuffer has no nodes to snap to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SnapOutcome {
+    Snapped {vertex_index: usize, distance: f64},
+    TooFar {vertex_index: usize, distance: f64},
+    EmptyGraph,
+}
+
+/// Location on a connection closest to a query point, see `VertexBuffer::nearest_point_on_network`.
+///
+/// * `point` - the closest location, on the great-circle arc of the connection or of a part of its shape
+/// * `distance` - great-circle distance from query point in kilometers
+/// * `edge_id` - EdgeId of the connection
+/// * `fraction` - position of `point` along the connection, its shape included, in direction of
+///   `VertexBuffer::edge`, 0.0 at its first node and 1.0 at the second one
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetworkPoint {
+    pub point: SpherePoint,
+    pub distance: f64,
+    pub edge_id: EdgeId,
+    pub fraction: f64,
+}
+
+/// # Stable indices
+/// Node indices and EdgeIds depend only on input connections and their order, never on hashing,
+/// platform or crate features: nodes are numbered in order of first appearance, start of every
+/// connection before its finish, and EdgeIds in order of stored connections. GraphRelations of every
+/// node are kept in EdgeId order. Building again from the same connections with the same BuildOptions
+/// gives the same indices and relations, so external systems may store them as long as the graph data
+/// is kept alongside. So does building from `to_connections` with default BuildOptions on two-way graphs,
+/// and from `to_directed_connections` with `with_directions` on graphs with one-way connections, where
+/// `to_connections` would turn them two-way.
+///
+/// ```
+/// use path_navigator::components::*;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::vertex::VertexBuffer;
+///
+/// let connections = vec![
+///     SphereConnection::new(SpherePoint::new(5.0, 5.0), SpherePoint::new(0.0, 0.0)),
+///     SphereConnection::new(SpherePoint::new(1.0, 1.0), SpherePoint::new(0.0, 0.0)),
+/// ];
+/// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+/// assert_eq!(vertex.index_of(&SpherePoint::new(5.0, 5.0)), Some(0));
+/// assert_eq!(vertex.index_of(&SpherePoint::new(1.0, 1.0)), Some(2));
+/// assert_eq!(vertex.edge(1), Some((2, 1)));
+/// ```
+#[derive(Debug)]
 pub struct VertexBuffer {
     pub celestial_object: CelestialObject,
     pub vector: Vec<VertexSpherePoint>,
+    edges: Vec<(NodeIndex, NodeIndex)>,
+    directions: Vec<Direction>,
+    indices: NodeMap<(u64, u64), usize>,
+    edge_metadata: NodeMap<EdgeId, Metadata>,
+    node_metadata: NodeMap<usize, Metadata>,
+    edge_shapes: NodeMap<EdgeId, Vec<SpherePoint>>,
+    lengths: LengthCache,
+    version: u64,
+    generation: u64,
+}
+
+/// Last `VertexBuffer::generation` handed out in this process.
+static GENERATIONS: AtomicU64 = AtomicU64::new(0);
+
+fn next_generation() -> u64 {
+    GENERATIONS.fetch_add(1, atomic::Ordering::Relaxed) + 1
+}
+
+impl Clone for VertexBuffer {
+    /// Clone gets its own generation, as it may be changed independently of the original.
+    fn clone(&self) -> Self {
+        Self {
+            celestial_object: self.celestial_object.clone(),
+            vector: self.vector.clone(),
+            edges: self.edges.clone(),
+            directions: self.directions.clone(),
+            indices: self.indices.clone(),
+            edge_metadata: self.edge_metadata.clone(),
+            node_metadata: self.node_metadata.clone(),
+            edge_shapes: self.edge_shapes.clone(),
+            lengths: self.lengths.clone(),
+            version: self.version,
+            generation: next_generation(),
+        }
+    }
+}
+
+/// Coordinates keys of both ends of connection, in canonical order.
+type ConnectionKey = ((u64, u64), (u64, u64));
+
+/// Content of VertexBuffer written by `save` and serialized with `serde` feature, node lookup is
+/// rebuilt from it by `VertexBuffer::restore`.
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
+struct StoredVertexBuffer {
+    celestial_object: CelestialObject,
+    vector: Vec<VertexSpherePoint>,
+    edges: Vec<(usize, usize)>,
+    edge_metadata: BTreeMap<EdgeId, Metadata>,
+    node_metadata: BTreeMap<usize, Metadata>,
+    edge_shapes: BTreeMap<EdgeId, Vec<SpherePoint>>,
+}
+
+/// StoredVertexBuffer borrowed from VertexBuffer being serialized.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize)]
+struct StoredVertexBufferRef<'a> {
+    celestial_object: &'a CelestialObject,
+    vector: &'a [VertexSpherePoint],
+    edges: Vec<(usize, usize)>,
+    edge_metadata: BTreeMap<EdgeId, &'a Metadata>,
+    node_metadata: BTreeMap<usize, &'a Metadata>,
+    edge_shapes: BTreeMap<EdgeId, &'a Vec<SpherePoint>>,
+}
+
+/// Serializes nodes with their GraphRelations, connections, metadata and shapes, like `save`.
+#[cfg(feature = "serde")]
+impl serde::Serialize for VertexBuffer {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        StoredVertexBufferRef {
+            celestial_object: &self.celestial_object,
+            vector: &self.vector,
+            edges: self.edges.iter().map(|(from, to)| (from_node_index(*from), from_node_index(*to))).collect(),
+            edge_metadata: self.edge_metadata.iter().map(|(edge_id, metadata)| (*edge_id, metadata)).collect(),
+            node_metadata: self.node_metadata.iter().map(|(vertex_index, metadata)| (*vertex_index, metadata)).collect(),
+            edge_shapes: self.edge_shapes.iter().map(|(edge_id, points)| (*edge_id, points)).collect(),
+        }.serialize(serializer)
+    }
+}
+
+/// Deserializes VertexBuffer with the same node indices and EdgeIds, failing on the data `load`
+/// rejects as `DataItemIncorrect`.
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for VertexBuffer {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let stored = StoredVertexBuffer::deserialize(deserializer)?;
+        Self::restore(stored).map_err(serde::de::Error::custom)
+    }
+}
+
+const MAGIC: &[u8; 4] = b"PNVB";
+const FORMAT_VERSION: u32 = 1;
+
+/// Great-circle lengths of connections keyed by coordinates of their ends in canonical order,
+/// valid for the radius they were computed with.
+#[derive(Debug, Clone, Default)]
+struct LengthCache {
+    radius: f64,
+    lengths: NodeMap<ConnectionKey, f64>,
 }
 
 impl VertexBuffer {
     pub fn new(connections: Vec<SphereConnection>, celestial_object: CelestialObject) -> Result<Self> {
+        Self::with_options(connections, celestial_object, &BuildOptions::default())
+    }
+
+    /// Builds VertexBuffer of one-way connections, which can be travelled only from start to finish,
+    /// see `Direction::OneWay`.
+    ///
+    /// ```
+    /// use path_navigator::components::*;
+    /// use path_navigator::data::CelestialObject;
+    /// use path_navigator::dijkstra::find_path;
+    /// use path_navigator::vertex::VertexBuffer;
+    ///
+    /// let (a, b) = (SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0));
+    /// let vertex = VertexBuffer::new_directed(vec![SphereConnection::new(a.clone(), b.clone())], CelestialObject::EARTH).unwrap();
+    /// assert!(find_path(&a, &b, &vertex).is_some());
+    /// assert!(find_path(&b, &a, &vertex).is_none());
+    /// ```
+    pub fn new_directed(connections: Vec<SphereConnection>, celestial_object: CelestialObject) -> Result<Self> {
+        Self::with_options(connections, celestial_object, &BuildOptions {direction: Direction::OneWay, ..BuildOptions::default()})
+    }
+
+    /// Builds VertexBuffer applying given BuildOptions to connections, each with its own Direction instead
+    /// of `BuildOptions::direction`, like the list of `to_directed_connections`.
+    ///
+    /// ```
+    /// use path_navigator::components::*;
+    /// use path_navigator::data::CelestialObject;
+    /// use path_navigator::vertex::{BuildOptions, Direction, VertexBuffer};
+    ///
+    /// let connections = vec![
+    ///     (SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)), Direction::OneWay),
+    ///     (SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 2.0)), Direction::TwoWay),
+    /// ];
+    /// let vertex = VertexBuffer::with_directions(connections.clone(), CelestialObject::EARTH, &BuildOptions::default()).unwrap();
+    /// assert_eq!(vertex.edge_direction(0), Some(Direction::OneWay));
+    /// assert!(vertex.is_directed());
+    /// assert_eq!(vertex.to_directed_connections(), connections);
+    /// ```
+    pub fn with_directions(connections: Vec<(SphereConnection, Direction)>, celestial_object: CelestialObject,
+        options: &BuildOptions) -> Result<Self> {
+        let (connections, directions): (Vec<SphereConnection>, Vec<Direction>) = connections.into_iter().unzip();
+        Self::build_tracked(connections, celestial_object, options, Some(&directions), None, |_| true)
+            .map(|(vertex_buffer, _, _)| vertex_buffer)
+    }
+
+    /// Builds VertexBuffer from pairs of any GeoPoint type, see `new`.
+    pub fn from_geo_points<P: GeoPoint>(pairs: &[(P, P)], celestial_object: CelestialObject) -> Result<Self> {
+        let connections: Vec<SphereConnection> = pairs.iter()
+            .map(|(start, finish)| SphereConnection::new(start.to_sphere_point(), finish.to_sphere_point()))
+            .collect();
+        Self::new(connections, celestial_object)
+    }
+
+    /// Returns index of node closest to any GeoPoint by given Metric, the lowest index on ties,
+    /// None for empty VertexBuffer.
+    pub fn closest_node_with<P: GeoPoint>(&self, point: &P, metric: &dyn Metric) -> Option<usize> {
+        let point = point.to_sphere_point();
+        (0..self.len())
+            .map(|index| (metric.distance(&point, &self.vector[index].coordinates), index))
+            .min_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)))
+            .map(|(_, index)| index)
+    }
+
+    /// Returns index of node closest to any GeoPoint, None for empty VertexBuffer.
+    pub fn closest_node<P: GeoPoint>(&self, point: &P) -> Option<usize> {
+        let point = point.to_sphere_point();
+        (0..self.len()).min_by(|a, b| {
+            let to_a = SphereConnection::new(point.clone(), self.vector[*a].coordinates.clone()).central_angle();
+            let to_b = SphereConnection::new(point.clone(), self.vector[*b].coordinates.clone()).central_angle();
+            to_a.total_cmp(&to_b)
+        })
+    }
+
+    /// Snaps every point to its closest node and reports SnapOutcome in order of points, so points
+    /// further than `max_distance` kilometers from the graph can be told apart in preprocessing.
+    ///
+    /// ```
+    /// use path_navigator::components::*;
+    /// use path_navigator::data::CelestialObject;
+    /// use path_navigator::vertex::{SnapOutcome, VertexBuffer};
+    ///
+    /// let connections = vec![SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0))];
+    /// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+    /// let points = vec![SpherePoint::new(0.0, 1.0), SpherePoint::new(10.0, 1.0)];
+    /// let outcomes = vertex.snap_batch(&points, 5.0);
+    /// assert_eq!(outcomes[0], SnapOutcome::Snapped {vertex_index: 1, distance: 0.0});
+    /// assert!(matches!(outcomes[1], SnapOutcome::TooFar {vertex_index: 1, ..}));
+    /// ```
+    pub fn snap_batch<P: GeoPoint>(&self, points: &[P], max_distance: f64) -> Vec<SnapOutcome> {
+        self.snap_batch_with(points, max_distance, &HaversineMetric {radius: get_radius_km(&self.celestial_object)})
+    }
+
+    /// Snaps every point like `snap_batch`, measuring distances to nodes with given Metric.
+    ///
+    /// ```
+    /// use path_navigator::components::*;
+    /// use path_navigator::data::CelestialObject;
+    /// use path_navigator::vertex::{SnapOutcome, VertexBuffer};
+    ///
+    /// let connections = vec![SphereConnection::new(SpherePoint::new(60.0, 0.0), SpherePoint::new(60.0, 30.0))];
+    /// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+    /// let rhumb = RhumbMetric {radius: 6371.0};
+    /// let outcomes = vertex.snap_batch_with(&[SpherePoint::new(60.0, 10.0)], 1000.0, &rhumb);
+    /// assert!(matches!(outcomes[0], SnapOutcome::Snapped {vertex_index: 0, distance} if (distance - 555.97).abs() < 0.01));
+    /// ```
+    pub fn snap_batch_with<P: GeoPoint>(&self, points: &[P], max_distance: f64, metric: &dyn Metric) -> Vec<SnapOutcome> {
+        points.iter()
+            .map(|point| match self.closest_node_with(point, metric) {
+                None => SnapOutcome::EmptyGraph,
+                Some(vertex_index) => {
+                    let distance = metric.distance(&point.to_sphere_point(), &self.vector[vertex_index].coordinates);
+                    if distance.partial_cmp(&max_distance) == Some(Ordering::Greater) {
+                        SnapOutcome::TooFar {vertex_index, distance}
+                    } else {
+                        SnapOutcome::Snapped {vertex_index, distance}
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Builds VertexBuffer applying given BuildOptions to connections.
+    ///
+    /// ```
+    /// use path_navigator::components::*;
+    /// use path_navigator::data::CelestialObject;
+    /// use path_navigator::vertex::{BuildOptions, VertexBuffer};
+    ///
+    /// let connections = vec![SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 10.0))];
+    /// let options = BuildOptions {max_segment_km: Some(100.0), ..BuildOptions::default()};
+    /// let vertex = VertexBuffer::with_options(connections, CelestialObject::EARTH, &options).unwrap();
+    /// assert_eq!(vertex.len(), 13);
+    /// ```
+    pub fn with_options(connections: Vec<SphereConnection>, celestial_object: CelestialObject, options: &BuildOptions)
+    -> Result<Self> {
+        Self::build(connections, celestial_object, options).map(|(vertex_buffer, _)| vertex_buffer)
+    }
+
+    /// Builds VertexBuffer applying given BuildOptions to connections, with connection costs measured
+    /// by given Metric instead of `distance_formula` and `local_projection` of BuildOptions.
+    /// Densification and `max_edge_km` still measure great-circle lengths.
+    ///
+    /// ```
+    /// use path_navigator::components::*;
+    /// use path_navigator::data::CelestialObject;
+    /// use path_navigator::vertex::{BuildOptions, VertexBuffer};
+    ///
+    /// let connections = vec![SphereConnection::new(SpherePoint::new(50.0, 0.0), SpherePoint::new(50.0, 90.0))];
+    /// let rhumb = RhumbMetric {radius: 6371.0};
+    /// let vertex = VertexBuffer::with_metric(connections.clone(), CelestialObject::EARTH, &BuildOptions::default(), &rhumb).unwrap();
+    /// assert_eq!(vertex.vector[0].graphs[0].cost, rhumb.distance(&connections[0].start, &connections[0].finish));
+    /// ```
+    pub fn with_metric(connections: Vec<SphereConnection>, celestial_object: CelestialObject, options: &BuildOptions,
+        metric: &dyn Metric) -> Result<Self> {
+        Self::build_tracked(connections, celestial_object, options, None, Some(metric), |_| true).map(|(vertex_buffer, _, _)| vertex_buffer)
+    }
+
+    /// Builds VertexBuffer applying given BuildOptions to connections and reports EdgePolicy
+    /// applied to duplicated connections and self-loops.
+    ///
+    /// ```
+    /// use path_navigator::components::*;
+    /// use path_navigator::data::CelestialObject;
+    /// use path_navigator::vertex::*;
+    ///
+    /// let connections = vec![
+    ///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+    ///     SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 0.0)),
+    /// ];
+    /// let (vertex, report) = VertexBuffer::build(connections, CelestialObject::EARTH, &BuildOptions::default()).unwrap();
+    /// assert_eq!(vertex.edge_count(), 1);
+    /// assert_eq!(report.applied[0].connection_index, 1);
+    /// assert_eq!(report.applied[0].action, PolicyAction::Skipped);
+    /// ```
+    pub fn build(connections: Vec<SphereConnection>, celestial_object: CelestialObject, options: &BuildOptions)
+    -> Result<(Self, BuildReport)> {
+        Self::build_with_progress(connections, celestial_object, options, |_| true)
+    }
+
+    /// Builds VertexBuffer like `build`, calling `progress` every `PROGRESS_INTERVAL` input connections
+    /// and once after the last one, so applications can show progress of long imports.
+    /// Returning false from `progress` aborts the build with `Aborted` error.
+    ///
+    /// ```
+    /// use path_navigator::components::*;
+    /// use path_navigator::data::CelestialObject;
+    /// use path_navigator::vertex::*;
+    ///
+    /// let connections: Vec<SphereConnection> = (0..5000)
+    ///     .map(|i| SphereConnection::new(SpherePoint::new(0.0, i as f64 * 0.01), SpherePoint::new(0.0, (i + 1) as f64 * 0.01)))
+    ///     .collect();
+    /// let mut reports: Vec<BuildProgress> = Vec::new();
+    /// let options = BuildOptions::default();
+    /// VertexBuffer::build_with_progress(connections.clone(), CelestialObject::EARTH, &options, |progress| {
+    ///     reports.push(progress);
+    ///     true
+    /// }).unwrap();
+    /// assert_eq!(reports.len(), 5);
+    /// assert_eq!(reports[4].nodes_created, 5001);
+    /// let aborted = VertexBuffer::build_with_progress(connections, CelestialObject::EARTH, &options, |_| false);
+    /// assert!(aborted.is_err());
+    /// ```
+    pub fn build_with_progress<F>(connections: Vec<SphereConnection>, celestial_object: CelestialObject, options: &BuildOptions,
+        progress: F) -> Result<(Self, BuildReport)>
+    where F: FnMut(BuildProgress) -> bool {
+        Self::build_tracked(connections, celestial_object, options, None, None, progress).map(|(vertex_buffer, report, _)| (vertex_buffer, report))
+    }
+
+    /// Builds VertexBuffer from connections carrying Metadata, like OSM ways with their tags, which is
+    /// attached to every connection created from them. With `BuildOptions::oneway_key` set, connections
+    /// become one-way following the value of the key, see BuildOptions.
+    ///
+    /// ```
+    /// use path_navigator::components::*;
+    /// use path_navigator::data::CelestialObject;
+    /// use path_navigator::vertex::*;
+    ///
+    /// let street = SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 0.01));
+    /// let tags: Metadata = vec![("oneway".to_string(), "yes".to_string())].into_iter().collect();
+    /// let options = BuildOptions {oneway_key: Some("oneway".to_string()), ..BuildOptions::default()};
+    /// let vertex = VertexBuffer::with_edge_metadata(vec![(street, tags)], CelestialObject::EARTH, &options).unwrap();
+    /// assert_eq!(vertex.edge_metadata(0).unwrap()["oneway"], "yes");
+    /// assert_eq!(vertex.vector[0].graphs.len(), 1);
+    /// assert!(vertex.vector[1].graphs.is_empty());
+    /// ```
+    pub fn with_edge_metadata(connections: Vec<(SphereConnection, Metadata)>, celestial_object: CelestialObject,
+        options: &BuildOptions) -> Result<Self> {
+        let (connections, metadata): (Vec<SphereConnection>, Vec<Metadata>) = connections.into_iter().unzip();
+        let (mut vertex_buffer, _, edge_sources) = Self::build_tracked(connections, celestial_object, options, None, None, |_| true)?;
+        for (edge_id, connection_index) in edge_sources.iter().enumerate() {
+            if !metadata[*connection_index].is_empty() {
+                vertex_buffer.edge_metadata.insert(edge_id, metadata[*connection_index].clone());
+            }
+        }
+        if let Some(key) = &options.oneway_key {
+            vertex_buffer.apply_oneway(key);
+        }
+        Ok(vertex_buffer)
+    }
+
+    /// Removes GraphRelations against the one-way direction given by metadata `key`, connections
+    /// allowed only in reverse are turned around, so `edge` always gives the allowed direction.
+    fn apply_oneway(&mut self, key: &str) {
+        for edge_id in 0..self.edges.len() {
+            let reverse = match self.edge_metadata.get(&edge_id).and_then(|metadata| metadata.get(key)).map(String::as_str) {
+                Some("yes") | Some("true") | Some("1") => false,
+                Some("-1") | Some("reverse") => true,
+                _ => continue,
+            };
+            let (mut from, mut to) = (from_node_index(self.edges[edge_id].0), from_node_index(self.edges[edge_id].1));
+            if from == to {
+                continue;
+            }
+            if reverse {
+                std::mem::swap(&mut from, &mut to);
+                self.edges[edge_id] = (to_node_index(from), to_node_index(to));
+                if let Some(points) = self.edge_shapes.get_mut(&edge_id) {
+                    points.reverse();
+                }
+            }
+            if !self.vector[from].graphs.iter().any(|rel| from_node_index(rel.edge_id) == edge_id) {
+                // connection was one-way already, its only relation moves to the new tail
+                if let Some(position) = self.vector[to].graphs.iter().position(|rel| from_node_index(rel.edge_id) == edge_id) {
+                    let mut relation = self.vector[to].graphs.remove(position);
+                    relation.vertex_index = to_node_index(to);
+                    self.vector[from].graphs.push(relation);
+                }
+            }
+            self.vector[to].graphs.retain(|rel| from_node_index(rel.edge_id) != edge_id);
+            self.directions[edge_id] = Direction::OneWay;
+        }
+    }
+
+    /// Builds VertexBuffer like `build_with_progress` and returns index of input connection of every EdgeId.
+    /// Connections get Direction of the same index in `directions` when given, of BuildOptions otherwise.
+    /// Connection costs are measured by `metric` when given.
+    fn build_tracked<F>(connections: Vec<SphereConnection>, celestial_object: CelestialObject, options: &BuildOptions,
+        directions: Option<&[Direction]>, metric: Option<&dyn Metric>, mut progress: F) -> Result<(Self, BuildReport, Vec<usize>)>
+    where F: FnMut(BuildProgress) -> bool {
+        let mut edge_sources: Vec<usize> = Vec::new();
+        let started = Instant::now();
+        let connections_total = connections.len();
         let vector = Vec::new();
-        let mut vertex_buffer = Self {celestial_object, vector};
-        if !vertex_buffer.is_connections_vec_correct(&connections) {
+        let edges = Vec::new();
+        let directions_of_edges = Vec::new();
+        let indices = NodeMap::default();
+        let edge_metadata = NodeMap::default();
+        let node_metadata = NodeMap::default();
+        let edge_shapes = NodeMap::default();
+        let lengths = LengthCache::default();
+        let mut vertex_buffer = Self {
+            celestial_object, vector, edges, directions: directions_of_edges, indices, edge_metadata, node_metadata, edge_shapes, lengths, version: 0,
+            generation: next_generation(),
+        };
+        let mut report = BuildReport::default();
+        if connections.is_empty() || directions.is_some_and(|directions| directions.len() != connections.len()) {
             return Err(Error::from_kind(ErrorKind::DataItemIncorrect));
         }
-        connections.iter().for_each(|conn| vertex_buffer.append(conn.clone()));
+        if options.cost_decimals.is_some_and(|decimals| decimals > MAX_COST_DECIMALS) {
+            return Err(Error::from_kind(ErrorKind::InvalidParameter));
+        }
+        for limit in [options.max_segment_km, options.max_edge_km, options.antimeridian_km].iter().flatten() {
+            if limit.partial_cmp(&0.0) != Some(Ordering::Greater) {
+                return Err(Error::from_kind(ErrorKind::InvalidParameter));
+            }
+        }
+        let radius = get_radius_km(&vertex_buffer.celestial_object);
+        let projection = if options.local_projection {
+            let points: Vec<SpherePoint> = connections.iter()
+                .flat_map(|connection| [connection.start.clone(), connection.finish.clone()])
+                .collect();
+            SphereBoundingBox::from_points(&points).map(|bbox| LocalProjection::new(&bbox, radius))
+        } else {
+            None
+        };
+        for (connection_index, connection) in connections.iter().enumerate() {
+            if connection_index > 0 && connection_index % PROGRESS_INTERVAL == 0 {
+                let state = vertex_buffer.progress(connection_index, connections_total, started);
+                if !progress(state) {
+                    return Err(Error::from_kind(ErrorKind::Aborted));
+                }
+            }
+            if connection.start == connection.finish {
+                let action = match options.self_loops {
+                    EdgePolicy::Reject => return Err(Error::from_kind(ErrorKind::DataItemIncorrect)),
+                    EdgePolicy::WarnAndSkip => Some(PolicyAction::Skipped),
+                    EdgePolicy::KeepCheapest | EdgePolicy::KeepAll => None,
+                };
+                if let Some(action) = action {
+                    report.applied.push(AppliedPolicy {connection_index, issue: EdgeIssue::SelfLoop, action});
+                    continue;
+                }
+            }
+            if let Some(max_edge_km) = options.max_edge_km {
+                let length_km = vertex_buffer.price(connection, radius);
+                if length_km > max_edge_km {
+                    let action = match options.long_edges {
+                        EdgePolicy::Reject => {
+                            return Err(Error::from_kind(ErrorKind::EdgeTooLong(connection_index, length_km)));
+                        }
+                        EdgePolicy::WarnAndSkip => PolicyAction::Skipped,
+                        EdgePolicy::KeepCheapest | EdgePolicy::KeepAll => PolicyAction::Kept,
+                    };
+                    report.applied.push(AppliedPolicy {connection_index, issue: EdgeIssue::TooLong, action});
+                    if action == PolicyAction::Skipped {
+                        continue;
+                    }
+                }
+            }
+            let segments = match options.max_segment_km {
+                Some(max_segment_km) => densify(connection, max_segment_km, radius),
+                None => vec![connection.clone()],
+            };
+            for segment in segments {
+                let is_self_loop = segment.start == segment.finish;
+                let cost = match (metric, &projection, options.distance_formula) {
+                    (Some(metric), _, _) => metric.distance(&segment.start, &segment.finish),
+                    (None, Some(projection), _) => projection.distance(&segment.start, &segment.finish),
+                    (None, None, DistanceFormula::Haversine) => vertex_buffer.price(&segment, radius),
+                    (None, None, formula) => segment.cost_with(radius, formula),
+                };
+                let cost = options.cost_decimals.map_or(cost, |decimals| round_cost(cost, decimals));
+                let direction = directions.map_or(options.direction, |directions| directions[connection_index]);
+                if let Some(action) = vertex_buffer.append(segment, cost, options.duplicates, direction)? {
+                    report.applied.push(AppliedPolicy {connection_index, issue: EdgeIssue::Duplicate, action});
+                } else if is_self_loop {
+                    report.applied.push(AppliedPolicy {connection_index, issue: EdgeIssue::SelfLoop, action: PolicyAction::Kept});
+                }
+            }
+            edge_sources.resize(vertex_buffer.edges.len(), connection_index);
+        }
+        if let Some(tolerance_km) = options.antimeridian_km {
+            vertex_buffer.stitch_antimeridian(tolerance_km, radius, options.cost_decimals);
+        }
+        if !progress(vertex_buffer.progress(connections_total, connections_total, started)) {
+            return Err(Error::from_kind(ErrorKind::Aborted));
+        }
+        Ok((vertex_buffer, report, edge_sources))
+    }
+
+    fn progress(&self, connections_processed: usize, connections_total: usize, started: Instant) -> BuildProgress {
+        BuildProgress {
+            connections_processed,
+            connections_total,
+            nodes_created: self.vector.len(),
+            edges_created: self.edges.len(),
+            elapsed: started.elapsed(),
+        }
+    }
+
+    /// Returns number of nodes stored in VertexBuffer.
+    pub fn len(&self) -> usize {
+        self.vector.len()
+    }
+
+    /// Returns true when VertexBuffer holds no nodes.
+    pub fn is_empty(&self) -> bool {
+        self.vector.is_empty()
+    }
+
+    /// Returns index of node placed exactly at given coordinates.
+    pub fn index_of(&self, point: &SpherePoint) -> Option<usize> {
+        self.indices.get(&coordinates_key(point)).copied()
+    }
+
+    /// Returns number of connections stored in VertexBuffer, valid EdgeIds are `0..edge_count()`.
+    pub fn edge_count(&self) -> usize {
+        self.edges.len()
+    }
+
+    /// Returns indexes of nodes joined by connection with given EdgeId,
+    /// in the direction the connection was appended.
+    pub fn edge(&self, edge_id: EdgeId) -> Option<(usize, usize)> {
+        self.edges.get(edge_id).map(|(from, to)| (from_node_index(*from), from_node_index(*to)))
+    }
+
+    /// Returns Direction of connection with given EdgeId, one-way connections can be travelled only
+    /// as given by `edge`. Self-loops are two-way.
+    pub fn edge_direction(&self, edge_id: EdgeId) -> Option<Direction> {
+        self.directions.get(edge_id).copied()
+    }
+
+    /// Returns true when any connection is one-way, so cost from one node to another may differ from
+    /// the cost back and searches can not be run the other way over the same relations.
+    pub fn is_directed(&self) -> bool {
+        self.directions.contains(&Direction::OneWay)
+    }
+
+    /// Returns stored connections as edge list, one SphereConnection per EdgeId in EdgeId order and in
+    /// the direction it was appended. Skipped duplicates and self-loops are not there and densified input
+    /// connections come as their segments, so building VertexBuffer from the list with default
+    /// BuildOptions gives the same nodes and EdgeIds. Costs and metadata are not part of the list.
+    ///
+    /// ```
+    /// use path_navigator::components::*;
+    /// use path_navigator::data::CelestialObject;
+    /// use path_navigator::vertex::VertexBuffer;
+    ///
+    /// let connections = vec![
+    ///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+    ///     SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 0.0)),
+    /// ];
+    /// let vertex = VertexBuffer::new(connections.clone(), CelestialObject::EARTH).unwrap();
+    /// assert_eq!(vertex.to_connections(), connections[..1].to_vec());
+    /// ```
+    pub fn to_connections(&self) -> Vec<SphereConnection> {
+        self.edges.iter()
+            .map(|(from, to)| SphereConnection::new(self.vector[from_node_index(*from)].coordinates.clone(), self.vector[from_node_index(*to)].coordinates.clone()))
+            .collect()
+    }
+
+    /// Returns reverse graph of VertexBuffer: the same nodes, EdgeIds, costs and metadata with every
+    /// connection and its shape turned around, so searches over it find paths leading to a node instead of from it.
+    /// Cost from `a` to `b` in the reverse graph is the cost from `b` to `a` in this one.
+    ///
+    /// ```
+    /// use path_navigator::components::*;
+    /// use path_navigator::data::CelestialObject;
+    /// use path_navigator::dijkstra::find_path;
+    /// use path_navigator::vertex::VertexBuffer;
+    ///
+    /// let (a, b) = (SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0));
+    /// let vertex = VertexBuffer::new_directed(vec![SphereConnection::new(a.clone(), b.clone())], CelestialObject::EARTH).unwrap();
+    /// let reversed = vertex.reversed();
+    /// assert_eq!(reversed.edge(0), Some((1, 0)));
+    /// assert!(find_path(&b, &a, &reversed).is_some());
+    /// assert!(find_path(&a, &b, &reversed).is_none());
+    /// ```
+    pub fn reversed(&self) -> Self {
+        let mut reversed = Self {
+            vector: self.vector.iter().map(|node| VertexSpherePoint::new(node.coordinates.clone())).collect(),
+            edges: self.edges.iter().map(|(from, to)| (*to, *from)).collect(),
+            ..self.clone()
+        };
+        reversed.edge_shapes.values_mut().for_each(|points| points.reverse());
+        for (from, node) in self.vector.iter().enumerate() {
+            for graph in &node.graphs {
+                reversed.vector[from_node_index(graph.vertex_index)].graphs.push(GraphRelation {vertex_index: to_node_index(from), ..graph.clone()});
+            }
+        }
+        reversed
+    }
+
+    /// Returns stored connections like `to_connections` with Direction of each, so building VertexBuffer
+    /// from them with `with_directions` keeps one-way connections one-way.
+    pub fn to_directed_connections(&self) -> Vec<(SphereConnection, Direction)> {
+        self.to_connections().into_iter().zip(self.directions.iter().copied()).collect()
+    }
+
+    /// Writes VertexBuffer in compact little-endian binary format: celestial object, nodes with their
+    /// GraphRelations, connections, metadata and shapes. Cached connection lengths are not stored.
+    /// With `serde` feature VertexBuffer is also Serialize and Deserialize, storing the same content.
+    ///
+    /// ```
+    /// use path_navigator::components::*;
+    /// use path_navigator::data::CelestialObject;
+    /// use path_navigator::vertex::VertexBuffer;
+    ///
+    /// let connections = vec![SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0))];
+    /// let vertex = VertexBuffer::new(connections, CelestialObject::MARS).unwrap();
+    /// let mut saved: Vec<u8> = Vec::new();
+    /// vertex.save(&mut saved).unwrap();
+    /// let loaded = VertexBuffer::load(&mut saved.as_slice()).unwrap();
+    /// assert_eq!(loaded.to_connections(), vertex.to_connections());
+    /// assert!(loaded.check_same_body(&vertex).is_ok());
+    /// ```
+    pub fn save<W: Write>(&self, writer: &mut W) -> Result<()> {
+        write_header(writer, MAGIC, FORMAT_VERSION)?;
+        write_u64(writer, celestial_code(&self.celestial_object))?;
+        write_u64(writer, self.vector.len() as u64)?;
+        for node in &self.vector {
+            write_f64(writer, node.coordinates.lat)?;
+            write_f64(writer, node.coordinates.lng)?;
+            write_u64(writer, node.graphs.len() as u64)?;
+            for graph in &node.graphs {
+                write_u64(writer, from_node_index(graph.vertex_index) as u64)?;
+                write_u64(writer, from_node_index(graph.edge_id) as u64)?;
+                write_f64(writer, graph.cost)?;
+                write_f64(writer, graph.variance)?;
+            }
+        }
+        write_u64(writer, self.edges.len() as u64)?;
+        for (from, to) in &self.edges {
+            write_u64(writer, from_node_index(*from) as u64)?;
+            write_u64(writer, from_node_index(*to) as u64)?;
+        }
+        for metadata in [&self.edge_metadata, &self.node_metadata].iter() {
+            let mut keys: Vec<&usize> = metadata.keys().collect();
+            keys.sort();
+            write_u64(writer, keys.len() as u64)?;
+            for key in keys {
+                write_u64(writer, *key as u64)?;
+                write_u64(writer, metadata[key].len() as u64)?;
+                for (name, value) in &metadata[key] {
+                    write_string(writer, name)?;
+                    write_string(writer, value)?;
+                }
+            }
+        }
+        let mut shaped: Vec<&EdgeId> = self.edge_shapes.keys().collect();
+        shaped.sort();
+        write_u64(writer, shaped.len() as u64)?;
+        for edge_id in shaped {
+            write_u64(writer, *edge_id as u64)?;
+            write_u64(writer, self.edge_shapes[edge_id].len() as u64)?;
+            for point in &self.edge_shapes[edge_id] {
+                write_f64(writer, point.lat)?;
+                write_f64(writer, point.lng)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads VertexBuffer written by `save`, with the same node indices and EdgeIds.
+    /// Fails with `DataItemIncorrect` for data in other format, unknown celestial object, nodes at
+    /// the same coordinates or references to unknown nodes and connections.
+    pub fn load<R: Read>(reader: &mut R) -> Result<Self> {
+        let incorrect = || Error::from_kind(ErrorKind::DataItemIncorrect);
+        read_header(reader, MAGIC, FORMAT_VERSION)?;
+        let celestial_object = celestial_object(read_u64(reader)?).ok_or_else(incorrect)?;
+        let node_count = read_usize(reader)?;
+        if NodeIndex::try_from(node_count).is_err() {
+            return Err(incorrect());
+        }
+        let mut vector: Vec<VertexSpherePoint> = Vec::new();
+        for _ in 0..node_count {
+            let mut node = VertexSpherePoint::new(SpherePoint::new(read_f64(reader)?, read_f64(reader)?));
+            let graph_count = read_usize(reader)?;
+            for _ in 0..graph_count {
+                node.graphs.push(GraphRelation {
+                    vertex_index: NodeIndex::try_from(read_usize(reader)?).map_err(|_| incorrect())?,
+                    edge_id: NodeIndex::try_from(read_usize(reader)?).map_err(|_| incorrect())?,
+                    cost: read_f64(reader)?,
+                    variance: read_f64(reader)?,
+                });
+            }
+            vector.push(node);
+        }
+        let edge_count = read_usize(reader)?;
+        let edges = (0..edge_count)
+            .map(|_| Ok((read_usize(reader)?, read_usize(reader)?)))
+            .collect::<Result<Vec<(usize, usize)>>>()?;
+        let mut metadata = [BTreeMap::new(), BTreeMap::new()];
+        for entries in metadata.iter_mut() {
+            let count = read_usize(reader)?;
+            for _ in 0..count {
+                let key = read_usize(reader)?;
+                let pair_count = read_usize(reader)?;
+                let mut pairs = Metadata::new();
+                for _ in 0..pair_count {
+                    let name = read_string(reader)?;
+                    pairs.insert(name, read_string(reader)?);
+                }
+                entries.insert(key, pairs);
+            }
+        }
+        let [edge_metadata, node_metadata] = metadata;
+        let shaped_count = read_usize(reader)?;
+        let mut edge_shapes = BTreeMap::new();
+        for _ in 0..shaped_count {
+            let edge_id = read_usize(reader)?;
+            let point_count = read_usize(reader)?;
+            let points = (0..point_count)
+                .map(|_| Ok(SpherePoint::new(read_f64(reader)?, read_f64(reader)?)))
+                .collect::<Result<Vec<SpherePoint>>>()?;
+            edge_shapes.insert(edge_id, points);
+        }
+        Self::restore(StoredVertexBuffer {celestial_object, vector, edges, edge_metadata, node_metadata, edge_shapes})
+    }
+
+    /// Reads VertexBuffer on Earth from GeoJSON FeatureCollection of LineString features, like exported
+    /// by GIS tools, every pair of consecutive positions becomes a connection costing its great-circle
+    /// length, built like by `VertexBuffer::new`.
+    ///
+    /// # Remarks:
+    ///
+    /// Positions are `[longitude, latitude]` as RFC 7946 prescribes, altitude is ignored. String, number
+    /// and boolean properties of a feature become Metadata of all its connections, other properties are
+    /// skipped. Fails with `SwappedCoordinates` carrying position number, counted from 0 over the whole
+    /// document, when latitude is out of range but would be a valid longitude, and with `DataItemIncorrect`
+    /// for malformed JSON, other geometries and lines of less than two positions.
+    ///
+    /// ```
+    /// use path_navigator::vertex::VertexBuffer;
+    ///
+    /// let text = r#"{"type": "FeatureCollection", "features": [{"type": "Feature", "properties": {"name": "Nowy Swiat"},
+    ///     "geometry": {"type": "LineString", "coordinates": [[21.02, 52.23], [21.02, 52.24], [21.03, 52.24]]}}]}"#;
+    /// let vertex = VertexBuffer::from_geojson(text).unwrap();
+    /// assert_eq!(vertex.edge_count(), 2);
+    /// assert_eq!(vertex.edge_metadata(1).unwrap()["name"], "Nowy Swiat");
+    /// ```
+    pub fn from_geojson(text: &str) -> Result<Self> {
+        let incorrect = || Error::from_kind(ErrorKind::DataItemIncorrect);
+        let document = geojson::parse(text)?;
+        if document.get("type").and_then(Json::as_str) != Some("FeatureCollection") {
+            return Err(incorrect());
+        }
+        let config = IoConfig::default();
+        let mut connections: Vec<(SphereConnection, Metadata)> = Vec::new();
+        let mut item = 0;
+        for feature in document.get("features").and_then(Json::as_array).ok_or_else(incorrect)? {
+            let geometry = feature.get("geometry").ok_or_else(incorrect)?;
+            if geometry.get("type").and_then(Json::as_str) != Some("LineString") {
+                return Err(incorrect());
+            }
+            let positions = geometry.get("coordinates").and_then(Json::as_array)
+                .filter(|positions| positions.len() >= 2)
+                .ok_or_else(incorrect)?;
+            let mut points: Vec<SpherePoint> = Vec::with_capacity(positions.len());
+            for position in positions {
+                let values = position.as_array().filter(|values| values.len() >= 2).ok_or_else(incorrect)?;
+                let (lng, lat) = (values[0].as_f64().ok_or_else(incorrect)?, values[1].as_f64().ok_or_else(incorrect)?);
+                points.push(config.point(lng, lat, item)?);
+                item += 1;
+            }
+            let metadata: Metadata = match feature.get("properties") {
+                Some(Json::Object(members)) => members.iter()
+                    .filter_map(|(key, value)| match value {
+                        Json::String(text) => Some((key.clone(), text.clone())),
+                        Json::Number(number) => Some((key.clone(), number.to_string())),
+                        Json::Bool(flag) => Some((key.clone(), flag.to_string())),
+                        _ => None,
+                    })
+                    .collect(),
+                _ => Metadata::new(),
+            };
+            connections.extend(points.windows(2)
+                .map(|pair| (SphereConnection::new(pair[0].clone(), pair[1].clone()), metadata.clone())));
+        }
+        Self::with_edge_metadata(connections, CelestialObject::EARTH, &BuildOptions::default())
+    }
+
+    /// Rebuilds VertexBuffer with node lookup from its stored content, read by `load` or deserialized.
+    fn restore(stored: StoredVertexBuffer) -> Result<Self> {
+        let incorrect = || Error::from_kind(ErrorKind::DataItemIncorrect);
+        let (node_count, edge_count) = (stored.vector.len(), stored.edges.len());
+        if NodeIndex::try_from(node_count).is_err() {
+            return Err(incorrect());
+        }
+        let mut vertex_buffer = Self {
+            celestial_object: stored.celestial_object,
+            vector: Vec::with_capacity(node_count),
+            edges: Vec::with_capacity(edge_count),
+            directions: Vec::with_capacity(edge_count),
+            indices: NodeMap::default(),
+            edge_metadata: NodeMap::default(),
+            node_metadata: NodeMap::default(),
+            edge_shapes: NodeMap::default(),
+            lengths: LengthCache::default(),
+            version: 0,
+            generation: next_generation(),
+        };
+        for node in stored.vector {
+            let is_incorrect = vertex_buffer.indices.contains_key(&coordinates_key(&node.coordinates))
+                || node.graphs.iter().any(|graph| from_node_index(graph.vertex_index) >= node_count || from_node_index(graph.edge_id) >= edge_count);
+            if is_incorrect {
+                return Err(incorrect());
+            }
+            let vertex_index = vertex_buffer.add(node.coordinates);
+            vertex_buffer.vector[vertex_index].graphs = node.graphs;
+        }
+        for (from, to) in stored.edges {
+            if from >= node_count || to >= node_count {
+                return Err(incorrect());
+            }
+            vertex_buffer.edges.push((to_node_index(from), to_node_index(to)));
+        }
+        // Direction is not stored, connections are two-way when the finish node relates back to the start
+        for edge_id in 0..edge_count {
+            let (from, to) = vertex_buffer.edge(edge_id).unwrap();
+            let is_two_way = from == to || vertex_buffer.vector[to].graphs.iter()
+                .any(|graph| from_node_index(graph.edge_id) == edge_id && from_node_index(graph.vertex_index) == from);
+            vertex_buffer.directions.push(if is_two_way { Direction::TwoWay } else { Direction::OneWay });
+        }
+        let is_out_of_range = stored.edge_metadata.keys().any(|edge_id| *edge_id >= edge_count)
+            || stored.node_metadata.keys().any(|vertex_index| *vertex_index >= node_count)
+            || stored.edge_shapes.iter().any(|(edge_id, points)| *edge_id >= edge_count || points.is_empty());
+        if is_out_of_range {
+            return Err(incorrect());
+        }
+        vertex_buffer.edge_metadata.extend(stored.edge_metadata);
+        vertex_buffer.node_metadata.extend(stored.node_metadata);
+        vertex_buffer.edge_shapes.extend(stored.edge_shapes);
         Ok(vertex_buffer)
     }
 
-   fn len(&self) -> usize {
-       self.vector.len()
-   }
+    /// Returns haversine great-circle length of connection in kilometers on the celestial object of
+    /// VertexBuffer, like `SphereConnection::cost`. Lengths priced while building are cached by
+    /// coordinates of both ends in either direction, so features pricing stored connections again
+    /// do not repeat the trigonometry.
+    ///
+    /// ```
+    /// use path_navigator::components::*;
+    /// use path_navigator::data::*;
+    /// use path_navigator::vertex::VertexBuffer;
+    ///
+    /// let connection = SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(1.0, 1.0));
+    /// let vertex = VertexBuffer::new(vec![connection.clone()], CelestialObject::EARTH).unwrap();
+    /// let reversed = SphereConnection::new(connection.finish.clone(), connection.start.clone());
+    /// assert_eq!(vertex.great_circle_length(&reversed), connection.cost(get_radius_km(&CelestialObject::EARTH)));
+    /// ```
+    pub fn great_circle_length(&self, connection: &SphereConnection) -> f64 {
+        let radius = get_radius_km(&self.celestial_object);
+        let (key, canonical) = canonical_connection(connection);
+        match self.lengths.lengths.get(&key) {
+            Some(length) if self.lengths.radius == radius => *length,
+            _ => canonical.cost(radius),
+        }
+    }
+
+    /// Drops cached connection lengths, so they are priced again for the current celestial object.
+    /// Lengths cached for another radius are never used, so it is only needed to free their memory
+    /// after `celestial_object` was changed.
+    pub fn invalidate_length_cache(&mut self) {
+        self.lengths = LengthCache::default();
+    }
 
-    fn is_connections_vec_correct(&self, connections: &Vec<SphereConnection>) -> bool {
-        if connections.len() == 0 {
-            return false;
+    /// Returns haversine length of connection, from cache when it was priced before for the same radius.
+    fn price(&mut self, connection: &SphereConnection, radius: f64) -> f64 {
+        if self.lengths.radius != radius {
+            self.lengths = LengthCache {radius, lengths: NodeMap::default()};
         }
-        for connection in connections {
-            if connection.start == connection.finish {
-                return false;
+        let (key, canonical) = canonical_connection(connection);
+        *self.lengths.lengths.entry(key).or_insert_with(|| canonical.cost(radius))
+    }
+
+    /// Returns cost of connection with given EdgeId in the direction it was appended.
+    pub fn edge_cost(&self, edge_id: EdgeId) -> Option<f64> {
+        let (from, _) = self.edge(edge_id)?;
+        self.vector[from].graphs.iter().find(|rel| from_node_index(rel.edge_id) == edge_id).map(|rel| rel.cost)
+    }
+
+    /// Checks that other VertexBuffer lies on the celestial object of this one, so their geometry and
+    /// costs can be combined, like when merging graphs or stacking them in layers. Bodies are compared
+    /// by radius, as costs depend on nothing else. Fails with `BodyMismatch` carrying radii of this and
+    /// the other VertexBuffer otherwise.
+    ///
+    /// ```
+    /// use path_navigator::components::*;
+    /// use path_navigator::data::CelestialObject;
+    /// use path_navigator::errors::ErrorKind;
+    /// use path_navigator::vertex::VertexBuffer;
+    ///
+    /// let connections = vec![SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0))];
+    /// let earth = VertexBuffer::new(connections.clone(), CelestialObject::EARTH).unwrap();
+    /// let mars = VertexBuffer::new(connections, CelestialObject::MARS).unwrap();
+    /// assert!(earth.check_same_body(&earth.clone()).is_ok());
+    /// let error = earth.check_same_body(&mars).unwrap_err();
+    /// assert!(matches!(error.kind(), ErrorKind::BodyMismatch(expected, found) if *expected == 6371.0 && *found == 3389.5));
+    /// ```
+    pub fn check_same_body(&self, other: &VertexBuffer) -> Result<()> {
+        let (expected, found) = (get_radius_km(&self.celestial_object), get_radius_km(&other.celestial_object));
+        if expected != found {
+            return Err(Error::from_kind(ErrorKind::BodyMismatch(expected, found)));
+        }
+        Ok(())
+    }
+
+    /// Compares VertexBuffer with another one, like graphs of two nightly builds, and reports
+    /// nodes and connections added or removed and connections with cost changed by more than `cost_tolerance`.
+    ///
+    /// # Remarks:
+    ///
+    /// Nodes are matched by exact coordinates and connections by coordinates of their ends, in either
+    /// direction, so differences in node order or EdgeIds alone are not reported. Parallel connections
+    /// are matched in EdgeId order. One-way connections match only one-way connections in the same
+    /// direction, a connection turned one-way, two-way or around is reported removed and added.
+    /// Fails with `BodyMismatch` for VertexBuffer on other celestial object, see `check_same_body`.
+    ///
+    /// ```
+    /// use path_navigator::components::*;
+    /// use path_navigator::data::CelestialObject;
+    /// use path_navigator::vertex::VertexBuffer;
+    ///
+    /// let yesterday = VertexBuffer::new(vec![
+    ///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+    ///     SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 2.0)),
+    /// ], CelestialObject::EARTH).unwrap();
+    /// let today = VertexBuffer::new(vec![
+    ///     SphereConnection::new(SpherePoint::new(0.0, 2.0), SpherePoint::new(0.0, 1.0)),
+    /// ], CelestialObject::EARTH).unwrap();
+    /// let diff = yesterday.structural_diff(&today, 1e-9).unwrap();
+    /// assert_eq!(diff.removed_nodes, vec![0]);
+    /// assert_eq!(diff.removed_edges, vec![0]);
+    /// assert!(diff.added_edges.is_empty() && diff.cost_changes.is_empty());
+    /// ```
+    pub fn structural_diff(&self, other: &VertexBuffer, cost_tolerance: f64) -> Result<GraphDiff> {
+        self.check_same_body(other)?;
+        let mut diff = GraphDiff {
+            added_nodes: (0..other.len()).filter(|index| self.index_of(&other.vector[*index].coordinates).is_none()).collect(),
+            removed_nodes: (0..self.len()).filter(|index| other.index_of(&self.vector[*index].coordinates).is_none()).collect(),
+            ..GraphDiff::default()
+        };
+        let edge_key = |vertex: &VertexBuffer, edge_id: EdgeId| {
+            let (from, to) = vertex.edge(edge_id).unwrap();
+            let (from, to) = (coordinates_key(&vertex.vector[from].coordinates), coordinates_key(&vertex.vector[to].coordinates));
+            let is_one_way = vertex.edge_direction(edge_id) == Some(Direction::OneWay);
+            if from <= to || is_one_way { (from, to, is_one_way) } else { (to, from, is_one_way) }
+        };
+        let mut unmatched: NodeMap<_, Vec<EdgeId>> = NodeMap::default();
+        for other_edge_id in (0..other.edge_count()).rev() {
+            unmatched.entry(edge_key(other, other_edge_id)).or_default().push(other_edge_id);
+        }
+        for edge_id in 0..self.edge_count() {
+            match unmatched.get_mut(&edge_key(self, edge_id)).and_then(|edge_ids| edge_ids.pop()) {
+                Some(other_edge_id) => {
+                    let (cost, other_cost) = (self.edge_cost(edge_id).unwrap(), other.edge_cost(other_edge_id).unwrap());
+                    if (cost - other_cost).abs() > cost_tolerance {
+                        diff.cost_changes.push(CostChange {edge_id, other_edge_id, cost, other_cost});
+                    }
+                }
+                None => diff.removed_edges.push(edge_id),
             }
         }
-        true
+        diff.added_edges = unmatched.into_values().flatten().collect();
+        diff.added_edges.sort_unstable();
+        Ok(diff)
     }
 
-    fn append(&mut self, connection: SphereConnection) {
-        let start_index_option: Option<usize> = self
-            .vector
-            .iter()
-            .position(|r| r.has_point(&connection.start));
-        let end_index_option: Option<usize> = self
-            .vector
-            .iter()
-            .position(|r| r.has_point(&connection.finish));
-        let start_vertex_index = match start_index_option {
+    /// Returns EdgeIds of all, possibly parallel, connections leading from one node to another.
+    pub fn edges_between(&self, from: usize, to: usize) -> Vec<EdgeId> {
+        self.vector.get(from)
+            .map(|node| node.graphs.iter()
+                .filter(|rel| from_node_index(rel.vertex_index) == to)
+                .map(|rel| from_node_index(rel.edge_id))
+                .collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns EdgeId of the first connection between two nodes, see `edges_between` for parallel connections.
+    pub fn find_edge(&self, from: usize, to: usize) -> Option<EdgeId> {
+        self.vector.get(from)?
+            .graphs.iter()
+            .find(|rel| from_node_index(rel.vertex_index) == to)
+            .map(|rel| from_node_index(rel.edge_id))
+    }
+
+    /// Returns indexes of all nodes reachable from `node` using at most `n` connections,
+    /// regardless of their cost. Result is in breadth-first order and starts with `node` itself,
+    /// it is empty when `node` is not in VertexBuffer.
+    pub fn within_hops(&self, node: usize, n: usize) -> Vec<usize> {
+        if node >= self.len() {
+            return Vec::new();
+        }
+        let mut visited = vec![false; self.len()];
+        let mut reached: Vec<usize> = vec![node];
+        visited[node] = true;
+        let mut layer_start = 0;
+        for _ in 0..n {
+            let layer_end = reached.len();
+            for i in layer_start..layer_end {
+                for rel in &self.vector[reached[i]].graphs {
+                    let child = from_node_index(rel.vertex_index);
+                    if !visited[child] {
+                        visited[child] = true;
+                        reached.push(child);
+                    }
+                }
+            }
+            if reached.len() == layer_end {
+                break;
+            }
+            layer_start = layer_end;
+        }
+        reached
+    }
+
+    /// Returns Metadata of connection, None for unknown EdgeId or connection without metadata.
+    pub fn edge_metadata(&self, edge_id: EdgeId) -> Option<&Metadata> {
+        self.edge_metadata.get(&edge_id)
+    }
+
+    /// Sets metadata value of connection, replacing earlier value of the same key.
+    /// Returns `DataItemIncorrect` for unknown EdgeId.
+    pub fn set_edge_metadata(&mut self, edge_id: EdgeId, key: &str, value: &str) -> Result<()> {
+        if edge_id >= self.edges.len() {
+            return Err(Error::from_kind(ErrorKind::DataItemIncorrect));
+        }
+        self.edge_metadata.entry(edge_id).or_default().insert(key.to_string(), value.to_string());
+        self.generation = next_generation();
+        Ok(())
+    }
+
+    /// Returns intermediate points of connection between its nodes, in direction of `edge`,
+    /// None for unknown EdgeId or connection without shape, which is the great-circle arc.
+    pub fn edge_shape(&self, edge_id: EdgeId) -> Option<&[SpherePoint]> {
+        self.edge_shapes.get(&edge_id).map(Vec::as_slice)
+    }
+
+    /// Sets original shape of connection, like a road curve replaced by a single connection,
+    /// as its intermediate points in direction of `edge` without both nodes. Empty shape removes it.
+    /// Costs are not changed. Returns `DataItemIncorrect` for unknown EdgeId.
+    ///
+    /// ```
+    /// use path_navigator::components::*;
+    /// use path_navigator::data::CelestialObject;
+    /// use path_navigator::vertex::VertexBuffer;
+    ///
+    /// let connections = vec![SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 2.0))];
+    /// let mut vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+    /// vertex.set_edge_shape(0, vec![SpherePoint::new(0.5, 1.0)]).unwrap();
+    /// assert_eq!(vertex.edge_shape(0), Some(&[SpherePoint::new(0.5, 1.0)][..]));
+    /// ```
+    pub fn set_edge_shape(&mut self, edge_id: EdgeId, points: Vec<SpherePoint>) -> Result<()> {
+        if edge_id >= self.edges.len() {
+            return Err(Error::from_kind(ErrorKind::DataItemIncorrect));
+        }
+        if points.is_empty() {
+            self.edge_shapes.remove(&edge_id);
+        } else {
+            self.edge_shapes.insert(edge_id, points);
+        }
+        self.generation = next_generation();
+        Ok(())
+    }
+
+    /// Returns Metadata of node, None for unknown node or node without metadata.
+    pub fn node_metadata(&self, vertex_index: usize) -> Option<&Metadata> {
+        self.node_metadata.get(&vertex_index)
+    }
+
+    /// Sets metadata value of node, replacing earlier value of the same key.
+    /// Returns `DataItemIncorrect` for unknown node.
+    pub fn set_node_metadata(&mut self, vertex_index: usize, key: &str, value: &str) -> Result<()> {
+        if vertex_index >= self.vector.len() {
+            return Err(Error::from_kind(ErrorKind::DataItemIncorrect));
+        }
+        self.node_metadata.entry(vertex_index).or_default().insert(key.to_string(), value.to_string());
+        self.generation = next_generation();
+        Ok(())
+    }
+
+    /// Returns index of node closest to any GeoPoint among nodes with Metadata accepted by `filter`,
+    /// like the nearest harbor, None when no node is accepted. Nodes without metadata are never accepted.
+    ///
+    /// ```
+    /// use path_navigator::components::*;
+    /// use path_navigator::data::CelestialObject;
+    /// use path_navigator::vertex::VertexBuffer;
+    ///
+    /// let connections = vec![
+    ///     SphereConnection::new(SpherePoint::new(54.35, 18.65), SpherePoint::new(54.52, 18.55)),
+    ///     SphereConnection::new(SpherePoint::new(54.52, 18.55), SpherePoint::new(54.44, 18.57)),
+    /// ];
+    /// let mut vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+    /// vertex.set_node_metadata(1, "name", "Gdynia").unwrap();
+    /// vertex.set_node_metadata(1, "kind", "harbor").unwrap();
+    /// vertex.set_node_metadata(2, "name", "Sopot").unwrap();
+    /// let harbor = vertex.nearest_named(&SpherePoint::new(54.36, 18.64), |metadata| {
+    ///     metadata.get("kind").is_some_and(|kind| kind == "harbor")
+    /// });
+    /// assert_eq!(harbor, Some(1));
+    /// ```
+    pub fn nearest_named<P: GeoPoint, F: Fn(&Metadata) -> bool>(&self, point: &P, filter: F) -> Option<usize> {
+        let point = point.to_sphere_point();
+        let angle = |index: &usize| SphereConnection::new(point.clone(), self.vector[*index].coordinates.clone()).central_angle();
+        self.node_metadata.iter()
+            .filter(|(_, metadata)| filter(metadata))
+            .map(|(index, _)| (*index, angle(index)))
+            .min_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)))
+            .map(|(index, _)| index)
+    }
+
+    /// Returns the closest location on any connection to given point, not only on nodes, for
+    /// distance-from-network displays and data checks. Connections with shape set by `set_edge_shape`
+    /// are measured along the shape. On ties the lowest EdgeId wins.
+    /// None for VertexBuffer without connections.
+    ///
+    /// ```
+    /// use path_navigator::components::*;
+    /// use path_navigator::data::CelestialObject;
+    /// use path_navigator::vertex::VertexBuffer;
+    ///
+    /// let connections = vec![
+    ///     SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+    ///     SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(1.0, 1.0)),
+    /// ];
+    /// let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+    /// let nearest = vertex.nearest_point_on_network(&SpherePoint::new(0.1, 0.5)).unwrap();
+    /// assert_eq!(nearest.edge_id, 0);
+    /// assert!((nearest.fraction - 0.5).abs() < 1e-3);
+    /// assert!((nearest.distance - 11.12).abs() < 0.01);
+    /// ```
+    pub fn nearest_point_on_network<P: GeoPoint>(&self, point: &P) -> Option<NetworkPoint> {
+        let point = point.to_sphere_point();
+        let radius = get_radius_km(&self.celestial_object);
+        let mut nearest: Option<(EdgeId, usize, SphereConnection, f64)> = None;
+        for edge_id in 0..self.edges.len() {
+            for (segment, (start, finish)) in self.edge_points(edge_id).zip(self.edge_points(edge_id).skip(1)).enumerate() {
+                let connection = SphereConnection::new(start.clone(), finish.clone());
+                let angle = connection.angular_distance_to(&point);
+                if nearest.as_ref().is_none_or(|(_, _, _, nearest_angle)| angle < *nearest_angle) {
+                    nearest = Some((edge_id, segment, connection, angle));
+                }
+            }
+        }
+        let (edge_id, segment, connection, angle) = nearest?;
+        let closest = connection.closest_point(&point);
+        let segment_angles: Vec<f64> = self.edge_points(edge_id).zip(self.edge_points(edge_id).skip(1))
+            .map(|(start, finish)| SphereConnection::new(start.clone(), finish.clone()).central_angle())
+            .collect();
+        let length: f64 = segment_angles.iter().sum();
+        let along = segment_angles[..segment].iter().sum::<f64>()
+            + SphereConnection::new(connection.start.clone(), closest.clone()).central_angle();
+        let fraction = if length > 0.0 { (along / length).min(1.0) } else { 0.0 };
+        Some(NetworkPoint {point: closest, distance: angle * radius, edge_id, fraction})
+    }
+
+    /// Returns points of connection in direction of `edge`: its first node, shape and second node.
+    fn edge_points(&self, edge_id: EdgeId) -> impl Iterator<Item = &SpherePoint> {
+        let (from, to) = (from_node_index(self.edges[edge_id].0), from_node_index(self.edges[edge_id].1));
+        std::iter::once(&self.vector[from].coordinates)
+            .chain(self.edge_shapes.get(&edge_id).into_iter().flatten())
+            .chain(std::iter::once(&self.vector[to].coordinates))
+    }
+
+    /// Returns indices of nodes within `angular_radius` (central angle in radians) from `center`,
+    /// in ascending order. See `SphereCap`.
+    pub fn nodes_in_cap(&self, center: &SpherePoint, angular_radius: f64) -> Vec<usize> {
+        let cap = SphereCap::new(center.clone(), angular_radius);
+        (0..self.len())
+            .filter(|index| cap.contains(&self.vector[*index].coordinates))
+            .collect()
+    }
+
+    /// Sets new costs of connections identified by EdgeId, in both directions.
+    /// Updates are applied atomically: when any EdgeId is unknown (`DataItemIncorrect`),
+    /// any cost is negative (`NegativeCost`) or not finite (`InvalidParameter`) no cost is changed.
+    /// When the same EdgeId is given more than once the last cost wins.
+    ///
+    /// ```
+    /// use path_navigator::components::*;
+    /// use path_navigator::data::CelestialObject;
+    /// use path_navigator::vertex::VertexBuffer;
+    ///
+    /// let connections = vec![SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(1.0, 1.0))];
+    /// let mut vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+    /// let edge_id = vertex.find_edge(0, 1).unwrap();
+    /// vertex.update_costs(&[(edge_id, 42.0)]).unwrap();
+    /// assert_eq!(vertex.vector[1].graphs[0].cost, 42.0);
+    /// ```
+    pub fn update_costs(&mut self, updates: &[(EdgeId, f64)]) -> Result<()> {
+        for (edge_id, cost) in updates {
+            if *edge_id >= self.edges.len() {
+                return Err(Error::from_kind(ErrorKind::DataItemIncorrect));
+            }
+            if !cost.is_finite() {
+                return Err(Error::from_kind(ErrorKind::InvalidParameter));
+            }
+            if *cost < 0.0 {
+                return Err(Error::from_kind(ErrorKind::NegativeCost(*edge_id, *cost)));
+            }
+        }
+        for (edge_id, cost) in updates {
+            let (from, to) = (from_node_index(self.edges[*edge_id].0), from_node_index(self.edges[*edge_id].1));
+            for index in [from, to] {
+                self.vector[index].graphs.iter_mut()
+                    .filter(|rel| from_node_index(rel.edge_id) == *edge_id)
+                    .for_each(|rel| rel.cost = *cost);
+            }
+        }
+        self.version += 1;
+        self.generation = next_generation();
+        Ok(())
+    }
+
+    /// Returns version of connection costs and structure, 0 for newly built or loaded VertexBuffer.
+    /// It grows with every `update_costs` and `EditSession::commit`, so it counts changes, but two
+    /// VertexBuffers, or a clone changed apart from its original, may share it, see `generation`.
+    /// Changes made directly in `vector` are not counted.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Returns stamp unique within the process, new for every built, cloned, loaded or deserialized
+    /// VertexBuffer and after every change made through its methods, so caches of search results like
+    /// `cache::RouteCache` can tell they were filled on the same graph. Changes made directly in
+    /// `vector` keep it.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Sets version of rebuilt VertexBuffer replacing an older one.
+    pub(crate) fn set_version(&mut self, version: u64) {
+        self.version = version;
+    }
+
+    /// Checks that all connection costs are valid for shortest path search, which is useful after
+    /// costs were changed directly in `vector`. Dijkstra gives silently wrong answers on negative
+    /// costs, and this crate has no Bellman-Ford alternative, so negative costs are rejected.
+    ///
+    /// Returns `NegativeCost` with the first offending EdgeId, or `InvalidParameter` for NaN cost.
+    pub fn validate_costs(&self) -> Result<()> {
+        for node in &self.vector {
+            for rel in &node.graphs {
+                if rel.cost.is_nan() {
+                    return Err(Error::from_kind(ErrorKind::InvalidParameter));
+                }
+                if rel.cost < 0.0 {
+                    return Err(Error::from_kind(ErrorKind::NegativeCost(from_node_index(rel.edge_id), rel.cost)));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets variance of travel cost of connection with given EdgeId, on both of its GraphRelations when
+    /// it is two-way. Connection cost is treated as the mean of travel cost, variance defaults to 0.0.
+    /// Used by percentile routing in `stochastic` module.
+    ///
+    /// Returns `DataItemIncorrect` for unknown EdgeId and `InvalidParameter` when variance is negative
+    /// or not finite.
+    pub fn set_edge_variance(&mut self, edge_id: EdgeId, variance: f64) -> Result<()> {
+        if !variance.is_finite() || variance < 0.0 {
+            return Err(Error::from_kind(ErrorKind::InvalidParameter));
+        }
+        let (from, to) = self.edge(edge_id).ok_or_else(|| Error::from_kind(ErrorKind::DataItemIncorrect))?;
+        let ends = match self.directions[edge_id] {
+            Direction::TwoWay => vec![from, to],
+            Direction::OneWay => vec![from],
+        };
+        for index in ends {
+            self.vector[index].graphs.iter_mut()
+                .filter(|rel| from_node_index(rel.edge_id) == edge_id)
+                .for_each(|rel| rel.variance = variance);
+        }
+        self.generation = next_generation();
+        Ok(())
+    }
+
+    /// Appends connection, returns PolicyAction applied when it duplicates earlier connection.
+    fn append(&mut self, connection: SphereConnection, cost: f64, duplicates: EdgePolicy, direction: Direction)
+    -> Result<Option<PolicyAction>> {
+        // two new nodes and the new EdgeId have to fit into NodeIndex
+        if NodeIndex::try_from(self.vector.len() + 1).is_err() || NodeIndex::try_from(self.edges.len()).is_err() {
+            return Err(Error::from_kind(ErrorKind::DataItemIncorrect));
+        }
+        let start_vertex_index = match self.index_of(&connection.start) {
             Some(v) => v,
             None => self.add(connection.start.clone()),
         };
-        let end_vertex_index = match end_index_option {
+        let end_vertex_index = match self.index_of(&connection.finish) {
             Some(v) => v,
             None => self.add(connection.finish.clone()),
         };
-        let radius = get_radius_km(&self.celestial_object);
-        let cost: f64 = connection.cost(radius);
-        &mut self.update(&start_vertex_index, &end_vertex_index, cost.clone());
-        &mut self.update(&end_vertex_index, &start_vertex_index, cost.clone());
+        let existing = match self.find_edge(start_vertex_index, end_vertex_index) {
+            Some(edge_id) => edge_id,
+            None => {
+                self.insert_edge(start_vertex_index, end_vertex_index, cost, direction);
+                return Ok(None);
+            }
+        };
+        match duplicates {
+            EdgePolicy::Reject => Err(Error::from_kind(ErrorKind::DataItemIncorrect)),
+            EdgePolicy::WarnAndSkip => Ok(Some(PolicyAction::Skipped)),
+            EdgePolicy::KeepAll => {
+                self.insert_edge(start_vertex_index, end_vertex_index, cost, direction);
+                Ok(Some(PolicyAction::KeptParallel))
+            }
+            EdgePolicy::KeepCheapest => {
+                let existing_cost = self.vector[start_vertex_index].graphs.iter()
+                    .find(|rel| from_node_index(rel.edge_id) == existing)
+                    .map_or(f64::INFINITY, |rel| rel.cost);
+                if cost < existing_cost {
+                    // set directly, as `update_costs` would count a change of the VertexBuffer being built
+                    for index in [start_vertex_index, end_vertex_index] {
+                        self.vector[index].graphs.iter_mut()
+                            .filter(|rel| from_node_index(rel.edge_id) == existing)
+                            .for_each(|rel| rel.cost = cost);
+                    }
+                    Ok(Some(PolicyAction::Replaced))
+                } else {
+                    Ok(Some(PolicyAction::Skipped))
+                }
+            }
+        }
+    }
+
+    fn insert_edge(&mut self, from: usize, to: usize, cost: f64, direction: Direction) {
+        let edge_id: EdgeId = self.edges.len();
+        // self-loop is a single GraphRelation, which can be travelled either way
+        let direction = if from == to { Direction::TwoWay } else { direction };
+        self.vector[from].graphs.push(GraphRelation {edge_id: to_node_index(edge_id), ..GraphRelation::new(to, cost)});
+        if direction == Direction::TwoWay && from != to {
+            self.vector[to].graphs.push(GraphRelation {edge_id: to_node_index(edge_id), ..GraphRelation::new(from, cost)});
+        }
+        self.edges.push((to_node_index(from), to_node_index(to)));
+        self.directions.push(direction);
+    }
+
+    /// Joins nodes on both sides of the antimeridian within `tolerance_km`, see `BuildOptions::antimeridian_km`.
+    fn stitch_antimeridian(&mut self, tolerance_km: f64, radius: f64, cost_decimals: Option<u32>) {
+        let near_seam = |point: &SpherePoint| {
+            SphereConnection::new(point.clone(), SpherePoint::new(point.lat, 180.0)).cost(radius) <= tolerance_km
+        };
+        let (east, west): (Vec<usize>, Vec<usize>) = (0..self.vector.len())
+            .filter(|index| self.vector[*index].coordinates.lng != 0.0 && near_seam(&self.vector[*index].coordinates))
+            .partition(|index| self.vector[*index].coordinates.lng > 0.0);
+        for from in east {
+            let closest = west.iter()
+                .map(|to| (*to, SphereConnection::new(self.vector[from].coordinates.clone(), self.vector[*to].coordinates.clone()).cost(radius)))
+                .filter(|(_, distance)| *distance <= tolerance_km)
+                .min_by(|a, b| a.1.total_cmp(&b.1));
+            if let Some((to, distance)) = closest {
+                if self.find_edge(from, to).is_none() {
+                    let cost = cost_decimals.map_or(distance, |decimals| round_cost(distance, decimals));
+                    self.insert_edge(from, to, cost, Direction::TwoWay);
+                }
+            }
+        }
     }
 
     fn add(&mut self, coordinates: SpherePoint) -> usize {
+        self.indices.insert(coordinates_key(&coordinates), self.vector.len());
         self.vector.push(VertexSpherePoint::new(coordinates));
         self.vector.len() - 1
     }
+}
 
-    fn update(&mut self, index_to_update: &usize, index_related: &usize, cost: f64) {
-        if self.vector[*index_to_update].graphs.iter()
-            .position(|rel| rel.vertex_index == *index_related)
-            .is_none() {
-                &mut self.vector[*index_to_update].graphs
-                .push(GraphRelation::new(*index_related, cost));
-        }
+/// Rounds cost to given decimal places half away from zero. Scale is built by exact multiplications,
+/// so the result only depends on IEEE 754 arithmetic and is the same on every platform.
+fn round_cost(cost: f64, decimals: u32) -> f64 {
+    let scale = (0..decimals).fold(1.0, |scale: f64, _| scale * 10.0);
+    (cost * scale).round() / scale
+}
+
+/// Splits connection longer than `max_segment_km` into chain of equal great-circle segments.
+/// Intermediate points are interpolated from the lower of both endpoints, so connections given in
+/// opposite directions produce exactly the same nodes.
+fn densify(connection: &SphereConnection, max_segment_km: f64, radius: f64) -> Vec<SphereConnection> {
+    let segments = (connection.cost(radius) / max_segment_km).ceil();
+    if segments <= 1.0 {
+        return vec![connection.clone()];
+    }
+    let is_reversed = (connection.start.lat, connection.start.lng) > (connection.finish.lat, connection.finish.lng);
+    let canonical = if is_reversed {
+        SphereConnection::new(connection.finish.clone(), connection.start.clone())
+    } else {
+        connection.clone()
+    };
+    let count = segments as usize;
+    let mut points: Vec<SpherePoint> = vec![canonical.start.clone()];
+    points.extend((1..count).map(|i| canonical.interpolate(i as f64 / segments)));
+    points.push(canonical.finish.clone());
+    if is_reversed {
+        points.reverse();
+    }
+    points.windows(2)
+        .map(|pair| SphereConnection::new(pair[0].clone(), pair[1].clone()))
+        .collect()
+}
+
+/// Code of celestial object in binary format of VertexBuffer, its position in CelestialObject.
+fn celestial_code(celestial_object: &CelestialObject) -> u64 {
+    match celestial_object {
+        CelestialObject::MERCURY => 0,
+        CelestialObject::VENUS => 1,
+        CelestialObject::EARTH => 2,
+        CelestialObject::MARS => 3,
+        CelestialObject::JUPITER => 4,
+        CelestialObject::SATURN => 5,
+        CelestialObject::URANUS => 6,
+        CelestialObject::NEPTUNE => 7,
+    }
+}
+
+fn celestial_object(code: u64) -> Option<CelestialObject> {
+    match code {
+        0 => Some(CelestialObject::MERCURY),
+        1 => Some(CelestialObject::VENUS),
+        2 => Some(CelestialObject::EARTH),
+        3 => Some(CelestialObject::MARS),
+        4 => Some(CelestialObject::JUPITER),
+        5 => Some(CelestialObject::SATURN),
+        6 => Some(CelestialObject::URANUS),
+        7 => Some(CelestialObject::NEPTUNE),
+        _ => None,
+    }
+}
+
+/// Key of node lookup by exact coordinates, zero is normalized as `0.0 == -0.0` for SpherePoint.
+pub(crate) fn coordinates_key(point: &SpherePoint) -> (u64, u64) {
+    ((point.lat + 0.0).to_bits(), (point.lng + 0.0).to_bits())
+}
+
+/// Returns cache key of connection with its ends ordered by coordinates, and connection in that order,
+/// so both directions are priced by the same computation.
+fn canonical_connection(connection: &SphereConnection) -> (ConnectionKey, SphereConnection) {
+    let (start, finish) = (coordinates_key(&connection.start), coordinates_key(&connection.finish));
+    if (connection.start.lat, connection.start.lng) <= (connection.finish.lat, connection.finish.lng) {
+        ((start, finish), connection.clone())
+    } else {
+        ((finish, start), SphereConnection::new(connection.finish.clone(), connection.start.clone()))
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::dijkstra::find_path;
 
     #[test]
     fn test_vertex_creation() {
@@ -220,5 +1838,700 @@ mod test {
         // beggining and last node of each arm are connected
         assert_eq!(connections.len(), vertex_buffer.unwrap().len() + 2);
     }
-}
 
+    #[test]
+    fn test_update_costs() {
+        // given
+        let first_point = SpherePoint::new(0.0, 0.0);
+        let second_point = SpherePoint::new(1.0, 2.0);
+        let third_point = SpherePoint::new(2.0, 2.0);
+        let connections = vec![
+            SphereConnection::new(first_point.clone(), second_point.clone()),
+            SphereConnection::new(second_point.clone(), first_point.clone()),
+            SphereConnection::new(second_point, third_point),
+        ];
+        let mut vertex_buffer = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        // when
+        let edge_id = vertex_buffer.find_edge(2, 1).unwrap();
+        let rejected = vertex_buffer.update_costs(&[(edge_id, 1.0), (7, 2.0)]);
+        let applied = vertex_buffer.update_costs(&[(edge_id, 3.0)]);
+        // then
+        assert_eq!(vertex_buffer.edge_count(), 2);
+        assert_eq!(vertex_buffer.edge(edge_id), Some((1, 2)));
+        assert!(rejected.is_err());
+        assert!(applied.is_ok());
+        assert!(vertex_buffer.vector[1].graphs.iter().any(|rel| rel.vertex_index == 2 && rel.cost == 3.0));
+        assert!(vertex_buffer.vector[2].graphs.iter().all(|rel| rel.cost == 3.0));
+        assert!(vertex_buffer.update_costs(&[(edge_id, -1.0)]).is_err());
+    }
+
+    #[test]
+    fn test_negative_cost_validation() {
+        // given
+        let connections = vec![SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(1.0, 2.0))];
+        let mut vertex_buffer = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        // when
+        let rejected = vertex_buffer.update_costs(&[(0, -1.0)]);
+        vertex_buffer.vector[1].graphs[0].cost = -2.0;
+        // then
+        match rejected.unwrap_err().kind() {
+            ErrorKind::NegativeCost(edge_id, cost) => assert_eq!((*edge_id, *cost), (0, -1.0)),
+            other => panic!("unexpected error {}", other),
+        }
+        assert!(vertex_buffer.vector[0].graphs[0].cost > 0.0);
+        assert!(matches!(vertex_buffer.validate_costs().unwrap_err().kind(), ErrorKind::NegativeCost(0, _)));
+    }
+
+    #[test]
+    fn test_local_projection() {
+        // given
+        let connections = vec![
+            SphereConnection::new(SpherePoint::new(54.3520, 18.6466), SpherePoint::new(54.3525, 18.6470)),
+            SphereConnection::new(SpherePoint::new(54.3525, 18.6470), SpherePoint::new(54.3600, 18.6600)),
+        ];
+        let options = BuildOptions {local_projection: true, ..BuildOptions::default()};
+        // when
+        let haversine = VertexBuffer::new(connections.clone(), CelestialObject::EARTH).unwrap();
+        let (planar, _) = VertexBuffer::build(connections, CelestialObject::EARTH, &options).unwrap();
+        // then
+        for edge_id in 0..haversine.edge_count() {
+            let (from, _) = haversine.edge(edge_id).unwrap();
+            let expected = haversine.vector[from].graphs.iter().find(|rel| from_node_index(rel.edge_id) == edge_id).unwrap().cost;
+            let actual = planar.vector[from].graphs.iter().find(|rel| from_node_index(rel.edge_id) == edge_id).unwrap().cost;
+            assert!(relative_eq!(actual, expected, max_relative = 1e-6));
+        }
+    }
+
+    #[test]
+    fn test_nodes_in_cap() {
+        // given
+        let connections = vec![
+            SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+            SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 3.0)),
+            SphereConnection::new(SpherePoint::new(0.0, 3.0), SpherePoint::new(0.0, -179.5)),
+        ];
+        let vertex_buffer = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        // when
+        let near_origin = vertex_buffer.nodes_in_cap(&SpherePoint::new(0.5, 0.5), 1.0_f64.to_radians());
+        let over_antimeridian = vertex_buffer.nodes_in_cap(&SpherePoint::new(0.0, 179.5), 1.5_f64.to_radians());
+        // then
+        assert_eq!(near_origin, vec![0, 1]);
+        assert_eq!(over_antimeridian, vec![3]);
+    }
+
+    #[test]
+    fn test_build_from_geo_points() {
+        // given
+        struct Station {
+            position: (f64, f64),
+        }
+        impl GeoPoint for Station {
+            fn latitude(&self) -> f64 { self.position.0 }
+            fn longitude(&self) -> f64 { self.position.1 }
+        }
+        let pairs = vec![(Station {position: (1.0, 1.0)}, Station {position: (2.0, 2.0)})];
+        // when
+        let vertex_buffer = VertexBuffer::from_geo_points(&pairs, CelestialObject::MARS).unwrap();
+        // then
+        assert_eq!(vertex_buffer.index_of(&SpherePoint::new(2.0, 2.0)), Some(1));
+        assert_eq!(vertex_buffer.closest_node(&Station {position: (1.2, 0.9)}), Some(0));
+    }
+
+    #[test]
+    fn test_densification() {
+        // given
+        let start = SpherePoint::new(10.0, 10.0);
+        let finish = SpherePoint::new(20.0, 30.0);
+        let connections = vec![
+            SphereConnection::new(start.clone(), finish.clone()),
+            SphereConnection::new(finish.clone(), start.clone()),
+            SphereConnection::new(finish.clone(), SpherePoint::new(20.0, 30.5)),
+        ];
+        let options = BuildOptions {max_segment_km: Some(250.0), ..BuildOptions::default()};
+        let radius = get_radius_km(&CelestialObject::EARTH);
+        let length = SphereConnection::new(start, finish).cost(radius);
+        // when
+        let vertex_buffer = VertexBuffer::with_options(connections, CelestialObject::EARTH, &options).unwrap();
+        // then
+        let segments = (length / 250.0).ceil() as usize;
+        assert_eq!(vertex_buffer.edge_count(), segments + 1);
+        assert_eq!(vertex_buffer.len(), segments + 2);
+        for node in &vertex_buffer.vector {
+            for rel in &node.graphs {
+                assert!(rel.cost <= 250.0);
+            }
+        }
+        assert!(VertexBuffer::with_options(vec![], CelestialObject::EARTH, &options).is_err());
+        let zero_options = BuildOptions {max_segment_km: Some(0.0), ..BuildOptions::default()};
+        let connections = vec![SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(1.0, 1.0))];
+        assert!(VertexBuffer::with_options(connections, CelestialObject::EARTH, &zero_options).is_err());
+    }
+
+    #[test]
+    fn test_duplicate_and_self_loop_policies() {
+        // given
+        let first_point = SpherePoint::new(0.0, 0.0);
+        let second_point = SpherePoint::new(1.0, 2.0);
+        let connections = vec![
+            SphereConnection::new(first_point.clone(), second_point.clone()),
+            SphereConnection::new(second_point.clone(), first_point.clone()),
+            SphereConnection::new(second_point.clone(), second_point.clone()),
+        ];
+        let options = |duplicates: EdgePolicy, self_loops: EdgePolicy| BuildOptions {duplicates, self_loops, ..BuildOptions::default()};
+        // when
+        let rejected = VertexBuffer::build(connections.clone(), CelestialObject::EARTH, &options(EdgePolicy::Reject, EdgePolicy::WarnAndSkip));
+        let (skipped, skipped_report) = VertexBuffer::build(connections.clone(), CelestialObject::EARTH,
+            &options(EdgePolicy::WarnAndSkip, EdgePolicy::WarnAndSkip)).unwrap();
+        let (parallel, parallel_report) = VertexBuffer::build(connections.clone(), CelestialObject::EARTH,
+            &options(EdgePolicy::KeepAll, EdgePolicy::KeepAll)).unwrap();
+        let (cheapest, _) = VertexBuffer::build(connections, CelestialObject::EARTH,
+            &options(EdgePolicy::KeepCheapest, EdgePolicy::WarnAndSkip)).unwrap();
+        // then
+        assert!(rejected.is_err());
+        assert_eq!(skipped.edge_count(), 1);
+        assert_eq!(skipped_report.applied, vec![
+            AppliedPolicy {connection_index: 1, issue: EdgeIssue::Duplicate, action: PolicyAction::Skipped},
+            AppliedPolicy {connection_index: 2, issue: EdgeIssue::SelfLoop, action: PolicyAction::Skipped},
+        ]);
+        assert_eq!(parallel.edge_count(), 3);
+        assert_eq!(parallel.vector[0].graphs.len(), 2);
+        assert_eq!(parallel.vector[1].graphs.len(), 3);
+        assert_eq!(parallel_report.applied[0].action, PolicyAction::KeptParallel);
+        assert_eq!(parallel_report.applied[1].action, PolicyAction::Kept);
+        assert_eq!(cheapest.edge_count(), 1);
+    }
+
+    #[test]
+    fn test_version_of_build_replacing_duplicate() {
+        // given: a connection and its reverse, cheaper by an asymmetric metric
+        let uphill = |a: &SpherePoint, b: &SpherePoint| if a.lng < b.lng { 10.0 } else { 5.0 };
+        let connections = vec![
+            SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+            SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 0.0)),
+        ];
+        let options = BuildOptions {duplicates: EdgePolicy::KeepCheapest, ..BuildOptions::default()};
+        // when
+        let vertex = VertexBuffer::with_metric(connections, CelestialObject::EARTH, &options, &uphill).unwrap();
+        // then
+        assert_eq!(vertex.edge_cost(0), Some(5.0));
+        assert_eq!(vertex.version(), 0);
+    }
+
+    #[test]
+    fn test_keep_cheapest_replaces_dearer_duplicate() {
+        // given: a connection and its cheaper reverse by an asymmetric metric
+        let uphill = |a: &SpherePoint, b: &SpherePoint| if a.lng < b.lng { 10.0 } else { 5.0 };
+        let connections = vec![
+            SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+            SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 0.0)),
+            SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+        ];
+        let options = BuildOptions {duplicates: EdgePolicy::KeepCheapest, ..BuildOptions::default()};
+        // when
+        let (vertex, report, _) = VertexBuffer::build_tracked(connections, CelestialObject::EARTH, &options, None, Some(&uphill), |_| true).unwrap();
+        // then
+        assert_eq!(vertex.edge_count(), 1);
+        assert_eq!((vertex.vector[0].graphs[0].cost, vertex.vector[1].graphs[0].cost), (5.0, 5.0));
+        assert_eq!(report.applied, vec![
+            AppliedPolicy {connection_index: 1, issue: EdgeIssue::Duplicate, action: PolicyAction::Replaced},
+            AppliedPolicy {connection_index: 2, issue: EdgeIssue::Duplicate, action: PolicyAction::Skipped},
+        ]);
+    }
+
+    #[test]
+    fn test_nearest_named() {
+        // given
+        let connections = vec![
+            SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+            SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 2.0)),
+        ];
+        let mut vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        vertex.set_node_metadata(0, "name", "west").unwrap();
+        vertex.set_node_metadata(2, "name", "east").unwrap();
+        vertex.set_node_metadata(2, "waypoint", "yes").unwrap();
+        let query = SpherePoint::new(0.0, 0.9);
+        // when
+        let named = vertex.nearest_named(&query, |metadata| metadata.contains_key("name"));
+        let waypoint = vertex.nearest_named(&query, |metadata| metadata.contains_key("waypoint"));
+        let missing = vertex.nearest_named(&query, |metadata| metadata.contains_key("harbor"));
+        // then
+        assert_eq!(named, Some(0));
+        assert_eq!(waypoint, Some(2));
+        assert_eq!(missing, None);
+        assert_eq!(vertex.node_metadata(2).unwrap()["name"], "east");
+        assert!(vertex.node_metadata(1).is_none());
+        assert!(vertex.set_node_metadata(3, "name", "none").is_err());
+    }
+
+    #[test]
+    fn test_round_trip_through_connections() {
+        // given: a duplicate in reverse direction and a connection densified into segments
+        let connections = vec![
+            SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+            SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(0.0, 0.0)),
+            SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(2.0, 1.0)),
+        ];
+        let options = BuildOptions {max_segment_km: Some(150.0), ..BuildOptions::default()};
+        let vertex = VertexBuffer::with_options(connections, CelestialObject::EARTH, &options).unwrap();
+        // when
+        let edge_list = vertex.to_connections();
+        let rebuilt = VertexBuffer::new(edge_list.clone(), CelestialObject::EARTH).unwrap();
+        // then
+        assert_eq!(edge_list.len(), 3);
+        assert_eq!(edge_list[1].finish, edge_list[2].start);
+        assert_eq!(rebuilt.vector, vertex.vector);
+        assert!((0..vertex.edge_count()).all(|edge_id| rebuilt.edge(edge_id) == vertex.edge(edge_id)));
+        assert_eq!(rebuilt.to_connections(), edge_list);
+    }
+
+    #[test]
+    fn test_cost_rounding() {
+        // given
+        let connections = vec![SphereConnection::new(SpherePoint::new(54.35, 18.65), SpherePoint::new(54.52, 18.55))];
+        let rounded = |cost_decimals: Option<u32>| {
+            let options = BuildOptions {cost_decimals, ..BuildOptions::default()};
+            VertexBuffer::with_options(connections.clone(), CelestialObject::EARTH, &options).map(|vertex| vertex.vector[0].graphs[0].cost)
+        };
+        // when
+        let exact = rounded(None).unwrap();
+        let meters = rounded(Some(3)).unwrap();
+        let kilometers = rounded(Some(0)).unwrap();
+        // then
+        assert_ne!(exact, meters);
+        assert!((exact - meters).abs() <= 0.0005);
+        assert_eq!(meters, (exact * 1000.0).round() / 1000.0);
+        assert_eq!(kilometers, exact.round());
+        assert_eq!(round_cost(2.5, 0), 3.0);
+        assert_eq!(round_cost(0.125, 2), 0.13);
+        assert!(rounded(Some(MAX_COST_DECIMALS + 1)).is_err());
+    }
+
+    #[test]
+    fn test_metrics() {
+        // given: connections along a meridian, along the 60th parallel and across the antimeridian
+        let radius = get_radius_km(&CelestialObject::EARTH);
+        let connections = vec![
+            SphereConnection::new(SpherePoint::new(0.0, 10.0), SpherePoint::new(10.0, 10.0)),
+            SphereConnection::new(SpherePoint::new(60.0, 0.0), SpherePoint::new(60.0, 20.0)),
+            SphereConnection::new(SpherePoint::new(10.0, 179.0), SpherePoint::new(20.0, -179.0)),
+        ];
+        let costs = |metric: &dyn Metric| {
+            let vertex = VertexBuffer::with_metric(connections.clone(), CelestialObject::EARTH, &BuildOptions::default(), metric).unwrap();
+            (0..vertex.edge_count()).map(|edge_id| vertex.edge_cost(edge_id).unwrap()).collect::<Vec<f64>>()
+        };
+        // when
+        let haversine = costs(&HaversineMetric {radius});
+        let vincenty = costs(&VincentyMetric {radius});
+        let rhumb = costs(&RhumbMetric {radius});
+        let flat = costs(&|_: &SpherePoint, _: &SpherePoint| 1.0);
+        // then
+        assert_eq!(haversine, (0..3).map(|edge_id| VertexBuffer::new(connections.clone(), CelestialObject::EARTH).unwrap().edge_cost(edge_id).unwrap()).collect::<Vec<f64>>());
+        assert!(haversine.iter().zip(&vincenty).all(|(a, b)| relative_eq!(a, b, max_relative = 1e-9)));
+        assert!(relative_eq!(rhumb[0], haversine[0], max_relative = 1e-12));
+        assert!(relative_eq!(rhumb[1], radius * 60f64.to_radians().cos() * 20f64.to_radians(), max_relative = 1e-12));
+        assert!(rhumb[1] > haversine[1]);
+        // the shorter way across the antimeridian, not around the globe
+        assert!(rhumb[2] < 1.01 * haversine[2]);
+        assert_eq!(flat, vec![1.0; 3]);
+        let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        let points = vec![SpherePoint::new(60.0, 12.0)];
+        assert_eq!(vertex.closest_node_with(&points[0], &HaversineMetric {radius}), vertex.closest_node(&points[0]));
+        assert_eq!(vertex.snap_batch(&points, 1e4), vertex.snap_batch_with(&points, 1e4, &HaversineMetric {radius}));
+    }
+
+    #[test]
+    fn test_nearest_point_on_network() {
+        // given: an L of connections 0 - 1 along the equator and 1 - 2 north along meridian 1
+        let connections = vec![
+            SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+            SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(1.0, 1.0)),
+        ];
+        let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        let degree = get_radius_km(&CelestialObject::EARTH) * 1f64.to_radians();
+        // when
+        let beside_meridian = vertex.nearest_point_on_network(&SpherePoint::new(0.75, 1.2)).unwrap();
+        let behind_start = vertex.nearest_point_on_network(&SpherePoint::new(0.0, -0.5)).unwrap();
+        let on_node = vertex.nearest_point_on_network(&SpherePoint::new(0.0, 1.0)).unwrap();
+        // then
+        assert_eq!(beside_meridian.edge_id, 1);
+        assert!(relative_eq!(beside_meridian.point.lat, 0.75, max_relative = 1e-3));
+        assert!(relative_eq!(beside_meridian.point.lng, 1.0, max_relative = 1e-9));
+        assert!(relative_eq!(beside_meridian.fraction, 0.75, max_relative = 1e-3));
+        assert!(relative_eq!(beside_meridian.distance, 0.2 * degree * 0.75f64.to_radians().cos(), max_relative = 1e-3));
+        assert_eq!((behind_start.edge_id, behind_start.fraction), (0, 0.0));
+        assert_eq!(behind_start.point, SpherePoint::new(0.0, 0.0));
+        assert!(relative_eq!(behind_start.distance, 0.5 * degree, max_relative = 1e-9));
+        assert_eq!((on_node.edge_id, on_node.distance), (0, 0.0));
+        assert_eq!(on_node.fraction, 1.0);
+    }
+
+    #[test]
+    fn test_nearest_point_on_shaped_connection() {
+        // given: a connection along the equator bent north through (1, 1) by its shape
+        let connections = vec![SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 2.0))];
+        let mut vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        vertex.set_edge_shape(0, vec![SpherePoint::new(1.0, 1.0)]).unwrap();
+        // when
+        let on_bend = vertex.nearest_point_on_network(&SpherePoint::new(1.0, 1.0)).unwrap();
+        let on_chord = vertex.nearest_point_on_network(&SpherePoint::new(0.0, 1.0)).unwrap();
+        // then
+        assert_eq!(on_bend.edge_id, 0);
+        assert!(on_bend.distance < 1e-6);
+        assert!(relative_eq!(on_bend.fraction, 0.5, max_relative = 1e-6));
+        let degree = get_radius_km(&CelestialObject::EARTH) * 1f64.to_radians();
+        assert!(relative_eq!(on_chord.distance, degree * 0.5f64.sqrt(), max_relative = 1e-2));
+    }
+
+    #[test]
+    fn test_stable_indices() {
+        // given: pseudo-random connections with shared nodes, repeated connections and reversed duplicates
+        let point = |seed: u64| SpherePoint::new((seed * 7919 % 60) as f64 - 30.0, (seed * 104_729 % 120) as f64 - 60.0);
+        let mut connections: Vec<SphereConnection> = (0..200)
+            .map(|i| SphereConnection::new(point(i % 37), point((i * 13 + 5) % 41)))
+            .filter(|connection| connection.start != connection.finish)
+            .collect();
+        connections.push(SphereConnection::new(connections[3].finish.clone(), connections[3].start.clone()));
+        // when
+        let vertex = VertexBuffer::new(connections.clone(), CelestialObject::EARTH).unwrap();
+        let again = VertexBuffer::new(connections.clone(), CelestialObject::EARTH).unwrap();
+        let rebuilt = VertexBuffer::new(vertex.to_connections(), CelestialObject::EARTH).unwrap();
+        let directed = VertexBuffer::new_directed(connections.clone(), CelestialObject::EARTH).unwrap();
+        let rebuilt_directed = VertexBuffer::with_directions(directed.to_directed_connections(), CelestialObject::EARTH,
+            &BuildOptions::default()).unwrap();
+        // then: nodes numbered by first appearance
+        let mut expected: Vec<SpherePoint> = Vec::new();
+        for connection in &connections {
+            for end in [&connection.start, &connection.finish] {
+                if !expected.contains(end) {
+                    expected.push(end.clone());
+                }
+            }
+        }
+        let coordinates = |vertex: &VertexBuffer| vertex.vector.iter().map(|node| node.coordinates.clone()).collect::<Vec<SpherePoint>>();
+        assert_eq!(coordinates(&vertex), expected);
+        for other in [&again, &rebuilt] {
+            assert_eq!(other.vector, vertex.vector);
+            assert!((0..vertex.edge_count()).all(|edge_id| other.edge(edge_id) == vertex.edge(edge_id)));
+        }
+        assert!(vertex.vector.iter().all(|node| node.graphs.windows(2).all(|pair| pair[0].edge_id < pair[1].edge_id)));
+        assert_eq!(rebuilt_directed.vector, directed.vector);
+        assert_eq!(coordinates(&directed), expected);
+    }
+
+    #[test]
+    fn test_length_cache() {
+        // given: the same connection given twice in opposite directions and once densified
+        let connection = SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0));
+        let reversed = SphereConnection::new(connection.finish.clone(), connection.start.clone());
+        let options = BuildOptions {duplicates: EdgePolicy::KeepAll, ..BuildOptions::default()};
+        let mut vertex = VertexBuffer::with_options(vec![connection.clone(), reversed.clone()], CelestialObject::EARTH, &options).unwrap();
+        let earth = connection.cost(get_radius_km(&CelestialObject::EARTH));
+        // when
+        let cached = (vertex.great_circle_length(&connection), vertex.great_circle_length(&reversed));
+        vertex.celestial_object = CelestialObject::MARS;
+        let on_mars = vertex.great_circle_length(&connection);
+        vertex.invalidate_length_cache();
+        // then
+        assert_eq!(cached, (earth, earth));
+        assert_eq!(vertex.vector[0].graphs[0].cost, vertex.vector[0].graphs[1].cost);
+        assert_eq!(on_mars, connection.cost(get_radius_km(&CelestialObject::MARS)));
+        assert_eq!(vertex.great_circle_length(&reversed), on_mars);
+        assert!(vertex.lengths.lengths.is_empty());
+    }
+
+    #[test]
+    fn test_structural_diff() {
+        // given: nightly build removing a connection, adding one and changing a cost
+        let point = |lng: f64| SpherePoint::new(0.0, lng);
+        let old = VertexBuffer::new(vec![
+            SphereConnection::new(point(0.0), point(1.0)),
+            SphereConnection::new(point(1.0), point(2.0)),
+            SphereConnection::new(point(2.0), point(3.0)),
+        ], CelestialObject::EARTH).unwrap();
+        let mut new = VertexBuffer::new(vec![
+            SphereConnection::new(point(3.0), point(2.0)),
+            SphereConnection::new(point(1.0), point(2.0)),
+            SphereConnection::new(point(3.0), point(4.0)),
+        ], CelestialObject::EARTH).unwrap();
+        new.update_costs(&[(1, 150.0)]).unwrap();
+        // when
+        let diff = old.structural_diff(&new, 1e-6).unwrap();
+        let loose = old.structural_diff(&new, 100.0).unwrap();
+        // then
+        assert_eq!(diff.removed_nodes, vec![0]);
+        assert_eq!(diff.added_nodes, vec![new.index_of(&point(4.0)).unwrap()]);
+        assert_eq!(diff.removed_edges, vec![0]);
+        assert_eq!(diff.added_edges, vec![2]);
+        assert_eq!(diff.cost_changes.len(), 1);
+        assert_eq!((diff.cost_changes[0].edge_id, diff.cost_changes[0].other_edge_id, diff.cost_changes[0].other_cost), (1, 1, 150.0));
+        assert!(loose.cost_changes.is_empty());
+        assert!(old.structural_diff(&old, 0.0).unwrap().is_empty());
+        assert_eq!(old.edge_cost(1), Some(old.vector[1].graphs[1].cost));
+        assert_eq!(old.edge_cost(3), None);
+    }
+
+    #[test]
+    fn test_structural_diff_of_directions() {
+        // given: the same line two-way, one-way, and one-way with the second connection turned around
+        let point = |lng: f64| SpherePoint::new(0.0, lng);
+        let connections = vec![SphereConnection::new(point(0.0), point(1.0)), SphereConnection::new(point(1.0), point(2.0))];
+        let two_way = VertexBuffer::new(connections.clone(), CelestialObject::EARTH).unwrap();
+        let one_way = VertexBuffer::new_directed(connections, CelestialObject::EARTH).unwrap();
+        let turned = VertexBuffer::new_directed(vec![
+            SphereConnection::new(point(0.0), point(1.0)),
+            SphereConnection::new(point(2.0), point(1.0)),
+        ], CelestialObject::EARTH).unwrap();
+        // when
+        let directed = two_way.structural_diff(&one_way, 0.0).unwrap();
+        let reversed = one_way.structural_diff(&turned, 0.0).unwrap();
+        // then
+        assert!(directed.added_nodes.is_empty() && directed.removed_nodes.is_empty());
+        assert_eq!(directed.removed_edges, vec![0, 1]);
+        assert_eq!(directed.added_edges, vec![0, 1]);
+        assert_eq!(reversed.removed_edges, vec![1]);
+        assert_eq!(reversed.added_edges, vec![1]);
+        assert!(one_way.structural_diff(&one_way.clone(), 0.0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_oneway_metadata() {
+        // given: one-way, reversed one-way, two-way and untagged connections around a block
+        let tags = |value: &str| -> Metadata { vec![("oneway".to_string(), value.to_string())].into_iter().collect() };
+        let connections = vec![
+            (SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 0.01)), tags("yes")),
+            (SphereConnection::new(SpherePoint::new(0.0, 0.01), SpherePoint::new(0.01, 0.01)), tags("-1")),
+            (SphereConnection::new(SpherePoint::new(0.01, 0.01), SpherePoint::new(0.01, 0.0)), tags("no")),
+            (SphereConnection::new(SpherePoint::new(0.01, 0.0), SpherePoint::new(0.0, 0.0)), Metadata::new()),
+        ];
+        let options = BuildOptions {oneway_key: Some("oneway".to_string()), max_segment_km: Some(0.6), ..BuildOptions::default()};
+        // when
+        let vertex = VertexBuffer::with_edge_metadata(connections.clone(), CelestialObject::EARTH, &options).unwrap();
+        let two_way = VertexBuffer::with_edge_metadata(connections, CelestialObject::EARTH, &BuildOptions {oneway_key: None, ..options}).unwrap();
+        // then: every connection is densified into two segments carrying its tags
+        assert_eq!(vertex.edge_count(), 8);
+        assert_eq!(vertex.edge_metadata(1).unwrap()["oneway"], "yes");
+        assert!(vertex.edge_metadata(6).is_none());
+        let corner = vertex.index_of(&SpherePoint::new(0.0, 0.01)).unwrap();
+        let (from, to) = vertex.edge(2).unwrap();
+        assert_eq!(to, corner);
+        assert!(vertex.vector[from].graphs.iter().any(|rel| rel.edge_id == 2));
+        assert!(vertex.vector[corner].graphs.is_empty());
+        assert_eq!(vertex.edge_cost(2), two_way.edge_cost(2));
+        assert_eq!(two_way.vector[corner].graphs.len(), 2);
+    }
+
+    #[test]
+    fn test_oneway_metadata_on_one_way_build() {
+        // given: connections built one-way, the first tagged as allowed only in reverse
+        let tags = |value: &str| -> Metadata { vec![("oneway".to_string(), value.to_string())].into_iter().collect() };
+        let (a, b, c) = (SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0), SpherePoint::new(1.0, 1.0));
+        let connections = vec![
+            (SphereConnection::new(a.clone(), b.clone()), tags("-1")),
+            (SphereConnection::new(b.clone(), c.clone()), tags("yes")),
+        ];
+        let options = BuildOptions {direction: Direction::OneWay, oneway_key: Some("oneway".to_string()), ..BuildOptions::default()};
+        // when
+        let vertex = VertexBuffer::with_edge_metadata(connections, CelestialObject::EARTH, &options).unwrap();
+        let mut session = crate::edit::EditSession::new(&vertex);
+        session.add_connection(SphereConnection::new(c.clone(), a.clone()));
+        let mut committed = vertex.clone();
+        let commit = session.commit(&mut committed);
+        // then: the reversed connection keeps its relation, now in its new start node
+        assert_eq!(vertex.edge(0), Some((1, 0)));
+        assert_eq!(vertex.edge_cost(0), Some(vertex.vector[1].graphs[0].cost));
+        assert!(vertex.vector[0].graphs.is_empty());
+        assert_eq!(vertex.vector[1].graphs.len(), 2);
+        let report = crate::health::health(&vertex, &crate::health::HealthOptions::default());
+        assert!(report.issues.is_empty());
+        assert!(commit.is_ok());
+        assert_eq!(find_path(&b, &a, &vertex).unwrap().vertex_indices, vec![1, 0]);
+        assert!(find_path(&a, &b, &vertex).is_none());
+    }
+
+    #[test]
+    fn test_directed_connections() {
+        // given: one-way ring a -> b -> c -> a, a repeated a -> b and its reverse b -> a
+        let (a, b, c) = (SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0), SpherePoint::new(1.0, 1.0));
+        let connections = vec![
+            SphereConnection::new(a.clone(), b.clone()),
+            SphereConnection::new(b.clone(), c.clone()),
+            SphereConnection::new(c.clone(), a.clone()),
+            SphereConnection::new(a.clone(), b.clone()),
+            SphereConnection::new(b.clone(), a.clone()),
+        ];
+        // when
+        let directed = VertexBuffer::new_directed(connections[..3].to_vec(), CelestialObject::EARTH).unwrap();
+        let (with_reverse, report) = VertexBuffer::build(connections.clone(), CelestialObject::EARTH,
+            &BuildOptions {direction: Direction::OneWay, ..BuildOptions::default()}).unwrap();
+        let two_way = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        // then
+        assert!(directed.vector.iter().all(|node| node.graphs.len() == 1));
+        assert_eq!(find_path(&a, &b, &directed).unwrap().vertex_indices, vec![0, 1]);
+        assert_eq!(find_path(&b, &a, &directed).unwrap().vertex_indices, vec![1, 2, 0]);
+        assert_eq!(with_reverse.edge_count(), 4);
+        assert_eq!(report.applied.len(), 1);
+        assert_eq!(with_reverse.find_edge(1, 0), Some(3));
+        assert_eq!(find_path(&b, &a, &with_reverse).unwrap().edge_ids, vec![3]);
+        assert_eq!(two_way.edge_count(), 3);
+        assert_eq!(find_path(&b, &a, &two_way).unwrap().edge_ids, vec![0]);
+    }
+
+    #[test]
+    fn test_edge_directions_survive_rebuild() {
+        // given: one-way street a -> b and two-way street b - c tagged for oneway_key
+        let (a, b, c) = (SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 0.01), SpherePoint::new(0.01, 0.01));
+        let tagged = |oneway: &str| vec![("oneway".to_string(), oneway.to_string())].into_iter().collect::<Metadata>();
+        let connections = vec![
+            (SphereConnection::new(a.clone(), b.clone()), tagged("yes")),
+            (SphereConnection::new(b.clone(), c.clone()), tagged("no")),
+        ];
+        let options = BuildOptions {oneway_key: Some("oneway".to_string()), ..BuildOptions::default()};
+        let vertex = VertexBuffer::with_edge_metadata(connections, CelestialObject::EARTH, &options).unwrap();
+        // when
+        let rebuilt = VertexBuffer::with_directions(vertex.to_directed_connections(), CelestialObject::EARTH,
+            &BuildOptions::default()).unwrap();
+        let mut saved: Vec<u8> = Vec::new();
+        vertex.save(&mut saved).unwrap();
+        let loaded = VertexBuffer::load(&mut saved.as_slice()).unwrap();
+        // then
+        for graph in [&vertex, &rebuilt, &loaded] {
+            assert_eq!((graph.edge_direction(0), graph.edge_direction(1)), (Some(Direction::OneWay), Some(Direction::TwoWay)));
+            assert!(graph.is_directed());
+            assert!(find_path(&b, &a, graph).is_none());
+            assert!(find_path(&c, &b, graph).is_some());
+        }
+        assert!(!VertexBuffer::new(vertex.to_connections(), CelestialObject::EARTH).unwrap().is_directed());
+        assert_eq!(vertex.edge_direction(2), None);
+    }
+
+    #[test]
+    fn test_snap_batch() {
+        // given
+        let connections = vec![
+            SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+            SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(1.0, 1.0)),
+        ];
+        let vertex = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        let points = vec![SpherePoint::new(0.01, 0.0), SpherePoint::new(1.0, 1.0), SpherePoint::new(3.0, 1.0)];
+        // when
+        let outcomes = vertex.snap_batch(&points, 2.0);
+        // then
+        let radius = get_radius_km(&CelestialObject::EARTH);
+        match outcomes[0] {
+            SnapOutcome::Snapped {vertex_index, distance} => {
+                assert_eq!(vertex_index, 0);
+                assert!(relative_eq!(distance, 0.01_f64.to_radians() * radius, max_relative = 1e-9));
+            }
+            _ => panic!("point close to node 0 should be snapped"),
+        }
+        assert_eq!(outcomes[1], SnapOutcome::Snapped {vertex_index: 2, distance: 0.0});
+        assert!(matches!(outcomes[2], SnapOutcome::TooFar {vertex_index: 2, distance} if distance > 200.0));
+    }
+
+    #[test]
+    fn test_long_edge_policies() {
+        // given: the last connection has a bad coordinate sending it across the globe
+        let connections = vec![
+            SphereConnection::new(SpherePoint::new(54.0, 18.0), SpherePoint::new(54.1, 18.2)),
+            SphereConnection::new(SpherePoint::new(54.1, 18.2), SpherePoint::new(54.2, 18.1)),
+            SphereConnection::new(SpherePoint::new(54.2, 18.1), SpherePoint::new(-54.2, 18.1)),
+        ];
+        let options = |long_edges: EdgePolicy| BuildOptions {max_edge_km: Some(50.0), long_edges, ..BuildOptions::default()};
+        // when
+        let rejected = VertexBuffer::build(connections.clone(), CelestialObject::EARTH, &options(EdgePolicy::Reject));
+        let (skipped, skipped_report) = VertexBuffer::build(connections.clone(), CelestialObject::EARTH,
+            &options(EdgePolicy::WarnAndSkip)).unwrap();
+        let (kept, kept_report) = VertexBuffer::build(connections, CelestialObject::EARTH, &options(EdgePolicy::KeepAll)).unwrap();
+        // then
+        match rejected.unwrap_err().kind() {
+            ErrorKind::EdgeTooLong(connection_index, length_km) => {
+                assert_eq!(*connection_index, 2);
+                assert!(*length_km > 10_000.0);
+            }
+            kind => panic!("unexpected error {:?}", kind),
+        }
+        assert_eq!((skipped.len(), skipped.edge_count()), (3, 2));
+        assert_eq!(skipped_report.applied, vec![
+            AppliedPolicy {connection_index: 2, issue: EdgeIssue::TooLong, action: PolicyAction::Skipped},
+        ]);
+        assert_eq!(kept.edge_count(), 3);
+        assert_eq!(kept_report.applied[0].action, PolicyAction::Kept);
+    }
+
+    #[test]
+    fn test_within_hops() {
+        // given
+        let mut connections: Vec<SphereConnection> = Vec::new();
+        for i in 0..5 {
+            connections.push(SphereConnection::new(SpherePoint::new(0.0, i as f64), SpherePoint::new(0.0, i as f64 + 1.0)));
+        }
+        let vertex_buffer = VertexBuffer::new(connections, CelestialObject::EARTH).unwrap();
+        // when
+        let mut neighborhood = vertex_buffer.within_hops(2, 2);
+        neighborhood.sort();
+        // then
+        assert_eq!(neighborhood, vec![0, 1, 2, 3, 4]);
+        assert_eq!(vertex_buffer.within_hops(0, 0), vec![0]);
+        assert_eq!(vertex_buffer.within_hops(0, 100).len(), 6);
+        assert!(vertex_buffer.within_hops(6, 1).is_empty());
+    }
+
+    #[test]
+    fn test_antimeridian_stitching() {
+        // given: two tiles split at the seam, the western one has its seam node at -180 and a second
+        // seam node slightly off
+        let connections = vec![
+            SphereConnection::new(SpherePoint::new(0.0, 179.0), SpherePoint::new(0.0, 180.0)),
+            SphereConnection::new(SpherePoint::new(0.0, 179.0), SpherePoint::new(1.0, 179.999)),
+            SphereConnection::new(SpherePoint::new(0.0, -180.0), SpherePoint::new(0.0, -179.0)),
+            SphereConnection::new(SpherePoint::new(1.0, -179.9995), SpherePoint::new(0.0, -179.0)),
+            SphereConnection::new(SpherePoint::new(30.0, -179.0), SpherePoint::new(30.0, -178.0)),
+        ];
+        let options = BuildOptions {antimeridian_km: Some(1.0), ..BuildOptions::default()};
+        // when
+        let split = VertexBuffer::new(connections.clone(), CelestialObject::EARTH).unwrap();
+        let stitched = VertexBuffer::with_options(connections, CelestialObject::EARTH, &options).unwrap();
+        // then
+        let (west, east) = (SpherePoint::new(0.0, 179.0), SpherePoint::new(0.0, -179.0));
+        assert!(crate::dijkstra::is_reachable(&west, &east, &split, f64::INFINITY).unwrap().is_none());
+        assert_eq!(stitched.edge_count(), 7);
+        assert_eq!(stitched.edge(5), Some((1, 3)));
+        assert!(stitched.edge_cost(5).unwrap() < 1e-9);
+        assert_eq!(stitched.edge(6), Some((2, 5)));
+        assert!(stitched.edge_cost(6).unwrap() < 0.2);
+        let cost = crate::dijkstra::is_reachable(&west, &east, &stitched, f64::INFINITY).unwrap().unwrap();
+        assert!(cost < 230.0);
+        assert!(VertexBuffer::with_options(vec![SphereConnection::new(west, east)], CelestialObject::EARTH,
+            &BuildOptions {antimeridian_km: Some(-1.0), ..BuildOptions::default()}).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        // given: one-way network with metadata and a shape
+        let connections = vec![
+            SphereConnection::new(SpherePoint::new(0.0, 0.0), SpherePoint::new(0.0, 1.0)),
+            SphereConnection::new(SpherePoint::new(0.0, 1.0), SpherePoint::new(1.0, 1.0)),
+        ];
+        let mut vertex = VertexBuffer::new_directed(connections, CelestialObject::MARS).unwrap();
+        vertex.set_edge_metadata(1, "name", "Olympus Road").unwrap();
+        vertex.set_node_metadata(2, "kind", "crater").unwrap();
+        vertex.set_edge_shape(0, vec![SpherePoint::new(0.1, 0.5)]).unwrap();
+        // when
+        let json = serde_json::to_string(&vertex).unwrap();
+        let loaded: VertexBuffer = serde_json::from_str(&json).unwrap();
+        // then
+        assert_eq!(loaded.to_connections(), vertex.to_connections());
+        assert!(loaded.check_same_body(&vertex).is_ok());
+        assert_eq!(loaded.index_of(&SpherePoint::new(1.0, 1.0)), Some(2));
+        assert_eq!(loaded.edge_cost(1), vertex.edge_cost(1));
+        assert_eq!(loaded.edge_metadata(1), vertex.edge_metadata(1));
+        assert_eq!(loaded.node_metadata(2), vertex.node_metadata(2));
+        assert_eq!(loaded.edge_shape(0), vertex.edge_shape(0));
+        assert!(find_path(&SpherePoint::new(1.0, 1.0), &SpherePoint::new(0.0, 0.0), &loaded).is_none());
+        let point: SpherePoint = serde_json::from_str(&serde_json::to_string(&SpherePoint::new(1.5, -2.0)).unwrap()).unwrap();
+        assert_eq!(point, SpherePoint::new(1.5, -2.0));
+        let unknown_node = json.replacen("\"vertex_index\":1", "\"vertex_index\":7", 1);
+        assert!(serde_json::from_str::<VertexBuffer>(&unknown_node).is_err());
+    }
+}
@@ -0,0 +1,146 @@
+use crate::errors::*;
+use crate::data::CelestialObject;
+use crate::components::*;
+use crate::vertex::VertexBuffer;
+use crate::vertex::generators::k_nearest;
+
+/// Ready to route networks compiled in with `builtin-networks` feature, for evaluation and examples.
+///
+/// * `EarthOceanGrid` - 5 degree grid over oceans between 60°S and 75°N with connections to 8
+///   neighbours which do not cross coarse continent outlines, costs are great-circle kilometers;
+///   outlines have a few dozen vertices, so straits and islands are not represented
+/// * `MarsLandingSites` - network of landing sites of Mars missions from Viking 1 to Zhurong,
+///   each linked to its 2 nearest sites
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuiltinNetwork {
+    EarthOceanGrid,
+    MarsLandingSites,
+}
+
+const GRID_STEP: f64 = 5.0;
+const GRID_MIN_LAT: f64 = -60.0;
+const GRID_MAX_LAT: f64 = 75.0;
+
+/// Coarse continent outlines as (latitude, longitude) vertices.
+const LAND: &[&[(f64, f64)]] = &[
+    // North America
+    &[(70.0, -165.0), (72.0, -125.0), (70.0, -60.0), (50.0, -55.0), (45.0, -65.0), (30.0, -80.0), (25.0, -80.0),
+        (30.0, -90.0), (18.0, -95.0), (15.0, -88.0), (8.0, -78.0), (20.0, -105.0), (32.0, -117.0), (48.0, -125.0),
+        (60.0, -140.0), (60.0, -165.0)],
+    // Greenland
+    &[(83.0, -35.0), (76.0, -70.0), (60.0, -45.0), (70.0, -21.0)],
+    // South America
+    &[(12.0, -72.0), (10.0, -62.0), (5.0, -52.0), (-5.0, -35.0), (-23.0, -41.0), (-35.0, -57.0), (-55.0, -68.0),
+        (-50.0, -75.0), (-18.0, -70.0), (-5.0, -81.0), (5.0, -77.0)],
+    // Africa
+    &[(35.0, -6.0), (37.0, 10.0), (32.0, 32.0), (12.0, 44.0), (11.0, 51.0), (-25.0, 35.0), (-35.0, 20.0),
+        (-17.0, 11.0), (5.0, 9.0), (5.0, -8.0), (15.0, -17.0), (28.0, -13.0)],
+    // Europe and the Middle East
+    &[(36.0, -9.0), (44.0, -9.0), (48.0, -5.0), (58.0, 5.0), (71.0, 25.0), (70.0, 60.0), (25.0, 60.0),
+        (12.0, 45.0), (30.0, 32.0), (37.0, 27.0), (36.0, -6.0)],
+    // Asia
+    &[(70.0, 60.0), (77.0, 105.0), (72.0, 140.0), (65.0, 178.0), (60.0, 163.0), (52.0, 157.0), (40.0, 142.0),
+        (35.0, 129.0), (22.0, 114.0), (10.0, 107.0), (1.0, 104.0), (8.0, 98.0), (16.0, 94.0), (22.0, 90.0),
+        (20.0, 86.0), (8.0, 77.0), (22.0, 69.0), (25.0, 60.0)],
+    // Australia
+    &[(-11.0, 131.0), (-12.0, 142.0), (-25.0, 153.0), (-38.0, 148.0), (-38.0, 140.0), (-32.0, 116.0),
+        (-22.0, 114.0), (-14.0, 126.0)],
+];
+
+/// Landing sites of Mars missions as (latitude, longitude) in planetocentric degrees.
+const MARS_LANDING_SITES: &[(f64, f64)] = &[
+    (22.27, -47.95), // Viking 1
+    (47.64, 134.29), // Viking 2
+    (19.13, -33.22), // Mars Pathfinder
+    (-14.57, 175.47), // Spirit
+    (-1.95, -5.53), // Opportunity
+    (68.22, -125.75), // Phoenix
+    (-4.59, 137.44), // Curiosity
+    (4.50, 135.62), // InSight
+    (18.44, 77.45), // Perseverance
+    (25.07, 109.93), // Zhurong
+];
+
+impl VertexBuffer {
+    /// Builds one of BuiltinNetworks, something routable right away without own data.
+    ///
+    /// ```
+    /// use path_navigator::components::SpherePoint;
+    /// use path_navigator::dijkstra::is_reachable;
+    /// use path_navigator::vertex::VertexBuffer;
+    /// use path_navigator::vertex::builtin::BuiltinNetwork;
+    ///
+    /// let ocean = VertexBuffer::builtin(BuiltinNetwork::EarthOceanGrid).unwrap();
+    /// let (lisbon, new_york) = (SpherePoint::new(38.7, -9.1), SpherePoint::new(40.7, -74.0));
+    /// assert!(is_reachable(&lisbon, &new_york, &ocean, f64::INFINITY).unwrap().is_some());
+    /// ```
+    pub fn builtin(network: BuiltinNetwork) -> Result<VertexBuffer> {
+        match network {
+            BuiltinNetwork::EarthOceanGrid => ocean_grid(),
+            BuiltinNetwork::MarsLandingSites => {
+                let sites: Vec<SpherePoint> = MARS_LANDING_SITES.iter().map(|(lat, lng)| SpherePoint::new(*lat, *lng)).collect();
+                k_nearest(&sites, 2, CelestialObject::MARS)
+            }
+        }
+    }
+}
+
+fn ocean_grid() -> Result<VertexBuffer> {
+    let land: Vec<SpherePolygon> = LAND.iter()
+        .map(|outline| SpherePolygon::new(outline.iter().map(|(lat, lng)| SpherePoint::new(*lat, *lng)).collect()))
+        .collect();
+    let rows = ((GRID_MAX_LAT - GRID_MIN_LAT) / GRID_STEP) as usize + 1;
+    let columns = (360.0 / GRID_STEP) as usize;
+    let point = |row: usize, column: usize| {
+        SpherePoint::new(GRID_MIN_LAT + row as f64 * GRID_STEP, -180.0 + column as f64 * GRID_STEP)
+    };
+    let is_water: Vec<Vec<bool>> = (0..rows)
+        .map(|row| (0..columns).map(|column| !land.iter().any(|outline| outline.contains(&point(row, column)))).collect())
+        .collect();
+    let mut connections: Vec<SphereConnection> = Vec::new();
+    for row in 0..rows {
+        for column in 0..columns {
+            if !is_water[row][column] {
+                continue;
+            }
+            // east, north-east, north and north-west neighbours, the other four link back to this node
+            let neighbours = [(row, column + 1), (row + 1, column + 1), (row + 1, column), (row + 1, column + columns - 1)];
+            for (other_row, other_column) in neighbours.iter() {
+                let other_column = other_column % columns;
+                if *other_row < rows && is_water[*other_row][other_column]
+                    && is_clear(&point(row, column), &point(*other_row, other_column), &land) {
+                    connections.push(SphereConnection::new(point(row, column), point(*other_row, other_column)));
+                }
+            }
+        }
+    }
+    VertexBuffer::new(connections, CelestialObject::EARTH)
+}
+
+#[cfg(test)]
+mod builtin_tests {
+    use super::*;
+    use crate::dijkstra::{find_path_with_policy, DegenerateQuery};
+
+    #[test]
+    fn test_builtin_networks() {
+        // given
+        let ocean = VertexBuffer::builtin(BuiltinNetwork::EarthOceanGrid).unwrap();
+        let mars = VertexBuffer::builtin(BuiltinNetwork::MarsLandingSites).unwrap();
+        let land: Vec<SpherePolygon> = LAND.iter()
+            .map(|outline| SpherePolygon::new(outline.iter().map(|(lat, lng)| SpherePoint::new(*lat, *lng)).collect()))
+            .collect();
+        // when: sailing from the North Atlantic to the Indian Ocean, around Africa
+        let voyage = find_path_with_policy(&SpherePoint::new(30.0, -40.0), &SpherePoint::new(-10.0, 70.0), &ocean,
+            DegenerateQuery::NoPath).unwrap().unwrap();
+        // then
+        assert!(ocean.index_of(&SpherePoint::new(0.0, -30.0)).is_some());
+        assert!(ocean.index_of(&SpherePoint::new(0.0, 20.0)).is_none());
+        assert!(ocean.index_of(&SpherePoint::new(0.0, 175.0)).unwrap() < ocean.len());
+        assert!(voyage.points().iter().all(|point| !land.iter().any(|outline| outline.contains(point))));
+        assert!(voyage.points().iter().any(|point| point.lat < -35.0));
+        assert_eq!(mars.len(), MARS_LANDING_SITES.len());
+        assert!(mars.check_same_body(&VertexBuffer::new(mars.to_connections(), CelestialObject::MARS).unwrap()).is_ok());
+        assert_eq!(mars.within_hops(0, mars.len()).len(), mars.len());
+    }
+}
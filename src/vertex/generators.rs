@@ -0,0 +1,230 @@
+use crate::errors::*;
+use crate::data::CelestialObject;
+use crate::components::*;
+use crate::vertex::VertexBuffer;
+use crate::rng::SplitMix64;
+use crate::hashing::{NodeHasher, NodeSet};
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+
+/// Returns connected VertexBuffer built from bare points by linking every point with its
+/// `k` nearest neighbours by great-circle distance.
+///
+/// # Remarks:
+///
+/// kNN linking alone may leave separate clusters, so they are joined afterwards with the
+/// shortest possible great-circle connections (minimum spanning tree over clusters), which
+/// makes every point reachable from every other. With `k` equal 0 only those joining
+/// connections are created. Duplicated points are merged.
+///
+/// Fails with `DataItemIncorrect` when there are less than two distinct points.
+///
+/// ```
+/// use path_navigator::components::SpherePoint;
+/// use path_navigator::data::CelestialObject;
+/// use path_navigator::vertex::generators::k_nearest;
+///
+/// let airports = vec![
+///     SpherePoint::new(52.1657, 20.9671), // Warsaw
+///     SpherePoint::new(54.3776, 18.4662), // Gdansk
+///     SpherePoint::new(50.0777, 19.7848), // Krakow
+///     SpherePoint::new(40.6413, -73.7781), // New York
+/// ];
+/// let vertex = k_nearest(&airports, 1, CelestialObject::EARTH).unwrap();
+/// assert_eq!(vertex.within_hops(0, 3).len(), 4);
+/// ```
+pub fn k_nearest(points: &[SpherePoint], k: usize, celestial_object: CelestialObject) -> Result<VertexBuffer> {
+    let mut distinct: Vec<SpherePoint> = Vec::new();
+    for point in points {
+        if !distinct.contains(point) {
+            distinct.push(point.clone());
+        }
+    }
+    if distinct.len() < 2 {
+        return Err(Error::from_kind(ErrorKind::DataItemIncorrect));
+    }
+    let count = distinct.len();
+    let vectors: Vec<Vector3> = distinct.iter().map(|point| point.to_vector()).collect();
+    let angle = |i: usize, j: usize| norm(&cross(&vectors[i], &vectors[j])).atan2(dot(&vectors[i], &vectors[j]));
+    let mut links: BTreeSet<(usize, usize)> = BTreeSet::new();
+    for i in 0..count {
+        let mut neighbours: Vec<usize> = (0..count).filter(|j| *j != i).collect();
+        // dot product of unit vectors falls as angle grows, so ranking needs no arc tangents
+        #[cfg(feature = "fast-math")]
+        neighbours.sort_by(|a, b| dot(&vectors[i], &vectors[*b]).total_cmp(&dot(&vectors[i], &vectors[*a])).then(a.cmp(b)));
+        #[cfg(not(feature = "fast-math"))]
+        neighbours.sort_by(|a, b| angle(i, *a).total_cmp(&angle(i, *b)).then(a.cmp(b)));
+        for j in neighbours.into_iter().take(k) {
+            links.insert((i.min(j), i.max(j)));
+        }
+    }
+    let clusters = cluster_ids(count, &links);
+    // Prim's algorithm where points of the same cluster are already joined at no cost
+    let mut in_tree = vec![false; count];
+    let mut best: Vec<(f64, usize)> = vec![(f64::INFINITY, 0); count];
+    best[0] = (0.0, 0);
+    for _ in 0..count {
+        let next = (0..count)
+            .filter(|i| !in_tree[*i])
+            .min_by(|a, b| best[*a].0.total_cmp(&best[*b].0))
+            .unwrap();
+        in_tree[next] = true;
+        let (cost, from) = best[next];
+        if cost > 0.0 {
+            links.insert((from.min(next), from.max(next)));
+        }
+        for i in 0..count {
+            if in_tree[i] {
+                continue;
+            }
+            let cost = if clusters[i] == clusters[next] { 0.0 } else { angle(next, i) };
+            if cost < best[i].0 {
+                best[i] = (cost, next);
+            }
+        }
+    }
+    let connections: Vec<SphereConnection> = links.iter()
+        .map(|(i, j)| SphereConnection::new(distinct[*i].clone(), distinct[*j].clone()))
+        .collect();
+    VertexBuffer::new(connections, celestial_object)
+}
+
+/// Returns reproducible synthetic connected VertexBuffer on Earth for benchmarks and tests.
+///
+/// # Arguments:
+/// * `n_nodes` which is usize - number of nodes, at least 2
+/// * `avg_degree` which is f64 - requested average number of connections per node
+/// * `bbox` which is &SphereBoundingBox - area in which nodes are placed, uniformly in latitude and longitude
+/// * `seed` which is u64 - the same seed always produces the same graph
+///
+/// # Remarks:
+///
+/// Nodes are first joined into a random spanning tree, so the graph is connected and average
+/// degree can not be lower than `2 * (n_nodes - 1) / n_nodes`, then random connections are added
+/// until requested average degree is reached (at most complete graph).
+///
+/// Fails with `InvalidParameter` for less than two nodes or empty bounding box.
+///
+/// ```
+/// use path_navigator::components::SphereBoundingBox;
+/// use path_navigator::vertex::generators::random;
+///
+/// let bbox = SphereBoundingBox::new(49.0, 14.0, 54.8, 24.1);
+/// let vertex = random(1000, 3.0, &bbox, 42).unwrap();
+/// assert_eq!(vertex.len(), 1000);
+/// assert_eq!(vertex.edge_count(), 1500);
+/// ```
+pub fn random(n_nodes: usize, avg_degree: f64, bbox: &SphereBoundingBox, seed: u64) -> Result<VertexBuffer> {
+    let is_empty_bbox = bbox.max_lat.partial_cmp(&bbox.min_lat) != Some(Ordering::Greater)
+        || bbox.lng_span().partial_cmp(&0.0) != Some(Ordering::Greater);
+    if n_nodes < 2 || is_empty_bbox {
+        return Err(Error::from_kind(ErrorKind::InvalidParameter));
+    }
+    let mut rng = SplitMix64::new(seed);
+    let mut points: Vec<SpherePoint> = Vec::with_capacity(n_nodes);
+    let mut seen: NodeSet<(u64, u64)> = NodeSet::with_capacity_and_hasher(n_nodes, NodeHasher::default());
+    while points.len() < n_nodes {
+        let lat = bbox.min_lat + rng.next_f64() * (bbox.max_lat - bbox.min_lat);
+        let mut lng = bbox.min_lng + rng.next_f64() * bbox.lng_span();
+        if lng > 180.0 {
+            lng -= 360.0;
+        }
+        if seen.insert((lat.to_bits(), lng.to_bits())) {
+            points.push(SpherePoint::new(lat, lng));
+        }
+    }
+    let max_links = n_nodes * (n_nodes - 1) / 2;
+    let requested_links = (n_nodes as f64 * avg_degree.max(0.0) / 2.0).round() as usize;
+    let link_count = requested_links.clamp(n_nodes - 1, max_links);
+    let mut links: BTreeSet<(usize, usize)> = BTreeSet::new();
+    for i in 1..n_nodes {
+        let j = rng.below(i);
+        links.insert((j, i));
+    }
+    while links.len() < link_count {
+        let i = rng.below(n_nodes);
+        let j = rng.below(n_nodes);
+        if i != j {
+            links.insert((i.min(j), i.max(j)));
+        }
+    }
+    let connections: Vec<SphereConnection> = links.iter()
+        .map(|(i, j)| SphereConnection::new(points[*i].clone(), points[*j].clone()))
+        .collect();
+    VertexBuffer::new(connections, CelestialObject::EARTH)
+}
+
+/// Labels points with id of the connected cluster they belong to.
+fn cluster_ids(count: usize, links: &BTreeSet<(usize, usize)>) -> Vec<usize> {
+    let mut parent: Vec<usize> = (0..count).collect();
+    fn find(parent: &mut [usize], i: usize) -> usize {
+        let mut root = i;
+        while parent[root] != root {
+            root = parent[root];
+        }
+        parent[i] = root;
+        root
+    }
+    for (i, j) in links {
+        let root_i = find(&mut parent, *i);
+        let root_j = find(&mut parent, *j);
+        parent[root_i] = root_j;
+    }
+    (0..count).map(|i| find(&mut parent, i)).collect()
+}
+
+#[cfg(test)]
+mod generators_tests {
+    use super::*;
+
+    #[test]
+    fn test_k_nearest_is_connected() {
+        // given
+        let mut points: Vec<SpherePoint> = Vec::new();
+        for cluster_lng in [0.0, 40.0, 80.0] {
+            for i in 0..4 {
+                points.push(SpherePoint::new(i as f64 * 0.5, cluster_lng + i as f64 * 0.3));
+            }
+        }
+        points.push(points[0].clone());
+        // when
+        let vertex = k_nearest(&points, 2, CelestialObject::EARTH).unwrap();
+        // then
+        assert_eq!(vertex.len(), 12);
+        assert_eq!(vertex.within_hops(0, vertex.len()).len(), 12);
+        // two joining connections between three clusters
+        let long_connections = (0..vertex.edge_count())
+            .filter(|edge_id| {
+                let (from, to) = vertex.edge(*edge_id).unwrap();
+                (vertex.vector[from].coordinates.lng - vertex.vector[to].coordinates.lng).abs() > 30.0
+            })
+            .count();
+        assert_eq!(long_connections, 2);
+    }
+
+    #[test]
+    fn test_random_is_reproducible() {
+        // given
+        let bbox = SphereBoundingBox::new(-10.0, 170.0, 10.0, -170.0);
+        // when
+        let vertex = random(200, 4.0, &bbox, 7).unwrap();
+        let same_vertex = random(200, 4.0, &bbox, 7).unwrap();
+        let other_vertex = random(200, 4.0, &bbox, 8).unwrap();
+        // then
+        assert_eq!(vertex.edge_count(), 400);
+        assert_eq!(vertex.within_hops(0, vertex.len()).len(), 200);
+        assert!(vertex.vector.iter().all(|node| bbox.contains(&node.coordinates)));
+        assert_eq!(vertex.vector, same_vertex.vector);
+        assert!(vertex.vector != other_vertex.vector);
+        assert_eq!(random(3, 100.0, &bbox, 7).unwrap().edge_count(), 3);
+        assert!(random(1, 4.0, &bbox, 7).is_err());
+    }
+
+    #[test]
+    fn test_k_nearest_needs_two_points() {
+        // given
+        let points = vec![SpherePoint::new(1.0, 1.0), SpherePoint::new(1.0, 1.0)];
+        // when, then
+        assert!(k_nearest(&points, 3, CelestialObject::MARS).is_err());
+    }
+}